@@ -0,0 +1,2 @@
+-- Optional per-device icon: an emoji or a short icon key/filename
+ALTER TABLE devices ADD COLUMN icon TEXT;
@@ -0,0 +1,13 @@
+-- Record which rule generated each alert
+ALTER TABLE alerts ADD COLUMN rule_id TEXT;
+
+-- Backfill from the built-in rule that produces each alert type
+UPDATE alerts SET rule_id = CASE alert_type
+    WHEN 'new_device' THEN 'rule_new_device'
+    WHEN 'device_departed' THEN 'rule_device_departed'
+    WHEN 'port_changed' THEN 'rule_port_changed'
+    WHEN 'unknown_device' THEN 'rule_untrusted_device'
+END
+WHERE rule_id IS NULL;
+
+CREATE INDEX idx_alerts_rule ON alerts(rule_id, created_at DESC);
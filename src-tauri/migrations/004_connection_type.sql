@@ -0,0 +1,2 @@
+-- How each device is attached: wired, wireless, virtual or unknown
+ALTER TABLE devices ADD COLUMN connection_type TEXT NOT NULL DEFAULT 'unknown';
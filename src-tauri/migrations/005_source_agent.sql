@@ -0,0 +1,2 @@
+-- Name of the remote agent that reported a device; NULL for locally discovered devices
+ALTER TABLE devices ADD COLUMN source_agent TEXT;
@@ -0,0 +1,2 @@
+-- When a device last answered a ping, as opposed to merely appearing in the ARP table
+ALTER TABLE devices ADD COLUMN last_confirmed_ping TEXT;
@@ -0,0 +1,2 @@
+-- Cached triage score (0-100) combining trust, open ports and OS knowledge
+ALTER TABLE devices ADD COLUMN risk_score INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Comma-separated discovery source tags from the latest scan that saw the device
+ALTER TABLE devices ADD COLUMN discovery_sources TEXT NOT NULL DEFAULT '';
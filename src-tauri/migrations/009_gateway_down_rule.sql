@@ -0,0 +1,3 @@
+-- Alert when the default gateway stops answering the gateway monitor's pings
+INSERT INTO alert_rules (id, rule_type, is_enabled, severity, notify_desktop) VALUES
+    ('rule_gateway_down', 'gateway_down', 1, 'critical', 1);
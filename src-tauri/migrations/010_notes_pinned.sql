@@ -0,0 +1,2 @@
+-- Let users flag a device's notes as important
+ALTER TABLE devices ADD COLUMN notes_pinned INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Flag devices whose resolved hostname changes between scans
+INSERT INTO alert_rules (id, rule_type, is_enabled, severity, notify_desktop) VALUES
+    ('rule_hostname_changed', 'hostname_changed', 1, 'info', 0);
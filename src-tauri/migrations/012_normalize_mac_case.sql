@@ -0,0 +1,63 @@
+-- Store MAC addresses uppercase with colon separators.
+-- Rows that differ only by case or separator are the same device: fold each group into
+-- its oldest row before rewriting, since mac_address is UNIQUE.
+CREATE TEMP TABLE mac_merge AS
+SELECT id AS dup_id,
+       FIRST_VALUE(id) OVER (
+           PARTITION BY UPPER(REPLACE(mac_address, '-', ':'))
+           ORDER BY first_seen, id
+       ) AS keep_id
+FROM devices
+WHERE mac_address IS NOT NULL;
+
+DELETE FROM mac_merge WHERE dup_id = keep_id;
+
+UPDATE devices SET
+    last_seen = (
+        SELECT MAX(d.last_seen) FROM devices d
+        WHERE d.id = devices.id OR d.id IN (SELECT dup_id FROM mac_merge WHERE keep_id = devices.id)
+    ),
+    is_trusted = (
+        SELECT MAX(d.is_trusted) FROM devices d
+        WHERE d.id = devices.id OR d.id IN (SELECT dup_id FROM mac_merge WHERE keep_id = devices.id)
+    ),
+    -- The user's name and notes survive from the oldest duplicate that has them
+    custom_name = COALESCE(custom_name, (
+        SELECT d.custom_name FROM devices d
+        WHERE d.id IN (SELECT dup_id FROM mac_merge WHERE keep_id = devices.id) AND d.custom_name IS NOT NULL
+        ORDER BY d.first_seen, d.id
+        LIMIT 1
+    )),
+    notes = COALESCE(notes, (
+        SELECT d.notes FROM devices d
+        WHERE d.id IN (SELECT dup_id FROM mac_merge WHERE keep_id = devices.id) AND d.notes IS NOT NULL
+        ORDER BY d.first_seen, d.id
+        LIMIT 1
+    ))
+WHERE id IN (SELECT keep_id FROM mac_merge);
+
+UPDATE device_ips SET device_id = (SELECT keep_id FROM mac_merge WHERE dup_id = device_ips.device_id)
+WHERE device_id IN (SELECT dup_id FROM mac_merge);
+UPDATE device_ports SET device_id = (SELECT keep_id FROM mac_merge WHERE dup_id = device_ports.device_id)
+WHERE device_id IN (SELECT dup_id FROM mac_merge);
+UPDATE latency_history SET device_id = (SELECT keep_id FROM mac_merge WHERE dup_id = latency_history.device_id)
+WHERE device_id IN (SELECT dup_id FROM mac_merge);
+UPDATE alerts SET device_id = (SELECT keep_id FROM mac_merge WHERE dup_id = alerts.device_id)
+WHERE device_id IN (SELECT dup_id FROM mac_merge);
+
+-- Only the most recently seen IP of a merged device stays current
+UPDATE device_ips SET is_current = 0
+WHERE device_id IN (SELECT keep_id FROM mac_merge)
+  AND id != (
+      SELECT di.id FROM device_ips di
+      WHERE di.device_id = device_ips.device_id AND di.is_current = 1
+      ORDER BY di.last_seen DESC, di.id
+      LIMIT 1
+  );
+
+DELETE FROM devices WHERE id IN (SELECT dup_id FROM mac_merge);
+
+UPDATE devices SET mac_address = UPPER(REPLACE(mac_address, '-', ':'))
+WHERE mac_address IS NOT NULL;
+
+DROP TABLE mac_merge;
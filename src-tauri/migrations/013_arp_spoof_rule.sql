@@ -0,0 +1,3 @@
+-- Flag an IP answered by more than one MAC on the same link (possible ARP spoofing)
+INSERT INTO alert_rules (id, rule_type, is_enabled, severity, notify_desktop) VALUES
+    ('rule_arp_spoof', 'arp_spoof', 1, 'critical', 1);
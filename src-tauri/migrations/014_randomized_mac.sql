@@ -0,0 +1,6 @@
+-- Flag locally administered (typically randomized private) MAC addresses
+ALTER TABLE devices ADD COLUMN is_randomized_mac INTEGER NOT NULL DEFAULT 0;
+
+-- The locally administered bit is 0x02 of the first octet; MACs are stored as uppercase AA:BB:...
+UPDATE devices SET is_randomized_mac = 1
+WHERE SUBSTR(mac_address, 2, 1) IN ('2', '3', '6', '7', 'A', 'B', 'E', 'F');
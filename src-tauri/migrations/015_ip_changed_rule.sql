@@ -0,0 +1,3 @@
+-- Flag known devices whose address moves between scans (opt-in)
+INSERT INTO alert_rules (id, rule_type, is_enabled, severity, notify_desktop) VALUES
+    ('rule_ip_changed', 'ip_changed', 0, 'info', 0);
@@ -0,0 +1,12 @@
+-- Which devices each scan saw, with their address, OS guess and open ports at the time
+CREATE TABLE scan_devices (
+    scan_id TEXT NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+    ip_address TEXT,
+    os_guess TEXT,
+    -- Comma-separated port/protocol entries, e.g. '22/tcp,53/udp'
+    open_ports TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (scan_id, device_id)
+);
+
+CREATE INDEX idx_scan_devices_device ON scan_devices(device_id);
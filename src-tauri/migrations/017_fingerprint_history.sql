@@ -0,0 +1,12 @@
+-- Every OS guess and device type a fingerprint pass settled on, so reclassifications can be audited
+CREATE TABLE fingerprint_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+    os_guess TEXT,
+    os_confidence REAL,
+    device_type TEXT NOT NULL,
+    scan_id TEXT REFERENCES scans(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX idx_fingerprint_history_device ON fingerprint_history(device_id, created_at);
@@ -0,0 +1,7 @@
+-- Loss and jitter across the echo requests behind each latency sample
+ALTER TABLE latency_history ADD COLUMN packet_loss REAL;
+ALTER TABLE latency_history ADD COLUMN jitter_ms REAL;
+
+-- Flag devices that start dropping pings (opt-in)
+INSERT INTO alert_rules (id, rule_type, is_enabled, severity, notify_desktop) VALUES
+    ('rule_packet_loss', 'packet_loss', 0, 'warning', 0);
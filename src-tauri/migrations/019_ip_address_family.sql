@@ -0,0 +1,12 @@
+-- Keep a current address per family, so a device's IPv6 neighbors no longer replace its IPv4 address
+ALTER TABLE device_ips ADD COLUMN address_family TEXT NOT NULL DEFAULT 'ipv4';
+UPDATE device_ips SET address_family = 'ipv6' WHERE ip_address LIKE '%:%';
+
+-- The newest address of each family is current
+UPDATE device_ips SET is_current = (
+    id = (SELECT newest.id FROM device_ips newest
+          WHERE newest.device_id = device_ips.device_id
+            AND newest.address_family = device_ips.address_family
+          ORDER BY newest.last_seen DESC, newest.is_current DESC, newest.rowid DESC
+          LIMIT 1)
+);
@@ -0,0 +1,9 @@
+-- Free-form labels users attach to devices, e.g. "work" or "kids-devices"
+CREATE TABLE device_tags (
+    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TEXT DEFAULT (datetime('now')),
+    PRIMARY KEY (device_id, tag)
+);
+
+CREATE INDEX idx_device_tags_tag ON device_tags(tag);
@@ -0,0 +1,8 @@
+-- User- or fingerprint-supplied key/values on a device, e.g. room=office
+CREATE TABLE device_properties (
+    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TEXT DEFAULT (datetime('now')),
+    PRIMARY KEY (device_id, key)
+);
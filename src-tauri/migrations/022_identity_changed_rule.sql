@@ -0,0 +1,3 @@
+-- Flag known devices whose OS guess or device type changes between scans
+INSERT INTO alert_rules (id, rule_type, is_enabled, severity, notify_desktop) VALUES
+    ('rule_identity_changed', 'identity_changed', 0, 'warning', 1);
@@ -0,0 +1,3 @@
+-- upsert_device_ip looks up a device's address on every sighting; the existing
+-- device_ips indexes only cover current rows, so that lookup scanned the table
+CREATE INDEX idx_device_ips_device_ip ON device_ips(device_id, ip_address);
@@ -0,0 +1,4 @@
+-- How sure the fingerprint pass that set device_type was, and whether the user set it.
+-- A later pass only replaces an automatic type with a more confident one.
+ALTER TABLE devices ADD COLUMN type_confidence REAL NOT NULL DEFAULT 0;
+ALTER TABLE devices ADD COLUMN type_manual INTEGER NOT NULL DEFAULT 0;
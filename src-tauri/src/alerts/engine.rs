@@ -1,7 +1,24 @@
 use crate::db::queries::alerts::{self as db_alerts, AlertRule};
-use crate::db::queries::devices::Device;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::db::queries::devices::{self as db_devices, Device};
+use crate::db::queries::ports::PortInfo;
+use crate::db::queries::settings as db_settings;
+use crate::scanner::DiscoveredDevice;
 use rusqlite::Connection;
 
+/// Departures needed in one scan before they are reported as a single network event.
+pub const CORRELATION_MIN_DEVICES: usize = 5;
+
+/// ...and the share of previously online devices they must make up.
+pub const CORRELATION_MIN_SHARE: f64 = 0.5;
+
+/// Seconds during which an identical alert is not stored or raised again.
+pub const DEFAULT_ALERT_COOLDOWN_SECS: u64 = 3600;
+
+/// Percent of echo requests lost at which a device is reported by the `packet_loss` rule.
+pub const PACKET_LOSS_ALERT_PERCENT: f64 = 30.0;
+
 /// Evaluate scan results against alert rules and generate alerts.
 pub fn evaluate_alerts(
     conn: &Connection,
@@ -24,6 +41,7 @@ pub fn evaluate_alerts(
                         message: format!("New device discovered: {} ({})", name, device.current_ip.as_deref().unwrap_or("unknown IP")),
                         severity: rule.severity.clone(),
                         notify_desktop: rule.notify_desktop,
+                        rule_id: Some(rule.id.clone()),
                     });
                 }
             }
@@ -43,12 +61,138 @@ pub fn evaluate_alerts(
                         message: format!("Untrusted device on network: {} ({})", name, device.current_ip.as_deref().unwrap_or("unknown IP")),
                         severity: rule.severity.clone(),
                         notify_desktop: rule.notify_desktop,
+                        rule_id: Some(rule.id.clone()),
+                    });
+                }
+            }
+        }
+    }
+
+    // Hostname change detection
+    if let Some(rule) = find_rule(&rules, "hostname_changed") {
+        if rule.is_enabled {
+            for device in current_devices {
+                let Some(new_name) = device.hostname.as_deref() else { continue };
+                let old_name = previous_devices
+                    .iter()
+                    .find(|d| d.id == device.id)
+                    .and_then(|d| d.hostname.as_deref());
+                if let Some(old_name) = old_name.filter(|old| *old != new_name) {
+                    alerts.push(GeneratedAlert {
+                        alert_type: "hostname_changed".to_string(),
+                        device_id: Some(device.id.clone()),
+                        message: format!("Hostname changed: {} is now {} ({})", old_name, new_name, device.current_ip.as_deref().unwrap_or("unknown IP")),
+                        severity: rule.severity.clone(),
+                        notify_desktop: rule.notify_desktop,
+                        rule_id: Some(rule.id.clone()),
+                    });
+                }
+            }
+        }
+    }
+
+    // Address changes on known devices
+    if let Some(rule) = find_rule(&rules, "ip_changed") {
+        if rule.is_enabled {
+            for device in current_devices {
+                let Some(new_ip) = device.current_ip.as_deref() else { continue };
+                let old_ip = previous_devices
+                    .iter()
+                    .find(|d| d.id == device.id)
+                    .and_then(|d| d.current_ip.as_deref());
+                if let Some(old_ip) = old_ip.filter(|old| *old != new_ip) {
+                    alerts.push(GeneratedAlert {
+                        alert_type: "ip_changed".to_string(),
+                        device_id: Some(device.id.clone()),
+                        message: format!("{} moved from {} to {}", device_display_name(device), old_ip, new_ip),
+                        severity: rule.severity.clone(),
+                        notify_desktop: rule.notify_desktop,
+                        rule_id: Some(rule.id.clone()),
+                    });
+                }
+            }
+        }
+    }
+
+    // Known devices that now fingerprint as a different OS or kind of device,
+    // e.g. a printer that looks like a computer after a compromise or MAC reuse
+    if let Some(rule) = find_rule(&rules, "identity_changed") {
+        if rule.is_enabled {
+            for device in current_devices {
+                let Some(prev) = previous_devices.iter().find(|d| d.id == device.id) else { continue };
+                let changes = identity_changes(prev, device);
+                if !changes.is_empty() {
+                    alerts.push(GeneratedAlert {
+                        alert_type: "identity_changed".to_string(),
+                        device_id: Some(device.id.clone()),
+                        message: format!("{} changed identity: {}", device_display_name(device), changes.join(", ")),
+                        severity: rule.severity.clone(),
+                        notify_desktop: rule.notify_desktop,
+                        rule_id: Some(rule.id.clone()),
                     });
                 }
             }
         }
     }
 
+    // Devices that have started dropping pings; reported once, when loss first crosses the threshold
+    if let Some(rule) = find_rule(&rules, "packet_loss") {
+        if rule.is_enabled {
+            let lossy = |d: &Device| d.packet_loss.is_some_and(|loss| loss >= PACKET_LOSS_ALERT_PERCENT);
+            for device in current_devices.iter().filter(|d| lossy(d)) {
+                let was_lossy = previous_devices.iter().any(|d| d.id == device.id && lossy(d));
+                if was_lossy {
+                    continue;
+                }
+                alerts.push(GeneratedAlert {
+                    alert_type: "packet_loss".to_string(),
+                    device_id: Some(device.id.clone()),
+                    message: format!(
+                        "{} is dropping {:.0}% of pings",
+                        device_display_name(device),
+                        device.packet_loss.unwrap_or_default()
+                    ),
+                    severity: rule.severity.clone(),
+                    notify_desktop: rule.notify_desktop,
+                    rule_id: Some(rule.id.clone()),
+                });
+            }
+        }
+    }
+
+    // Newly opened ports on known devices
+    if let Some(rule) = find_rule(&rules, "port_changed") {
+        if rule.is_enabled {
+            for device in current_devices {
+                let Some(prev_device) = previous_devices.iter().find(|d| d.id == device.id) else { continue };
+                let opened = newly_opened_ports(prev_device, device);
+                if opened.is_empty() {
+                    continue;
+                }
+                let listed: Vec<String> = opened
+                    .iter()
+                    .map(|p| match p.service_name.as_deref() {
+                        Some(service) => format!("{} ({})", p.port, service),
+                        None => p.port.to_string(),
+                    })
+                    .collect();
+                alerts.push(GeneratedAlert {
+                    alert_type: "port_changed".to_string(),
+                    device_id: Some(device.id.clone()),
+                    message: format!(
+                        "{} opened port{} {}",
+                        device.current_ip.clone().unwrap_or_else(|| device_display_name(device)),
+                        if opened.len() == 1 { "" } else { "s" },
+                        listed.join(", ")
+                    ),
+                    severity: rule.severity.clone(),
+                    notify_desktop: rule.notify_desktop,
+                    rule_id: Some(rule.id.clone()),
+                });
+            }
+        }
+    }
+
     // Device departed detection
     if let Some(rule) = find_rule(&rules, "device_departed") {
         if rule.is_enabled {
@@ -63,6 +207,7 @@ pub fn evaluate_alerts(
                             message: format!("Device departed: {}", name),
                             severity: rule.severity.clone(),
                             notify_desktop: rule.notify_desktop,
+                            rule_id: Some(rule.id.clone()),
                         });
                     }
                 }
@@ -70,20 +215,48 @@ pub fn evaluate_alerts(
         }
     }
 
-    // Persist generated alerts
-    for alert in &alerts {
-        let alert_id = uuid::Uuid::new_v4().to_string();
-        db_alerts::insert_alert(
-            conn,
-            &alert_id,
-            &alert.alert_type,
-            alert.device_id.as_deref(),
-            &alert.message,
-            &alert.severity,
-        )?;
+    let previously_online = previous_devices.iter().filter(|d| d.is_online).count();
+    let alerts = correlate_departures(alerts, previous_devices, previously_online);
+
+    persist(conn, alerts)
+}
+
+/// Store generated alerts in one transaction, dropping any already stored with the
+/// same type, device and message within the alert cooldown. Returns the alerts kept.
+fn persist(conn: &Connection, alerts: Vec<GeneratedAlert>) -> Result<Vec<GeneratedAlert>, rusqlite::Error> {
+    if alerts.is_empty() {
+        return Ok(alerts);
+    }
+    let cooldown_secs = db_settings::get_settings(conn)?.alert_cooldown_secs;
+    let mut kept = Vec::with_capacity(alerts.len());
+    for alert in alerts {
+        let repeat = cooldown_secs > 0
+            && db_alerts::has_recent_alert(
+                conn,
+                &alert.alert_type,
+                alert.device_id.as_deref(),
+                &alert.message,
+                cooldown_secs,
+            )?;
+        if repeat {
+            log::debug!("Suppressing repeated alert: {}", alert.message);
+        } else {
+            kept.push(alert);
+        }
     }
 
-    Ok(alerts)
+    let rows: Vec<db_alerts::NewAlert> = kept
+        .iter()
+        .map(|alert| db_alerts::NewAlert {
+            alert_type: &alert.alert_type,
+            device_id: alert.device_id.as_deref(),
+            message: &alert.message,
+            severity: &alert.severity,
+            rule_id: alert.rule_id.as_deref(),
+        })
+        .collect();
+    db_alerts::insert_alerts(conn, &rows)?;
+    Ok(kept)
 }
 
 #[derive(Debug, Clone)]
@@ -93,6 +266,175 @@ pub struct GeneratedAlert {
     pub message: String,
     pub severity: String,
     pub notify_desktop: bool,
+    pub rule_id: Option<String>,
+}
+
+/// Alert event emitted to the frontend as `alert:new`.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertEvent {
+    pub alert_type: String,
+    pub device_id: Option<String>,
+    pub message: String,
+    pub severity: String,
+}
+
+impl From<&GeneratedAlert> for AlertEvent {
+    fn from(alert: &GeneratedAlert) -> Self {
+        Self {
+            alert_type: alert.alert_type.clone(),
+            device_id: alert.device_id.clone(),
+            message: alert.message.clone(),
+            severity: alert.severity.clone(),
+        }
+    }
+}
+
+/// Replace a burst of departures (e.g. the gateway rebooting) with one `network_event` alert.
+fn correlate_departures(
+    alerts: Vec<GeneratedAlert>,
+    previous_devices: &[Device],
+    previously_online: usize,
+) -> Vec<GeneratedAlert> {
+    let departed: Vec<&GeneratedAlert> = alerts
+        .iter()
+        .filter(|a| a.alert_type == "device_departed")
+        .collect();
+    let count = departed.len();
+    if count < CORRELATION_MIN_DEVICES
+        || (count as f64) < CORRELATION_MIN_SHARE * previously_online as f64
+    {
+        return alerts;
+    }
+
+    let gateway = previous_devices.iter().find(|d| {
+        d.is_gateway && departed.iter().any(|a| a.device_id.as_deref() == Some(d.id.as_str()))
+    });
+    let message = match gateway {
+        Some(_) => format!("Gateway outage suspected: {} devices departed simultaneously", count),
+        None => format!("Network event: {} devices departed simultaneously", count),
+    };
+    let event = GeneratedAlert {
+        alert_type: "network_event".to_string(),
+        device_id: gateway.map(|g| g.id.clone()),
+        message,
+        severity: departed[0].severity.clone(),
+        notify_desktop: departed.iter().any(|a| a.notify_desktop),
+        rule_id: departed[0].rule_id.clone(),
+    };
+
+    let mut kept: Vec<GeneratedAlert> = alerts
+        .into_iter()
+        .filter(|a| a.alert_type != "device_departed")
+        .collect();
+    kept.push(event);
+    kept
+}
+
+/// Persist the alert for the gateway no longer answering pings, unless the
+/// `gateway_down` rule is disabled or the same alert is still in its cooldown.
+pub fn gateway_down_alert(
+    conn: &Connection,
+    gateway_ip: &str,
+    device_id: Option<&str>,
+) -> Result<Option<GeneratedAlert>, rusqlite::Error> {
+    let rules = db_alerts::get_alert_rules(conn)?;
+    let Some(rule) = find_rule(&rules, "gateway_down").filter(|r| r.is_enabled) else {
+        return Ok(None);
+    };
+
+    let alert = GeneratedAlert {
+        alert_type: "gateway_down".to_string(),
+        device_id: device_id.map(|id| id.to_string()),
+        message: format!("Gateway {} is not responding", gateway_ip),
+        severity: rule.severity.clone(),
+        notify_desktop: rule.notify_desktop,
+        rule_id: Some(rule.id.clone()),
+    };
+    Ok(persist(conn, vec![alert])?.pop())
+}
+
+/// IPs that more than one MAC answered for on the same interface, with those MACs sorted.
+/// Entries learned on different interfaces may legitimately reuse an address (overlapping subnets).
+pub fn ip_conflicts(discovered: &[DiscoveredDevice]) -> Vec<(String, Vec<String>)> {
+    let mut macs_by_ip: BTreeMap<(&str, Option<&str>), BTreeSet<String>> = BTreeMap::new();
+    for device in discovered {
+        if let Some(mac) = device.mac.as_deref() {
+            macs_by_ip
+                .entry((device.ip.as_str(), device.interface.as_deref()))
+                .or_default()
+                .insert(db_devices::normalize_mac(mac));
+        }
+    }
+    macs_by_ip
+        .into_iter()
+        .filter(|(_, macs)| macs.len() > 1)
+        .map(|((ip, _), macs)| (ip.to_string(), macs.into_iter().collect()))
+        .collect()
+}
+
+/// Persist an alert for each IP claimed by several MACs in one discovery pass,
+/// unless the `arp_spoof` rule is disabled.
+pub fn arp_spoof_alerts(
+    conn: &Connection,
+    discovered: &[DiscoveredDevice],
+) -> Result<Vec<GeneratedAlert>, rusqlite::Error> {
+    let conflicts = ip_conflicts(discovered);
+    if conflicts.is_empty() {
+        return Ok(Vec::new());
+    }
+    let rules = db_alerts::get_alert_rules(conn)?;
+    let Some(rule) = find_rule(&rules, "arp_spoof").filter(|r| r.is_enabled) else {
+        return Ok(Vec::new());
+    };
+
+    let alerts: Vec<GeneratedAlert> = conflicts
+        .into_iter()
+        .map(|(ip, macs)| GeneratedAlert {
+            alert_type: "arp_spoof".to_string(),
+            device_id: None,
+            message: format!("Possible ARP spoofing: {} is claimed by {}", ip, macs.join(", ")),
+            severity: rule.severity.clone(),
+            notify_desktop: rule.notify_desktop,
+            rule_id: Some(rule.id.clone()),
+        })
+        .collect();
+    persist(conn, alerts)
+}
+
+/// Open ports on `current` that were not open on `previous`.
+/// A device with no recorded open ports has no baseline yet, so its first port scan is not a change.
+fn newly_opened_ports<'a>(previous: &Device, current: &'a Device) -> Vec<&'a PortInfo> {
+    let was_open = |port: &PortInfo| {
+        previous
+            .open_ports
+            .iter()
+            .any(|p| p.port == port.port && p.protocol == port.protocol && p.state == "open")
+    };
+    if !previous.open_ports.iter().any(|p| p.state == "open") {
+        return Vec::new();
+    }
+    current
+        .open_ports
+        .iter()
+        .filter(|p| p.state == "open" && !was_open(p))
+        .collect()
+}
+
+/// "OS Linux → Windows"-style descriptions of how a device's classification moved.
+/// A first classification, from no OS guess or an unknown type, is not a change.
+fn identity_changes(prev: &Device, current: &Device) -> Vec<String> {
+    let mut changes = Vec::new();
+    if let (Some(old), Some(new)) = (prev.os_guess.as_deref(), current.os_guess.as_deref()) {
+        if old != new {
+            changes.push(format!("OS {} → {}", old, new));
+        }
+    }
+    let known = |t: &str| t != "unknown";
+    if known(&prev.device_type) && known(&current.device_type) && prev.device_type != current.device_type {
+        changes.push(format!("type {} → {}", prev.device_type, current.device_type));
+    }
+    changes
 }
 
 fn find_rule<'a>(rules: &'a [AlertRule], rule_type: &str) -> Option<&'a AlertRule> {
@@ -128,23 +470,11 @@ mod tests {
 
     fn make_device(id: &str, mac: &str, ip: &str, online: bool, trusted: bool) -> Device {
         Device {
-            id: id.to_string(),
             mac_address: Some(mac.to_string()),
-            vendor: None,
-            hostname: None,
-            custom_name: None,
-            device_type: "unknown".to_string(),
-            os_guess: None,
-            os_confidence: 0.0,
-            is_trusted: trusted,
-            is_gateway: false,
-            notes: None,
             current_ip: Some(ip.to_string()),
             is_online: online,
-            latency_ms: None,
-            open_ports: Vec::new(),
-            first_seen: "2024-01-01 00:00:00".to_string(),
-            last_seen: "2024-01-01 00:00:00".to_string(),
+            is_trusted: trusted,
+            ..devices::test_device(id)
         }
     }
 
@@ -161,6 +491,248 @@ mod tests {
 
         let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
         assert!(alerts.iter().any(|a| a.alert_type == "new_device"));
+
+        // The persisted alert records the rule that fired
+        let stored = db_alerts::get_alerts_by_rule(&conn, "rule_new_device").unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].device_id.as_deref(), Some("dev1"));
+    }
+
+    #[test]
+    fn test_hostname_changed_alert() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_test_device(&conn, "dev1", "AA:BB:CC:DD:EE:FF");
+        insert_test_device(&conn, "dev2", "AA:BB:CC:DD:EE:00");
+
+        let named = |id: &str, mac: &str, hostname: Option<&str>| Device {
+            hostname: hostname.map(|h| h.to_string()),
+            ..make_device(id, mac, "192.168.1.42", true, true)
+        };
+        let previous = vec![
+            named("dev1", "AA:BB:CC:DD:EE:FF", Some("nas.local")),
+            named("dev2", "AA:BB:CC:DD:EE:00", None),
+        ];
+        let current = vec![
+            named("dev1", "AA:BB:CC:DD:EE:FF", Some("printer.local")),
+            named("dev2", "AA:BB:CC:DD:EE:00", Some("tv.local")),
+        ];
+
+        let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
+        let changed: Vec<_> = alerts.iter().filter(|a| a.alert_type == "hostname_changed").collect();
+        // A first-time hostname is not a change
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].device_id.as_deref(), Some("dev1"));
+        assert!(changed[0].message.contains("nas.local is now printer.local"));
+        assert_eq!(changed[0].severity, "info");
+
+        let alerts = evaluate_alerts(&conn, &current, &current).unwrap();
+        assert!(!alerts.iter().any(|a| a.alert_type == "hostname_changed"));
+    }
+
+    #[test]
+    fn test_ip_changed_alert() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_test_device(&conn, "dev1", "AA:BB:CC:DD:EE:FF");
+
+        let previous = vec![make_device("dev1", "AA:BB:CC:DD:EE:FF", "192.168.1.10", true, true)];
+        let current = vec![make_device("dev1", "AA:BB:CC:DD:EE:FF", "192.168.1.42", true, true)];
+
+        // Off by default
+        let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
+        assert!(!alerts.iter().any(|a| a.alert_type == "ip_changed"));
+
+        conn.execute("UPDATE alert_rules SET is_enabled = 1 WHERE id = 'rule_ip_changed'", []).unwrap();
+        let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
+        let moved: Vec<_> = alerts.iter().filter(|a| a.alert_type == "ip_changed").collect();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].device_id.as_deref(), Some("dev1"));
+        assert!(moved[0].message.ends_with("moved from 192.168.1.10 to 192.168.1.42"));
+        assert_eq!(moved[0].severity, "info");
+
+        let alerts = evaluate_alerts(&conn, &current, &current).unwrap();
+        assert!(!alerts.iter().any(|a| a.alert_type == "ip_changed"));
+    }
+
+    #[test]
+    fn test_repeated_alerts_suppressed_within_cooldown() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_test_device(&conn, "dev1", "AA:BB:CC:DD:EE:FF");
+        let current = vec![make_device("dev1", "AA:BB:CC:DD:EE:FF", "192.168.1.10", true, true)];
+
+        // Against an empty snapshot the device is new every time
+        let first = evaluate_alerts(&conn, &[], &current).unwrap();
+        assert_eq!(first.len(), 1);
+        assert!(evaluate_alerts(&conn, &[], &current).unwrap().is_empty());
+        assert_eq!(db_alerts::get_alerts(&conn, false).unwrap().len(), 1);
+
+        // Once the cooldown has passed it fires again
+        conn.execute("UPDATE alerts SET created_at = datetime('now', '-2 hours')", []).unwrap();
+        assert_eq!(evaluate_alerts(&conn, &[], &current).unwrap().len(), 1);
+
+        let mut settings = db_settings::get_settings(&conn).unwrap();
+        settings.alert_cooldown_secs = 0;
+        db_settings::update_settings(&conn, &settings).unwrap();
+        assert_eq!(evaluate_alerts(&conn, &[], &current).unwrap().len(), 1);
+        assert_eq!(db_alerts::get_alerts(&conn, false).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_identity_changed_alert() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_test_device(&conn, "dev1", "AA:BB:CC:DD:EE:FF");
+        insert_test_device(&conn, "dev2", "AA:BB:CC:DD:EE:00");
+
+        let classified = |id: &str, mac: &str, os: Option<&str>, device_type: &str| Device {
+            os_guess: os.map(|o| o.to_string()),
+            device_type: device_type.to_string(),
+            ..make_device(id, mac, "192.168.1.42", true, true)
+        };
+        let previous = vec![
+            classified("dev1", "AA:BB:CC:DD:EE:FF", Some("Embedded Linux"), "printer"),
+            classified("dev2", "AA:BB:CC:DD:EE:00", Some("Linux"), "unknown"),
+        ];
+        let current = vec![
+            classified("dev1", "AA:BB:CC:DD:EE:FF", Some("Windows"), "computer"),
+            classified("dev2", "AA:BB:CC:DD:EE:00", Some("Linux"), "nas"),
+        ];
+
+        // Off by default
+        let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
+        assert!(!alerts.iter().any(|a| a.alert_type == "identity_changed"));
+
+        conn.execute("UPDATE alert_rules SET is_enabled = 1 WHERE id = 'rule_identity_changed'", []).unwrap();
+        let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
+        let changed: Vec<_> = alerts.iter().filter(|a| a.alert_type == "identity_changed").collect();
+        // dev2 was only classified for the first time
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].device_id.as_deref(), Some("dev1"));
+        assert!(changed[0].message.ends_with("changed identity: OS Embedded Linux → Windows, type printer → computer"));
+        assert_eq!(changed[0].severity, "warning");
+
+        let alerts = evaluate_alerts(&conn, &current, &current).unwrap();
+        assert!(!alerts.iter().any(|a| a.alert_type == "identity_changed"));
+    }
+
+    #[test]
+    fn test_packet_loss_alert() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_test_device(&conn, "dev1", "AA:BB:CC:DD:EE:FF");
+
+        let with_loss = |loss: Option<f64>| Device {
+            packet_loss: loss,
+            ..make_device("dev1", "AA:BB:CC:DD:EE:FF", "192.168.1.10", true, true)
+        };
+        let (healthy, lossy) = (vec![with_loss(Some(0.0))], vec![with_loss(Some(66.7))]);
+
+        // Off by default
+        let alerts = evaluate_alerts(&conn, &healthy, &lossy).unwrap();
+        assert!(!alerts.iter().any(|a| a.alert_type == "packet_loss"));
+
+        conn.execute("UPDATE alert_rules SET is_enabled = 1 WHERE id = 'rule_packet_loss'", []).unwrap();
+        let alerts = evaluate_alerts(&conn, &healthy, &lossy).unwrap();
+        let dropped: Vec<_> = alerts.iter().filter(|a| a.alert_type == "packet_loss").collect();
+        assert_eq!(dropped.len(), 1);
+        assert!(dropped[0].message.ends_with("is dropping 67% of pings"));
+
+        // Still lossy, or loss not measured: nothing new to report
+        for (previous, current) in [(&lossy, &lossy), (&healthy, &vec![with_loss(None)])] {
+            let alerts = evaluate_alerts(&conn, previous, current).unwrap();
+            assert!(!alerts.iter().any(|a| a.alert_type == "packet_loss"));
+        }
+    }
+
+    #[test]
+    fn test_port_changed_alert() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_test_device(&conn, "dev1", "AA:BB:CC:DD:EE:FF");
+        insert_test_device(&conn, "dev2", "AA:BB:CC:DD:EE:00");
+
+        let port = |port: u16, service: Option<&str>| PortInfo {
+            port,
+            protocol: "tcp".to_string(),
+            state: "open".to_string(),
+            service_name: service.map(|s| s.to_string()),
+            banner: None,
+        };
+        let with_ports = |id: &str, mac: &str, ports: Vec<PortInfo>| Device {
+            open_ports: ports,
+            ..make_device(id, mac, "192.168.1.42", true, true)
+        };
+        let previous = vec![
+            with_ports("dev1", "AA:BB:CC:DD:EE:FF", vec![port(22, Some("ssh"))]),
+            with_ports("dev2", "AA:BB:CC:DD:EE:00", Vec::new()),
+        ];
+        let current = vec![
+            with_ports("dev1", "AA:BB:CC:DD:EE:FF", vec![port(22, Some("ssh")), port(3389, Some("rdp"))]),
+            with_ports("dev2", "AA:BB:CC:DD:EE:00", vec![port(80, Some("http"))]),
+        ];
+
+        let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
+        let changed: Vec<_> = alerts.iter().filter(|a| a.alert_type == "port_changed").collect();
+        // dev2 had no baseline, so its first port scan is not a change
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].device_id.as_deref(), Some("dev1"));
+        assert_eq!(changed[0].message, "192.168.1.42 opened port 3389 (rdp)");
+        assert_eq!(changed[0].severity, "warning");
+
+        // Closing a port is not reported
+        let alerts = evaluate_alerts(&conn, &current, &previous).unwrap();
+        assert!(!alerts.iter().any(|a| a.alert_type == "port_changed"));
+
+        let more = vec![with_ports(
+            "dev1",
+            "AA:BB:CC:DD:EE:FF",
+            vec![port(22, Some("ssh")), port(23, None), port(445, Some("smb"))],
+        )];
+        let alerts = evaluate_alerts(&conn, &previous[..1], &more).unwrap();
+        let changed = alerts.iter().find(|a| a.alert_type == "port_changed").unwrap();
+        assert_eq!(changed.message, "192.168.1.42 opened ports 23, 445 (smb)");
+    }
+
+    fn discovered(ip: &str, mac: &str, interface: &str) -> DiscoveredDevice {
+        DiscoveredDevice {
+            ip: ip.to_string(),
+            mac: Some(mac.to_string()),
+            hostname: None,
+            is_gateway: false,
+            interface: Some(interface.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_arp_spoof_alert() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let list = vec![
+            discovered("192.168.1.1", "aa:bb:cc:00:00:01", "en0"),
+            discovered("192.168.1.1", "DE:AD:BE:EF:00:01", "en0"),
+            // Same MAC in another spelling is not a conflict
+            discovered("192.168.1.20", "aa:bb:cc:00:00:20", "en0"),
+            discovered("192.168.1.20", "AA-BB-CC-00-00-20", "en0"),
+            // Overlapping subnet behind another interface
+            discovered("192.168.1.30", "aa:bb:cc:00:00:30", "en0"),
+            discovered("192.168.1.30", "aa:bb:cc:00:00:31", "utun3"),
+        ];
+
+        let alerts = arp_spoof_alerts(&conn, &list).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, "arp_spoof");
+        assert_eq!(alerts[0].severity, "critical");
+        assert_eq!(
+            alerts[0].message,
+            "Possible ARP spoofing: 192.168.1.1 is claimed by AA:BB:CC:00:00:01, DE:AD:BE:EF:00:01"
+        );
+        assert_eq!(db_alerts::get_alerts_by_rule(&conn, "rule_arp_spoof").unwrap().len(), 1);
+
+        conn.execute("UPDATE alert_rules SET is_enabled = 0 WHERE id = 'rule_arp_spoof'", []).unwrap();
+        assert!(arp_spoof_alerts(&conn, &list).unwrap().is_empty());
     }
 
     #[test]
@@ -208,6 +780,68 @@ mod tests {
         assert!(alerts.iter().any(|a| a.alert_type == "device_departed"));
     }
 
+    #[test]
+    fn test_mass_departure_correlated() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let mut previous: Vec<Device> = (0..6)
+            .map(|i| {
+                let mac = format!("AA:BB:CC:DD:EE:0{}", i);
+                insert_test_device(&conn, &format!("dev{}", i), &mac);
+                make_device(&format!("dev{}", i), &mac, &format!("192.168.1.{}", i + 1), true, true)
+            })
+            .collect();
+        previous[0].is_gateway = true;
+
+        let alerts = evaluate_alerts(&conn, &previous, &[]).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, "network_event");
+        assert_eq!(alerts[0].device_id.as_deref(), Some("dev0"));
+        assert!(alerts[0].message.contains("Gateway outage suspected: 6 devices"));
+
+        let stored = db_alerts::get_alerts_by_rule(&conn, "rule_device_departed").unwrap();
+        assert_eq!(stored.len(), 1);
+    }
+
+    #[test]
+    fn test_few_departures_stay_individual() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        // 5 of 20 online devices leaving is below the share threshold
+        let previous: Vec<Device> = (0..20)
+            .map(|i| make_device(&format!("dev{}", i), &format!("AA:BB:CC:DD:EE:{:02}", i), "192.168.1.2", true, true))
+            .collect();
+        for d in &previous[..5] {
+            insert_test_device(&conn, &d.id, d.mac_address.as_deref().unwrap());
+        }
+        let current = previous[5..].to_vec();
+
+        let alerts = evaluate_alerts(&conn, &previous, &current).unwrap();
+        assert_eq!(alerts.len(), 5);
+        assert!(alerts.iter().all(|a| a.alert_type == "device_departed"));
+    }
+
+    #[test]
+    fn test_gateway_down_alert_follows_rule() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let alert = gateway_down_alert(&conn, "192.168.1.1", None).unwrap().unwrap();
+        assert_eq!(alert.severity, "critical");
+        assert!(alert.message.contains("192.168.1.1"));
+        assert_eq!(db_alerts::get_alerts_by_rule(&conn, "rule_gateway_down").unwrap().len(), 1);
+
+        db_alerts::update_alert_rule(
+            &conn,
+            "rule_gateway_down",
+            &db_alerts::AlertRuleUpdate { is_enabled: Some(false), severity: None, notify_desktop: None },
+        )
+        .unwrap();
+        assert!(gateway_down_alert(&conn, "192.168.1.1", None).unwrap().is_none());
+    }
+
     #[test]
     fn test_no_alert_for_returning_device() {
         let pool = db::init_test_db();
@@ -1,29 +1,220 @@
-use tauri::AppHandle;
+use std::sync::Arc;
+
+use chrono::{Local, NaiveTime};
+use tauri::{AppHandle, Manager};
 use tauri_plugin_notification::NotificationExt;
 
 use super::engine::GeneratedAlert;
+use crate::db::queries::settings as db_settings;
+use crate::state::AppState;
 
-/// Send desktop notifications for alerts that have notify_desktop enabled.
-pub fn notify(app: &AppHandle, alerts: &[GeneratedAlert]) {
-    for alert in alerts {
-        if !alert.notify_desktop {
-            continue;
+/// More notify-enabled alerts than this from one batch are shown as a single summary.
+pub const GROUP_THRESHOLD: usize = 5;
+
+/// Where desktop notifications are shown.
+pub trait DesktopNotifier {
+    fn show(&self, title: &str, body: &str);
+}
+
+impl DesktopNotifier for AppHandle {
+    fn show(&self, title: &str, body: &str) {
+        if let Err(e) = self.notification().builder().title(title).body(body).show() {
+            log::warn!("Failed to send notification: {}", e);
         }
+    }
+}
 
-        let title = match alert.severity.as_str() {
-            "critical" => "Echolocate - Critical Alert",
-            "warning" => "Echolocate - Warning",
-            _ => "Echolocate",
+/// Local time window in which desktop notifications are held back. A window whose
+/// end is before its start wraps past midnight, e.g. 22:00–07:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parse `HH:MM` start and end times.
+    pub fn parse(start: &str, end: &str) -> Result<Self, String> {
+        let time = |s: &str| {
+            NaiveTime::parse_from_str(s.trim(), "%H:%M")
+                .map_err(|_| format!("Invalid quiet hours time {:?} (expected HH:MM)", s))
         };
+        Ok(Self { start: time(start)?, end: time(end)? })
+    }
 
-        if let Err(e) = app
-            .notification()
-            .builder()
-            .title(title)
-            .body(&alert.message)
-            .show()
-        {
-            log::warn!("Failed to send notification: {}", e);
+    /// Whether `time` falls in the window. The start is inclusive and the end exclusive;
+    /// equal start and end make an empty window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Send desktop notifications for alerts that have notify_desktop enabled, unless the
+/// local time is inside the configured quiet hours. The alerts are already stored.
+/// A large batch, such as a first scan of a busy network, becomes one summary.
+pub fn notify(app: &AppHandle, alerts: &[GeneratedAlert]) {
+    let quiet_hours = app
+        .state::<Arc<AppState>>()
+        .conn()
+        .ok()
+        .and_then(|conn| db_settings::get_settings(&conn).ok())
+        .and_then(|settings| settings.quiet_hours().ok().flatten());
+    notify_at(app, alerts, quiet_hours, Local::now().time());
+}
+
+/// `notify` with the quiet hours and current local time supplied by the caller.
+fn notify_at(
+    notifier: &impl DesktopNotifier,
+    alerts: &[GeneratedAlert],
+    quiet_hours: Option<QuietHours>,
+    now: NaiveTime,
+) {
+    let shown: Vec<&GeneratedAlert> = alerts.iter().filter(|a| a.notify_desktop).collect();
+    if shown.is_empty() {
+        return;
+    }
+    if quiet_hours.is_some_and(|quiet| quiet.contains(now)) {
+        log::debug!("Quiet hours: not showing {} notification(s)", shown.len());
+        return;
+    }
+
+    if shown.len() > GROUP_THRESHOLD {
+        let worst = shown.iter().map(|a| a.severity.as_str()).max_by_key(|s| severity_rank(s));
+        notifier.show(title(worst.unwrap_or_default()), &summary(&shown));
+        return;
+    }
+    for alert in shown {
+        notifier.show(title(&alert.severity), &alert.message);
+    }
+}
+
+fn title(severity: &str) -> &'static str {
+    match severity {
+        "critical" => "Echolocate - Critical Alert",
+        "warning" => "Echolocate - Warning",
+        _ => "Echolocate",
+    }
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "critical" => 2,
+        "warning" => 1,
+        _ => 0,
+    }
+}
+
+/// One line for a batch of alerts, e.g. "7 new devices discovered".
+fn summary(alerts: &[&GeneratedAlert]) -> String {
+    let count = alerts.len();
+    let alert_type = alerts[0].alert_type.as_str();
+    if alerts.iter().any(|a| a.alert_type != alert_type) {
+        return format!("{} new alerts", count);
+    }
+    match alert_type {
+        "new_device" => format!("{} new devices discovered", count),
+        "device_departed" => format!("{} devices left the network", count),
+        "unknown_device" => format!("{} untrusted devices on the network", count),
+        "port_changed" => format!("{} devices changed their open ports", count),
+        _ => format!("{} new alerts", count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct Recorder(RefCell<Vec<(String, String)>>);
+
+    impl DesktopNotifier for Recorder {
+        fn show(&self, title: &str, body: &str) {
+            self.0.borrow_mut().push((title.to_string(), body.to_string()));
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn alert(alert_type: &str, severity: &str, message: &str) -> GeneratedAlert {
+        GeneratedAlert {
+            alert_type: alert_type.to_string(),
+            device_id: None,
+            message: message.to_string(),
+            severity: severity.to_string(),
+            notify_desktop: true,
+            rule_id: None,
         }
     }
+
+    #[test]
+    fn test_large_batch_shown_as_one_summary() {
+        let mut alerts: Vec<_> = (0..10).map(|i| alert("new_device", "info", &format!("New device {}", i))).collect();
+        alerts[3].severity = "warning".to_string();
+        alerts.push(GeneratedAlert { notify_desktop: false, ..alert("new_device", "critical", "Silent") });
+
+        let recorder = Recorder::default();
+        notify_at(&recorder, &alerts, None, at(12, 0));
+        let shown = recorder.0.into_inner();
+        assert_eq!(shown, [("Echolocate - Warning".to_string(), "10 new devices discovered".to_string())]);
+
+        let mixed: Vec<_> = (0..3)
+            .flat_map(|_| [alert("new_device", "info", "a"), alert("port_changed", "info", "b")])
+            .collect();
+        let recorder = Recorder::default();
+        notify_at(&recorder, &mixed, None, at(12, 0));
+        assert_eq!(recorder.0.into_inner()[0].1, "6 new alerts");
+    }
+
+    #[test]
+    fn test_small_batch_shown_individually() {
+        let alerts: Vec<_> = (0..GROUP_THRESHOLD).map(|i| alert("new_device", "info", &format!("New device {}", i))).collect();
+        let recorder = Recorder::default();
+        notify_at(&recorder, &alerts, None, at(12, 0));
+        let shown = recorder.0.into_inner();
+        assert_eq!(shown.len(), GROUP_THRESHOLD);
+        assert_eq!(shown[0], ("Echolocate".to_string(), "New device 0".to_string()));
+
+        // Nothing at all during quiet hours
+        let recorder = Recorder::default();
+        notify_at(&recorder, &alerts, QuietHours::parse("22:00", "07:00").ok(), at(3, 0));
+        assert!(recorder.0.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_quiet_hours_same_day() {
+        let quiet = QuietHours::parse("13:00", "14:30").unwrap();
+        assert!(quiet.contains(at(13, 0)));
+        assert!(quiet.contains(at(14, 29)));
+        assert!(!quiet.contains(at(14, 30)));
+        assert!(!quiet.contains(at(3, 0)));
+    }
+
+    #[test]
+    fn test_quiet_hours_wrap_past_midnight() {
+        let quiet = QuietHours::parse("22:00", "07:00").unwrap();
+        assert!(quiet.contains(at(23, 59)));
+        assert!(quiet.contains(at(0, 0)));
+        assert!(quiet.contains(at(3, 0)));
+        assert!(!quiet.contains(at(7, 0)));
+        assert!(!quiet.contains(at(12, 0)));
+        assert!(!quiet.contains(at(21, 59)));
+
+        let empty = QuietHours::parse("08:00", "08:00").unwrap();
+        assert!(!empty.contains(at(8, 0)));
+    }
+
+    #[test]
+    fn test_quiet_hours_parse_errors() {
+        assert!(QuietHours::parse("7:00", " 22:15 ").is_ok());
+        assert!(QuietHours::parse("25:00", "07:00").is_err());
+        assert!(QuietHours::parse("22:00", "seven").is_err());
+    }
 }
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use tauri::State;
 
 use crate::db::queries::alerts as db_alerts;
@@ -5,7 +7,7 @@ use crate::state::AppState;
 
 #[tauri::command]
 pub fn get_alerts(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     unread_only: bool,
 ) -> Result<Vec<db_alerts::Alert>, String> {
     let conn = state.conn().map_err(|e| e.to_string())?;
@@ -13,26 +15,35 @@ pub fn get_alerts(
 }
 
 #[tauri::command]
-pub fn mark_alert_read(state: State<'_, AppState>, alert_id: String) -> Result<(), String> {
+pub fn get_alerts_by_rule(
+    state: State<'_, Arc<AppState>>,
+    rule_id: String,
+) -> Result<Vec<db_alerts::Alert>, String> {
+    let conn = state.conn().map_err(|e| e.to_string())?;
+    db_alerts::get_alerts_by_rule(&conn, &rule_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn mark_alert_read(state: State<'_, Arc<AppState>>, alert_id: String) -> Result<(), String> {
     let conn = state.conn().map_err(|e| e.to_string())?;
     db_alerts::mark_alert_read(&conn, &alert_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn mark_all_alerts_read(state: State<'_, AppState>) -> Result<(), String> {
+pub fn mark_all_alerts_read(state: State<'_, Arc<AppState>>) -> Result<(), String> {
     let conn = state.conn().map_err(|e| e.to_string())?;
     db_alerts::mark_all_alerts_read(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_alert_rules(state: State<'_, AppState>) -> Result<Vec<db_alerts::AlertRule>, String> {
+pub fn get_alert_rules(state: State<'_, Arc<AppState>>) -> Result<Vec<db_alerts::AlertRule>, String> {
     let conn = state.conn().map_err(|e| e.to_string())?;
     db_alerts::get_alert_rules(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn update_alert_rule(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     rule_id: String,
     updates: db_alerts::AlertRuleUpdate,
 ) -> Result<db_alerts::AlertRule, String> {
@@ -0,0 +1,379 @@
+use std::sync::Arc;
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::State;
+
+use crate::commands::export::ImportStrategy;
+use crate::commands::validate::Validator;
+use crate::db::queries::devices::{self as db_devices, DeviceUpdate};
+use crate::network::oui::OuiDatabase;
+use crate::state::AppState;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvImportResult {
+    pub imported: u32,
+    pub updated: u32,
+    pub skipped: u32,
+    pub errors: Vec<RowError>,
+}
+
+/// A row that could not be imported, by 1-based line number.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowError {
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Ip,
+    Mac,
+    Name,
+    Ignored,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct CsvRow {
+    ip: Option<String>,
+    mac: Option<String>,
+    name: Option<String>,
+}
+
+/// Seed the inventory from a simple `ip,mac,name` CSV (or tab-separated `arp-scan` output).
+/// Known devices are handled per `strategy`, as in a JSON import.
+#[tauri::command]
+pub fn import_devices_csv(
+    state: State<'_, Arc<AppState>>,
+    csv: String,
+    strategy: ImportStrategy,
+) -> Result<CsvImportResult, String> {
+    let conn = state.conn().map_err(|e| e.to_string())?;
+    import_csv(&conn, &state.oui_db, &csv, strategy)
+}
+
+/// Import every valid row, collecting per-row errors instead of failing the whole file.
+pub fn import_csv(
+    conn: &Connection,
+    oui: &OuiDatabase,
+    csv: &str,
+    strategy: ImportStrategy,
+) -> Result<CsvImportResult, String> {
+    let mut result = CsvImportResult::default();
+    let mut columns: Option<Vec<Column>> = None;
+    let mut first = true;
+
+    for (line_no, fields) in read_records(csv) {
+        let fields = match fields {
+            Ok(fields) => fields,
+            Err(reason) => {
+                result.errors.push(RowError { line: line_no, reason });
+                continue;
+            }
+        };
+        if first {
+            first = false;
+            if let Some(header) = parse_header(&fields) {
+                columns = Some(header);
+                continue;
+            }
+        }
+
+        let row = match parse_row(&fields, columns.as_deref()) {
+            Ok(row) => row,
+            Err(reason) => {
+                result.errors.push(RowError { line: line_no, reason });
+                continue;
+            }
+        };
+
+        match apply_row(conn, oui, &row, strategy) {
+            Ok(RowOutcome::Imported) => result.imported += 1,
+            Ok(RowOutcome::Updated) => result.updated += 1,
+            Ok(RowOutcome::Skipped) => result.skipped += 1,
+            Err(e) => result.errors.push(RowError { line: line_no, reason: e.to_string() }),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Delimiter of the first data line: commas, else tabs, else semicolons.
+fn detect_delimiter(csv: &str) -> u8 {
+    let line = csv
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .unwrap_or_default();
+    if line.contains(',') {
+        b','
+    } else if line.contains('\t') {
+        b'\t'
+    } else if line.contains(';') {
+        b';'
+    } else {
+        b','
+    }
+}
+
+/// Parse the file into trimmed fields per record, keyed by the 1-based line each starts on.
+/// Quoted fields may span lines; blank records and `#` comment lines are dropped.
+fn read_records(csv: &str) -> Vec<(usize, Result<Vec<String>, String>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(detect_delimiter(csv))
+        .comment(Some(b'#'))
+        .trim(csv::Trim::All)
+        .from_reader(csv.as_bytes());
+
+    reader
+        .records()
+        .filter_map(|record| match record {
+            Ok(record) if record.iter().all(str::is_empty) => None,
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line() as usize);
+                Some((line, Ok(record.iter().map(str::to_string).collect())))
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line() as usize);
+                Some((line, Err(e.to_string())))
+            }
+        })
+        .collect()
+}
+
+/// Recognise a header row by its column names. Returns `None` if the row looks like data.
+fn parse_header(fields: &[String]) -> Option<Vec<Column>> {
+    let columns: Vec<Column> = fields
+        .iter()
+        .map(|f| match f.to_ascii_lowercase().replace([' ', '-'], "_").as_str() {
+            "ip" | "ip_address" | "ipaddress" | "address" | "ipv4" | "ipv6" => Column::Ip,
+            "mac" | "mac_address" | "macaddress" | "hwaddr" | "hw_address" | "ethernet" => Column::Mac,
+            "name" | "custom_name" | "device_name" | "hostname" | "host" | "label" => Column::Name,
+            _ => Column::Ignored,
+        })
+        .collect();
+
+    let known = columns.iter().any(|c| *c == Column::Ip || *c == Column::Mac);
+    known.then_some(columns)
+}
+
+fn is_ip(field: &str) -> bool {
+    Validator::validate_ipv4(field).is_ok() || Validator::validate_ipv6(field).is_ok()
+}
+
+fn is_mac(field: &str) -> bool {
+    Validator::validate_mac_address(field).is_ok()
+}
+
+/// Build a row from named columns, or without a header by recognising IPs and MACs
+/// and taking the first other field as the name.
+fn parse_row(fields: &[String], columns: Option<&[Column]>) -> Result<CsvRow, String> {
+    let mut row = CsvRow::default();
+    let value = |f: &String| Some(f.clone()).filter(|f| !f.is_empty());
+
+    match columns {
+        Some(columns) => {
+            for (column, field) in columns.iter().zip(fields) {
+                let slot = match column {
+                    Column::Ip => &mut row.ip,
+                    Column::Mac => &mut row.mac,
+                    Column::Name => &mut row.name,
+                    Column::Ignored => continue,
+                };
+                if slot.is_none() {
+                    *slot = value(field);
+                }
+            }
+        }
+        None => {
+            for field in fields.iter().filter(|f| !f.is_empty()) {
+                if row.ip.is_none() && is_ip(field) {
+                    row.ip = value(field);
+                } else if row.mac.is_none() && is_mac(field) {
+                    row.mac = value(field);
+                } else if row.name.is_none() {
+                    row.name = value(field);
+                }
+            }
+        }
+    }
+
+    if let Some(ref ip) = row.ip {
+        if !is_ip(ip) {
+            return Err(format!("Invalid IP address: {}", ip));
+        }
+    }
+    if let Some(ref mac) = row.mac {
+        Validator::validate_mac_address(mac)?;
+        row.mac = Some(db_devices::normalize_mac(mac));
+    }
+    if let Some(ref name) = row.name {
+        Validator::validate_device_name(name)?;
+    }
+    if row.ip.is_none() && row.mac.is_none() {
+        return Err("Row has neither an IP nor a MAC address".to_string());
+    }
+    Ok(row)
+}
+
+enum RowOutcome {
+    Imported,
+    Updated,
+    Skipped,
+}
+
+/// Insert the row's device, or apply `strategy` when its MAC (or, without a MAC, its IP)
+/// is known: overwrite refreshes the IP and name, merge only fills them in if missing.
+fn apply_row(
+    conn: &Connection,
+    oui: &OuiDatabase,
+    row: &CsvRow,
+    strategy: ImportStrategy,
+) -> Result<RowOutcome, rusqlite::Error> {
+    let existing = match (row.mac.as_deref(), row.ip.as_deref()) {
+        (Some(mac), _) => db_devices::get_device_by_mac(conn, mac)?,
+        (None, Some(ip)) => db_devices::get_device_by_ip(conn, ip)?,
+        (None, None) => None,
+    };
+    let rename = |name: Option<&String>| DeviceUpdate {
+        custom_name: name.cloned().map(Some),
+        ..Default::default()
+    };
+
+    match existing {
+        Some(_) if strategy == ImportStrategy::Skip => Ok(RowOutcome::Skipped),
+        Some(id) => {
+            let overwrite = strategy == ImportStrategy::Overwrite;
+            let Some(current) = db_devices::get_device_by_id(conn, &id)? else {
+                return Ok(RowOutcome::Skipped);
+            };
+            let ip = row.ip.as_ref().filter(|_| overwrite || current.current_ip.is_none());
+            let name = row.name.as_ref().filter(|_| overwrite || current.custom_name.is_none());
+            if ip.is_none() && name.is_none() {
+                return Ok(RowOutcome::Skipped);
+            }
+
+            if let Some(ip) = ip {
+                db_devices::upsert_device_ip(conn, &id, ip)?;
+            }
+            db_devices::update_device(conn, &id, &rename(name))?;
+            Ok(RowOutcome::Updated)
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            let vendor = row.mac.as_deref().and_then(|m| oui.lookup(m));
+            db_devices::insert_device(
+                conn,
+                &id,
+                row.mac.as_deref(),
+                vendor,
+                None,
+                "unknown",
+                false,
+                row.ip.as_deref(),
+            )?;
+            db_devices::update_device(conn, &id, &rename(row.name.as_ref()))?;
+            Ok(RowOutcome::Imported)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_header_detected_in_any_order() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        let csv = "Name,MAC Address,IP\n\"Living room, TV\",AA-BB-CC-00-11-22,192.168.1.50\n";
+
+        let result = import_csv(&conn, &OuiDatabase::empty(), csv, ImportStrategy::Skip).unwrap();
+        assert_eq!(result.imported, 1);
+        assert!(result.errors.is_empty());
+
+        let devices = db_devices::get_all_devices(&conn).unwrap();
+        assert_eq!(devices[0].mac_address.as_deref(), Some("AA:BB:CC:00:11:22"));
+        assert_eq!(devices[0].current_ip.as_deref(), Some("192.168.1.50"));
+        assert_eq!(devices[0].custom_name.as_deref(), Some("Living room, TV"));
+    }
+
+    #[test]
+    fn test_headerless_arp_scan_output() {
+        let records = read_records("192.168.1.1\t00:11:22:33:44:55\tNETGEAR\n");
+        let fields = records[0].1.as_ref().unwrap();
+        let row = parse_row(fields, None).unwrap();
+        assert_eq!(row.ip.as_deref(), Some("192.168.1.1"));
+        assert_eq!(row.mac.as_deref(), Some("00:11:22:33:44:55"));
+        assert_eq!(row.name.as_deref(), Some("NETGEAR"));
+
+        assert!(parse_header(fields).is_none());
+    }
+
+    #[test]
+    fn test_quoted_field_spans_lines() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        let csv = "ip,name,notes\n10.0.0.5,printer,\"second\nfloor\"\n10.0.0.6,scanner,\n10.0.0.300,bad,\n";
+
+        let result = import_csv(&conn, &OuiDatabase::empty(), csv, ImportStrategy::Skip).unwrap();
+        assert_eq!(result.imported, 2);
+        // Line numbers still count the embedded newline
+        let lines: Vec<_> = result.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![5]);
+    }
+
+    #[test]
+    fn test_row_errors_reported_by_line() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        let csv = "ip,mac,name\n192.168.1.300,,bad\n\n10.0.0.5,,printer\n,,nothing\n";
+
+        let result = import_csv(&conn, &OuiDatabase::empty(), csv, ImportStrategy::Skip).unwrap();
+        assert_eq!(result.imported, 1);
+        let lines: Vec<_> = result.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 5]);
+    }
+
+    #[test]
+    fn test_mode_controls_existing_devices() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "known", Some("aa:bb:cc:00:11:22"), None, None, "unknown", false, Some("10.0.0.2")).unwrap();
+        let csv = "aa:bb:cc:00:11:22,10.0.0.9,Desk\n";
+
+        let skip = import_csv(&conn, &OuiDatabase::empty(), csv, ImportStrategy::Skip).unwrap();
+        assert_eq!(skip.skipped, 1);
+        assert_eq!(db_devices::get_device_by_id(&conn, "known").unwrap().unwrap().current_ip.as_deref(), Some("10.0.0.2"));
+
+        let update = import_csv(&conn, &OuiDatabase::empty(), csv, ImportStrategy::Overwrite).unwrap();
+        assert_eq!(update.updated, 1);
+        let device = db_devices::get_device_by_id(&conn, "known").unwrap().unwrap();
+        assert_eq!(device.current_ip.as_deref(), Some("10.0.0.9"));
+        assert_eq!(device.custom_name.as_deref(), Some("Desk"));
+        assert_eq!(db_devices::count_devices(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_merge_fills_only_missing_details() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "known", Some("aa:bb:cc:00:11:22"), None, None, "unknown", false, Some("10.0.0.2")).unwrap();
+        let csv = "aa:bb:cc:00:11:22,10.0.0.9,Desk\n";
+
+        let merge = import_csv(&conn, &OuiDatabase::empty(), csv, ImportStrategy::Merge).unwrap();
+        assert_eq!(merge.updated, 1);
+        let device = db_devices::get_device_by_id(&conn, "known").unwrap().unwrap();
+        assert_eq!(device.current_ip.as_deref(), Some("10.0.0.2"));
+        assert_eq!(device.custom_name.as_deref(), Some("Desk"));
+
+        // Nothing left to fill in
+        let again = import_csv(&conn, &OuiDatabase::empty(), csv, ImportStrategy::Merge).unwrap();
+        assert_eq!(again.skipped, 1);
+    }
+}
@@ -1,40 +1,346 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rusqlite::Connection;
 use tauri::State;
 
-use crate::db::queries::devices as db_devices;
+use crate::commands::validate::Validator;
+use crate::db::queries::{devices as db_devices, settings as db_settings};
+use crate::network::wol;
+use crate::scanner::probe::{self, Reachability};
+use crate::security::risk;
 use crate::state::AppState;
+use crate::{AppError, TauriResult};
+
+/// Look up a saved device, or a `NOT_FOUND` error naming the id.
+fn load_device(conn: &Connection, device_id: &str) -> TauriResult<db_devices::Device> {
+    db_devices::get_device_by_id(conn, device_id)?.ok_or_else(|| AppError::not_found("Device", device_id))
+}
+
+#[tauri::command]
+pub fn get_devices(state: State<'_, Arc<AppState>>) -> TauriResult<Vec<db_devices::Device>> {
+    let conn = state.conn()?;
+    Ok(db_devices::get_all_devices(&conn)?)
+}
 
+/// One filtered page of devices, for lists too large to load whole.
 #[tauri::command]
-pub fn get_devices(state: State<'_, AppState>) -> Result<Vec<db_devices::Device>, String> {
-    let conn = state.conn().map_err(|e| e.to_string())?;
-    db_devices::get_all_devices(&conn).map_err(|e| e.to_string())
+pub fn get_devices_paged(
+    state: State<'_, Arc<AppState>>,
+    query: db_devices::DeviceQuery,
+) -> TauriResult<db_devices::DevicePage> {
+    let conn = state.conn()?;
+    Ok(db_devices::get_devices_paged(&conn, &query)?)
+}
+
+/// Untrusted devices that still lack a name, vendor or type, least identified first.
+#[tauri::command]
+pub fn get_devices_needing_review(state: State<'_, Arc<AppState>>) -> TauriResult<Vec<db_devices::Device>> {
+    let conn = state.conn()?;
+    Ok(db_devices::get_devices_needing_review(&conn)?)
 }
 
 #[tauri::command]
 pub fn get_device(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     device_id: String,
-) -> Result<db_devices::Device, String> {
-    let conn = state.conn().map_err(|e| e.to_string())?;
-    db_devices::get_device_by_id(&conn, &device_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Device not found: {}", device_id))
+) -> TauriResult<db_devices::Device> {
+    let conn = state.conn()?;
+    load_device(&conn, &device_id)
 }
 
 #[tauri::command]
 pub fn update_device(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     device_id: String,
     updates: db_devices::DeviceUpdate,
-) -> Result<db_devices::Device, String> {
-    let conn = state.conn().map_err(|e| e.to_string())?;
-    db_devices::update_device(&conn, &device_id, &updates).map_err(|e| e.to_string())?;
-    db_devices::get_device_by_id(&conn, &device_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Device not found: {}", device_id))
+) -> TauriResult<db_devices::Device> {
+    let conn = state.conn()?;
+    apply_update(&conn, &device_id, updates)
+}
+
+/// Apply the same update to several devices in one transaction, e.g. to trust a batch of
+/// newly discovered devices. An unknown id rolls the whole batch back. Returns how many
+/// devices were updated.
+#[tauri::command]
+pub fn bulk_update_devices(
+    state: State<'_, Arc<AppState>>,
+    device_ids: Vec<String>,
+    updates: db_devices::DeviceUpdate,
+) -> TauriResult<u32> {
+    let conn = state.conn()?;
+    apply_bulk_update(&conn, &device_ids, updates)
+}
+
+fn apply_bulk_update(
+    conn: &Connection,
+    device_ids: &[String],
+    updates: db_devices::DeviceUpdate,
+) -> TauriResult<u32> {
+    let updates = validate_update(updates)?;
+    let mut unique: Vec<&String> = device_ids.iter().collect();
+    unique.sort();
+    unique.dedup();
+
+    let tx = conn.unchecked_transaction()?;
+    for device_id in &unique {
+        db_devices::update_device(&tx, device_id, &updates)?;
+        risk::refresh_risk_score(&tx, &load_device(&tx, device_id)?)?;
+    }
+    tx.commit()?;
+    Ok(unique.len() as u32)
+}
+
+/// Normalize user-entered icon and notes, rejecting invalid ones.
+fn validate_update(mut updates: db_devices::DeviceUpdate) -> TauriResult<db_devices::DeviceUpdate> {
+    if let Some(Some(ref icon)) = updates.icon {
+        let icon = Validator::validate_icon(icon).map_err(|e| AppError::validation("icon", &e))?;
+        updates.icon = Some(Some(icon));
+    }
+    if let Some(Some(ref notes)) = updates.notes {
+        let notes = Validator::validate_notes(notes).map_err(|e| AppError::validation("notes", &e))?;
+        updates.notes = Some(Some(notes));
+    }
+    Ok(updates)
+}
+
+fn apply_update(
+    conn: &Connection,
+    device_id: &str,
+    updates: db_devices::DeviceUpdate,
+) -> TauriResult<db_devices::Device> {
+    let updates = validate_update(updates)?;
+    db_devices::update_device(conn, device_id, &updates)?;
+    let mut device = load_device(conn, device_id)?;
+
+    // Trust affects the score, so keep it current without waiting for the next scan
+    device.risk_score = risk::refresh_risk_score(conn, &device)?;
+    Ok(device)
+}
+
+/// Devices whose notes mention `query`, pinned notes first, each with a snippet of the match.
+#[tauri::command]
+pub fn search_notes(state: State<'_, Arc<AppState>>, query: String) -> TauriResult<Vec<db_devices::NoteMatch>> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let conn = state.conn()?;
+    Ok(db_devices::search_notes(&conn, query)?)
+}
+
+/// Every IP the device has held, most recently seen first.
+#[tauri::command]
+pub fn get_ip_history(state: State<'_, Arc<AppState>>, device_id: String) -> TauriResult<Vec<db_devices::IpHistoryEntry>> {
+    let conn = state.conn()?;
+    Ok(db_devices::get_ip_history(&conn, &device_id)?)
+}
+
+/// Every OS guess and device type fingerprinting has recorded for the device, newest first.
+#[tauri::command]
+pub fn get_fingerprint_history(
+    state: State<'_, Arc<AppState>>,
+    device_id: String,
+) -> TauriResult<Vec<db_devices::FingerprintEntry>> {
+    let conn = state.conn()?;
+    Ok(db_devices::get_fingerprint_history(&conn, &device_id)?)
+}
+
+/// Attach a tag to a device and return the updated device.
+#[tauri::command]
+pub fn add_device_tag(
+    state: State<'_, Arc<AppState>>,
+    device_id: String,
+    tag: String,
+) -> TauriResult<db_devices::Device> {
+    let tag = Validator::validate_tag(&tag).map_err(|e| AppError::validation("tag", &e))?;
+    let conn = state.conn()?;
+    load_device(&conn, &device_id)?;
+    db_devices::add_device_tag(&conn, &device_id, &tag)?;
+    load_device(&conn, &device_id)
+}
+
+/// Detach a tag from a device and return the updated device.
+#[tauri::command]
+pub fn remove_device_tag(
+    state: State<'_, Arc<AppState>>,
+    device_id: String,
+    tag: String,
+) -> TauriResult<db_devices::Device> {
+    let conn = state.conn()?;
+    db_devices::remove_device_tag(&conn, &device_id, &tag.trim().to_lowercase())?;
+    load_device(&conn, &device_id)
+}
+
+/// Set a property such as `room=office` on a device, or remove it when `value` is
+/// null, and return the updated device.
+#[tauri::command]
+pub fn set_device_property(
+    state: State<'_, Arc<AppState>>,
+    device_id: String,
+    key: String,
+    value: Option<String>,
+) -> TauriResult<db_devices::Device> {
+    let key = Validator::validate_property_key(&key).map_err(|e| AppError::validation("key", &e))?;
+    let value = value
+        .map(|v| Validator::validate_property_value(&v))
+        .transpose()
+        .map_err(|e| AppError::validation("value", &e))?;
+    let conn = state.conn()?;
+    load_device(&conn, &device_id)?;
+    db_devices::set_device_property(&conn, &device_id, &key, value.as_deref())?;
+    load_device(&conn, &device_id)
+}
+
+#[tauri::command]
+pub fn get_device_properties(
+    state: State<'_, Arc<AppState>>,
+    device_id: String,
+) -> TauriResult<HashMap<String, String>> {
+    let conn = state.conn()?;
+    Ok(db_devices::get_device_properties(&conn, &device_id)?)
 }
 
+/// Every tag in use, for the tag filter.
 #[tauri::command]
-pub fn delete_device(state: State<'_, AppState>, device_id: String) -> Result<(), String> {
-    let conn = state.conn().map_err(|e| e.to_string())?;
-    db_devices::delete_device(&conn, &device_id).map_err(|e| e.to_string())
+pub fn get_tags(state: State<'_, Arc<AppState>>) -> TauriResult<Vec<String>> {
+    let conn = state.conn()?;
+    Ok(db_devices::get_tags(&conn)?)
+}
+
+#[tauri::command]
+pub fn delete_device(state: State<'_, Arc<AppState>>, device_id: String) -> TauriResult<()> {
+    let conn = state.conn()?;
+    Ok(db_devices::delete_device(&conn, &device_id)?)
+}
+
+/// Probe a device right now over ICMP, TCP and ARP. Refreshes `last_seen` when it answers.
+#[tauri::command]
+pub async fn check_device_now(
+    state: State<'_, Arc<AppState>>,
+    device_id: String,
+) -> TauriResult<Reachability> {
+    let (device, settings) = {
+        let conn = state.conn()?;
+        let device = load_device(&conn, &device_id)?;
+        let settings = db_settings::get_settings(&conn)?;
+        (device, settings)
+    };
+
+    let ip = device
+        .current_ip
+        .ok_or_else(|| AppError::network(&format!("Device has no known IP: {}", device_id)))?;
+
+    let reach = probe::probe_device(
+        &ip,
+        device.mac_address.as_deref(),
+        settings.ping_timeout_ms,
+        &settings.scan_tools(),
+    )
+    .await;
+
+    if reach.reachable {
+        let conn = state.conn()?;
+        db_devices::touch_device(&conn, &device_id)?;
+        if reach.methods.contains(&probe::ProbeMethod::Icmp) {
+            db_devices::mark_ping_confirmed(&conn, &device_id)?;
+        }
+        if let Some(latency) = reach.latency_ms {
+            db_devices::record_latency(&conn, &device_id, latency, &settings.latency_coalescing())?;
+        }
+    }
+
+    Ok(reach)
+}
+
+/// Send a Wake-on-LAN magic packet to a saved device's MAC address.
+#[tauri::command]
+pub fn wake_device(state: State<'_, Arc<AppState>>, device_id: String) -> TauriResult<()> {
+    let conn = state.conn()?;
+    let bytes = wake_target(&conn, &device_id)?;
+    wol::send_magic_packet(&bytes).map_err(|e| AppError::network(&e))
+}
+
+/// The MAC address to wake a saved device at.
+fn wake_target(conn: &Connection, device_id: &str) -> TauriResult<[u8; 6]> {
+    let device = load_device(conn, device_id)?;
+    let mac = device.mac_address.ok_or_else(|| {
+        AppError::validation("mac_address", &format!("Device has no known MAC address: {}", device_id))
+    })?;
+    let mac = Validator::validate_mac_address(&mac).map_err(|e| AppError::validation("mac_address", &e))?;
+    let bytes = wol::parse_mac(&mac)
+        .ok_or_else(|| AppError::validation("mac_address", &format!("Invalid MAC address: {}", mac)))?;
+
+    log::info!("Sending Wake-on-LAN packet to {}", mac);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_missing_device_is_not_found() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let err = load_device(&conn, "missing").unwrap_err();
+        assert_eq!(err.code, "NOT_FOUND");
+        assert_eq!(err.message, "Device not found: missing");
+
+        let err = apply_update(&conn, "missing", Default::default()).unwrap_err();
+        assert_eq!(err.code, "NOT_FOUND");
+        assert_eq!(wake_target(&conn, "missing").unwrap_err().code, "NOT_FOUND");
+    }
+
+    #[test]
+    fn test_invalid_update_is_rejected_before_lookup() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let updates = db_devices::DeviceUpdate {
+            notes: Some(Some("x".repeat(2000))),
+            ..Default::default()
+        };
+        assert_eq!(apply_update(&conn, "missing", updates).unwrap_err().code, "INVALID_INPUT");
+    }
+
+    #[test]
+    fn test_bulk_update_trusts_every_device() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        for id in ["dev1", "dev2", "dev3", "dev4"] {
+            db_devices::insert_device(&conn, id, None, None, None, "unknown", false, None).unwrap();
+        }
+
+        let ids: Vec<String> = ["dev1", "dev2", "dev3", "dev2"].iter().map(|id| id.to_string()).collect();
+        let updates = db_devices::DeviceUpdate {
+            is_trusted: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(apply_bulk_update(&conn, &ids, updates).unwrap(), 3);
+
+        for id in ["dev1", "dev2", "dev3"] {
+            let device = load_device(&conn, id).unwrap();
+            assert!(device.is_trusted);
+            assert_eq!(device.risk_score, risk::refresh_risk_score(&conn, &device).unwrap());
+        }
+        assert!(!load_device(&conn, "dev4").unwrap().is_trusted);
+    }
+
+    #[test]
+    fn test_bulk_update_rolls_back_on_unknown_device() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        let ids = vec!["dev1".to_string(), "missing".to_string()];
+        let updates = db_devices::DeviceUpdate {
+            is_trusted: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(apply_bulk_update(&conn, &ids, updates).unwrap_err().code, "NOT_FOUND");
+        assert!(!load_device(&conn, "dev1").unwrap().is_trusted);
+    }
 }
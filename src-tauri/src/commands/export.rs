@@ -1,8 +1,18 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use rusqlite::Connection;
 use tauri::State;
 use serde::{Deserialize, Serialize};
 
-use crate::db::queries::{devices as db_devices, alerts as db_alerts};
+use crate::commands::validate::Validator;
+use crate::db::queries::{devices as db_devices, alerts as db_alerts, ports as db_ports, scans as db_scans, settings as db_settings};
+use crate::scanner::ipv6;
 use crate::state::AppState;
+use crate::util::time;
+
+/// Newest export format this build can read.
+pub const EXPORT_VERSION: u32 = 1;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -14,15 +24,15 @@ pub struct ExportData {
 }
 
 #[tauri::command]
-pub fn export_devices(state: State<'_, AppState>) -> Result<String, String> {
+pub fn export_devices(state: State<'_, Arc<AppState>>) -> Result<String, String> {
     let conn = state.conn().map_err(|e| e.to_string())?;
 
     let devices = db_devices::get_all_devices(&conn).map_err(|e| e.to_string())?;
     let alerts = db_alerts::get_alerts(&conn, false).map_err(|e| e.to_string())?;
 
     let export = ExportData {
-        version: 1,
-        exported_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        version: EXPORT_VERSION,
+        exported_at: time::now_db_string(),
         devices,
         alerts,
     };
@@ -30,50 +40,807 @@ pub fn export_devices(state: State<'_, AppState>) -> Result<String, String> {
     serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
 }
 
+/// Column headers of the CSV export, in order.
+pub const CSV_HEADERS: [&str; 10] = [
+    "name", "mac", "ip", "vendor", "device_type", "os_guess", "is_trusted", "open_ports", "first_seen", "last_seen",
+];
+
+/// Export the device inventory as CSV for spreadsheets and SIEM tools.
+#[tauri::command]
+pub fn export_devices_csv(state: State<'_, Arc<AppState>>) -> Result<String, String> {
+    let conn = state.conn().map_err(|e| e.to_string())?;
+    let devices = db_devices::get_all_devices(&conn).map_err(|e| e.to_string())?;
+    devices_to_csv(&devices)
+}
+
+/// One row per device under `CSV_HEADERS`. The name is the custom name, else the
+/// hostname; open ports are joined with semicolons.
+pub fn devices_to_csv(devices: &[db_devices::Device]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADERS).map_err(|e| e.to_string())?;
+
+    for device in devices {
+        let open_ports = device
+            .open_ports
+            .iter()
+            .filter(|p| p.state == "open")
+            .map(|p| p.port.to_string())
+            .collect::<Vec<_>>()
+            .join(";");
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        writer
+            .write_record([
+                device.custom_name.clone().or_else(|| device.hostname.clone()).unwrap_or_default(),
+                optional(&device.mac_address),
+                optional(&device.current_ip),
+                optional(&device.vendor),
+                device.device_type.clone(),
+                optional(&device.os_guess),
+                device.is_trusted.to_string(),
+                open_ports,
+                device.first_seen.clone(),
+                device.last_seen.clone(),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// Column headers of the latency CSV export, in order.
+pub const LATENCY_CSV_HEADERS: [&str; 4] = ["measured_at", "latency_ms", "packet_loss", "jitter_ms"];
+
+/// Export a device's raw latency samples for the last `hours` as CSV.
+#[tauri::command]
+pub fn export_latency_csv(state: State<'_, Arc<AppState>>, device_id: String, hours: u32) -> Result<String, String> {
+    let conn = state.conn().map_err(|e| e.to_string())?;
+    let points = db_settings::get_latency_history(&conn, &device_id, hours, 0).map_err(|e| e.to_string())?;
+    latency_to_csv(&points)
+}
+
+/// One row per sample under `LATENCY_CSV_HEADERS`, oldest first. Loss and jitter are
+/// empty for single-echo samples.
+pub fn latency_to_csv(points: &[db_settings::LatencyPoint]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(LATENCY_CSV_HEADERS).map_err(|e| e.to_string())?;
+    for point in points {
+        let optional = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        writer
+            .write_record([
+                point.measured_at.clone(),
+                point.latency_ms.to_string(),
+                optional(point.packet_loss),
+                optional(point.jitter_ms),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// Export the hosts one scan saw as an Nmap XML report, for tools that read Nmap output.
+#[tauri::command]
+pub fn export_scan_nmap_xml(state: State<'_, Arc<AppState>>, scan_id: String) -> Result<String, String> {
+    let conn = state.conn().map_err(|e| e.to_string())?;
+    scan_to_nmap_xml(&conn, &scan_id)
+}
+
+/// An Nmap `<nmaprun>` document with one `<host>` per device in the scan's snapshot:
+/// its address at the time, MAC and vendor, hostname, and the ports then open.
+/// Service names come from the ports the scan recorded and are left out when unknown.
+pub fn scan_to_nmap_xml(conn: &Connection, scan_id: &str) -> Result<String, String> {
+    let scan = db_scans::get_scan(conn, scan_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Scan not found: {}", scan_id))?;
+    let hosts = db_scans::get_scan_devices(conn, scan_id).map_err(|e| e.to_string())?;
+    let ports = db_ports::get_scan_ports_by_device(conn, scan_id).map_err(|e| e.to_string())?;
+
+    let started = time::parse_db_time(&scan.started_at).unwrap_or_default().and_utc();
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        format!(
+            r#"<nmaprun scanner="echolocate" args="{}" start="{}" startstr="{}" version="{}" xmloutputversion="1.05">"#,
+            xml_escape(&scan.scan_type),
+            started.timestamp(),
+            started.format("%a %b %e %H:%M:%S %Y"),
+            env!("CARGO_PKG_VERSION"),
+        ),
+    ];
+
+    for host in &hosts {
+        let device = db_devices::get_device_by_id(conn, &host.device_id).map_err(|e| e.to_string())?;
+        let recorded = ports.get(&host.device_id).map(Vec::as_slice).unwrap_or_default();
+
+        lines.push(r#"<host><status state="up" reason="echolocate"/>"#.to_string());
+        if let Some(ip) = &host.ip_address {
+            let family = match ip.parse::<IpAddr>() {
+                Ok(IpAddr::V6(_)) => "ipv6",
+                _ => "ipv4",
+            };
+            lines.push(format!(r#"<address addr="{}" addrtype="{}"/>"#, xml_escape(ip), family));
+        }
+        if let Some(device) = &device {
+            if let Some(mac) = &device.mac_address {
+                let vendor = device
+                    .vendor
+                    .as_deref()
+                    .map(|v| format!(r#" vendor="{}""#, xml_escape(v)))
+                    .unwrap_or_default();
+                lines.push(format!(r#"<address addr="{}" addrtype="mac"{}/>"#, xml_escape(mac), vendor));
+            }
+        }
+        lines.push(match device.as_ref().and_then(|d| d.hostname.as_deref()) {
+            Some(name) => format!(r#"<hostnames><hostname name="{}" type="PTR"/></hostnames>"#, xml_escape(name)),
+            None => "<hostnames/>".to_string(),
+        });
+
+        lines.push("<ports>".to_string());
+        for entry in &host.open_ports {
+            let Some((port, protocol)) = entry.split_once('/') else { continue };
+            let service = recorded
+                .iter()
+                .find(|p| p.port.to_string() == port && p.protocol == protocol)
+                .and_then(|p| p.service_name.as_deref())
+                .map(|name| format!(r#"<service name="{}" method="table"/>"#, xml_escape(name)))
+                .unwrap_or_default();
+            lines.push(format!(
+                r#"<port protocol="{}" portid="{}"><state state="open" reason="echolocate"/>{}</port>"#,
+                xml_escape(protocol),
+                xml_escape(port),
+                service,
+            ));
+        }
+        lines.push("</ports>".to_string());
+        if let Some(os) = &host.os_guess {
+            lines.push(format!(r#"<os><osmatch name="{}" accuracy="100"/></os>"#, xml_escape(os)));
+        }
+        lines.push("</host>".to_string());
+    }
+
+    let finished = scan.completed_at.as_deref().and_then(time::parse_db_time).unwrap_or_default().and_utc();
+    lines.push(format!(
+        r#"<runstats><finished time="{}" exit="{}"/><hosts up="{}" down="0" total="{}"/></runstats>"#,
+        finished.timestamp().max(started.timestamp()),
+        if scan.status == "completed" { "success" } else { "error" },
+        hosts.len(),
+        hosts.len(),
+    ));
+    lines.push("</nmaprun>\n".to_string());
+    Ok(lines.join("\n"))
+}
+
+/// Escape text for use in XML attribute values and content.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab and newline are not allowed in XML 1.0
+            c if c.is_control() && c != '\t' && c != '\n' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// What to do with an imported device whose MAC is already known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStrategy {
+    /// Leave the existing device untouched.
+    #[default]
+    Skip,
+    /// Replace the existing device's details with the imported ones.
+    Overwrite,
+    /// Only fill in details the existing device is missing.
+    Merge,
+}
+
 #[tauri::command]
 pub fn import_devices(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     json_data: String,
+    strategy: Option<ImportStrategy>,
 ) -> Result<ImportResult, String> {
-    let data: ExportData = serde_json::from_str(&json_data)
-        .map_err(|e| format!("Invalid import data: {}", e))?;
+    let data = parse_export(&json_data)?;
+    let conn = state.conn().map_err(|e| e.to_string())?;
+    import_data(&conn, &data, None, strategy.unwrap_or_default())
+}
 
+/// Describe what importing `json_data` would do, without writing anything.
+#[tauri::command]
+pub fn preview_import(
+    state: State<'_, Arc<AppState>>,
+    json_data: String,
+) -> Result<ImportPreview, String> {
+    let data = parse_export(&json_data)?;
     let conn = state.conn().map_err(|e| e.to_string())?;
+    preview_data(&conn, &data)
+}
+
+/// Parse an export file and reject formats newer than this build understands.
+pub fn parse_export(json_data: &str) -> Result<ExportData, String> {
+    let data: ExportData = serde_json::from_str(json_data)
+        .map_err(|e| format!("Invalid import data: {}", e))?;
+    check_version(&data)?;
+    Ok(data)
+}
+
+fn check_version(data: &ExportData) -> Result<(), String> {
+    if data.version == 0 || data.version > EXPORT_VERSION {
+        return Err(format!(
+            "Unsupported export version {} (this build reads up to {})",
+            data.version, EXPORT_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Check the user-editable and identifying fields of an imported device.
+fn validate_record(device: &db_devices::Device) -> Result<(), String> {
+    if let Some(ref mac) = device.mac_address {
+        Validator::validate_mac_address(mac)?;
+    }
+    if let Some(ref ip) = device.current_ip {
+        Validator::validate_ipv4(ip).or_else(|_| Validator::validate_ipv6(ip))?;
+    }
+    for ip in &device.ipv6_addresses {
+        Validator::validate_ipv6(ip)?;
+    }
+    if let Some(ref name) = device.custom_name {
+        Validator::validate_device_name(name)?;
+    }
+    if let Some(ref notes) = device.notes {
+        Validator::validate_notes(notes)?;
+    }
+    if let Some(ref icon) = device.icon {
+        Validator::validate_icon(icon)?;
+    }
+    if device.device_type.trim().is_empty() {
+        return Err("Device type cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+enum RecordStatus {
+    New,
+    /// Already known, by the ID of the stored device.
+    Existing(String),
+    Invalid(String),
+}
+
+/// The record's MAC, or for a record without one, the MAC embedded in an EUI-64 IPv6
+/// address. Lets an agent that only saw a v6 address match the device's IPv4 sightings.
+fn record_mac(device: &db_devices::Device) -> Option<String> {
+    device.mac_address.clone().or_else(|| {
+        device
+            .current_ip
+            .iter()
+            .chain(&device.ipv6_addresses)
+            .find_map(|ip| ipv6::mac_from_eui64(ip))
+    })
+}
+
+/// Decide how the import would treat a device; existing means its MAC is already known.
+fn record_status(conn: &Connection, device: &db_devices::Device) -> Result<RecordStatus, String> {
+    if let Err(reason) = validate_record(device) {
+        return Ok(RecordStatus::Invalid(reason));
+    }
+
+    let existing = match record_mac(device) {
+        Some(mac) => db_devices::get_device_by_mac(conn, &mac).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    Ok(match existing {
+        Some(id) => RecordStatus::Existing(id),
+        None => RecordStatus::New,
+    })
+}
+
+/// Apply an imported device to the stored device `id` under `strategy`.
+/// Returns whether anything was written.
+fn update_existing(
+    conn: &Connection,
+    id: &str,
+    imported: &db_devices::Device,
+    strategy: ImportStrategy,
+) -> Result<bool, rusqlite::Error> {
+    let overwrite = match strategy {
+        ImportStrategy::Skip => return Ok(false),
+        ImportStrategy::Overwrite => true,
+        ImportStrategy::Merge => false,
+    };
+    let Some(current) = db_devices::get_device_by_id(conn, id)? else {
+        return Ok(false);
+    };
+
+    // Overwrite takes every imported value that is set; merge only fills gaps
+    let take = |have: &Option<String>, new: &Option<String>| -> Option<Option<String>> {
+        match new {
+            Some(_) if overwrite || have.is_none() => Some(new.clone()),
+            _ => None,
+        }
+    };
+    let update = db_devices::DeviceUpdate {
+        custom_name: take(&current.custom_name, &imported.custom_name),
+        // An overwrite sets the type as the user's; a merge fills it in like a fingerprint
+        // pass would, so a later, more confident scan can still refine it
+        device_type: (overwrite && imported.device_type != "unknown").then(|| imported.device_type.clone()),
+        is_trusted: overwrite.then_some(imported.is_trusted),
+        notes: take(&current.notes, &imported.notes),
+        notes_pinned: (overwrite && imported.notes.is_some()).then_some(imported.notes_pinned),
+        icon: take(&current.icon, &imported.icon),
+    };
+    let mut changed = update.custom_name.is_some()
+        || update.device_type.is_some()
+        || update.is_trusted.is_some_and(|t| t != current.is_trusted)
+        || update.notes.is_some()
+        || update.icon.is_some();
+    db_devices::update_device(conn, id, &update)?;
+
+    if !overwrite && imported.device_type != "unknown" && current.device_type == "unknown" {
+        changed |= db_devices::update_device_type(conn, id, &imported.device_type, imported.os_confidence)?;
+    }
+    if let Some(Some(hostname)) = take(&current.hostname, &imported.hostname) {
+        db_devices::update_hostname(conn, id, &hostname)?;
+        changed = true;
+    }
+    if let Some(Some(os)) = take(&current.os_guess, &imported.os_guess) {
+        db_devices::update_os_guess(conn, id, &os, imported.os_confidence)?;
+        changed = true;
+    }
+    if let Some(Some(ip)) = take(&current.current_ip, &imported.current_ip) {
+        db_devices::upsert_device_ip(conn, id, &ip)?;
+        changed = true;
+    }
+    Ok(changed)
+}
+
+/// Merge exported devices into the database, skipping invalid records. Devices whose
+/// MAC is already known are handled per `strategy`. Devices inserted on behalf of a
+/// remote agent are tagged with `source_agent`.
+pub fn import_data(
+    conn: &Connection,
+    data: &ExportData,
+    source_agent: Option<&str>,
+    strategy: ImportStrategy,
+) -> Result<ImportResult, String> {
+    check_version(data)?;
+
     let mut imported = 0u32;
+    let mut updated = 0u32;
     let mut skipped = 0u32;
+    let mut invalid = 0u32;
 
     for device in &data.devices {
-        // Check if device already exists by MAC
-        let exists = device.mac_address.as_deref()
-            .and_then(|mac| db_devices::get_device_by_mac(&conn, mac).ok().flatten())
-            .is_some();
-
-        if exists {
-            skipped += 1;
-            continue;
+        match record_status(conn, device)? {
+            RecordStatus::New => {}
+            RecordStatus::Existing(id) => {
+                if update_existing(conn, &id, device, strategy).map_err(|e| e.to_string())? {
+                    updated += 1;
+                } else {
+                    skipped += 1;
+                }
+                continue;
+            }
+            RecordStatus::Invalid(reason) => {
+                log::warn!("Skipping invalid imported device {}: {}", device.id, reason);
+                invalid += 1;
+                continue;
+            }
         }
 
         let id = uuid::Uuid::new_v4().to_string();
         db_devices::insert_device(
-            &conn,
+            conn,
             &id,
-            device.mac_address.as_deref(),
+            record_mac(device).as_deref(),
             device.vendor.as_deref(),
             device.hostname.as_deref(),
             &device.device_type,
             device.is_gateway,
             device.current_ip.as_deref(),
         ).map_err(|e| e.to_string())?;
+        // Oldest first, so the record's current IPv6 address ends up current here too
+        for ip in device.ipv6_addresses.iter().rev() {
+            db_devices::upsert_device_ip(conn, &id, ip).map_err(|e| e.to_string())?;
+        }
+        if let Some(agent) = source_agent {
+            db_devices::set_source_agent(conn, &id, agent).map_err(|e| e.to_string())?;
+        }
 
         imported += 1;
     }
 
-    Ok(ImportResult { imported, skipped })
+    Ok(ImportResult { imported, updated, skipped, invalid })
+}
+
+/// Run the import checks against the database without writing.
+pub fn preview_data(conn: &Connection, data: &ExportData) -> Result<ImportPreview, String> {
+    check_version(data)?;
+
+    let mut invalid_records = Vec::new();
+    let mut new_vs_existing = NewVsExisting::default();
+
+    for (index, device) in data.devices.iter().enumerate() {
+        match record_status(conn, device)? {
+            RecordStatus::New => new_vs_existing.new += 1,
+            RecordStatus::Existing(_) => new_vs_existing.existing += 1,
+            RecordStatus::Invalid(reason) => invalid_records.push(InvalidRecord {
+                index,
+                device_id: device.id.clone(),
+                reason,
+            }),
+        }
+    }
+
+    Ok(ImportPreview {
+        version: data.version,
+        exported_at: data.exported_at.clone(),
+        device_count: data.devices.len() as u32,
+        alert_count: data.alerts.len() as u32,
+        invalid_records,
+        new_vs_existing,
+    })
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImportResult {
     pub imported: u32,
+    /// Known devices changed by an overwrite or merge.
+    pub updated: u32,
+    /// Known devices left as they were.
     pub skipped: u32,
+    /// Records that failed validation and were not imported.
+    pub invalid: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub version: u32,
+    pub exported_at: String,
+    pub device_count: u32,
+    pub alert_count: u32,
+    pub invalid_records: Vec<InvalidRecord>,
+    pub new_vs_existing: NewVsExisting,
+}
+
+/// A device the import would reject, by its position in the file.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidRecord {
+    pub index: usize,
+    pub device_id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewVsExisting {
+    pub new: u32,
+    pub existing: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn device(id: &str, mac: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "macAddress": mac,
+            "vendor": null,
+            "hostname": null,
+            "customName": null,
+            "deviceType": "unknown",
+            "osGuess": null,
+            "osConfidence": 0.0,
+            "isTrusted": false,
+            "isGateway": false,
+            "notes": null,
+            "currentIp": "192.168.1.20",
+            "isOnline": false,
+            "latencyMs": null,
+            "openPorts": [],
+            "firstSeen": "2026-01-01 00:00:00",
+            "lastSeen": "2026-01-01 00:00:00"
+        })
+    }
+
+    fn export_json(version: u32, devices: Vec<serde_json::Value>) -> String {
+        serde_json::json!({
+            "version": version,
+            "exportedAt": "2026-01-01 00:00:00",
+            "devices": devices,
+            "alerts": []
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_preview_reports_without_writing() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "known", Some("AA:BB:CC:DD:EE:01"), None, None, "unknown", false, None).unwrap();
+
+        let data = parse_export(&export_json(1, vec![
+            device("a", "AA:BB:CC:DD:EE:01"),
+            device("b", "AA:BB:CC:DD:EE:02"),
+            device("c", "not-a-mac"),
+        ])).unwrap();
+
+        let preview = preview_data(&conn, &data).unwrap();
+        assert_eq!(preview.version, 1);
+        assert_eq!(preview.device_count, 3);
+        assert_eq!(preview.alert_count, 0);
+        assert_eq!(preview.new_vs_existing.new, 1);
+        assert_eq!(preview.new_vs_existing.existing, 1);
+        assert_eq!(preview.invalid_records.len(), 1);
+        assert_eq!(preview.invalid_records[0].index, 2);
+
+        assert_eq!(db_devices::count_devices(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_import_matches_preview() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let data = parse_export(&export_json(1, vec![
+            device("a", "AA:BB:CC:DD:EE:01"),
+            device("c", "not-a-mac"),
+        ])).unwrap();
+
+        let result = import_data(&conn, &data, None, ImportStrategy::Skip).unwrap();
+        assert_eq!(result.imported, 1);
+        assert_eq!(result.invalid, 1);
+        assert_eq!(db_devices::count_devices(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_parse_export_rejects_unknown_version() {
+        let err = parse_export(&export_json(EXPORT_VERSION + 1, vec![])).unwrap_err();
+        assert!(err.contains("Unsupported export version"));
+        assert!(parse_export("{").is_err());
+    }
+
+    #[test]
+    fn test_devices_to_csv_round_trip() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(
+            &conn, "dev1", Some("AA:BB:CC:00:11:22"), Some("Acme, Inc."), Some("nas.lan"), "computer", false, Some("192.168.1.20"),
+        )
+        .unwrap();
+        db_devices::insert_device(&conn, "dev2", None, None, None, "unknown", false, Some("192.168.1.21")).unwrap();
+        conn.execute("INSERT INTO scans (id, scan_type, status) VALUES ('scan1', 'full', 'completed')", []).unwrap();
+        for port in [22, 445] {
+            crate::db::queries::ports::insert_port(&conn, "dev1", "scan1", port, "tcp", "open", None, None).unwrap();
+        }
+        conn.execute("UPDATE devices SET custom_name = 'Living room \"NAS\"' WHERE id = 'dev1'", []).unwrap();
+
+        let devices = db_devices::get_all_devices(&conn).unwrap();
+        let csv = devices_to_csv(&devices).unwrap();
+
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        assert_eq!(reader.headers().unwrap().iter().collect::<Vec<_>>(), CSV_HEADERS);
+        let rows: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|r| r.len() == CSV_HEADERS.len()));
+
+        let nas = rows.iter().find(|r| &r[1] == "AA:BB:CC:00:11:22").unwrap();
+        assert_eq!(&nas[0], "Living room \"NAS\"");
+        assert_eq!(&nas[3], "Acme, Inc.");
+        assert_eq!(&nas[7], "22;445");
+        let bare = rows.iter().find(|r| &r[2] == "192.168.1.21").unwrap();
+        assert_eq!(&bare[0], "");
+        assert_eq!(&bare[6], "false");
+    }
+
+    /// Every start tag has a matching end tag, nested properly, under one root, and
+    /// no bare `&` or `<` appears in text or attribute values.
+    fn assert_well_formed(xml: &str) {
+        let unescaped_amp = |s: &str| {
+            ["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"]
+                .iter()
+                .fold(s.to_string(), |s, entity| s.replace(entity, ""))
+                .contains('&')
+        };
+        let mut rest = xml.trim().strip_prefix(r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        let mut open: Vec<&str> = Vec::new();
+        let mut roots = 0;
+        while let Some(at) = rest.find('<') {
+            assert!(!unescaped_amp(&rest[..at]));
+            let end = rest[at..].find('>').unwrap() + at;
+            let tag = &rest[at + 1..end];
+            assert!(!tag.contains('<') && !unescaped_amp(tag), "unescaped text in {}", tag);
+            assert_eq!(tag.matches('"').count() % 2, 0, "unbalanced quotes in {}", tag);
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name));
+            } else {
+                if open.is_empty() {
+                    roots += 1;
+                }
+                if !tag.ends_with('/') {
+                    open.push(tag.split(|c: char| c.is_whitespace()).next().unwrap());
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        assert!(open.is_empty(), "unclosed {:?}", open);
+        assert_eq!(roots, 1);
+    }
+
+    #[test]
+    fn test_scan_to_nmap_xml() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(
+            &conn, "dev1", Some("AA:BB:CC:00:11:22"), Some("Smith & Sons"), Some("nas.lan"), "computer", false, Some("192.168.1.20"),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO scans (id, scan_type, status, started_at, completed_at)
+             VALUES ('scan1', 'full', 'completed', '2026-01-01 10:00:00', '2026-01-01 10:01:00')",
+            [],
+        )
+        .unwrap();
+        db_ports::insert_port(&conn, "dev1", "scan1", 22, "tcp", "open", Some("ssh"), None).unwrap();
+        db_ports::insert_port(&conn, "dev1", "scan1", 8080, "tcp", "open", None, None).unwrap();
+        db_ports::insert_port(&conn, "dev1", "scan1", 23, "tcp", "closed", None, None).unwrap();
+        let device = db_devices::get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        let ports = db_ports::get_latest_ports(&conn, "dev1").unwrap();
+        db_scans::record_scan_device(&conn, "scan1", &device, &ports).unwrap();
+
+        let xml = scan_to_nmap_xml(&conn, "scan1").unwrap();
+        assert_well_formed(&xml);
+
+        let port_elements: Vec<&str> = xml.lines().filter(|l| l.starts_with("<port ")).collect();
+        assert_eq!(port_elements, [
+            r#"<port protocol="tcp" portid="22"><state state="open" reason="echolocate"/><service name="ssh" method="table"/></port>"#,
+            r#"<port protocol="tcp" portid="8080"><state state="open" reason="echolocate"/></port>"#,
+        ]);
+        assert!(xml.contains(r#"<address addr="192.168.1.20" addrtype="ipv4"/>"#));
+        assert!(xml.contains(r#"<address addr="AA:BB:CC:00:11:22" addrtype="mac" vendor="Smith &amp; Sons"/>"#));
+        assert!(xml.contains(r#"<hostname name="nas.lan" type="PTR"/>"#));
+        assert!(xml.contains(r#"start="1767261600""#));
+        assert!(xml.contains(r#"<hosts up="1" down="0" total="1"/>"#));
+
+        assert!(scan_to_nmap_xml(&conn, "missing").unwrap_err().contains("Scan not found"));
+    }
+
+    #[test]
+    fn test_latency_to_csv() {
+        let points = [
+            db_settings::LatencyPoint {
+                latency_ms: 12.5,
+                measured_at: "2026-01-01 10:00:00".to_string(),
+                packet_loss: None,
+                jitter_ms: None,
+            },
+            db_settings::LatencyPoint {
+                latency_ms: 14.0,
+                measured_at: "2026-01-01 10:01:00".to_string(),
+                packet_loss: Some(0.25),
+                jitter_ms: Some(1.5),
+            },
+        ];
+        let csv = latency_to_csv(&points).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            [
+                "measured_at,latency_ms,packet_loss,jitter_ms",
+                "2026-01-01 10:00:00,12.5,,",
+                "2026-01-01 10:01:00,14,0.25,1.5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(xml_escape(r#"<a href="x">Tom's & Jerry's</a>"#), "&lt;a href=&quot;x&quot;&gt;Tom&apos;s &amp; Jerry&apos;s&lt;/a&gt;");
+        assert_eq!(xml_escape("bell\u{7}"), "bell");
+    }
+
+    /// A stored device with a custom name and type but no hostname or notes, and an
+    /// export of the same MAC with different details.
+    fn conflicting_import(conn: &Connection) -> ExportData {
+        db_devices::insert_device(conn, "known", Some("AA:BB:CC:DD:EE:01"), None, None, "computer", false, Some("192.168.1.20")).unwrap();
+        conn.execute("UPDATE devices SET custom_name = 'Office PC' WHERE id = 'known'", []).unwrap();
+
+        let mut incoming = device("incoming", "aa:bb:cc:dd:ee:01");
+        incoming["customName"] = "Renamed".into();
+        incoming["hostname"] = "office.lan".into();
+        incoming["deviceType"] = "printer".into();
+        incoming["notes"] = "Second floor".into();
+        incoming["isTrusted"] = true.into();
+        incoming["currentIp"] = "192.168.1.99".into();
+        parse_export(&export_json(1, vec![incoming])).unwrap()
+    }
+
+    fn stored(conn: &Connection) -> db_devices::Device {
+        db_devices::get_device_by_id(conn, "known").unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_import_correlates_eui64_address_to_known_mac() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "known", Some("00:1A:2B:3C:4D:5E"), None, None, "unknown", false, Some("192.168.1.20")).unwrap();
+
+        // An agent that only saw the SLAAC address, with no link-layer address
+        let mut seen_v6 = device("remote", "unused");
+        seen_v6["macAddress"] = serde_json::Value::Null;
+        seen_v6["currentIp"] = serde_json::json!("2001:db8::21a:2bff:fe3c:4d5e");
+        let mut privacy = device("remote2", "unused");
+        privacy["macAddress"] = serde_json::Value::Null;
+        privacy["currentIp"] = serde_json::json!("2001:db8::1c3e:9a41:77d2:5f10");
+        let data = parse_export(&export_json(1, vec![seen_v6, privacy])).unwrap();
+
+        let result = import_data(&conn, &data, None, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.imported, result.skipped), (1, 1));
+        assert_eq!(db_devices::count_devices(&conn).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_import_strategy_skip() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        let data = conflicting_import(&conn);
+
+        let result = import_data(&conn, &data, None, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.imported, result.updated, result.skipped), (0, 0, 1));
+        let device = stored(&conn);
+        assert_eq!(device.custom_name.as_deref(), Some("Office PC"));
+        assert!(device.hostname.is_none());
+    }
+
+    #[test]
+    fn test_import_strategy_overwrite() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        let data = conflicting_import(&conn);
+
+        let result = import_data(&conn, &data, None, ImportStrategy::Overwrite).unwrap();
+        assert_eq!((result.imported, result.updated, result.skipped), (0, 1, 0));
+        let device = stored(&conn);
+        assert_eq!(device.custom_name.as_deref(), Some("Renamed"));
+        assert_eq!(device.hostname.as_deref(), Some("office.lan"));
+        assert_eq!(device.device_type, "printer");
+        assert_eq!(device.notes.as_deref(), Some("Second floor"));
+        assert!(device.is_trusted);
+        assert_eq!(device.current_ip.as_deref(), Some("192.168.1.99"));
+        assert_eq!(db_devices::count_devices(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_import_strategy_merge() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        let data = conflicting_import(&conn);
+
+        let result = import_data(&conn, &data, None, ImportStrategy::Merge).unwrap();
+        assert_eq!((result.imported, result.updated, result.skipped), (0, 1, 0));
+        let device = stored(&conn);
+        // Existing details stay; missing ones are filled in
+        assert_eq!(device.custom_name.as_deref(), Some("Office PC"));
+        assert_eq!(device.device_type, "computer");
+        assert_eq!(device.current_ip.as_deref(), Some("192.168.1.20"));
+        assert!(!device.is_trusted);
+        assert_eq!(device.hostname.as_deref(), Some("office.lan"));
+        assert_eq!(device.notes.as_deref(), Some("Second floor"));
+
+        // Nothing left to fill: the second merge is a skip
+        let again = import_data(&conn, &data, None, ImportStrategy::Merge).unwrap();
+        assert_eq!((again.updated, again.skipped), (0, 1));
+    }
 }
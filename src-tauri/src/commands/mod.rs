@@ -1,4 +1,5 @@
 pub mod alert;
+pub mod csv_import;
 pub mod device;
 pub mod export;
 pub mod scan;
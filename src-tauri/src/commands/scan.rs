@@ -1,38 +1,48 @@
-use tauri::{AppHandle, State};
+use std::sync::Arc;
+
+use tauri::{AppHandle, Manager, State};
 use tokio_util::sync::CancellationToken;
 
-use crate::db::queries::scans as db_scans;
-use crate::scanner::{orchestrator, ScanConfig, ScanResult};
+use crate::db::queries::{scans as db_scans, settings as db_settings};
+use crate::network::{interface, watcher};
+use crate::scanner::{orchestrator, queue, scan_log, ScanConfig, ScanStart};
 use crate::state::AppState;
+use crate::{AppError, TauriResult};
 
 #[tauri::command]
 pub async fn start_scan(
     app: AppHandle,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     config: ScanConfig,
-) -> Result<ScanResult, String> {
-    let cancel = CancellationToken::new();
-
-    // Store the cancellation token so stop_scan can trigger it
-    {
-        let mut guard = state.scan_cancel.lock().map_err(|e| e.to_string())?;
-        *guard = Some(cancel.clone());
-    }
+) -> TauriResult<ScanStart> {
+    // The selected interface may have gone away since the frontend listed it
+    let interfaces = tokio::task::spawn_blocking(interface::get_interfaces).await?;
+    watcher::refresh(&app, &state, &interfaces);
+    watcher::ensure_available(&interfaces, &config.interface_id).map_err(|e| AppError::network(&e))?;
+    // Reject a malformed or oversized range before it is queued
+    config.target.hosts().map_err(|e| AppError::validation("target", &e))?;
 
-    let result = orchestrator::run_scan(app, &state, config, cancel).await;
+    let queue_scans = {
+        let conn = state.conn()?;
+        db_settings::get_settings(&conn)
+            .map(|s| s.queue_scans)
+            .unwrap_or(false)
+    };
 
-    // Clear the token after scan completes
-    {
-        let mut guard = state.scan_cancel.lock().map_err(|e| e.to_string())?;
-        *guard = None;
+    if queue_scans {
+        return Ok(ScanStart::Queued(queue::enqueue(app, config).await));
     }
 
-    result
+    // Holding the slot lets stop_scan reach this scan and turns away a second one
+    let cancel = CancellationToken::new();
+    let _slot = state.claim_scan(cancel.clone())?;
+
+    orchestrator::run_scan(app, &state, config, cancel).await.map(ScanStart::Completed)
 }
 
 #[tauri::command]
-pub async fn stop_scan(state: State<'_, AppState>) -> Result<(), String> {
-    let guard = state.scan_cancel.lock().map_err(|e| e.to_string())?;
+pub async fn stop_scan(state: State<'_, Arc<AppState>>) -> TauriResult<()> {
+    let guard = state.scan_cancel.lock()?;
     if let Some(ref token) = *guard {
         token.cancel();
         log::info!("Scan cancellation requested");
@@ -44,9 +54,32 @@ pub async fn stop_scan(state: State<'_, AppState>) -> Result<(), String> {
 
 #[tauri::command]
 pub fn get_scan_history(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     limit: u32,
-) -> Result<Vec<db_scans::ScanSummary>, String> {
-    let conn = state.conn().map_err(|e| e.to_string())?;
-    db_scans::get_scan_history(&conn, limit).map_err(|e| e.to_string())
+) -> TauriResult<Vec<db_scans::ScanSummary>> {
+    let conn = state.conn()?;
+    Ok(db_scans::get_scan_history(&conn, limit)?)
+}
+
+/// Devices added, removed and changed between two scans, `scan_id_a` being the earlier.
+#[tauri::command]
+pub fn get_scan_diff(
+    state: State<'_, Arc<AppState>>,
+    scan_id_a: String,
+    scan_id_b: String,
+) -> TauriResult<db_scans::ScanDiff> {
+    let conn = state.conn()?;
+    for id in [&scan_id_a, &scan_id_b] {
+        if !db_scans::scan_exists(&conn, id)? {
+            return Err(AppError::not_found("Scan", id));
+        }
+    }
+    Ok(db_scans::get_scan_diff(&conn, &scan_id_a, &scan_id_b)?)
+}
+
+/// Read back the verbose log written for a scan, if one was recorded.
+#[tauri::command]
+pub fn get_scan_log(app: AppHandle, scan_id: String) -> TauriResult<String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| AppError::internal(&e.to_string()))?;
+    scan_log::read_log(&scan_log::log_dir(&app_data_dir), &scan_id).map_err(|e| AppError::new("NOT_FOUND", &e))
 }
@@ -1,31 +1,81 @@
-use tauri::{AppHandle, Emitter, State};
+use std::sync::Arc;
+
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio_util::sync::CancellationToken;
 
-use crate::db::queries::settings as db_settings;
-use crate::network::interface;
-use crate::scanner::{orchestrator, ping, PortRange, ScanConfig, ScanType};
-use crate::state::AppState;
+use crate::db::{self, queries::settings as db_settings, recovery};
+use crate::network::{interface, watcher};
+use crate::scanner::port::Protocol;
+use crate::scanner::{orchestrator, ping, PortRange, ScanConfig, ScanTarget, ScanType};
+use crate::state::{AppState, ScanSlot};
+use crate::util::time;
+use crate::{AppError, TauriResult};
+
+/// Shortest monitor interval accepted; anything faster keeps the network permanently busy.
+pub const MIN_MONITOR_INTERVAL_SECS: u64 = 10;
+
+/// Longest monitor interval accepted (one day).
+pub const MAX_MONITOR_INTERVAL_SECS: u64 = 86_400;
 
 #[tauri::command]
-pub fn get_interfaces() -> Vec<interface::NetworkInterface> {
-    interface::get_interfaces()
+pub fn get_interfaces(app: AppHandle, state: State<'_, Arc<AppState>>) -> Vec<interface::NetworkInterface> {
+    let interfaces = interface::get_interfaces();
+    watcher::refresh(&app, &state, &interfaces);
+    interfaces
 }
 
 #[tauri::command]
-pub fn get_settings(state: State<'_, AppState>) -> Result<db_settings::AppSettings, String> {
+pub fn get_settings(state: State<'_, Arc<AppState>>) -> Result<db_settings::AppSettings, String> {
     let conn = state.conn().map_err(|e| e.to_string())?;
     db_settings::get_settings(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn update_settings(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     settings: db_settings::AppSettings,
 ) -> Result<(), String> {
+    settings.validate_commands()?;
+    settings.quiet_hours()?;
     let conn = state.conn().map_err(|e| e.to_string())?;
     db_settings::update_settings(&conn, &settings).map_err(|e| e.to_string())
 }
 
+/// Run the database consistency checks and repairs on demand.
+#[tauri::command]
+pub fn diagnose_and_repair(state: State<'_, Arc<AppState>>) -> Result<db::RepairReport, String> {
+    let conn = state.conn().map_err(|e| e.to_string())?;
+    db::diagnose_and_repair(&conn).map_err(|e| e.to_string())
+}
+
+/// Automatic database backups, newest first.
+#[tauri::command]
+pub fn list_backups(app: AppHandle) -> Result<Vec<recovery::BackupInfo>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    recovery::list_backups(&recovery::backup_dir(&app_data_dir)).map_err(|e| e.to_string())
+}
+
+/// Replace the database with one of the automatic backups.
+#[tauri::command]
+pub fn restore_backup(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    filename: String,
+) -> Result<db::RepairReport, String> {
+    recovery::validate_backup_name(&filename)?;
+    // Hold the scan slot throughout, so no scan can start writing mid-restore
+    let _slot = state.claim_scan(CancellationToken::new()).map_err(|e| match e.code.as_str() {
+        "SCAN_IN_PROGRESS" => "Cannot restore while a scan is in progress".to_string(),
+        _ => e.message,
+    })?;
+
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let mut conn = state.conn().map_err(|e| e.to_string())?;
+    let report = recovery::restore_from_backup(&mut conn, &recovery::backup_dir(&app_data_dir), &filename)?;
+    log::warn!("Database restored from backup {}", filename);
+    Ok(report)
+}
+
 /// Monitor status event emitted to the frontend.
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -34,28 +84,44 @@ struct MonitorStatus {
     next_scan_in: Option<u64>,
 }
 
+/// Confirmation returned by `start_monitor` with the interval it accepted.
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ScanError {
-    message: String,
+pub struct MonitorStarted {
+    pub interval_secs: u64,
+    pub started_at: String,
+}
+
+fn validate_monitor_interval(interval_secs: u64) -> Result<u64, AppError> {
+    if !(MIN_MONITOR_INTERVAL_SECS..=MAX_MONITOR_INTERVAL_SECS).contains(&interval_secs) {
+        return Err(AppError::validation(
+            "interval_secs",
+            &format!(
+                "must be between {} and {} seconds (got {})",
+                MIN_MONITOR_INTERVAL_SECS, MAX_MONITOR_INTERVAL_SECS, interval_secs
+            ),
+        ));
+    }
+    Ok(interval_secs)
 }
 
 #[tauri::command]
 pub async fn start_monitor(
     app: AppHandle,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     interval_secs: u64,
-) -> Result<(), String> {
+) -> TauriResult<MonitorStarted> {
+    let interval_secs = validate_monitor_interval(interval_secs)?;
+
     // Stop existing monitor if running
     stop_monitor_inner(&state)?;
 
     let cancel = CancellationToken::new();
     let cancel_clone = cancel.clone();
 
-    // We need an Arc-wrapped reference to AppState for the spawned task.
-    // Since Tauri manages the state, we clone the DB pool and OUI db for the monitor.
-    let db_pool = state.db.clone();
-    let oui_db = state.oui_db.clone();
+    // The task shares the managed state, so its scans hold the same scan slot as
+    // foreground ones and stop_scan can reach them
+    let shared = Arc::clone(&state);
     let app_clone = app.clone();
 
     let handle = tokio::spawn(async move {
@@ -85,26 +151,22 @@ pub async fn start_monitor(
                 break;
             }
 
-            // Build a temporary AppState for the scan
-            let monitor_state = crate::state::AppState::new(db_pool.clone(), oui_db.clone());
-            let scan_cancel = CancellationToken::new();
-
-            let config = monitor_scan_config(&db_pool);
-
-            match orchestrator::run_scan(app_clone.clone(), &monitor_state, config, scan_cancel)
-                .await
-            {
-                Ok(result) => {
-                    log::info!(
-                        "Monitor scan completed: {} devices, {} new",
-                        result.devices_found,
-                        result.new_devices
-                    );
-                }
-                Err(e) => {
-                    log::error!("Monitor scan failed: {}", e);
-                    let _ = app_clone.emit("scan:error", ScanError { message: e });
+            match claim_monitor_scan(&shared, &cancel_clone) {
+                Some((_slot, scan_cancel)) => {
+                    let config = monitor_scan_config(&shared.db);
+                    match orchestrator::run_scan(app_clone.clone(), &shared, config, scan_cancel).await {
+                        Ok(result) => {
+                            log::info!(
+                                "Monitor scan completed: {} devices, {} new",
+                                result.devices_found,
+                                result.new_devices
+                            );
+                        }
+                        // The orchestrator has already sent `scan:error`
+                        Err(e) => log::error!("Monitor scan failed: {}", e.message),
+                    }
                 }
+                None => log::info!("Skipping monitor scan: another scan is in progress"),
             }
 
             // Emit next scan countdown
@@ -137,11 +199,14 @@ pub async fn start_monitor(
         *c = Some(cancel);
     }
 
-    Ok(())
+    Ok(MonitorStarted {
+        interval_secs,
+        started_at: time::now_db_string(),
+    })
 }
 
 #[tauri::command]
-pub async fn stop_monitor(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn stop_monitor(state: State<'_, Arc<AppState>>) -> Result<(), String> {
     stop_monitor_inner(&state)
 }
 
@@ -167,14 +232,16 @@ fn stop_monitor_inner(state: &AppState) -> Result<(), String> {
     Ok(())
 }
 
+/// Latency points for the last `hours`, averaged into `bucket_minutes` buckets when given.
 #[tauri::command]
 pub fn get_latency_history(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
     device_id: String,
     hours: u32,
+    bucket_minutes: Option<u32>,
 ) -> Result<Vec<db_settings::LatencyPoint>, String> {
     let conn = state.conn().map_err(|e| e.to_string())?;
-    db_settings::get_latency_history(&conn, &device_id, hours).map_err(|e| e.to_string())
+    db_settings::get_latency_history(&conn, &device_id, hours, bucket_minutes.unwrap_or(0)).map_err(|e| e.to_string())
 }
 
 #[derive(serde::Serialize)]
@@ -186,8 +253,14 @@ pub struct PingResult {
 }
 
 #[tauri::command]
-pub async fn ping_device(ip: String) -> Result<PingResult, String> {
-    let latency = ping::ping(&ip).await;
+pub async fn ping_device(state: State<'_, Arc<AppState>>, ip: String) -> Result<PingResult, String> {
+    let (timeout_ms, tools) = {
+        let conn = state.conn().map_err(|e| e.to_string())?;
+        db_settings::get_settings(&conn)
+            .map(|s| (s.ping_timeout_ms, s.scan_tools()))
+            .unwrap_or((ping::DEFAULT_PING_TIMEOUT_MS, Default::default()))
+    };
+    let latency = ping::ping_with(&ip, timeout_ms, &tools).await;
     Ok(PingResult {
         ip,
         success: latency.is_some(),
@@ -195,6 +268,18 @@ pub async fn ping_device(ip: String) -> Result<PingResult, String> {
     })
 }
 
+/// Claim the scan slot for one monitor tick. The scan's token is a child of the
+/// monitor's, so stopping either the monitor or the scan aborts it. `None` while
+/// another scan holds the slot.
+fn claim_monitor_scan<'a>(
+    state: &'a AppState,
+    monitor_cancel: &CancellationToken,
+) -> Option<(ScanSlot<'a>, CancellationToken)> {
+    let scan_cancel = monitor_cancel.child_token();
+    let slot = state.claim_scan(scan_cancel.clone()).ok()?;
+    Some((slot, scan_cancel))
+}
+
 fn monitor_scan_config(db_pool: &r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>) -> ScanConfig {
     let settings = db_pool
         .get()
@@ -206,6 +291,9 @@ fn monitor_scan_config(db_pool: &r2d2::Pool<r2d2_sqlite::SqliteConnectionManager
             interface_id: s.default_interface_id.unwrap_or_else(|| "auto".to_string()),
             scan_type: ScanType::Quick,
             port_range: parse_port_range(&s.port_range),
+            protocols: vec![Protocol::Tcp],
+            target: ScanTarget::AutoSubnet,
+            phases: None,
         },
         None => {
             log::warn!("Monitor could not load app settings from DB; using default scan config");
@@ -213,6 +301,9 @@ fn monitor_scan_config(db_pool: &r2d2::Pool<r2d2_sqlite::SqliteConnectionManager
                 interface_id: "auto".to_string(),
                 scan_type: ScanType::Quick,
                 port_range: PortRange::Top100,
+                protocols: vec![Protocol::Tcp],
+                target: ScanTarget::AutoSubnet,
+                phases: None,
             }
         }
     }
@@ -231,3 +322,52 @@ fn parse_port_range(port_range: &str) -> PortRange {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_monitor_interval() {
+        assert_eq!(validate_monitor_interval(60).unwrap(), 60);
+        assert!(validate_monitor_interval(MIN_MONITOR_INTERVAL_SECS).is_ok());
+        assert!(validate_monitor_interval(MAX_MONITOR_INTERVAL_SECS).is_ok());
+
+        let err = validate_monitor_interval(0).unwrap_err();
+        assert_eq!(err.code, "INVALID_INPUT");
+        assert!(err.message.contains("interval_secs"));
+        assert!(validate_monitor_interval(MAX_MONITOR_INTERVAL_SECS + 1).is_err());
+    }
+
+    #[test]
+    fn test_monitor_scan_shares_app_state() {
+        let state = AppState::new(db::init_test_db(), crate::network::oui::OuiDatabase::empty());
+        {
+            let conn = state.conn().unwrap();
+            let mut settings = db_settings::get_settings(&conn).unwrap();
+            settings.default_interface_id = Some("en7".to_string());
+            db_settings::update_settings(&conn, &settings).unwrap();
+        }
+
+        // Settings written through the foreground pool reach the monitor's scans
+        assert_eq!(monitor_scan_config(&state.db).interface_id, "en7");
+
+        // The monitor's claim is what stop_scan sees in the shared state
+        assert!(state.scan_cancel.lock().unwrap().is_none());
+        let monitor_cancel = CancellationToken::new();
+        let (slot, scan_cancel) = claim_monitor_scan(&state, &monitor_cancel).unwrap();
+        assert!(state.scan_cancel.lock().unwrap().is_some());
+
+        // start_scan claims the same slot, so it is turned away while the monitor scans
+        assert_eq!(state.claim_scan(CancellationToken::new()).err().unwrap().code, "SCAN_IN_PROGRESS");
+        assert!(claim_monitor_scan(&state, &monitor_cancel).is_none());
+
+        // Cancelling the stored token, as stop_scan does, stops the monitor scan
+        state.scan_cancel.lock().unwrap().as_ref().unwrap().cancel();
+        assert!(scan_cancel.is_cancelled());
+
+        drop(slot);
+        assert!(state.scan_cancel.lock().unwrap().is_none());
+        assert!(claim_monitor_scan(&state, &monitor_cancel).is_some());
+    }
+}
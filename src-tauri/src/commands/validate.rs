@@ -36,6 +36,26 @@ impl Validator {
         }
     }
 
+    /// Validate IPv4 CIDR notation (e.g. 192.168.1.0/24)
+    /// Returns the network address (host bits cleared) and prefix length, or an error message
+    pub fn validate_cidr(cidr: &str) -> Result<(u32, u8), String> {
+        let (addr, prefix) = cidr
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("Invalid CIDR: {} (expected address/prefix)", cidr))?;
+        let addr: std::net::Ipv4Addr = Self::validate_ipv4(addr)?
+            .parse()
+            .map_err(|_| format!("Invalid CIDR: {} (bad IPv4 address)", cidr))?;
+        let prefix: u8 = prefix
+            .parse()
+            .ok()
+            .filter(|p| *p <= 32)
+            .ok_or_else(|| format!("Invalid CIDR: {} (prefix must be 0-32)", cidr))?;
+
+        let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+        Ok((u32::from(addr) & mask, prefix))
+    }
+
     /// Validate port number (1-65535)
     /// Returns the validated port or an error message
     pub fn validate_port(port: u16) -> Result<u16, String> {
@@ -204,6 +224,83 @@ impl Validator {
 
         Ok(notes.to_string())
     }
+
+    /// Validate device icon (emoji or icon key/filename, 1-64 chars)
+    pub fn validate_icon(icon: &str) -> Result<String, String> {
+        let icon = icon.trim();
+        if icon.is_empty() {
+            return Err("Icon cannot be empty".to_string());
+        }
+
+        let len = icon.chars().count();
+        if len > 64 {
+            return Err(format!("Icon exceeds 64 characters (got {})", len));
+        }
+
+        if icon.chars().any(|c| c.is_control() || c.is_whitespace()) {
+            return Err("Icon cannot contain whitespace or control characters".to_string());
+        }
+
+        // Filenames are resolved against the app's icon directory, never as paths
+        if icon.contains('/') || icon.contains('\\') || icon.contains("..") {
+            return Err(format!("Icon must not be a path: {}", icon));
+        }
+
+        Ok(icon.to_string())
+    }
+
+    /// Validate a device tag (1-32 letters, digits, '-' or '_')
+    /// Returns the tag lowercased, so "Work" and "work" are the same tag
+    pub fn validate_tag(tag: &str) -> Result<String, String> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err("Tag cannot be empty".to_string());
+        }
+
+        let len = tag.chars().count();
+        if len > 32 {
+            return Err(format!("Tag exceeds 32 characters (got {})", len));
+        }
+
+        if !tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Tag may only contain letters, digits, '-' and '_': {}", tag));
+        }
+
+        Ok(tag.to_lowercase())
+    }
+
+    /// Validate a device property key (1-64 letters, digits, '-', '_' or '.')
+    pub fn validate_property_key(key: &str) -> Result<String, String> {
+        let key = key.trim();
+        if key.is_empty() {
+            return Err("Property key cannot be empty".to_string());
+        }
+
+        let len = key.chars().count();
+        if len > 64 {
+            return Err(format!("Property key exceeds 64 characters (got {})", len));
+        }
+
+        if !key.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            return Err(format!("Property key may only contain letters, digits, '-', '_' and '.': {}", key));
+        }
+
+        Ok(key.to_string())
+    }
+
+    /// Validate a device property value (up to 256 chars, no control characters)
+    pub fn validate_property_value(value: &str) -> Result<String, String> {
+        let len = value.chars().count();
+        if len > 256 {
+            return Err(format!("Property value exceeds 256 characters (got {})", len));
+        }
+
+        if value.chars().any(|c| c.is_control()) {
+            return Err("Property value cannot contain control characters".to_string());
+        }
+
+        Ok(value.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -239,6 +336,30 @@ mod tests {
         assert!(Validator::validate_ipv6("not-ipv6").is_err());
     }
 
+    #[test]
+    fn test_validate_cidr_valid() {
+        assert_eq!(Validator::validate_cidr("192.168.1.0/24"), Ok((0xC0A8_0100, 24)));
+        // Host bits are cleared
+        assert_eq!(Validator::validate_cidr("10.0.0.77/30"), Ok((0x0A00_004C, 30)));
+        assert_eq!(Validator::validate_cidr("10.0.0.9/32"), Ok((0x0A00_0009, 32)));
+        assert_eq!(Validator::validate_cidr("8.8.8.8/0"), Ok((0, 0)));
+    }
+
+    #[test]
+    fn test_validate_cidr_out_of_range_prefix() {
+        assert!(Validator::validate_cidr("192.168.1.0/33").is_err());
+        assert!(Validator::validate_cidr("192.168.1.0/-1").is_err());
+        assert!(Validator::validate_cidr("192.168.1.0/").is_err());
+        assert!(Validator::validate_cidr("192.168.1.0").is_err());
+    }
+
+    #[test]
+    fn test_validate_cidr_non_numeric_octet() {
+        assert!(Validator::validate_cidr("192.168.x.0/24").is_err());
+        assert!(Validator::validate_cidr("192.168.1.256/24").is_err());
+        assert!(Validator::validate_cidr("192.168.1/24").is_err());
+    }
+
     #[test]
     fn test_validate_port_valid() {
         assert!(Validator::validate_port(1).is_ok());
@@ -308,4 +429,42 @@ mod tests {
     fn test_validate_notes_invalid() {
         assert!(Validator::validate_notes(&"x".repeat(1025)).is_err());
     }
+
+    #[test]
+    fn test_validate_icon_valid() {
+        assert!(Validator::validate_icon("🖨️").is_ok());
+        assert!(Validator::validate_icon("printer").is_ok());
+        assert!(Validator::validate_icon("nas-synology.png").is_ok());
+        assert!(Validator::validate_icon(&"x".repeat(64)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_icon_invalid() {
+        assert!(Validator::validate_icon("").is_err());
+        assert!(Validator::validate_icon(&"x".repeat(65)).is_err());
+        assert!(Validator::validate_icon("two words").is_err());
+        assert!(Validator::validate_icon("../etc/passwd").is_err());
+        assert!(Validator::validate_icon("C:\\icons\\a.png").is_err());
+    }
+
+    #[test]
+    fn test_validate_tag() {
+        assert_eq!(Validator::validate_tag(" Kids-Devices ").as_deref(), Ok("kids-devices"));
+        assert_eq!(Validator::validate_tag("work_vpn").as_deref(), Ok("work_vpn"));
+        assert!(Validator::validate_tag("").is_err());
+        assert!(Validator::validate_tag(&"x".repeat(33)).is_err());
+        assert!(Validator::validate_tag("two words").is_err());
+        assert!(Validator::validate_tag("a,b").is_err());
+    }
+
+    #[test]
+    fn test_validate_property() {
+        assert_eq!(Validator::validate_property_key(" room ").as_deref(), Ok("room"));
+        assert!(Validator::validate_property_key("asset.id").is_ok());
+        assert!(Validator::validate_property_key("").is_err());
+        assert!(Validator::validate_property_key("a=b").is_err());
+        assert!(Validator::validate_property_value("Office, 2nd floor").is_ok());
+        assert!(Validator::validate_property_value(&"x".repeat(257)).is_err());
+        assert!(Validator::validate_property_value("line\nbreak").is_err());
+    }
 }
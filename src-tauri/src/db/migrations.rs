@@ -1,6 +1,29 @@
 use rusqlite::Connection;
 
 const MIGRATION_001: &str = include_str!("../../migrations/001_initial.sql");
+const MIGRATION_002: &str = include_str!("../../migrations/002_device_icon.sql");
+const MIGRATION_003: &str = include_str!("../../migrations/003_alert_rule_id.sql");
+const MIGRATION_004: &str = include_str!("../../migrations/004_connection_type.sql");
+const MIGRATION_005: &str = include_str!("../../migrations/005_source_agent.sql");
+const MIGRATION_006: &str = include_str!("../../migrations/006_last_confirmed_ping.sql");
+const MIGRATION_007: &str = include_str!("../../migrations/007_risk_score.sql");
+const MIGRATION_008: &str = include_str!("../../migrations/008_discovery_sources.sql");
+const MIGRATION_009: &str = include_str!("../../migrations/009_gateway_down_rule.sql");
+const MIGRATION_010: &str = include_str!("../../migrations/010_notes_pinned.sql");
+const MIGRATION_011: &str = include_str!("../../migrations/011_hostname_changed_rule.sql");
+const MIGRATION_012: &str = include_str!("../../migrations/012_normalize_mac_case.sql");
+const MIGRATION_013: &str = include_str!("../../migrations/013_arp_spoof_rule.sql");
+const MIGRATION_014: &str = include_str!("../../migrations/014_randomized_mac.sql");
+const MIGRATION_015: &str = include_str!("../../migrations/015_ip_changed_rule.sql");
+const MIGRATION_016: &str = include_str!("../../migrations/016_scan_devices.sql");
+const MIGRATION_017: &str = include_str!("../../migrations/017_fingerprint_history.sql");
+const MIGRATION_018: &str = include_str!("../../migrations/018_ping_quality.sql");
+const MIGRATION_019: &str = include_str!("../../migrations/019_ip_address_family.sql");
+const MIGRATION_020: &str = include_str!("../../migrations/020_device_tags.sql");
+const MIGRATION_021: &str = include_str!("../../migrations/021_device_properties.sql");
+const MIGRATION_022: &str = include_str!("../../migrations/022_identity_changed_rule.sql");
+const MIGRATION_023: &str = include_str!("../../migrations/023_device_ips_lookup_index.sql");
+const MIGRATION_024: &str = include_str!("../../migrations/024_device_type_confidence.sql");
 
 struct Migration {
     name: &'static str,
@@ -12,6 +35,98 @@ const MIGRATIONS: &[Migration] = &[
         name: "001_initial",
         sql: MIGRATION_001,
     },
+    Migration {
+        name: "002_device_icon",
+        sql: MIGRATION_002,
+    },
+    Migration {
+        name: "003_alert_rule_id",
+        sql: MIGRATION_003,
+    },
+    Migration {
+        name: "004_connection_type",
+        sql: MIGRATION_004,
+    },
+    Migration {
+        name: "005_source_agent",
+        sql: MIGRATION_005,
+    },
+    Migration {
+        name: "006_last_confirmed_ping",
+        sql: MIGRATION_006,
+    },
+    Migration {
+        name: "007_risk_score",
+        sql: MIGRATION_007,
+    },
+    Migration {
+        name: "008_discovery_sources",
+        sql: MIGRATION_008,
+    },
+    Migration {
+        name: "009_gateway_down_rule",
+        sql: MIGRATION_009,
+    },
+    Migration {
+        name: "010_notes_pinned",
+        sql: MIGRATION_010,
+    },
+    Migration {
+        name: "011_hostname_changed_rule",
+        sql: MIGRATION_011,
+    },
+    Migration {
+        name: "012_normalize_mac_case",
+        sql: MIGRATION_012,
+    },
+    Migration {
+        name: "013_arp_spoof_rule",
+        sql: MIGRATION_013,
+    },
+    Migration {
+        name: "014_randomized_mac",
+        sql: MIGRATION_014,
+    },
+    Migration {
+        name: "015_ip_changed_rule",
+        sql: MIGRATION_015,
+    },
+    Migration {
+        name: "016_scan_devices",
+        sql: MIGRATION_016,
+    },
+    Migration {
+        name: "017_fingerprint_history",
+        sql: MIGRATION_017,
+    },
+    Migration {
+        name: "018_ping_quality",
+        sql: MIGRATION_018,
+    },
+    Migration {
+        name: "019_ip_address_family",
+        sql: MIGRATION_019,
+    },
+    Migration {
+        name: "020_device_tags",
+        sql: MIGRATION_020,
+    },
+    Migration {
+        name: "021_device_properties",
+        sql: MIGRATION_021,
+    },
+    Migration {
+        name: "022_identity_changed_rule",
+        sql: MIGRATION_022,
+    },
+    Migration {
+        name: "023_device_ips_lookup_index",
+        sql: MIGRATION_023,
+    },
+    Migration {
+        name: "024_device_type_confidence",
+        sql: MIGRATION_024,
+    },
 ];
 
 /// Run all pending migrations inside a transaction.
@@ -84,6 +199,34 @@ mod tests {
         assert!(tables.contains(&"settings".to_string()));
     }
 
+    #[test]
+    fn test_per_device_lookups_use_indexes() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        run(&conn).unwrap();
+
+        let index_exists = |name: &str| {
+            conn.query_row("SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?1", [name], |_| Ok(()))
+                .is_ok()
+        };
+        assert!(index_exists("idx_devices_mac"));
+        assert!(index_exists("idx_device_ips_device_ip"));
+
+        for sql in [
+            "SELECT id FROM devices WHERE mac_address = 'AA:BB:CC:DD:EE:FF'",
+            "SELECT id FROM device_ips WHERE device_id = 'dev1' AND ip_address = '192.168.1.20'",
+        ] {
+            let plan: Vec<String> = conn
+                .prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+                .unwrap()
+                .query_map([], |row| row.get(3))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert!(plan.iter().all(|step| step.contains("USING")), "{}: {:?}", sql, plan);
+        }
+    }
+
     #[test]
     fn test_migrations_are_idempotent() {
         let conn = Connection::open_in_memory().unwrap();
@@ -102,7 +245,54 @@ mod tests {
         let count: i64 = conn
             .query_row("SELECT COUNT(*) FROM alert_rules", [], |row| row.get(0))
             .unwrap();
-        assert_eq!(count, 4);
+        assert_eq!(count, 10);
+    }
+
+    #[test]
+    fn test_mac_case_duplicates_merged() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        run(&conn).unwrap();
+
+        // Rows written before MACs were normalized, then re-apply the migration
+        conn.execute_batch(
+            "INSERT INTO devices (id, mac_address, is_trusted, custom_name, notes, first_seen, last_seen) VALUES
+                ('old', 'aa:bb:cc:dd:ee:ff', 0, NULL, 'Rack 2', '2026-01-01 00:00:00', '2026-01-02 00:00:00'),
+                ('dup', 'AA-BB-CC-DD-EE-FF', 1, 'Office NAS', 'Stale', '2026-01-03 00:00:00', '2026-01-04 00:00:00'),
+                ('other', '11:22:33:44:55:66', 0, NULL, NULL, '2026-01-01 00:00:00', '2026-01-01 00:00:00');
+             INSERT INTO device_ips (id, device_id, ip_address, is_current, last_seen) VALUES
+                ('ip1', 'old', '192.168.1.10', 1, '2026-01-02 00:00:00'),
+                ('ip2', 'dup', '192.168.1.20', 1, '2026-01-04 00:00:00');
+             INSERT INTO alerts (id, alert_type, device_id, message) VALUES ('a1', 'new_device', 'dup', 'New device');
+             DELETE FROM _migrations WHERE name = '012_normalize_mac_case';",
+        )
+        .unwrap();
+        run(&conn).unwrap();
+
+        let devices: Vec<(String, String, bool, String)> = conn
+            .prepare("SELECT id, mac_address, is_trusted, last_seen FROM devices ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0], ("old".to_string(), "AA:BB:CC:DD:EE:FF".to_string(), true, "2026-01-04 00:00:00".to_string()));
+        assert_eq!(devices[1].1, "11:22:33:44:55:66");
+
+        // The survivor keeps its own notes and takes the name only the duplicate had
+        let (name, notes): (Option<String>, Option<String>) = conn
+            .query_row("SELECT custom_name, notes FROM devices WHERE id = 'old'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(name.as_deref(), Some("Office NAS"));
+        assert_eq!(notes.as_deref(), Some("Rack 2"));
+
+        let current_ip: String = conn
+            .query_row("SELECT ip_address FROM device_ips WHERE device_id = 'old' AND is_current = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(current_ip, "192.168.1.20");
+        let alert_device: String = conn.query_row("SELECT device_id FROM alerts WHERE id = 'a1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(alert_device, "old");
     }
 
     #[test]
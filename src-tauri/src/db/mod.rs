@@ -1,10 +1,12 @@
 pub mod migrations;
 pub mod queries;
+pub mod recovery;
 
 use std::path::Path;
 
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::Connection;
 
 /// Initialize the SQLite database with connection pooling and WAL mode.
 pub fn init_db(app_data_dir: &Path) -> Result<Pool<SqliteConnectionManager>, Box<dyn std::error::Error>> {
@@ -29,11 +31,45 @@ pub fn init_db(app_data_dir: &Path) -> Result<Pool<SqliteConnectionManager>, Box
     // Run migrations
     let conn = pool.get()?;
     migrations::run(&conn)?;
+    let report = diagnose_and_repair(&conn)?;
+    if !report.integrity_ok || report.current_ips_repaired > 0 {
+        log::warn!("Database maintenance at startup: {:?}", report);
+    }
 
     log::info!("Database initialized successfully");
     Ok(pool)
 }
 
+/// Run SQLite's quick integrity check. Returns true when the database reports "ok".
+pub fn check_integrity(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    let result: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    Ok(result == "ok")
+}
+
+/// Outcome of `diagnose_and_repair`.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+    /// Result of SQLite's quick integrity check.
+    pub integrity_ok: bool,
+    /// `device_ips` rows whose current flag was corrected.
+    pub current_ips_repaired: usize,
+}
+
+/// Check the database and fix inconsistencies the app can leave behind after a crash.
+pub fn diagnose_and_repair(conn: &Connection) -> Result<RepairReport, rusqlite::Error> {
+    Ok(RepairReport {
+        integrity_ok: check_integrity(conn)?,
+        current_ips_repaired: queries::devices::repair_current_ips(conn)?,
+    })
+}
+
+/// Fold the WAL back into the main database file and truncate it.
+pub fn checkpoint(conn: &Connection) -> Result<(), rusqlite::Error> {
+    // Returns (busy, log frames, checkpointed frames); only success matters here
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+}
+
 #[cfg(test)]
 pub fn init_test_db() -> Pool<SqliteConnectionManager> {
     let manager = SqliteConnectionManager::memory()
@@ -46,3 +82,24 @@ pub fn init_test_db() -> Pool<SqliteConnectionManager> {
     migrations::run(&conn).unwrap();
     pool
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_integrity_on_fresh_db() {
+        let pool = init_test_db();
+        let conn = pool.get().unwrap();
+        assert!(check_integrity(&conn).unwrap());
+    }
+
+    #[test]
+    fn test_diagnose_and_repair_on_fresh_db() {
+        let pool = init_test_db();
+        let conn = pool.get().unwrap();
+        let report = diagnose_and_repair(&conn).unwrap();
+        assert!(report.integrity_ok);
+        assert_eq!(report.current_ips_repaired, 0);
+    }
+}
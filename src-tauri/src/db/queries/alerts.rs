@@ -11,6 +11,8 @@ pub struct Alert {
     pub severity: String,
     pub is_read: bool,
     pub created_at: String,
+    /// Rule that generated this alert, if known.
+    pub rule_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,41 +41,116 @@ pub fn insert_alert(
     device_id: Option<&str>,
     message: &str,
     severity: &str,
+    rule_id: Option<&str>,
 ) -> Result<(), rusqlite::Error> {
     conn.execute(
-        "INSERT INTO alerts (id, alert_type, device_id, message, severity)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![id, alert_type, device_id, message, severity],
+        "INSERT INTO alerts (id, alert_type, device_id, message, severity, rule_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, alert_type, device_id, message, severity, rule_id],
     )?;
     Ok(())
 }
 
+/// An alert to be persisted by `insert_alerts`.
+#[derive(Debug, Clone, Copy)]
+pub struct NewAlert<'a> {
+    pub alert_type: &'a str,
+    pub device_id: Option<&'a str>,
+    pub message: &'a str,
+    pub severity: &'a str,
+    pub rule_id: Option<&'a str>,
+}
+
+/// Insert many alerts in one transaction with a single prepared statement.
+/// Either all alerts are written or none are. Returns the generated ids in order.
+pub fn insert_alerts(conn: &Connection, alerts: &[NewAlert]) -> Result<Vec<String>, rusqlite::Error> {
+    if alerts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let mut ids = Vec::with_capacity(alerts.len());
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO alerts (id, alert_type, device_id, message, severity, rule_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for alert in alerts {
+            let id = uuid::Uuid::new_v4().to_string();
+            stmt.execute(params![
+                id,
+                alert.alert_type,
+                alert.device_id,
+                alert.message,
+                alert.severity,
+                alert.rule_id
+            ])?;
+            ids.push(id);
+        }
+    }
+    tx.commit()?;
+    Ok(ids)
+}
+
+/// Whether an alert with this type, device and message was stored in the last `within_secs`.
+pub fn has_recent_alert(
+    conn: &Connection,
+    alert_type: &str,
+    device_id: Option<&str>,
+    message: &str,
+    within_secs: u64,
+) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM alerts
+            WHERE alert_type = ?1 AND device_id IS ?2 AND message = ?3
+              AND created_at >= datetime('now', ?4)
+         )",
+        params![alert_type, device_id, message, format!("-{} seconds", within_secs)],
+        |row| row.get(0),
+    )
+}
+
 /// Get alerts, optionally filtering to unread only.
 pub fn get_alerts(conn: &Connection, unread_only: bool) -> Result<Vec<Alert>, rusqlite::Error> {
     let sql = if unread_only {
-        "SELECT id, alert_type, device_id, message, severity, is_read, created_at
+        "SELECT id, alert_type, device_id, message, severity, is_read, created_at, rule_id
          FROM alerts WHERE is_read = 0 ORDER BY created_at DESC"
     } else {
-        "SELECT id, alert_type, device_id, message, severity, is_read, created_at
+        "SELECT id, alert_type, device_id, message, severity, is_read, created_at, rule_id
          FROM alerts ORDER BY created_at DESC"
     };
 
     let mut stmt = conn.prepare(sql)?;
-    let alerts = stmt.query_map([], |row| {
-        Ok(Alert {
-            id: row.get(0)?,
-            alert_type: row.get(1)?,
-            device_id: row.get(2)?,
-            message: row.get(3)?,
-            severity: row.get(4)?,
-            is_read: row.get(5)?,
-            created_at: row.get(6)?,
-        })
-    })?;
+    let alerts = stmt.query_map([], alert_from_row)?;
+
+    alerts.collect()
+}
+
+/// Get all alerts generated by a specific rule, newest first.
+pub fn get_alerts_by_rule(conn: &Connection, rule_id: &str) -> Result<Vec<Alert>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, alert_type, device_id, message, severity, is_read, created_at, rule_id
+         FROM alerts WHERE rule_id = ?1 ORDER BY created_at DESC"
+    )?;
+    let alerts = stmt.query_map([rule_id], alert_from_row)?;
 
     alerts.collect()
 }
 
+fn alert_from_row(row: &rusqlite::Row<'_>) -> Result<Alert, rusqlite::Error> {
+    Ok(Alert {
+        id: row.get(0)?,
+        alert_type: row.get(1)?,
+        device_id: row.get(2)?,
+        message: row.get(3)?,
+        severity: row.get(4)?,
+        is_read: row.get(5)?,
+        created_at: row.get(6)?,
+        rule_id: row.get(7)?,
+    })
+}
+
 /// Mark a single alert as read.
 pub fn mark_alert_read(conn: &Connection, alert_id: &str) -> Result<(), rusqlite::Error> {
     conn.execute(
@@ -150,8 +227,8 @@ mod tests {
             &conn, "dev1", Some("AA:BB:CC:DD:EE:FF"), None, None, "unknown", false, None,
         ).unwrap();
 
-        insert_alert(&conn, "alert1", "new_device", Some("dev1"), "New device found", "info").unwrap();
-        insert_alert(&conn, "alert2", "port_changed", Some("dev1"), "Port 80 opened", "warning").unwrap();
+        insert_alert(&conn, "alert1", "new_device", Some("dev1"), "New device found", "info", None).unwrap();
+        insert_alert(&conn, "alert2", "port_changed", Some("dev1"), "Port 80 opened", "warning", None).unwrap();
 
         let all = get_alerts(&conn, false).unwrap();
         assert_eq!(all.len(), 2);
@@ -174,7 +251,7 @@ mod tests {
         let conn = pool.get().unwrap();
 
         let rules = get_alert_rules(&conn).unwrap();
-        assert_eq!(rules.len(), 4); // Seeded by migration
+        assert_eq!(rules.len(), 10); // Seeded by migration
 
         let update = AlertRuleUpdate {
             is_enabled: Some(false),
@@ -187,4 +264,47 @@ mod tests {
         let rule = rules.iter().find(|r| r.id == "rule_new_device").unwrap();
         assert!(!rule.is_enabled);
     }
+
+    #[test]
+    fn test_get_alerts_by_rule() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_alert(&conn, "a1", "new_device", None, "New", "info", Some("rule_new_device")).unwrap();
+        insert_alert(&conn, "a2", "new_device", None, "New", "info", Some("rule_new_device")).unwrap();
+        insert_alert(&conn, "a3", "device_departed", None, "Gone", "info", Some("rule_device_departed")).unwrap();
+
+        let alerts = get_alerts_by_rule(&conn, "rule_new_device").unwrap();
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts.iter().all(|a| a.rule_id.as_deref() == Some("rule_new_device")));
+
+        assert!(get_alerts_by_rule(&conn, "rule_port_changed").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_insert_alerts_batch_is_atomic() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        crate::db::queries::devices::insert_device(
+            &conn, "dev1", Some("AA:BB:CC:DD:EE:FF"), None, None, "unknown", false, None,
+        ).unwrap();
+
+        let alert = |device_id| NewAlert {
+            alert_type: "new_device",
+            device_id: Some(device_id),
+            message: "New device",
+            severity: "info",
+            rule_id: None,
+        };
+
+        let ids = insert_alerts(&conn, &[alert("dev1"), alert("dev1"), alert("dev1")]).unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(get_alerts(&conn, false).unwrap().len(), 3);
+
+        // A bad row rolls back the whole batch
+        assert!(insert_alerts(&conn, &[alert("dev1"), alert("missing")]).is_err());
+        assert_eq!(get_alerts(&conn, false).unwrap().len(), 3);
+
+        assert!(insert_alerts(&conn, &[]).unwrap().is_empty());
+    }
 }
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
+
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 
 use crate::db::queries::ports;
+use crate::network::oui;
+use crate::util::time;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,21 +21,144 @@ pub struct Device {
     pub is_trusted: bool,
     pub is_gateway: bool,
     pub notes: Option<String>,
+    /// Whether the user flagged the notes as important.
+    #[serde(default)]
+    pub notes_pinned: bool,
+    /// Locally administered MAC, usually a randomized private address.
+    #[serde(default)]
+    pub is_randomized_mac: bool,
+    /// Emoji or short icon key shown next to the device.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// "wired", "wireless", "virtual" or "unknown".
+    #[serde(default = "default_connection_type")]
+    pub connection_type: String,
+    /// Remote agent that reported this device, if it came in through the ingest endpoint.
+    #[serde(default)]
+    pub source_agent: Option<String>,
+    /// The current IPv4 address, or the current IPv6 one for a device without IPv4.
     pub current_ip: Option<String>,
+    /// Every IPv6 address the device has held, current first.
+    #[serde(default)]
+    pub ipv6_addresses: Vec<String>,
     pub is_online: bool,
+    /// Confidence in `[0, 1]` that the device is present, decaying with time since contact.
+    #[serde(default)]
+    pub presence_score: f64,
+    /// When the device last answered a ping.
+    #[serde(default)]
+    pub last_confirmed_ping: Option<String>,
+    /// Triage score from 0 to 100; see `security::risk`.
+    #[serde(default)]
+    pub risk_score: u32,
+    /// How the latest scan that saw the device found it, e.g. `["arp", "ping"]`.
+    #[serde(default)]
+    pub discovery_sources: Vec<String>,
+    /// User labels such as "work" or "kids-devices", alphabetical.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Key/values attached by the user or fingerprinting, e.g. `room=office`.
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
     pub latency_ms: Option<f64>,
+    /// Percent of echo requests lost in the latest latency sample, when it measured loss.
+    #[serde(default)]
+    pub packet_loss: Option<f64>,
     pub open_ports: Vec<ports::PortInfo>,
     pub first_seen: String,
     pub last_seen: String,
 }
 
-#[derive(Debug, Deserialize)]
+fn default_connection_type() -> String {
+    "unknown".to_string()
+}
+
+#[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeviceUpdate {
     pub custom_name: Option<Option<String>>,
     pub device_type: Option<String>,
     pub is_trusted: Option<bool>,
     pub notes: Option<Option<String>>,
+    #[serde(default)]
+    pub notes_pinned: Option<bool>,
+    pub icon: Option<Option<String>>,
+}
+
+/// Filters and paging for `get_devices_paged`. Unset filters match every device.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeviceQuery {
+    /// Page size, capped at `MAX_PAGE_SIZE`. Defaults to `DEFAULT_PAGE_SIZE`.
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// Case-insensitive substring of the hostname, custom name, current IP or MAC.
+    pub search: Option<String>,
+    pub device_type: Option<String>,
+    /// Only devices carrying this tag.
+    pub tag: Option<String>,
+    pub trusted_only: bool,
+    pub online_only: bool,
+}
+
+/// One page of devices, with how many match the filters across all pages.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevicePage {
+    pub items: Vec<Device>,
+    pub total: u32,
+}
+
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+/// A device whose notes match a search, with the matching part of the text.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteMatch {
+    pub device_id: String,
+    pub snippet: String,
+    pub notes_pinned: bool,
+}
+
+/// An address a device has held, from `device_ips`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IpHistoryEntry {
+    pub ip_address: String,
+    pub is_current: bool,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+/// What one fingerprint pass concluded about a device, from `fingerprint_history`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintEntry {
+    pub os_guess: Option<String>,
+    pub os_confidence: Option<f64>,
+    pub device_type: String,
+    pub scan_id: Option<String>,
+    pub created_at: String,
+}
+
+/// Characters of context kept on each side of a match in a note snippet.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Canonical stored form of a MAC address: uppercase, colon-separated (`AA:BB:CC:DD:EE:FF`).
+/// Accepts colon, hyphen and Cisco dot notation; anything else is only uppercased.
+pub fn normalize_mac(mac: &str) -> String {
+    let mac = mac.trim();
+    let well_formed = mac.chars().all(|c| c.is_ascii_hexdigit() || matches!(c, ':' | '-' | '.'));
+    let digits: Vec<char> = mac.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    if !well_formed || digits.len() != 12 {
+        return mac.to_uppercase();
+    }
+    digits
+        .chunks(2)
+        .map(|pair| pair.iter().collect::<String>().to_uppercase())
+        .collect::<Vec<_>>()
+        .join(":")
 }
 
 /// Insert a new device and its current IP.
@@ -45,96 +172,273 @@ pub fn insert_device(
     is_gateway: bool,
     ip_address: Option<&str>,
 ) -> Result<(), rusqlite::Error> {
+    let mac = mac_address.map(normalize_mac);
+    let randomized = mac.as_deref().is_some_and(oui::is_locally_administered);
     conn.execute(
-        "INSERT INTO devices (id, mac_address, vendor, hostname, device_type, is_gateway)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![id, mac_address, vendor, hostname, device_type, is_gateway],
+        "INSERT INTO devices (id, mac_address, vendor, hostname, device_type, is_gateway, is_randomized_mac)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![id, mac, vendor, hostname, device_type, is_gateway, randomized],
     )?;
 
     if let Some(ip) = ip_address {
         let ip_id = uuid::Uuid::new_v4().to_string();
         conn.execute(
-            "INSERT INTO device_ips (id, device_id, ip_address, is_current)
-             VALUES (?1, ?2, ?3, 1)",
-            params![ip_id, id, ip],
+            "INSERT INTO device_ips (id, device_id, ip_address, is_current, address_family)
+             VALUES (?1, ?2, ?3, 1, ?4)",
+            params![ip_id, id, ip, address_family(ip)],
         )?;
     }
 
     Ok(())
 }
 
+/// `device_ips.address_family` for an address.
+fn address_family(ip: &str) -> &'static str {
+    if ip.contains(':') {
+        "ipv6"
+    } else {
+        "ipv4"
+    }
+}
+
+/// Device columns with the current IP and latest latency, for `device_from_row`.
+/// `di` is the current IPv4 address, or the current IPv6 one when there is none.
+/// Callers append their own `WHERE`/`ORDER BY`.
+const DEVICE_SELECT: &str = "SELECT
+        d.id, d.mac_address, d.vendor, d.hostname, d.custom_name,
+        d.device_type, d.os_guess, d.os_confidence, d.is_trusted, d.is_gateway,
+        d.notes, d.first_seen, d.last_seen,
+        di.ip_address,
+        lh.latency_ms,
+        d.icon, d.connection_type, d.source_agent, d.last_confirmed_ping,
+        d.risk_score, d.discovery_sources, d.notes_pinned, d.is_randomized_mac,
+        lh.packet_loss,
+        (SELECT group_concat(ip_address, ',') FROM (
+            SELECT ip_address FROM device_ips
+            WHERE device_id = d.id AND address_family = 'ipv6'
+            ORDER BY is_current DESC, last_seen DESC
+        )) AS ipv6_addresses,
+        (SELECT group_concat(tag, ',') FROM (
+            SELECT tag FROM device_tags WHERE device_id = d.id ORDER BY tag
+        )) AS tags,
+        (SELECT json_group_object(key, value) FROM device_properties
+         WHERE device_id = d.id) AS properties
+     FROM devices d
+     LEFT JOIN device_ips di ON di.id = (
+        SELECT id FROM device_ips
+        WHERE device_id = d.id AND is_current = 1
+        ORDER BY address_family = 'ipv6'
+        LIMIT 1
+     )
+     LEFT JOIN (
+        SELECT device_id, latency_ms, packet_loss
+        FROM latency_history
+        WHERE id IN (SELECT MAX(id) FROM latency_history GROUP BY device_id)
+     ) lh ON lh.device_id = d.id";
+
+/// Map a `DEVICE_SELECT` row. Ports are left empty for the caller to attach.
+fn device_from_row(row: &rusqlite::Row) -> Result<Device, rusqlite::Error> {
+    let last_seen: String = row.get(12)?;
+
+    let last_confirmed_ping: Option<String> = row.get(18)?;
+    let discovery_sources: String = row.get(20)?;
+    let ipv6_addresses: Option<String> = row.get(24)?;
+    let tags: Option<String> = row.get(25)?;
+    let properties: String = row.get(26)?;
+    let properties = serde_json::from_str(&properties).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(26, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+
+    // Consider device online if seen in the last 5 minutes
+    let is_online = is_recently_seen(&last_seen);
+    let presence_score = presence_score(&last_seen, last_confirmed_ping.as_deref());
+
+    Ok(Device {
+        id: row.get(0)?,
+        mac_address: row.get(1)?,
+        vendor: row.get(2)?,
+        hostname: row.get(3)?,
+        custom_name: row.get(4)?,
+        device_type: row.get(5)?,
+        os_guess: row.get(6)?,
+        os_confidence: row.get(7)?,
+        is_trusted: row.get(8)?,
+        is_gateway: row.get(9)?,
+        notes: row.get(10)?,
+        notes_pinned: row.get(21)?,
+        is_randomized_mac: row.get(22)?,
+        first_seen: row.get(11)?,
+        last_seen,
+        current_ip: row.get(13)?,
+        ipv6_addresses: split_list(ipv6_addresses.as_deref().unwrap_or_default()),
+        latency_ms: row.get(14)?,
+        packet_loss: row.get(23)?,
+        icon: row.get(15)?,
+        connection_type: row.get(16)?,
+        source_agent: row.get(17)?,
+        is_online,
+        presence_score,
+        last_confirmed_ping,
+        risk_score: row.get(19)?,
+        discovery_sources: split_list(&discovery_sources),
+        tags: split_list(tags.as_deref().unwrap_or_default()),
+        properties,
+        open_ports: Vec::new(),
+    })
+}
+
+/// Split a comma-joined column into its non-empty items.
+fn split_list(joined: &str) -> Vec<String> {
+    joined
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
 /// Get all known devices with their current IP and latest port data.
 pub fn get_all_devices(conn: &Connection) -> Result<Vec<Device>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
-        "SELECT
-            d.id, d.mac_address, d.vendor, d.hostname, d.custom_name,
-            d.device_type, d.os_guess, d.os_confidence, d.is_trusted, d.is_gateway,
-            d.notes, d.first_seen, d.last_seen,
-            di.ip_address,
-            lh.latency_ms
-         FROM devices d
-         LEFT JOIN device_ips di ON di.device_id = d.id AND di.is_current = 1
-         LEFT JOIN (
-            SELECT device_id, latency_ms
-            FROM latency_history
-            WHERE id IN (SELECT MAX(id) FROM latency_history GROUP BY device_id)
-         ) lh ON lh.device_id = d.id
-         ORDER BY d.last_seen DESC"
+    let mut ports_by_device = ports::get_latest_ports_by_device(conn)?;
+
+    let mut stmt = conn.prepare(&format!("{} ORDER BY d.last_seen DESC", DEVICE_SELECT))?;
+    let devices = stmt.query_map([], device_from_row)?;
+
+    devices
+        .map(|device| {
+            let mut device = device?;
+            device.open_ports = ports_by_device.remove(&device.id).unwrap_or_default();
+            Ok(device)
+        })
+        .collect()
+}
+
+/// `DeviceQuery` filters over `DEVICE_SELECT`'s aliases. Every value is bound:
+/// ?1 escaped search, ?2 device type, ?3 trusted only, ?4 online only.
+const DEVICE_FILTER: &str = "
+     WHERE (?1 IS NULL
+            OR d.hostname LIKE '%' || ?1 || '%' ESCAPE '\\'
+            OR d.custom_name LIKE '%' || ?1 || '%' ESCAPE '\\'
+            OR di.ip_address LIKE '%' || ?1 || '%' ESCAPE '\\'
+            OR d.mac_address LIKE '%' || ?1 || '%' ESCAPE '\\')
+       AND (?2 IS NULL OR d.device_type = ?2)
+       AND (?3 = 0 OR d.is_trusted = 1)
+       AND (?4 = 0 OR d.last_seen > datetime('now', '-5 minutes'))
+       AND (?5 IS NULL OR EXISTS (
+            SELECT 1 FROM device_tags t WHERE t.device_id = d.id AND t.tag = ?5))";
+
+/// One page of devices matching `query`, most recently seen first, with the total match count.
+pub fn get_devices_paged(conn: &Connection, query: &DeviceQuery) -> Result<DevicePage, rusqlite::Error> {
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(escape_like);
+    let device_type = query.device_type.as_deref().filter(|t| !t.is_empty());
+    let tag = query.tag.as_deref().filter(|t| !t.is_empty());
+
+    let total: u32 = conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM devices d
+             LEFT JOIN device_ips di ON di.id = (
+                SELECT id FROM device_ips
+                WHERE device_id = d.id AND is_current = 1
+                ORDER BY address_family = 'ipv6'
+                LIMIT 1
+             )
+             {}",
+            DEVICE_FILTER
+        ),
+        params![search, device_type, query.trusted_only, query.online_only, tag],
+        |row| row.get(0),
     )?;
 
-    let devices = stmt.query_map([], |row| {
-        let id: String = row.get(0)?;
-        let last_seen: String = row.get(12)?;
-
-        // Consider device online if seen in the last 5 minutes
-        let is_online = is_recently_seen(&last_seen);
-
-        Ok(Device {
-            id,
-            mac_address: row.get(1)?,
-            vendor: row.get(2)?,
-            hostname: row.get(3)?,
-            custom_name: row.get(4)?,
-            device_type: row.get(5)?,
-            os_guess: row.get(6)?,
-            os_confidence: row.get(7)?,
-            is_trusted: row.get(8)?,
-            is_gateway: row.get(9)?,
-            notes: row.get(10)?,
-            first_seen: row.get(11)?,
-            last_seen,
-            current_ip: row.get(13)?,
-            latency_ms: row.get(14)?,
-            is_online,
-            open_ports: Vec::new(), // Populated separately if needed
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0);
+    let mut stmt = conn.prepare(&format!(
+        "{} {} ORDER BY d.last_seen DESC, d.id LIMIT ?6 OFFSET ?7",
+        DEVICE_SELECT, DEVICE_FILTER
+    ))?;
+    let items = stmt
+        .query_map(
+            params![search, device_type, query.trusted_only, query.online_only, tag, limit, offset],
+            device_from_row,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Only the page's own devices: loading every device's ports would defeat paging
+    let items = items
+        .into_iter()
+        .map(|mut device| {
+            device.open_ports = ports::get_latest_ports(conn, &device.id)?;
+            Ok(device)
         })
-    })?;
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+    Ok(DevicePage { items, total })
+}
 
-    devices.collect()
+/// Escape `LIKE` wildcards so user input matches literally under `ESCAPE '\'`.
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
 }
 
 /// Get a single device by ID, including its open ports.
 pub fn get_device_by_id(conn: &Connection, device_id: &str) -> Result<Option<Device>, rusqlite::Error> {
-    let mut devices = get_all_devices(conn)?;
-    let device = devices.iter_mut().find(|d| d.id == device_id);
+    let device = conn
+        .prepare_cached(&format!("{} WHERE d.id = ?1", DEVICE_SELECT))?
+        .query_row([device_id], device_from_row)
+        .optional()?;
 
     match device {
-        Some(d) => {
+        Some(mut d) => {
             d.open_ports = ports::get_latest_ports(conn, &d.id)?;
-            Ok(Some(d.clone()))
+            Ok(Some(d))
         }
         None => Ok(None),
     }
 }
 
+/// How much is unknown about a device: one point each for no name, no vendor and an
+/// unknown type. Trusted devices score zero since the user has already vouched for them.
+pub fn review_score(device: &Device) -> u32 {
+    if device.is_trusted {
+        return 0;
+    }
+    let unnamed = device.custom_name.is_none() && device.hostname.is_none();
+    let no_vendor = device.vendor.is_none();
+    let unknown_type = device.device_type == "unknown";
+    [unnamed, no_vendor, unknown_type].iter().filter(|gap| **gap).count() as u32
+}
+
+/// Untrusted devices with at least one identity gap, least identified first.
+pub fn get_devices_needing_review(conn: &Connection) -> Result<Vec<Device>, rusqlite::Error> {
+    // get_all_devices is already ordered by last_seen, which the stable sort keeps as a tiebreak
+    let mut devices: Vec<Device> = get_all_devices(conn)?
+        .into_iter()
+        .filter(|d| review_score(d) > 0)
+        .collect();
+    devices.sort_by_key(|d| std::cmp::Reverse(review_score(d)));
+    Ok(devices)
+}
+
+/// Count all known devices.
+pub fn count_devices(conn: &Connection) -> Result<u32, rusqlite::Error> {
+    conn.query_row("SELECT COUNT(*) FROM devices", [], |row| row.get(0))
+}
+
 /// Find a device by MAC address.
 pub fn get_device_by_mac(conn: &Connection, mac: &str) -> Result<Option<String>, rusqlite::Error> {
-    conn.query_row(
-        "SELECT id FROM devices WHERE mac_address = ?1",
-        [mac],
-        |row| row.get(0),
-    )
-    .optional()
+    conn.prepare_cached("SELECT id FROM devices WHERE mac_address = ?1")?
+        .query_row([normalize_mac(mac)], |row| row.get(0))
+        .optional()
+}
+
+/// Find a device by its current IP address.
+pub fn get_device_by_ip(conn: &Connection, ip: &str) -> Result<Option<String>, rusqlite::Error> {
+    conn.prepare_cached("SELECT device_id FROM device_ips WHERE ip_address = ?1 AND is_current = 1 LIMIT 1")?
+        .query_row([ip], |row| row.get(0))
+        .optional()
 }
 
 /// Update a device's user-editable fields.
@@ -147,7 +451,7 @@ pub fn update_device(conn: &Connection, device_id: &str, updates: &DeviceUpdate)
     }
     if let Some(ref dtype) = updates.device_type {
         conn.execute(
-            "UPDATE devices SET device_type = ?1 WHERE id = ?2",
+            "UPDATE devices SET device_type = ?1, type_manual = 1 WHERE id = ?2",
             params![dtype, device_id],
         )?;
     }
@@ -163,19 +467,128 @@ pub fn update_device(conn: &Connection, device_id: &str, updates: &DeviceUpdate)
             params![notes, device_id],
         )?;
     }
+    if let Some(pinned) = updates.notes_pinned {
+        conn.execute(
+            "UPDATE devices SET notes_pinned = ?1 WHERE id = ?2",
+            params![pinned, device_id],
+        )?;
+    }
+    if let Some(ref icon) = updates.icon {
+        conn.execute(
+            "UPDATE devices SET icon = ?1 WHERE id = ?2",
+            params![icon, device_id],
+        )?;
+    }
     Ok(())
 }
 
+/// Devices whose notes contain `query` (ASCII case-insensitive), pinned notes first.
+pub fn search_notes(conn: &Connection, query: &str) -> Result<Vec<NoteMatch>, rusqlite::Error> {
+    let escaped = escape_like(query);
+    let mut stmt = conn.prepare(
+        "SELECT id, notes, notes_pinned FROM devices
+         WHERE notes LIKE '%' || ?1 || '%' ESCAPE '\\'
+         ORDER BY notes_pinned DESC, last_seen DESC",
+    )?;
+
+    let matches = stmt.query_map([&escaped], |row| {
+        let notes: String = row.get(1)?;
+        Ok(NoteMatch {
+            device_id: row.get(0)?,
+            snippet: note_snippet(&notes, query),
+            notes_pinned: row.get(2)?,
+        })
+    })?;
+    matches.collect()
+}
+
+/// The first match of `query` in `notes` with some context either side, marking cut text with "…".
+fn note_snippet(notes: &str, query: &str) -> String {
+    // ASCII lowering keeps byte offsets aligned with the original, matching LIKE's case rules
+    let Some(at) = notes.to_ascii_lowercase().find(&query.to_ascii_lowercase()) else {
+        return notes.chars().take(2 * SNIPPET_CONTEXT).collect();
+    };
+
+    let start = notes[..at]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let end = notes[at + query.len()..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(notes.len(), |(i, _)| at + query.len() + i);
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(notes[start..end].trim());
+    if end < notes.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
 /// Delete a device and all associated data (cascades).
 pub fn delete_device(conn: &Connection, device_id: &str) -> Result<(), rusqlite::Error> {
     conn.execute("DELETE FROM devices WHERE id = ?1", [device_id])?;
     Ok(())
 }
 
-/// Update hostname for a device (only if it doesn't already have one).
+/// Update how a device is attached to the network.
+pub fn update_connection_type(
+    conn: &Connection,
+    device_id: &str,
+    connection_type: &str,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE devices SET connection_type = ?1 WHERE id = ?2",
+        params![connection_type, device_id],
+    )?;
+    Ok(())
+}
+
+/// Store a device's recomputed risk score.
+pub fn update_risk_score(conn: &Connection, device_id: &str, score: u32) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE devices SET risk_score = ?1 WHERE id = ?2",
+        params![score, device_id],
+    )?;
+    Ok(())
+}
+
+/// Replace the discovery source tags recorded for a device.
+pub fn set_discovery_sources(conn: &Connection, device_id: &str, sources: &[&str]) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE devices SET discovery_sources = ?1 WHERE id = ?2",
+        params![sources.join(","), device_id],
+    )?;
+    Ok(())
+}
+
+/// Record that a device just answered a ping.
+pub fn mark_ping_confirmed(conn: &Connection, device_id: &str) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE devices SET last_confirmed_ping = datetime('now') WHERE id = ?1",
+        [device_id],
+    )?;
+    Ok(())
+}
+
+/// Record which remote agent reported a device.
+pub fn set_source_agent(conn: &Connection, device_id: &str, agent: &str) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE devices SET source_agent = ?1 WHERE id = ?2",
+        params![agent, device_id],
+    )?;
+    Ok(())
+}
+
+/// Store the latest resolved hostname for a device.
 pub fn update_hostname(conn: &Connection, device_id: &str, hostname: &str) -> Result<(), rusqlite::Error> {
     conn.execute(
-        "UPDATE devices SET hostname = ?1 WHERE id = ?2 AND hostname IS NULL",
+        "UPDATE devices SET hostname = ?1 WHERE id = ?2",
         params![hostname, device_id],
     )?;
     Ok(())
@@ -190,77 +603,317 @@ pub fn update_os_guess(conn: &Connection, device_id: &str, os_guess: &str, confi
     Ok(())
 }
 
-/// Update device type classification.
-pub fn update_device_type(conn: &Connection, device_id: &str, device_type: &str) -> Result<(), rusqlite::Error> {
+/// Store a fingerprint pass's device type, made with `confidence`, when it beats the
+/// current one: an unknown type, or an automatic one made with less confidence. A type
+/// the user set is never replaced. Returns whether the type was stored.
+pub fn update_device_type(
+    conn: &Connection,
+    device_id: &str,
+    device_type: &str,
+    confidence: f64,
+) -> Result<bool, rusqlite::Error> {
+    let changed = conn.execute(
+        "UPDATE devices SET device_type = ?1, type_confidence = ?2
+         WHERE id = ?3 AND type_manual = 0 AND (device_type = 'unknown' OR ?2 > type_confidence)",
+        params![device_type, confidence, device_id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Record what a fingerprint pass in `scan_id` concluded about a device.
+pub fn record_fingerprint(
+    conn: &Connection,
+    device_id: &str,
+    scan_id: &str,
+    os_guess: Option<(&str, f64)>,
+    device_type: &str,
+) -> Result<(), rusqlite::Error> {
     conn.execute(
-        "UPDATE devices SET device_type = ?1 WHERE id = ?2 AND device_type = 'unknown'",
-        params![device_type, device_id],
+        "INSERT INTO fingerprint_history (device_id, os_guess, os_confidence, device_type, scan_id)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![device_id, os_guess.map(|g| g.0), os_guess.map(|g| g.1), device_type, scan_id],
     )?;
     Ok(())
 }
 
+/// Every fingerprint recorded for a device, newest first.
+pub fn get_fingerprint_history(conn: &Connection, device_id: &str) -> Result<Vec<FingerprintEntry>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT os_guess, os_confidence, device_type, scan_id, created_at
+         FROM fingerprint_history
+         WHERE device_id = ?1
+         ORDER BY created_at DESC, id DESC",
+    )?;
+    let entries = stmt.query_map([device_id], |row| {
+        Ok(FingerprintEntry {
+            os_guess: row.get(0)?,
+            os_confidence: row.get(1)?,
+            device_type: row.get(2)?,
+            scan_id: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    })?;
+    entries.collect()
+}
+
 /// Update last_seen timestamp for a device.
 pub fn touch_device(conn: &Connection, device_id: &str) -> Result<(), rusqlite::Error> {
+    conn.prepare_cached("UPDATE devices SET last_seen = datetime('now') WHERE id = ?1")?
+        .execute([device_id])?;
+    Ok(())
+}
+
+/// Every IP a device has been seen on, most recently seen first.
+pub fn get_ip_history(conn: &Connection, device_id: &str) -> Result<Vec<IpHistoryEntry>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT ip_address, is_current, first_seen, last_seen
+         FROM device_ips
+         WHERE device_id = ?1
+         ORDER BY last_seen DESC, is_current DESC, first_seen DESC",
+    )?;
+    let entries = stmt.query_map([device_id], |row| {
+        Ok(IpHistoryEntry {
+            ip_address: row.get(0)?,
+            is_current: row.get(1)?,
+            first_seen: row.get(2)?,
+            last_seen: row.get(3)?,
+        })
+    })?;
+    entries.collect()
+}
+
+/// Attach a tag to a device. Tagging twice is a no-op.
+pub fn add_device_tag(conn: &Connection, device_id: &str, tag: &str) -> Result<(), rusqlite::Error> {
     conn.execute(
-        "UPDATE devices SET last_seen = datetime('now') WHERE id = ?1",
-        [device_id],
+        "INSERT OR IGNORE INTO device_tags (device_id, tag) VALUES (?1, ?2)",
+        params![device_id, tag],
     )?;
     Ok(())
 }
 
-/// Upsert a device IP: mark old IPs as not current, insert or update the new one.
+/// Detach a tag from a device. Returns whether the device had it.
+pub fn remove_device_tag(conn: &Connection, device_id: &str, tag: &str) -> Result<bool, rusqlite::Error> {
+    let removed = conn.execute(
+        "DELETE FROM device_tags WHERE device_id = ?1 AND tag = ?2",
+        params![device_id, tag],
+    )?;
+    Ok(removed > 0)
+}
+
+/// Every tag in use on any device, alphabetical.
+pub fn get_tags(conn: &Connection) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT DISTINCT tag FROM device_tags ORDER BY tag")?;
+    let tags = stmt.query_map([], |row| row.get(0))?;
+    tags.collect()
+}
+
+/// Set a property on a device, replacing any previous value; `None` removes it.
+pub fn set_device_property(
+    conn: &Connection,
+    device_id: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<(), rusqlite::Error> {
+    match value {
+        Some(value) => conn.execute(
+            "INSERT INTO device_properties (device_id, key, value) VALUES (?1, ?2, ?3)
+             ON CONFLICT(device_id, key) DO UPDATE SET value = ?3, updated_at = datetime('now')",
+            params![device_id, key, value],
+        )?,
+        None => conn.execute(
+            "DELETE FROM device_properties WHERE device_id = ?1 AND key = ?2",
+            params![device_id, key],
+        )?,
+    };
+    Ok(())
+}
+
+/// Every property set on a device.
+pub fn get_device_properties(conn: &Connection, device_id: &str) -> Result<HashMap<String, String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT key, value FROM device_properties WHERE device_id = ?1")?;
+    let properties = stmt.query_map([device_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    properties.collect()
+}
+
+/// Upsert a device IP: mark old IPs of the same family as not current, insert or update the new one.
 pub fn upsert_device_ip(conn: &Connection, device_id: &str, ip: &str) -> Result<(), rusqlite::Error> {
+    let family = address_family(ip);
     // Check if this IP already exists for this device
-    let existing: Option<String> = conn.query_row(
-        "SELECT id FROM device_ips WHERE device_id = ?1 AND ip_address = ?2",
-        params![device_id, ip],
-        |row| row.get(0),
-    ).optional()?;
+    let existing: Option<String> = conn
+        .prepare_cached("SELECT id FROM device_ips WHERE device_id = ?1 AND ip_address = ?2")?
+        .query_row(params![device_id, ip], |row| row.get(0))
+        .optional()?;
 
     if let Some(ip_id) = existing {
         // Update existing: mark as current, update last_seen
-        conn.execute(
-            "UPDATE device_ips SET is_current = 0 WHERE device_id = ?1 AND id != ?2",
-            params![device_id, ip_id],
-        )?;
-        conn.execute(
-            "UPDATE device_ips SET is_current = 1, last_seen = datetime('now') WHERE id = ?1",
-            [&ip_id],
-        )?;
+        conn.prepare_cached(
+            "UPDATE device_ips SET is_current = 0 WHERE device_id = ?1 AND address_family = ?2 AND id != ?3",
+        )?
+        .execute(params![device_id, family, ip_id])?;
+        conn.prepare_cached("UPDATE device_ips SET is_current = 1, last_seen = datetime('now') WHERE id = ?1")?
+            .execute([&ip_id])?;
     } else {
         // New IP: mark all others as not current, insert new
         conn.execute(
-            "UPDATE device_ips SET is_current = 0 WHERE device_id = ?1",
-            [device_id],
+            "UPDATE device_ips SET is_current = 0 WHERE device_id = ?1 AND address_family = ?2",
+            params![device_id, family],
         )?;
         let ip_id = uuid::Uuid::new_v4().to_string();
         conn.execute(
-            "INSERT INTO device_ips (id, device_id, ip_address, is_current)
-             VALUES (?1, ?2, ?3, 1)",
-            params![ip_id, device_id, ip],
+            "INSERT INTO device_ips (id, device_id, ip_address, is_current, address_family)
+             VALUES (?1, ?2, ?3, 1, ?4)",
+            params![ip_id, device_id, ip, family],
         )?;
     }
 
     Ok(())
 }
 
+/// Make each device's most recently seen IP of each family its only current one.
+/// Repairs devices left with zero or several current IPs by an interrupted `upsert_device_ip`.
+/// Returns how many rows changed.
+pub fn repair_current_ips(conn: &Connection) -> Result<usize, rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    let changed = tx.execute(
+        "UPDATE device_ips SET is_current = (
+            id = (SELECT newest.id FROM device_ips newest
+                  WHERE newest.device_id = device_ips.device_id
+                    AND newest.address_family = device_ips.address_family
+                  ORDER BY newest.last_seen DESC, newest.is_current DESC, newest.rowid DESC
+                  LIMIT 1)
+         )
+         WHERE is_current IS NOT (
+            id = (SELECT newest.id FROM device_ips newest
+                  WHERE newest.device_id = device_ips.device_id
+                    AND newest.address_family = device_ips.address_family
+                  ORDER BY newest.last_seen DESC, newest.is_current DESC, newest.rowid DESC
+                  LIMIT 1)
+         )",
+        [],
+    )?;
+    tx.commit()?;
+    Ok(changed)
+}
+
+/// Write-coalescing policy for latency samples. The default records every sample.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencyCoalescing {
+    /// Always record when the value moved by more than this percentage of the last sample.
+    /// `0` means value changes alone never force a write.
+    pub change_threshold_pct: f64,
+    /// Record at most once per this many seconds per device (unless the threshold is exceeded).
+    /// `0` disables coalescing.
+    pub min_interval_secs: u64,
+}
+
+/// Loss and jitter measured along with a latency, from several echo requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PingQuality {
+    pub packet_loss: Option<f64>,
+    pub jitter_ms: Option<f64>,
+}
+
 /// Record a latency measurement for a device.
-pub fn record_latency(conn: &Connection, device_id: &str, latency_ms: f64) -> Result<(), rusqlite::Error> {
+/// Returns whether a row was written; samples suppressed by `coalesce` return `false`.
+pub fn record_latency(
+    conn: &Connection,
+    device_id: &str,
+    latency_ms: f64,
+    coalesce: &LatencyCoalescing,
+) -> Result<bool, rusqlite::Error> {
+    record_latency_with_quality(conn, device_id, latency_ms, PingQuality::default(), coalesce)
+}
+
+/// Like `record_latency`, also storing loss and jitter. A change in loss is always written.
+pub fn record_latency_with_quality(
+    conn: &Connection,
+    device_id: &str,
+    latency_ms: f64,
+    quality: PingQuality,
+    coalesce: &LatencyCoalescing,
+) -> Result<bool, rusqlite::Error> {
+    if coalesce.min_interval_secs > 0 {
+        let last: Option<(Option<f64>, f64, Option<f64>)> = conn
+            .query_row(
+                "SELECT latency_ms, (julianday('now') - julianday(measured_at)) * 86400.0, packet_loss
+                 FROM latency_history
+                 WHERE device_id = ?1
+                 ORDER BY id DESC LIMIT 1",
+                [device_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+
+        if let Some((Some(last_ms), age_secs, last_loss)) = last {
+            let within_interval = age_secs < coalesce.min_interval_secs as f64;
+            let change_pct = if last_ms > 0.0 {
+                ((latency_ms - last_ms) / last_ms).abs() * 100.0
+            } else if latency_ms > 0.0 {
+                f64::INFINITY
+            } else {
+                0.0
+            };
+            let significant = (coalesce.change_threshold_pct > 0.0 && change_pct > coalesce.change_threshold_pct)
+                || (quality.packet_loss.is_some() && quality.packet_loss != last_loss);
+
+            if within_interval && !significant {
+                return Ok(false);
+            }
+        }
+    }
+
     conn.execute(
-        "INSERT INTO latency_history (device_id, latency_ms) VALUES (?1, ?2)",
-        params![device_id, latency_ms],
+        "INSERT INTO latency_history (device_id, latency_ms, packet_loss, jitter_ms) VALUES (?1, ?2, ?3, ?4)",
+        params![device_id, latency_ms, quality.packet_loss, quality.jitter_ms],
     )?;
-    Ok(())
+    Ok(true)
+}
+
+/// Days of latency history kept; older samples are pruned.
+pub const LATENCY_RETENTION_DAYS: u32 = 30;
+
+/// Delete latency samples measured more than `keep_days` days ago. Returns how many were removed.
+pub fn prune_latency_history(conn: &Connection, keep_days: u32) -> Result<usize, rusqlite::Error> {
+    conn.execute(
+        "DELETE FROM latency_history WHERE measured_at < datetime('now', ?1)",
+        [format!("-{} days", keep_days)],
+    )
 }
 
 /// Check if a timestamp is within the last 5 minutes.
 fn is_recently_seen(timestamp: &str) -> bool {
-    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S")
-        .map(|dt| {
-            let now = chrono::Utc::now().naive_utc();
-            now.signed_duration_since(dt).num_minutes() < 5
-        })
-        .unwrap_or(false)
+    time::seconds_since(timestamp).is_some_and(|secs| secs < 5 * 60)
+}
+
+/// Presence confidence halves every this many seconds without fresh contact.
+const PRESENCE_HALF_LIFE_SECS: f64 = 300.0;
+
+/// A sighting without a ping reply (ARP, passive discovery) is worth less than a reply.
+const UNCONFIRMED_WEIGHT: f64 = 0.7;
+
+/// Confidence that a device is present, from `1.0` (just answered a ping) towards `0.0`.
+/// Decays exponentially with time since last contact; unconfirmed sightings start lower.
+pub fn presence_score(last_seen: &str, last_confirmed_ping: Option<&str>) -> f64 {
+    presence_score_at(chrono::Utc::now().naive_utc(), last_seen, last_confirmed_ping)
+}
+
+fn presence_score_at(
+    now: chrono::NaiveDateTime,
+    last_seen: &str,
+    last_confirmed_ping: Option<&str>,
+) -> f64 {
+    let decay = |timestamp: &str| {
+        time::parse_db_time(timestamp)
+            .map(|dt| {
+                let age = now.signed_duration_since(dt).num_seconds().max(0) as f64;
+                0.5f64.powf(age / PRESENCE_HALF_LIFE_SECS)
+            })
+            .unwrap_or(0.0)
+    };
+
+    let seen = UNCONFIRMED_WEIGHT * decay(last_seen);
+    let pinged = last_confirmed_ping.map(decay).unwrap_or(0.0);
+    seen.max(pinged)
 }
 
 /// Trait extension for optional query results.
@@ -278,6 +931,44 @@ impl<T> OptionalExt<T> for Result<T, rusqlite::Error> {
     }
 }
 
+/// An online, untrusted device with nothing else known, for tests to adjust with struct
+/// update syntax.
+#[cfg(test)]
+pub fn test_device(id: &str) -> Device {
+    Device {
+        id: id.to_string(),
+        mac_address: None,
+        vendor: None,
+        hostname: None,
+        custom_name: None,
+        device_type: "unknown".to_string(),
+        os_guess: None,
+        os_confidence: 0.0,
+        is_trusted: false,
+        is_gateway: false,
+        notes: None,
+        notes_pinned: false,
+        is_randomized_mac: false,
+        icon: None,
+        connection_type: "unknown".to_string(),
+        source_agent: None,
+        current_ip: None,
+        ipv6_addresses: Vec::new(),
+        is_online: true,
+        latency_ms: None,
+        packet_loss: None,
+        presence_score: 1.0,
+        last_confirmed_ping: None,
+        risk_score: 0,
+        discovery_sources: Vec::new(),
+        tags: Vec::new(),
+        properties: Default::default(),
+        open_ports: Vec::new(),
+        first_seen: "2026-01-01 00:00:00".to_string(),
+        last_seen: "2026-01-01 00:00:00".to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -300,6 +991,206 @@ mod tests {
         assert_eq!(devices[0].vendor.as_deref(), Some("Apple"));
     }
 
+    #[test]
+    fn test_get_all_devices_includes_ports() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, Some("192.168.1.10")).unwrap();
+        insert_device(&conn, "dev2", None, None, None, "unknown", false, Some("192.168.1.11")).unwrap();
+        insert_device(&conn, "dev3", None, None, None, "unknown", false, Some("192.168.1.12")).unwrap();
+        conn.execute("INSERT INTO scans (id, scan_type, status) VALUES ('scan1', 'full', 'completed')", []).unwrap();
+        ports::insert_port(&conn, "dev1", "scan1", 443, "tcp", "open", Some("https"), None).unwrap();
+        ports::insert_port(&conn, "dev1", "scan1", 22, "tcp", "open", Some("ssh"), None).unwrap();
+        ports::insert_port(&conn, "dev2", "scan1", 3389, "tcp", "open", Some("rdp"), None).unwrap();
+
+        let devices = get_all_devices(&conn).unwrap();
+        let ports_of = |id: &str| {
+            devices.iter().find(|d| d.id == id).unwrap().open_ports.iter().map(|p| p.port).collect::<Vec<_>>()
+        };
+        assert_eq!(ports_of("dev1"), [22, 443]);
+        assert_eq!(ports_of("dev2"), [3389]);
+        assert!(ports_of("dev3").is_empty());
+
+        let dev1 = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(dev1.open_ports[0].service_name.as_deref(), Some("ssh"));
+    }
+
+    #[test]
+    fn test_get_device_by_id() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(
+            &conn, "dev1", Some("AA:BB:CC:DD:EE:FF"), Some("Apple"),
+            Some("macbook.local"), "computer", true, Some("192.168.1.42"),
+        ).unwrap();
+        insert_device(&conn, "dev2", None, None, None, "unknown", false, None).unwrap();
+        conn.execute("INSERT INTO scans (id, scan_type, status) VALUES ('scan1', 'full', 'completed')", []).unwrap();
+        ports::insert_port(&conn, "dev1", "scan1", 22, "tcp", "open", Some("ssh"), None).unwrap();
+
+        assert!(get_device_by_id(&conn, "missing").unwrap().is_none());
+
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.id, "dev1");
+        assert_eq!(device.mac_address.as_deref(), Some("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(device.hostname.as_deref(), Some("macbook.local"));
+        assert_eq!(device.current_ip.as_deref(), Some("192.168.1.42"));
+        assert!(device.is_gateway);
+        assert_eq!(device.open_ports.len(), 1);
+        assert_eq!(device.open_ports[0].port, 22);
+    }
+
+    #[test]
+    fn test_get_devices_paged_search_and_filters() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "dev1", Some("AA:BB:CC:00:00:01"), None, Some("living-room-tv.local"), "tv", false, Some("192.168.1.10")).unwrap();
+        insert_device(&conn, "dev2", Some("AA:BB:CC:00:00:02"), None, Some("office-printer"), "printer", false, Some("192.168.1.20")).unwrap();
+        insert_device(&conn, "dev3", Some("AA:BB:CC:00:00:03"), None, None, "computer", false, Some("10.0.0.5")).unwrap();
+        conn.execute("UPDATE devices SET custom_name = 'Work Laptop', is_trusted = 1 WHERE id = 'dev3'", []).unwrap();
+        conn.execute("UPDATE devices SET last_seen = datetime('now', '-1 hour') WHERE id = 'dev2'", []).unwrap();
+
+        let search = |text: &str| {
+            let query = DeviceQuery { search: Some(text.to_string()), ..Default::default() };
+            let page = get_devices_paged(&conn, &query).unwrap();
+            assert_eq!(page.total as usize, page.items.len());
+            page.items.into_iter().map(|d| d.id).collect::<Vec<_>>()
+        };
+
+        // Partial, case-insensitive hostname; custom name; IP; MAC
+        assert_eq!(search("ROOM-tv"), vec!["dev1"]);
+        assert_eq!(search("laptop"), vec!["dev3"]);
+        assert_eq!(search("192.168.1.2"), vec!["dev2"]);
+        assert_eq!(search("00:00:03"), vec!["dev3"]);
+        assert_eq!(search("  ").len(), 3);
+
+        // Input is bound and wildcards match literally
+        assert!(search("%").is_empty());
+        assert!(search("' OR 1=1 --").is_empty());
+
+        let query = DeviceQuery { device_type: Some("printer".to_string()), ..Default::default() };
+        assert_eq!(get_devices_paged(&conn, &query).unwrap().items[0].id, "dev2");
+
+        let query = DeviceQuery { trusted_only: true, ..Default::default() };
+        assert_eq!(get_devices_paged(&conn, &query).unwrap().total, 1);
+
+        let query = DeviceQuery { online_only: true, ..Default::default() };
+        let page = get_devices_paged(&conn, &query).unwrap();
+        assert_eq!(page.total, 2);
+        assert!(page.items.iter().all(|d| d.is_online));
+    }
+
+    #[test]
+    fn test_device_tags() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "tablet", None, None, None, "tablet", false, Some("192.168.1.30")).unwrap();
+        insert_device(&conn, "laptop", None, None, None, "computer", false, Some("192.168.1.31")).unwrap();
+        insert_device(&conn, "tv", None, None, None, "tv", false, Some("192.168.1.32")).unwrap();
+
+        add_device_tag(&conn, "tablet", "kids-devices").unwrap();
+        add_device_tag(&conn, "tablet", "kids-devices").unwrap();
+        add_device_tag(&conn, "tablet", "bedroom").unwrap();
+        add_device_tag(&conn, "laptop", "work").unwrap();
+
+        let tablet = get_device_by_id(&conn, "tablet").unwrap().unwrap();
+        assert_eq!(tablet.tags, ["bedroom", "kids-devices"]);
+        assert!(get_device_by_id(&conn, "tv").unwrap().unwrap().tags.is_empty());
+        assert_eq!(get_tags(&conn).unwrap(), ["bedroom", "kids-devices", "work"]);
+
+        let tagged = |tag: &str| {
+            let query = DeviceQuery { tag: Some(tag.to_string()), ..Default::default() };
+            let page = get_devices_paged(&conn, &query).unwrap();
+            assert_eq!(page.total as usize, page.items.len());
+            page.items.into_iter().map(|d| d.id).collect::<Vec<_>>()
+        };
+        assert_eq!(tagged("work"), ["laptop"]);
+        assert_eq!(tagged("kids-devices"), ["tablet"]);
+        assert!(tagged("kids").is_empty());
+        assert_eq!(tagged("").len(), 3);
+
+        assert!(remove_device_tag(&conn, "tablet", "kids-devices").unwrap());
+        assert!(!remove_device_tag(&conn, "tablet", "kids-devices").unwrap());
+        assert!(tagged("kids-devices").is_empty());
+        assert_eq!(get_device_by_id(&conn, "tablet").unwrap().unwrap().tags, ["bedroom"]);
+
+        // Tags go with the device
+        delete_device(&conn, "laptop").unwrap();
+        assert_eq!(get_tags(&conn).unwrap(), ["bedroom"]);
+    }
+
+    #[test]
+    fn test_device_properties() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, Some("192.168.1.10")).unwrap();
+        assert!(get_device_by_id(&conn, "dev1").unwrap().unwrap().properties.is_empty());
+
+        set_device_property(&conn, "dev1", "room", Some("office")).unwrap();
+        set_device_property(&conn, "dev1", "owner", Some("sam")).unwrap();
+        set_device_property(&conn, "dev1", "room", Some("hallway")).unwrap();
+
+        let properties = get_device_properties(&conn, "dev1").unwrap();
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties["room"], "hallway");
+        assert_eq!(get_device_by_id(&conn, "dev1").unwrap().unwrap().properties, properties);
+        assert_eq!(get_all_devices(&conn).unwrap()[0].properties["owner"], "sam");
+
+        set_device_property(&conn, "dev1", "owner", None).unwrap();
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.properties.keys().collect::<Vec<_>>(), ["room"]);
+    }
+
+    #[test]
+    fn test_get_devices_paged_offsets() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        for i in 0..7 {
+            let id = format!("dev{}", i);
+            insert_device(&conn, &id, None, None, Some(&format!("host{}", i)), "unknown", false, None).unwrap();
+            conn.execute(
+                "UPDATE devices SET last_seen = datetime('now', ?1) WHERE id = ?2",
+                params![format!("-{} minutes", i), id],
+            )
+            .unwrap();
+        }
+
+        let page = |offset| {
+            let query = DeviceQuery { limit: Some(3), offset: Some(offset), ..Default::default() };
+            get_devices_paged(&conn, &query).unwrap()
+        };
+
+        let first = page(0);
+        assert_eq!(first.total, 7);
+        assert_eq!(first.items.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["dev0", "dev1", "dev2"]);
+        assert_eq!(page(3).items.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["dev3", "dev4", "dev5"]);
+        assert_eq!(page(6).items.len(), 1);
+        assert!(page(9).items.is_empty());
+        assert_eq!(page(9).total, 7);
+    }
+
+    #[test]
+    fn test_get_devices_paged_includes_ports() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+        insert_device(&conn, "dev2", None, None, None, "unknown", false, None).unwrap();
+        crate::db::queries::scans::create_scan(&conn, "scan1", None, "full").unwrap();
+        ports::insert_port(&conn, "dev1", "scan1", 22, "tcp", "open", Some("ssh"), None).unwrap();
+
+        let page = get_devices_paged(&conn, &DeviceQuery::default()).unwrap();
+        let ports_of = |id: &str| {
+            let device = page.items.iter().find(|d| d.id == id).unwrap();
+            device.open_ports.iter().map(|p| p.port).collect::<Vec<_>>()
+        };
+        assert_eq!(ports_of("dev1"), [22]);
+        assert!(ports_of("dev2").is_empty());
+    }
+
     #[test]
     fn test_update_device() {
         let pool = db::init_test_db();
@@ -315,6 +1206,8 @@ mod tests {
             device_type: Some("computer".to_string()),
             is_trusted: Some(true),
             notes: None,
+            notes_pinned: None,
+            icon: None,
         };
         update_device(&conn, "dev1", &updates).unwrap();
 
@@ -322,6 +1215,73 @@ mod tests {
         assert_eq!(device.custom_name.as_deref(), Some("My Laptop"));
         assert_eq!(device.device_type, "computer");
         assert!(device.is_trusted);
+        assert!(device.icon.is_none());
+    }
+
+    #[test]
+    fn test_update_device_icon() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        let set = DeviceUpdate {
+            custom_name: None,
+            device_type: None,
+            is_trusted: None,
+            notes: None,
+            notes_pinned: None,
+            icon: Some(Some("🖨️".to_string())),
+        };
+        update_device(&conn, "dev1", &set).unwrap();
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.icon.as_deref(), Some("🖨️"));
+
+        let clear = DeviceUpdate { icon: Some(None), ..set };
+        update_device(&conn, "dev1", &clear).unwrap();
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert!(device.icon.is_none());
+    }
+
+    #[test]
+    fn test_search_notes() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "nas", None, None, None, "unknown", false, None).unwrap();
+        insert_device(&conn, "cam", None, None, None, "unknown", false, None).unwrap();
+        insert_device(&conn, "tv", None, None, None, "unknown", false, None).unwrap();
+
+        let note = |notes: &str, pinned| DeviceUpdate {
+            custom_name: None,
+            device_type: None,
+            is_trusted: None,
+            notes: Some(Some(notes.to_string())),
+            notes_pinned: Some(pinned),
+            icon: None,
+        };
+        let long = format!("{} old NAS in the closet, decommission soon", "x".repeat(60));
+        update_device(&conn, "nas", &note(&long, false)).unwrap();
+        update_device(&conn, "cam", &note("Replace this NAS-backed camera", true)).unwrap();
+        update_device(&conn, "tv", &note("100% living room", false)).unwrap();
+
+        let found = search_notes(&conn, "nas").unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].device_id, "cam");
+        assert!(found[0].notes_pinned);
+        assert_eq!(found[0].snippet, "Replace this NAS-backed camera");
+        assert!(found[1].snippet.starts_with('…'));
+        assert!(found[1].snippet.contains("old NAS in the closet"));
+
+        // LIKE wildcards in the query match literally
+        assert_eq!(search_notes(&conn, "100%").unwrap().len(), 1);
+        assert!(search_notes(&conn, "_").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_note_snippet_respects_char_boundaries() {
+        let notes = format!("{}NAS{}", "é".repeat(50), "ü".repeat(50));
+        let snippet = note_snippet(&notes, "nas");
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert_eq!(snippet.chars().count(), 2 * SNIPPET_CONTEXT + 3 + 2);
     }
 
     #[test]
@@ -356,6 +1316,130 @@ mod tests {
         assert_eq!(device.current_ip.as_deref(), Some("192.168.1.42"));
     }
 
+    #[test]
+    fn test_ipv6_addresses_kept_per_family() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "dual", None, None, None, "unknown", false, Some("192.168.1.20")).unwrap();
+        upsert_device_ip(&conn, "dual", "fe80::1").unwrap();
+        upsert_device_ip(&conn, "dual", "2001:db8::20").unwrap();
+
+        // A new IPv6 address does not displace the IPv4 one
+        let device = get_device_by_id(&conn, "dual").unwrap().unwrap();
+        assert_eq!(device.current_ip.as_deref(), Some("192.168.1.20"));
+        assert_eq!(device.ipv6_addresses, ["2001:db8::20", "fe80::1"]);
+
+        let family: String = conn
+            .query_row(
+                "SELECT address_family FROM device_ips WHERE ip_address = 'fe80::1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(family, "ipv6");
+
+        insert_device(&conn, "v6only", None, None, None, "unknown", false, Some("fe80::99")).unwrap();
+        let device = get_device_by_id(&conn, "v6only").unwrap().unwrap();
+        assert_eq!(device.current_ip.as_deref(), Some("fe80::99"));
+        assert_eq!(device.ipv6_addresses, ["fe80::99"]);
+
+        let listed = get_all_devices(&conn).unwrap().into_iter().find(|d| d.id == "dual").unwrap();
+        assert_eq!(listed.ipv6_addresses.len(), 2);
+    }
+
+    #[test]
+    fn test_ip_history() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, Some("192.168.1.20")).unwrap();
+        conn.execute("UPDATE device_ips SET first_seen = '2026-01-01 00:00:00', last_seen = '2026-01-02 00:00:00'", []).unwrap();
+        upsert_device_ip(&conn, "dev1", "192.168.1.35").unwrap();
+
+        let history = get_ip_history(&conn, "dev1").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].ip_address, "192.168.1.35");
+        assert!(history[0].is_current);
+        assert_eq!(history[1].ip_address, "192.168.1.20");
+        assert!(!history[1].is_current);
+        assert_eq!(history[1].first_seen, "2026-01-01 00:00:00");
+
+        assert!(get_ip_history(&conn, "missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_history_and_reclassification() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+        for scan_id in ["scan1", "scan2"] {
+            crate::db::queries::scans::create_scan(&conn, scan_id, None, "full").unwrap();
+        }
+
+        update_os_guess(&conn, "dev1", "Linux", 0.5).unwrap();
+        assert!(update_device_type(&conn, "dev1", "computer", 0.5).unwrap());
+        record_fingerprint(&conn, "dev1", "scan1", Some(("Linux", 0.5)), "computer").unwrap();
+
+        // A later, more confident pass replaces both guesses
+        update_os_guess(&conn, "dev1", "Embedded Linux", 0.8).unwrap();
+        assert!(update_device_type(&conn, "dev1", "iot", 0.8).unwrap());
+        record_fingerprint(&conn, "dev1", "scan2", Some(("Embedded Linux", 0.8)), "iot").unwrap();
+
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.os_guess.as_deref(), Some("Embedded Linux"));
+        assert_eq!(device.os_confidence, 0.8);
+        assert_eq!(device.device_type, "iot");
+
+        let history = get_fingerprint_history(&conn, "dev1").unwrap();
+        let summary: Vec<_> = history
+            .iter()
+            .map(|e| (e.scan_id.as_deref(), e.os_guess.as_deref(), e.os_confidence, e.device_type.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("scan2"), Some("Embedded Linux"), Some(0.8), "iot"),
+                (Some("scan1"), Some("Linux"), Some(0.5), "computer"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_less_confident_classification_keeps_type() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        // Any classification replaces unknown, even one made without an OS guess
+        assert!(update_device_type(&conn, "dev1", "computer", 0.0).unwrap());
+        assert!(update_device_type(&conn, "dev1", "printer", 0.7).unwrap());
+        assert!(!update_device_type(&conn, "dev1", "iot", 0.4).unwrap());
+        assert!(!update_device_type(&conn, "dev1", "iot", 0.7).unwrap());
+
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.device_type, "printer");
+    }
+
+    #[test]
+    fn test_manual_device_type_survives_fingerprinting() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+        update_device_type(&conn, "dev1", "computer", 0.5).unwrap();
+
+        let updates = DeviceUpdate {
+            device_type: Some("nas".to_string()),
+            ..Default::default()
+        };
+        update_device(&conn, "dev1", &updates).unwrap();
+        assert!(!update_device_type(&conn, "dev1", "iot", 0.95).unwrap());
+
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.device_type, "nas");
+    }
+
     #[test]
     fn test_get_device_by_mac() {
         let pool = db::init_test_db();
@@ -372,4 +1456,285 @@ mod tests {
         let missing = get_device_by_mac(&conn, "11:22:33:44:55:66").unwrap();
         assert!(missing.is_none());
     }
+
+    #[test]
+    fn test_mac_lookup_ignores_case_and_separators() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "dev1", Some("aa:bb:cc:0d:ee:ff"), None, None, "unknown", false, None).unwrap();
+
+        assert_eq!(get_device_by_mac(&conn, "AA:BB:CC:0D:EE:FF").unwrap().as_deref(), Some("dev1"));
+        assert_eq!(get_device_by_mac(&conn, "aa-bb-cc-0d-ee-ff").unwrap().as_deref(), Some("dev1"));
+        assert_eq!(get_device_by_mac(&conn, "aabb.cc0d.eeff").unwrap().as_deref(), Some("dev1"));
+
+        // The second spelling is the same device, so the UNIQUE column rejects it
+        assert!(insert_device(&conn, "dev2", Some("AA-BB-CC-0D-EE-FF"), None, None, "unknown", false, None).is_err());
+        let devices = get_all_devices(&conn).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].mac_address.as_deref(), Some("AA:BB:CC:0D:EE:FF"));
+    }
+
+    #[test]
+    fn test_normalize_mac() {
+        assert_eq!(normalize_mac("aa:bb:cc:dd:ee:ff"), "AA:BB:CC:DD:EE:FF");
+        assert_eq!(normalize_mac(" AA-bb-CC-dd-EE-ff "), "AA:BB:CC:DD:EE:FF");
+        assert_eq!(normalize_mac("aabb.ccdd.eeff"), "AA:BB:CC:DD:EE:FF");
+        // Not a MAC we understand: only the case changes
+        assert_eq!(normalize_mac("aa:bb:cc"), "AA:BB:CC");
+        assert_eq!(normalize_mac("(incomplete)"), "(INCOMPLETE)");
+    }
+
+    #[test]
+    fn test_randomized_mac_flagged() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        insert_device(&conn, "phone", Some("da:a1:19:2b:3c:4d"), None, None, "phone", false, None).unwrap();
+        insert_device(&conn, "nas", Some("00:11:32:AA:BB:CC"), None, None, "unknown", false, None).unwrap();
+        insert_device(&conn, "no-mac", None, None, None, "unknown", false, Some("10.0.0.9")).unwrap();
+
+        let flagged = |id: &str| get_device_by_id(&conn, id).unwrap().unwrap().is_randomized_mac;
+        assert!(flagged("phone"));
+        assert!(!flagged("nas"));
+        assert!(!flagged("no-mac"));
+    }
+
+    fn latency_rows(conn: &Connection, device_id: &str) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM latency_history WHERE device_id = ?1",
+            [device_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_latency_without_coalescing() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        let policy = LatencyCoalescing::default();
+        assert!(record_latency(&conn, "dev1", 10.0, &policy).unwrap());
+        assert!(record_latency(&conn, "dev1", 10.0, &policy).unwrap());
+        assert_eq!(latency_rows(&conn, "dev1"), 2);
+    }
+
+    #[test]
+    fn test_record_latency_coalesces_stable_values() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        let policy = LatencyCoalescing { change_threshold_pct: 20.0, min_interval_secs: 300 };
+        assert!(record_latency(&conn, "dev1", 10.0, &policy).unwrap());
+        // Within the interval and under the threshold: suppressed
+        assert!(!record_latency(&conn, "dev1", 11.0, &policy).unwrap());
+        // Jump of 50% exceeds the threshold
+        assert!(record_latency(&conn, "dev1", 15.0, &policy).unwrap());
+        assert_eq!(latency_rows(&conn, "dev1"), 2);
+    }
+
+    #[test]
+    fn test_record_latency_with_quality() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        let policy = LatencyCoalescing { change_threshold_pct: 20.0, min_interval_secs: 300 };
+        let quality = |loss: f64| PingQuality { packet_loss: Some(loss), jitter_ms: Some(0.4) };
+        assert!(record_latency_with_quality(&conn, "dev1", 10.0, quality(0.0), &policy).unwrap());
+        assert!(!record_latency_with_quality(&conn, "dev1", 10.5, quality(0.0), &policy).unwrap());
+        // Same latency, but loss changed: written despite the interval
+        assert!(record_latency_with_quality(&conn, "dev1", 10.5, quality(33.3), &policy).unwrap());
+
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.packet_loss, Some(33.3));
+        let jitter: Option<f64> = conn
+            .query_row("SELECT jitter_ms FROM latency_history ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(jitter, Some(0.4));
+    }
+
+    #[test]
+    fn test_record_latency_writes_after_interval() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        conn.execute(
+            "INSERT INTO latency_history (device_id, latency_ms, measured_at)
+             VALUES ('dev1', 10.0, datetime('now', '-10 minutes'))",
+            [],
+        )
+        .unwrap();
+
+        let policy = LatencyCoalescing { change_threshold_pct: 0.0, min_interval_secs: 300 };
+        assert!(record_latency(&conn, "dev1", 10.0, &policy).unwrap());
+        assert!(!record_latency(&conn, "dev1", 50.0, &policy).unwrap());
+        assert_eq!(latency_rows(&conn, "dev1"), 2);
+    }
+
+    #[test]
+    fn test_prune_latency_history() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        for age in ["-31 days", "-29 days", "-1 hour"] {
+            conn.execute(
+                "INSERT INTO latency_history (device_id, latency_ms, measured_at)
+                 VALUES ('dev1', 10.0, datetime('now', ?1))",
+                [age],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(prune_latency_history(&conn, 30).unwrap(), 1);
+        assert_eq!(latency_rows(&conn, "dev1"), 2);
+        assert_eq!(prune_latency_history(&conn, 30).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_update_connection_type() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.connection_type, "unknown");
+
+        update_connection_type(&conn, "dev1", "wireless").unwrap();
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.connection_type, "wireless");
+    }
+
+    #[test]
+    fn test_presence_score_decays() {
+        let now = time::parse_db_time("2026-01-01 12:00:00").unwrap();
+
+        // Just pinged: full confidence
+        let score = presence_score_at(now, "2026-01-01 12:00:00", Some("2026-01-01 12:00:00"));
+        assert!((score - 1.0).abs() < 1e-9);
+
+        // Seen just now without a ping reply ranks below a fresh ping
+        let score = presence_score_at(now, "2026-01-01 12:00:00", None);
+        assert!((score - UNCONFIRMED_WEIGHT).abs() < 1e-9);
+
+        // One half-life after the last ping
+        let score = presence_score_at(now, "2026-01-01 11:55:00", Some("2026-01-01 11:55:00"));
+        assert!((score - 0.5).abs() < 1e-9);
+
+        // A recent ARP sighting outweighs a stale ping
+        let stale_ping = presence_score_at(now, "2026-01-01 11:59:00", Some("2026-01-01 11:00:00"));
+        assert!(stale_ping > 0.5);
+
+        assert_eq!(presence_score_at(now, "garbage", None), 0.0);
+    }
+
+    #[test]
+    fn test_mark_ping_confirmed() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+        assert!(get_device_by_id(&conn, "dev1").unwrap().unwrap().last_confirmed_ping.is_none());
+
+        mark_ping_confirmed(&conn, "dev1").unwrap();
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert!(device.last_confirmed_ping.is_some());
+        assert!(device.presence_score > 0.9);
+    }
+
+    #[test]
+    fn test_discovery_sources_roundtrip() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+        assert!(get_device_by_id(&conn, "dev1").unwrap().unwrap().discovery_sources.is_empty());
+
+        set_discovery_sources(&conn, "dev1", &["arp", "ping"]).unwrap();
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.discovery_sources, vec!["arp", "ping"]);
+
+        set_discovery_sources(&conn, "dev1", &["tcp_probe"]).unwrap();
+        let device = get_device_by_id(&conn, "dev1").unwrap().unwrap();
+        assert_eq!(device.discovery_sources, vec!["tcp_probe"]);
+    }
+
+    #[test]
+    fn test_repair_current_ips() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "none", None, None, None, "unknown", false, None).unwrap();
+        insert_device(&conn, "many", None, None, None, "unknown", false, None).unwrap();
+        insert_device(&conn, "fine", None, None, None, "unknown", false, Some("10.0.0.9")).unwrap();
+        conn.execute_batch(
+            "INSERT INTO device_ips (id, device_id, ip_address, is_current, last_seen) VALUES
+                ('n1', 'none', '10.0.0.1', 0, '2026-01-01 00:00:00'),
+                ('n2', 'none', '10.0.0.2', 0, '2026-01-02 00:00:00'),
+                ('m1', 'many', '10.0.0.3', 1, '2026-01-01 00:00:00'),
+                ('m2', 'many', '10.0.0.4', 1, '2026-01-03 00:00:00');",
+        )
+        .unwrap();
+
+        assert_eq!(repair_current_ips(&conn).unwrap(), 2);
+        let ip = |id: &str| get_device_by_id(&conn, id).unwrap().unwrap().current_ip;
+        assert_eq!(ip("none").as_deref(), Some("10.0.0.2"));
+        assert_eq!(ip("many").as_deref(), Some("10.0.0.4"));
+        assert_eq!(ip("fine").as_deref(), Some("10.0.0.9"));
+
+        let current: u32 = conn
+            .query_row("SELECT COUNT(*) FROM device_ips WHERE is_current = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(current, 3);
+        assert_eq!(repair_current_ips(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_devices_needing_review() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        insert_device(&conn, "mystery", None, None, None, "unknown", false, None).unwrap();
+        insert_device(&conn, "vendor_only", None, Some("Apple"), None, "unknown", false, None).unwrap();
+        insert_device(&conn, "known", None, Some("Apple"), Some("macbook"), "laptop", false, None).unwrap();
+        insert_device(&conn, "trusted", None, None, None, "unknown", false, None).unwrap();
+        conn.execute("UPDATE devices SET is_trusted = 1 WHERE id = 'trusted'", []).unwrap();
+
+        let review = get_devices_needing_review(&conn).unwrap();
+        let ids: Vec<_> = review.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["mystery", "vendor_only"]);
+        assert_eq!(review_score(&review[0]), 3);
+        assert_eq!(review_score(&review[1]), 2);
+    }
+
+    #[test]
+    fn test_cached_lookups_across_many_devices() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        let tx = conn.unchecked_transaction().unwrap();
+        for i in 0..300 {
+            let mac = format!("aa:bb:cc:dd:{:02x}:{:02x}", i / 256, i % 256);
+            let ip = format!("10.0.{}.{}", i / 256, i % 256);
+            insert_device(&tx, &format!("dev{}", i), Some(&mac), None, None, "unknown", false, Some(&ip)).unwrap();
+        }
+        tx.commit().unwrap();
+
+        // Two passes, so the second runs entirely on cached statements
+        for _ in 0..2 {
+            for i in 0..300 {
+                let id = format!("dev{}", i);
+                let mac = format!("AA:BB:CC:DD:{:02X}:{:02X}", i / 256, i % 256);
+                let ip = format!("10.0.{}.{}", i / 256, i % 256);
+                assert_eq!(get_device_by_mac(&conn, &mac).unwrap().as_deref(), Some(id.as_str()));
+                assert_eq!(get_device_by_ip(&conn, &ip).unwrap().as_deref(), Some(id.as_str()));
+                touch_device(&conn, &id).unwrap();
+                upsert_device_ip(&conn, &id, &ip).unwrap();
+            }
+        }
+        assert!(get_device_by_mac(&conn, "AA:BB:CC:DD:FF:FF").unwrap().is_none());
+
+        let ip_rows: u32 = conn.query_row("SELECT COUNT(*) FROM device_ips", [], |row| row.get(0)).unwrap();
+        assert_eq!(ip_rows, 300);
+    }
 }
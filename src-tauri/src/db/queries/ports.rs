@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 
@@ -23,17 +25,17 @@ pub fn insert_port(
     banner: Option<&str>,
 ) -> Result<(), rusqlite::Error> {
     let id = uuid::Uuid::new_v4().to_string();
-    conn.execute(
+    conn.prepare_cached(
         "INSERT INTO device_ports (id, device_id, scan_id, port, protocol, state, service_name, banner)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![id, device_id, scan_id, port, protocol, state, service_name, banner],
-    )?;
+    )?
+    .execute(params![id, device_id, scan_id, port, protocol, state, service_name, banner])?;
     Ok(())
 }
 
 /// Get the most recent port scan results for a device.
 pub fn get_latest_ports(conn: &Connection, device_id: &str) -> Result<Vec<PortInfo>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare_cached(
         "SELECT dp.port, dp.protocol, dp.state, dp.service_name, dp.banner
          FROM device_ports dp
          WHERE dp.device_id = ?1
@@ -59,6 +61,72 @@ pub fn get_latest_ports(conn: &Connection, device_id: &str) -> Result<Vec<PortIn
     ports.collect()
 }
 
+/// The most recent port scan results for every device, in one query, keyed by device ID.
+/// Devices never port-scanned are absent.
+pub fn get_latest_ports_by_device(conn: &Connection) -> Result<HashMap<String, Vec<PortInfo>>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "WITH latest AS (
+            SELECT device_id, scan_id FROM (
+                SELECT device_id, scan_id,
+                       ROW_NUMBER() OVER (PARTITION BY device_id ORDER BY discovered_at DESC) AS rn
+                FROM device_ports
+            ) WHERE rn = 1
+         )
+         SELECT dp.device_id, dp.port, dp.protocol, dp.state, dp.service_name, dp.banner
+         FROM device_ports dp
+         JOIN latest l ON l.device_id = dp.device_id AND l.scan_id IS dp.scan_id
+         ORDER BY dp.port ASC"
+    )?;
+
+    let mut by_device: HashMap<String, Vec<PortInfo>> = HashMap::new();
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            PortInfo {
+                port: row.get(1)?,
+                protocol: row.get(2)?,
+                state: row.get(3)?,
+                service_name: row.get(4)?,
+                banner: row.get(5)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (device_id, port) = row?;
+        by_device.entry(device_id).or_default().push(port);
+    }
+    Ok(by_device)
+}
+
+/// Every port a given scan recorded, keyed by device ID, in port order.
+pub fn get_scan_ports_by_device(conn: &Connection, scan_id: &str) -> Result<HashMap<String, Vec<PortInfo>>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT device_id, port, protocol, state, service_name, banner
+         FROM device_ports
+         WHERE scan_id = ?1
+         ORDER BY port ASC"
+    )?;
+
+    let mut by_device: HashMap<String, Vec<PortInfo>> = HashMap::new();
+    let rows = stmt.query_map([scan_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            PortInfo {
+                port: row.get(1)?,
+                protocol: row.get(2)?,
+                state: row.get(3)?,
+                service_name: row.get(4)?,
+                banner: row.get(5)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (device_id, port) = row?;
+        by_device.entry(device_id).or_default().push(port);
+    }
+    Ok(by_device)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,4 +159,30 @@ mod tests {
         assert_eq!(ports[1].port, 80);
         assert_eq!(ports[2].port, 443);
     }
+
+    #[test]
+    fn test_latest_ports_by_device() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        devices::insert_device(&conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+        devices::insert_device(&conn, "dev2", None, None, None, "unknown", false, None).unwrap();
+        devices::insert_device(&conn, "dev3", None, None, None, "unknown", false, None).unwrap();
+        conn.execute_batch(
+            "INSERT INTO scans (id, scan_type, status) VALUES ('old', 'full', 'completed'), ('new', 'full', 'completed');",
+        )
+        .unwrap();
+
+        insert_port(&conn, "dev1", "old", 80, "tcp", "open", Some("http"), None).unwrap();
+        insert_port(&conn, "dev2", "old", 22, "tcp", "open", Some("ssh"), None).unwrap();
+        conn.execute("UPDATE device_ports SET discovered_at = '2026-01-01 00:00:00'", []).unwrap();
+        insert_port(&conn, "dev1", "new", 3389, "tcp", "open", Some("rdp"), None).unwrap();
+        insert_port(&conn, "dev1", "new", 22, "tcp", "open", Some("ssh"), None).unwrap();
+
+        let by_device = get_latest_ports_by_device(&conn).unwrap();
+        let ports = |id: &str| by_device[id].iter().map(|p| p.port).collect::<Vec<_>>();
+        assert_eq!(ports("dev1"), [22, 3389]);
+        assert_eq!(ports("dev2"), [22]);
+        assert!(!by_device.contains_key("dev3"));
+        assert_eq!(ports("dev1"), get_latest_ports(&conn, "dev1").unwrap().iter().map(|p| p.port).collect::<Vec<_>>());
+    }
 }
@@ -1,6 +1,11 @@
-use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
+use crate::db::queries::devices::Device;
+use crate::db::queries::ports::PortInfo;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ScanSummary {
@@ -59,6 +64,15 @@ pub fn fail_scan(conn: &Connection, scan_id: &str) -> Result<(), rusqlite::Error
     Ok(())
 }
 
+/// Mark every scan still in `running` state as interrupted. Returns how many were updated.
+pub fn interrupt_running_scans(conn: &Connection) -> Result<usize, rusqlite::Error> {
+    conn.execute(
+        "UPDATE scans SET status = 'interrupted', completed_at = datetime('now')
+         WHERE status = 'running'",
+        [],
+    )
+}
+
 /// Get scan history, newest first.
 pub fn get_scan_history(conn: &Connection, limit: u32) -> Result<Vec<ScanSummary>, rusqlite::Error> {
     let mut stmt = conn.prepare(
@@ -68,20 +82,177 @@ pub fn get_scan_history(conn: &Connection, limit: u32) -> Result<Vec<ScanSummary
          LIMIT ?1"
     )?;
 
-    let scans = stmt.query_map([limit], |row| {
-        Ok(ScanSummary {
-            id: row.get(0)?,
-            scan_type: row.get(1)?,
-            status: row.get(2)?,
-            devices_found: row.get(3)?,
-            new_devices: row.get(4)?,
-            duration_ms: row.get(5)?,
-            started_at: row.get(6)?,
-            completed_at: row.get(7)?,
+    let scans = stmt.query_map([limit], scan_from_row)?;
+
+    scans.collect()
+}
+
+/// One scan by id.
+pub fn get_scan(conn: &Connection, scan_id: &str) -> Result<Option<ScanSummary>, rusqlite::Error> {
+    conn.query_row(
+        "SELECT id, scan_type, status, devices_found, new_devices, duration_ms, started_at, completed_at
+         FROM scans
+         WHERE id = ?1",
+        [scan_id],
+        scan_from_row,
+    )
+    .optional()
+}
+
+fn scan_from_row(row: &rusqlite::Row) -> Result<ScanSummary, rusqlite::Error> {
+    Ok(ScanSummary {
+        id: row.get(0)?,
+        scan_type: row.get(1)?,
+        status: row.get(2)?,
+        devices_found: row.get(3)?,
+        new_devices: row.get(4)?,
+        duration_ms: row.get(5)?,
+        started_at: row.get(6)?,
+        completed_at: row.get(7)?,
+    })
+}
+
+/// Seconds since the most recent successful scan completed, if any.
+pub fn last_scan_age_secs(conn: &Connection) -> Result<Option<i64>, rusqlite::Error> {
+    conn.query_row(
+        "SELECT CAST((julianday('now') - julianday(MAX(completed_at))) * 86400 AS INTEGER)
+         FROM scans
+         WHERE status = 'completed'",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// A device as one scan saw it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanDevice {
+    pub device_id: String,
+    pub ip_address: Option<String>,
+    pub os_guess: Option<String>,
+    /// `port/protocol` entries, e.g. `22/tcp`, in port order.
+    pub open_ports: Vec<String>,
+}
+
+/// A device both scans saw, with what differs between them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceChange {
+    pub device_id: String,
+    pub before: ScanDevice,
+    pub after: ScanDevice,
+    pub opened_ports: Vec<String>,
+    pub closed_ports: Vec<String>,
+}
+
+/// What changed from one scan (`scan_a`) to a later one (`scan_b`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanDiff {
+    pub scan_a: String,
+    pub scan_b: String,
+    /// Seen by `scan_b` only.
+    pub added: Vec<ScanDevice>,
+    /// Seen by `scan_a` only.
+    pub removed: Vec<ScanDevice>,
+    /// Seen by both, with a different IP, OS guess or set of open ports.
+    pub changed: Vec<DeviceChange>,
+}
+
+/// Record that a scan saw `device`, with its open ports at the end of the scan.
+pub fn record_scan_device(
+    conn: &Connection,
+    scan_id: &str,
+    device: &Device,
+    ports: &[PortInfo],
+) -> Result<(), rusqlite::Error> {
+    let open_ports = ports
+        .iter()
+        .filter(|p| p.state == "open")
+        .map(|p| format!("{}/{}", p.port, p.protocol))
+        .collect::<Vec<_>>()
+        .join(",");
+    conn.execute(
+        "INSERT INTO scan_devices (scan_id, device_id, ip_address, os_guess, open_ports)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(scan_id, device_id) DO UPDATE SET
+            ip_address = ?3, os_guess = ?4, open_ports = ?5",
+        params![scan_id, device.id, device.current_ip, device.os_guess, open_ports],
+    )?;
+    Ok(())
+}
+
+/// Every device a scan saw, by device id.
+pub fn get_scan_devices(conn: &Connection, scan_id: &str) -> Result<Vec<ScanDevice>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT device_id, ip_address, os_guess, open_ports
+         FROM scan_devices
+         WHERE scan_id = ?1
+         ORDER BY device_id",
+    )?;
+
+    let devices = stmt.query_map([scan_id], |row| {
+        let open_ports: String = row.get(3)?;
+        Ok(ScanDevice {
+            device_id: row.get(0)?,
+            ip_address: row.get(1)?,
+            os_guess: row.get(2)?,
+            open_ports: open_ports
+                .split(',')
+                .filter(|p| !p.is_empty())
+                .map(|p| p.to_string())
+                .collect(),
         })
     })?;
 
-    scans.collect()
+    devices.collect()
+}
+
+/// Whether a scan with this id was ever recorded.
+pub fn scan_exists(conn: &Connection, scan_id: &str) -> Result<bool, rusqlite::Error> {
+    Ok(conn
+        .query_row("SELECT 1 FROM scans WHERE id = ?1", [scan_id], |_| Ok(()))
+        .optional()?
+        .is_some())
+}
+
+/// Devices added, removed and changed between two scans' snapshots.
+pub fn get_scan_diff(conn: &Connection, scan_a: &str, scan_b: &str) -> Result<ScanDiff, rusqlite::Error> {
+    let before = get_scan_devices(conn, scan_a)?;
+    let after = get_scan_devices(conn, scan_b)?;
+    Ok(diff_snapshots(scan_a, scan_b, before, after))
+}
+
+fn diff_snapshots(scan_a: &str, scan_b: &str, before: Vec<ScanDevice>, after: Vec<ScanDevice>) -> ScanDiff {
+    let mut before: BTreeMap<String, ScanDevice> = before.into_iter().map(|d| (d.device_id.clone(), d)).collect();
+    let mut diff = ScanDiff {
+        scan_a: scan_a.to_string(),
+        scan_b: scan_b.to_string(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+
+    for now in after {
+        let Some(then) = before.remove(&now.device_id) else {
+            diff.added.push(now);
+            continue;
+        };
+        if then == now {
+            continue;
+        }
+        let opened_ports = now.open_ports.iter().filter(|p| !then.open_ports.contains(p)).cloned().collect();
+        let closed_ports = then.open_ports.iter().filter(|p| !now.open_ports.contains(p)).cloned().collect();
+        diff.changed.push(DeviceChange {
+            device_id: now.device_id.clone(),
+            before: then,
+            after: now,
+            opened_ports,
+            closed_ports,
+        });
+    }
+    diff.removed = before.into_values().collect();
+    diff
 }
 
 #[cfg(test)]
@@ -108,4 +279,154 @@ mod tests {
         assert_eq!(history[0].new_devices, 2);
         assert_eq!(history[0].duration_ms, Some(3500));
     }
+
+    /// The writes scan enrichment makes for one newly discovered device.
+    fn persist_device(conn: &Connection, scan_id: &str, i: usize) -> Result<(), rusqlite::Error> {
+        use crate::db::queries::devices as db_devices;
+
+        let id = format!("dev{}", i);
+        let ip = format!("10.0.{}.{}", i / 250, i % 250 + 1);
+        let mac = format!("00:11:22:33:{:02X}:{:02X}", i / 256, i % 256);
+        db_devices::insert_device(conn, &id, Some(&mac), None, None, "unknown", false, Some(&ip))?;
+        db_devices::touch_device(conn, &id)?;
+        db_devices::upsert_device_ip(conn, &id, &ip)?;
+        db_devices::set_discovery_sources(conn, &id, &["arp"])?;
+        let device = db_devices::get_device_by_id(conn, &id)?.expect("just inserted");
+        record_scan_device(conn, scan_id, &device, &[])
+    }
+
+    fn device_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM devices", [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_batched_scan_writes_commit_together() {
+        let pool = db::init_test_db();
+        let mut conn = pool.get().unwrap();
+        create_scan(&conn, "scan1", None, "quick").unwrap();
+
+        let tx = conn.transaction().unwrap();
+        for i in 0..50 {
+            persist_device(&tx, "scan1", i).unwrap();
+        }
+        tx.commit().unwrap();
+        assert_eq!(device_count(&conn), 50);
+        assert_eq!(get_scan_devices(&conn, "scan1").unwrap().len(), 50);
+    }
+
+    #[test]
+    fn test_failed_batch_rolls_back_every_device() {
+        let pool = db::init_test_db();
+        let mut conn = pool.get().unwrap();
+        create_scan(&conn, "scan1", None, "quick").unwrap();
+
+        let outcome = (|| {
+            let tx = conn.transaction()?;
+            for i in 0..10 {
+                persist_device(&tx, "scan1", i)?;
+            }
+            // The same device again violates its unique id partway through
+            persist_device(&tx, "scan1", 3)?;
+            tx.commit()
+        })();
+
+        assert!(outcome.is_err());
+        assert_eq!(device_count(&conn), 0);
+        assert!(get_scan_devices(&conn, "scan1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_last_scan_age_secs() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        assert_eq!(last_scan_age_secs(&conn).unwrap(), None);
+
+        create_scan(&conn, "scan1", None, "quick").unwrap();
+        // A running scan doesn't count as the last scan
+        assert_eq!(last_scan_age_secs(&conn).unwrap(), None);
+
+        complete_scan(&conn, "scan1", 1, 0, 100).unwrap();
+        let age = last_scan_age_secs(&conn).unwrap().unwrap();
+        assert!((0..5).contains(&age));
+    }
+
+    #[test]
+    fn test_interrupt_running_scans() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        create_scan(&conn, "scan1", None, "quick").unwrap();
+        create_scan(&conn, "scan2", None, "full").unwrap();
+        complete_scan(&conn, "scan2", 1, 0, 100).unwrap();
+
+        assert_eq!(interrupt_running_scans(&conn).unwrap(), 1);
+
+        let history = get_scan_history(&conn, 10).unwrap();
+        let scan1 = history.iter().find(|s| s.id == "scan1").unwrap();
+        let scan2 = history.iter().find(|s| s.id == "scan2").unwrap();
+        assert_eq!(scan1.status, "interrupted");
+        assert!(scan1.completed_at.is_some());
+        assert_eq!(scan2.status, "completed");
+    }
+
+    #[test]
+    fn test_scan_diff_categorizes_devices() {
+        use crate::db::queries::devices as db_devices;
+
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        for (id, ip) in [("kept", "10.0.0.2"), ("gone", "10.0.0.3"), ("moved", "10.0.0.4"), ("new", "10.0.0.5")] {
+            db_devices::insert_device(&conn, id, None, None, None, "unknown", false, Some(ip)).unwrap();
+        }
+        create_scan(&conn, "night", None, "full").unwrap();
+        create_scan(&conn, "morning", None, "full").unwrap();
+
+        let device = |id: &str, ip: &str, os: Option<&str>| {
+            let mut d = db_devices::get_device_by_id(&conn, id).unwrap().unwrap();
+            d.current_ip = Some(ip.to_string());
+            d.os_guess = os.map(|o| o.to_string());
+            d
+        };
+        let open = |port: u16| PortInfo {
+            port,
+            protocol: "tcp".to_string(),
+            state: "open".to_string(),
+            service_name: None,
+            banner: None,
+        };
+        let mut closed = open(443);
+        closed.state = "closed".to_string();
+
+        record_scan_device(&conn, "night", &device("kept", "10.0.0.2", Some("Linux")), &[open(22)]).unwrap();
+        record_scan_device(&conn, "night", &device("gone", "10.0.0.3", None), &[]).unwrap();
+        record_scan_device(&conn, "night", &device("moved", "10.0.0.4", None), &[open(22), open(80)]).unwrap();
+        record_scan_device(&conn, "morning", &device("kept", "10.0.0.2", Some("Linux")), &[open(22), closed]).unwrap();
+        record_scan_device(&conn, "morning", &device("moved", "10.0.0.9", Some("Windows")), &[open(80), open(3389)])
+            .unwrap();
+        record_scan_device(&conn, "morning", &device("new", "10.0.0.5", None), &[]).unwrap();
+
+        let diff = get_scan_diff(&conn, "night", "morning").unwrap();
+        let ids = |devices: &[ScanDevice]| devices.iter().map(|d| d.device_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&diff.added), ["new"]);
+        assert_eq!(ids(&diff.removed), ["gone"]);
+        // A closed port is not a change; "kept" is unchanged
+        assert_eq!(diff.changed.len(), 1);
+
+        let moved = &diff.changed[0];
+        assert_eq!(moved.device_id, "moved");
+        assert_eq!(moved.before.ip_address.as_deref(), Some("10.0.0.4"));
+        assert_eq!(moved.after.ip_address.as_deref(), Some("10.0.0.9"));
+        assert_eq!(moved.after.os_guess.as_deref(), Some("Windows"));
+        assert_eq!(moved.opened_ports, ["3389/tcp"]);
+        assert_eq!(moved.closed_ports, ["22/tcp"]);
+
+        // Reversing the scans swaps added and removed
+        let back = get_scan_diff(&conn, "morning", "night").unwrap();
+        assert_eq!(ids(&back.added), ["gone"]);
+        assert_eq!(ids(&back.removed), ["new"]);
+
+        assert!(scan_exists(&conn, "night").unwrap());
+        assert!(!scan_exists(&conn, "noon").unwrap());
+    }
 }
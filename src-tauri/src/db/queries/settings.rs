@@ -1,6 +1,16 @@
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 
+use crate::alerts::engine::DEFAULT_ALERT_COOLDOWN_SECS;
+use crate::alerts::notifier::QuietHours;
+use crate::db::queries::devices::LatencyCoalescing;
+use crate::db::recovery::DEFAULT_BACKUP_INTERVAL_HOURS;
+use crate::network::gateway_monitor::DEFAULT_GATEWAY_MONITOR_INTERVAL_SECS;
+use crate::scanner::budget::DEFAULT_SCAN_CONCURRENCY;
+use crate::scanner::ping::{self, LatencySampling, PingTool, DEFAULT_PING_TIMEOUT_MS};
+use crate::scanner::ScanTools;
+use crate::util::command::CommandTemplate;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettings {
@@ -11,6 +21,164 @@ pub struct AppSettings {
     pub graph_repulsion: f64,
     pub graph_link_distance: f64,
     pub graph_gravity: f64,
+    /// Address for the local health endpoint (`GET /healthz`). Disabled when unset.
+    /// A bare port binds to loopback. Read at startup.
+    #[serde(default)]
+    pub health_bind_addr: Option<String>,
+    /// Address for the agent ingest endpoint (`POST /ingest`). Disabled unless both this
+    /// and `ingest_token` are set. A bare port binds to loopback. Read at startup.
+    #[serde(default)]
+    pub ingest_bind_addr: Option<String>,
+    /// Shared secret agents must send as `Authorization: Bearer <token>`.
+    #[serde(default)]
+    pub ingest_token: Option<String>,
+    /// Skip latency writes that stay within this many percent of the last sample.
+    #[serde(default)]
+    pub latency_change_threshold_pct: f64,
+    /// Write latency at most once per this many seconds per device. `0` records every scan;
+    /// the gateway monitor then falls back to its own once-a-minute coalescing.
+    #[serde(default)]
+    pub latency_min_interval_secs: u64,
+    /// Queue scan requests that arrive while a scan is running instead of rejecting them.
+    #[serde(default)]
+    pub queue_scans: bool,
+    /// Write a human-readable `scan_<id>.log` per scan to the app data directory.
+    #[serde(default)]
+    pub verbose_scan_logs: bool,
+    /// Per-host ping reply timeout, used for both the command flag and the async deadline.
+    #[serde(default = "default_ping_timeout_ms")]
+    pub ping_timeout_ms: u64,
+    /// Echo requests sent per host in the ping sweep.
+    #[serde(default = "default_ping_samples")]
+    pub ping_samples: u32,
+    /// Replies required before a sweep records latency for a host.
+    #[serde(default = "default_ping_min_samples")]
+    pub ping_min_samples: u32,
+    /// Drop the slowest reply before averaging sweep samples.
+    #[serde(default = "default_ping_trim_outliers")]
+    pub ping_trim_outliers: bool,
+    /// Pings, hostname lookups and port connections one scan may have in flight at once.
+    #[serde(default = "default_scan_concurrency")]
+    pub scan_concurrency: usize,
+    /// Custom ping command line, e.g. `busybox ping -c 1 -W {timeout_s} {ip}`. Must contain
+    /// `{ip}`; `{timeout_ms}` and `{timeout_s}` are optional. An `fping` template pings the
+    /// whole sweep in one run. Unset uses the platform `ping`.
+    #[serde(default)]
+    pub ping_command: Option<String>,
+    /// Custom neighbor table command, e.g. `busybox arp -an` or `cat /proc/net/arp`.
+    /// Unset uses the platform default.
+    #[serde(default)]
+    pub arp_command: Option<String>,
+    /// Seconds between gateway monitor pings; `0` disables the monitor.
+    #[serde(default = "default_gateway_monitor_interval_secs")]
+    pub gateway_monitor_interval_secs: u64,
+    /// Hours between automatic database backups; `0` disables them.
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u64,
+    /// Seconds an identical alert (same type, device and message) is suppressed after
+    /// it fires; `0` stores every occurrence.
+    #[serde(default = "default_alert_cooldown_secs")]
+    pub alert_cooldown_secs: u64,
+    /// Local `HH:MM` time from which desktop notifications are held back. Alerts are still
+    /// recorded. Quiet hours apply only when both start and end are set.
+    #[serde(default)]
+    pub quiet_hours_start: Option<String>,
+    /// Local `HH:MM` time at which notifications resume; may be earlier than the start,
+    /// e.g. 22:00–07:00.
+    #[serde(default)]
+    pub quiet_hours_end: Option<String>,
+}
+
+fn default_ping_timeout_ms() -> u64 {
+    DEFAULT_PING_TIMEOUT_MS
+}
+
+fn default_gateway_monitor_interval_secs() -> u64 {
+    DEFAULT_GATEWAY_MONITOR_INTERVAL_SECS
+}
+
+fn default_backup_interval_hours() -> u64 {
+    DEFAULT_BACKUP_INTERVAL_HOURS
+}
+
+fn default_alert_cooldown_secs() -> u64 {
+    DEFAULT_ALERT_COOLDOWN_SECS
+}
+
+fn default_scan_concurrency() -> usize {
+    DEFAULT_SCAN_CONCURRENCY
+}
+
+fn default_ping_samples() -> u32 {
+    LatencySampling::default().samples
+}
+
+fn default_ping_min_samples() -> u32 {
+    LatencySampling::default().min_successful
+}
+
+fn default_ping_trim_outliers() -> bool {
+    LatencySampling::default().trim_outliers
+}
+
+impl AppSettings {
+    /// Latency write-coalescing policy derived from these settings.
+    pub fn latency_coalescing(&self) -> LatencyCoalescing {
+        LatencyCoalescing {
+            change_threshold_pct: self.latency_change_threshold_pct,
+            min_interval_secs: self.latency_min_interval_secs,
+        }
+    }
+
+    /// Check that the custom command templates have the placeholders they need.
+    pub fn validate_commands(&self) -> Result<(), String> {
+        if let Some(ref template) = self.ping_command {
+            CommandTemplate::parse(template, &[ping::PING_PLACEHOLDER])?;
+        }
+        if let Some(ref template) = self.arp_command {
+            CommandTemplate::parse(template, &[])?;
+        }
+        Ok(())
+    }
+
+    /// The quiet hours window, or `None` unless both ends are set.
+    pub fn quiet_hours(&self) -> Result<Option<QuietHours>, String> {
+        let set = |time: &Option<String>| time.clone().filter(|t| !t.trim().is_empty());
+        match (set(&self.quiet_hours_start), set(&self.quiet_hours_end)) {
+            (Some(start), Some(end)) => QuietHours::parse(&start, &end).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Ping and neighbor table programs from these settings. Invalid templates are logged
+    /// and fall back to the platform defaults.
+    pub fn scan_tools(&self) -> ScanTools {
+        let parse = |template: &Option<String>, required: &[&str]| {
+            template.as_deref().and_then(|t| {
+                CommandTemplate::parse(t, required)
+                    .map_err(|e| log::warn!("Ignoring custom command: {}", e))
+                    .ok()
+            })
+        };
+        ScanTools {
+            ping: parse(&self.ping_command, &[ping::PING_PLACEHOLDER])
+                .map(PingTool::Custom)
+                .unwrap_or_default(),
+            arp: parse(&self.arp_command, &[]),
+            ..ScanTools::default()
+        }
+    }
+
+    /// Ping sweep sampling policy derived from these settings.
+    /// `min_samples` is capped at `samples`, since more replies than requests can't arrive.
+    pub fn latency_sampling(&self) -> LatencySampling {
+        let samples = self.ping_samples.max(1);
+        LatencySampling {
+            samples,
+            min_successful: self.ping_min_samples.clamp(1, samples),
+            trim_outliers: self.ping_trim_outliers,
+        }
+    }
 }
 
 /// Load all settings from the key-value store.
@@ -40,6 +208,49 @@ pub fn get_settings(conn: &Connection) -> Result<AppSettings, rusqlite::Error> {
         graph_gravity: get("graph_gravity")?
             .and_then(|v| v.parse().ok())
             .unwrap_or(0.1),
+        health_bind_addr: get("health_bind_addr")?,
+        ingest_bind_addr: get("ingest_bind_addr")?,
+        ingest_token: get("ingest_token")?,
+        latency_change_threshold_pct: get("latency_change_threshold_pct")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0),
+        latency_min_interval_secs: get("latency_min_interval_secs")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0),
+        queue_scans: get("queue_scans")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false),
+        verbose_scan_logs: get("verbose_scan_logs")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false),
+        ping_timeout_ms: get("ping_timeout_ms")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_PING_TIMEOUT_MS),
+        ping_samples: get("ping_samples")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(default_ping_samples),
+        ping_min_samples: get("ping_min_samples")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(default_ping_min_samples),
+        ping_trim_outliers: get("ping_trim_outliers")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(default_ping_trim_outliers),
+        scan_concurrency: get("scan_concurrency")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SCAN_CONCURRENCY),
+        ping_command: get("ping_command")?,
+        arp_command: get("arp_command")?,
+        gateway_monitor_interval_secs: get("gateway_monitor_interval_secs")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_GATEWAY_MONITOR_INTERVAL_SECS),
+        backup_interval_hours: get("backup_interval_hours")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_BACKUP_INTERVAL_HOURS),
+        alert_cooldown_secs: get("alert_cooldown_secs")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_ALERT_COOLDOWN_SECS),
+        quiet_hours_start: get("quiet_hours_start")?,
+        quiet_hours_end: get("quiet_hours_end")?,
     })
 }
 
@@ -63,30 +274,121 @@ pub fn update_settings(conn: &Connection, settings: &AppSettings) -> Result<(),
     set("graph_repulsion", &settings.graph_repulsion.to_string())?;
     set("graph_link_distance", &settings.graph_link_distance.to_string())?;
     set("graph_gravity", &settings.graph_gravity.to_string())?;
+    set(
+        "latency_change_threshold_pct",
+        &settings.latency_change_threshold_pct.to_string(),
+    )?;
+    set(
+        "latency_min_interval_secs",
+        &settings.latency_min_interval_secs.to_string(),
+    )?;
+    set("queue_scans", &settings.queue_scans.to_string())?;
+    set("verbose_scan_logs", &settings.verbose_scan_logs.to_string())?;
+    set("ping_timeout_ms", &settings.ping_timeout_ms.to_string())?;
+    set("ping_samples", &settings.ping_samples.to_string())?;
+    set("ping_min_samples", &settings.ping_min_samples.to_string())?;
+    set("ping_trim_outliers", &settings.ping_trim_outliers.to_string())?;
+    set("scan_concurrency", &settings.scan_concurrency.to_string())?;
+    set(
+        "gateway_monitor_interval_secs",
+        &settings.gateway_monitor_interval_secs.to_string(),
+    )?;
+    set("backup_interval_hours", &settings.backup_interval_hours.to_string())?;
+    set("alert_cooldown_secs", &settings.alert_cooldown_secs.to_string())?;
+    match settings.health_bind_addr {
+        Some(ref addr) => set("health_bind_addr", addr)?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = 'health_bind_addr'", [])?;
+        }
+    }
+    match settings.ingest_bind_addr {
+        Some(ref addr) => set("ingest_bind_addr", addr)?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = 'ingest_bind_addr'", [])?;
+        }
+    }
+    match settings.ingest_token {
+        Some(ref token) => set("ingest_token", token)?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = 'ingest_token'", [])?;
+        }
+    }
+    match settings.ping_command {
+        Some(ref command) => set("ping_command", command)?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = 'ping_command'", [])?;
+        }
+    }
+    match settings.arp_command {
+        Some(ref command) => set("arp_command", command)?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = 'arp_command'", [])?;
+        }
+    }
+    match settings.quiet_hours_start {
+        Some(ref time) => set("quiet_hours_start", time)?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = 'quiet_hours_start'", [])?;
+        }
+    }
+    match settings.quiet_hours_end {
+        Some(ref time) => set("quiet_hours_end", time)?,
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = 'quiet_hours_end'", [])?;
+        }
+    }
 
     Ok(())
 }
 
-/// Get latency history for a device within a time window.
+/// Get latency history for a device within a time window. With `bucket_minutes` above
+/// zero, points are averaged per bucket of that many minutes and each is stamped with
+/// its bucket's start; zero returns every raw point. Samples without a latency are left out,
+/// so a bucket holding only those doesn't appear.
 pub fn get_latency_history(
     conn: &Connection,
     device_id: &str,
     hours: u32,
+    bucket_minutes: u32,
 ) -> Result<Vec<LatencyPoint>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
-        "SELECT latency_ms, measured_at FROM latency_history
-         WHERE device_id = ?1
-         AND measured_at >= datetime('now', ?2)
-         ORDER BY measured_at ASC"
-    )?;
+    let mut stmt = if bucket_minutes == 0 {
+        conn.prepare(
+            "SELECT latency_ms, measured_at, packet_loss, jitter_ms FROM latency_history
+             WHERE device_id = ?1
+             AND latency_ms IS NOT NULL
+             AND measured_at >= datetime('now', ?2)
+             ORDER BY measured_at ASC"
+        )?
+    } else {
+        conn.prepare(
+            "SELECT AVG(latency_ms),
+                    datetime(CAST(strftime('%s', measured_at) AS INTEGER) / ?3 * ?3, 'unixepoch') AS bucket,
+                    AVG(packet_loss), AVG(jitter_ms)
+             FROM latency_history
+             WHERE device_id = ?1
+             AND latency_ms IS NOT NULL
+             AND measured_at >= datetime('now', ?2)
+             GROUP BY bucket
+             ORDER BY bucket ASC"
+        )?
+    };
 
     let hours_param = format!("-{} hours", hours);
-    let points = stmt.query_map(params![device_id, hours_param], |row| {
+    // Widened so a huge bucket can't overflow
+    let bucket_secs = i64::from(bucket_minutes) * 60;
+    let map_point = |row: &rusqlite::Row| {
         Ok(LatencyPoint {
             latency_ms: row.get(0)?,
             measured_at: row.get(1)?,
+            packet_loss: row.get(2)?,
+            jitter_ms: row.get(3)?,
         })
-    })?;
+    };
+    let points = if bucket_minutes == 0 {
+        stmt.query_map(params![device_id, hours_param], map_point)?
+    } else {
+        stmt.query_map(params![device_id, hours_param, bucket_secs], map_point)?
+    };
 
     points.collect()
 }
@@ -96,6 +398,9 @@ pub fn get_latency_history(
 pub struct LatencyPoint {
     pub latency_ms: f64,
     pub measured_at: String,
+    /// Set for samples taken from several echo requests.
+    pub packet_loss: Option<f64>,
+    pub jitter_ms: Option<f64>,
 }
 
 /// Helper for optional query results.
@@ -135,4 +440,174 @@ mod tests {
         assert_eq!(loaded.theme, "light");
         assert_eq!(loaded.scan_interval_secs, 120);
     }
+
+    #[test]
+    fn test_health_bind_addr_set_and_clear() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let mut settings = get_settings(&conn).unwrap();
+        assert!(settings.health_bind_addr.is_none());
+
+        settings.health_bind_addr = Some("9477".to_string());
+        update_settings(&conn, &settings).unwrap();
+        assert_eq!(get_settings(&conn).unwrap().health_bind_addr.as_deref(), Some("9477"));
+
+        settings.health_bind_addr = None;
+        update_settings(&conn, &settings).unwrap();
+        assert!(get_settings(&conn).unwrap().health_bind_addr.is_none());
+    }
+
+    #[test]
+    fn test_quiet_hours_settings() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let mut settings = get_settings(&conn).unwrap();
+        assert_eq!(settings.quiet_hours(), Ok(None));
+
+        // Both ends are needed, and a cleared input doesn't count as set
+        settings.quiet_hours_start = Some("22:00".to_string());
+        settings.quiet_hours_end = Some(String::new());
+        assert_eq!(settings.quiet_hours(), Ok(None));
+
+        settings.quiet_hours_end = Some("07:00".to_string());
+        update_settings(&conn, &settings).unwrap();
+        let quiet = get_settings(&conn).unwrap().quiet_hours().unwrap().unwrap();
+        assert_eq!(quiet, QuietHours::parse("22:00", "07:00").unwrap());
+
+        settings.quiet_hours_end = Some("7am".to_string());
+        assert!(settings.quiet_hours().is_err());
+    }
+
+    #[test]
+    fn test_latency_coalescing_defaults_off() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let mut settings = get_settings(&conn).unwrap();
+        assert_eq!(settings.latency_coalescing().min_interval_secs, 0);
+
+        settings.latency_change_threshold_pct = 15.0;
+        settings.latency_min_interval_secs = 600;
+        update_settings(&conn, &settings).unwrap();
+
+        let policy = get_settings(&conn).unwrap().latency_coalescing();
+        assert_eq!(policy.change_threshold_pct, 15.0);
+        assert_eq!(policy.min_interval_secs, 600);
+    }
+
+    #[test]
+    fn test_latency_sampling_settings() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let mut settings = get_settings(&conn).unwrap();
+        assert_eq!(settings.latency_sampling(), LatencySampling::default());
+
+        settings.ping_samples = 1;
+        settings.ping_min_samples = 4;
+        settings.ping_trim_outliers = false;
+        update_settings(&conn, &settings).unwrap();
+
+        let sampling = get_settings(&conn).unwrap().latency_sampling();
+        assert_eq!(sampling, LatencySampling::SINGLE);
+    }
+
+    #[test]
+    fn test_scan_tools_from_settings() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+
+        let mut settings = get_settings(&conn).unwrap();
+        let tools = settings.scan_tools();
+        assert_eq!(tools.ping, PingTool::System);
+        assert!(tools.arp.is_none());
+
+        settings.ping_command = Some("fping -e {ip}".to_string());
+        settings.arp_command = Some("busybox arp -an".to_string());
+        assert!(settings.validate_commands().is_ok());
+        update_settings(&conn, &settings).unwrap();
+        let tools = get_settings(&conn).unwrap().scan_tools();
+        assert!(matches!(tools.ping, PingTool::Custom(_)));
+        assert!(tools.arp.is_some());
+
+        // A ping template without {ip} is rejected, and ignored if it gets stored anyway
+        settings.ping_command = Some("fping -e".to_string());
+        assert!(settings.validate_commands().is_err());
+        assert_eq!(settings.scan_tools().ping, PingTool::System);
+    }
+
+    /// Twenty samples a minute apart starting on a 10-minute boundary an hour ago:
+    /// 10..19 ms in the first ten minutes, 30..39 ms in the next ten.
+    fn seed_latency(conn: &Connection) {
+        crate::db::queries::devices::insert_device(conn, "dev1", None, None, None, "unknown", false, None).unwrap();
+        for i in 0..20 {
+            let latency = if i < 10 { 10.0 + i as f64 } else { 20.0 + i as f64 };
+            conn.execute(
+                "INSERT INTO latency_history (device_id, latency_ms, measured_at)
+                 VALUES ('dev1', ?1, datetime(CAST(strftime('%s', 'now') AS INTEGER) / 600 * 600 - 3600 + ?2, 'unixepoch'))",
+                params![latency, i * 60],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_latency_history_raw() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        seed_latency(&conn);
+
+        let points = get_latency_history(&conn, "dev1", 24, 0).unwrap();
+        assert_eq!(points.len(), 20);
+        assert_eq!(points[0].latency_ms, 10.0);
+        assert!(get_latency_history(&conn, "other", 24, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_latency_history_bucketed() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        seed_latency(&conn);
+
+        let buckets = get_latency_history(&conn, "dev1", 24, 10).unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].latency_ms, 14.5);
+        assert_eq!(buckets[1].latency_ms, 34.5);
+        assert_eq!(buckets[0].measured_at, get_latency_history(&conn, "dev1", 24, 0).unwrap()[0].measured_at);
+        assert!(buckets[0].packet_loss.is_none());
+
+        // Five-minute buckets split each run in half
+        let halves: Vec<f64> = get_latency_history(&conn, "dev1", 24, 5).unwrap().iter().map(|p| p.latency_ms).collect();
+        assert_eq!(halves, [12.0, 17.0, 32.0, 37.0]);
+
+        // Overall average is unchanged by bucketing
+        let raw = get_latency_history(&conn, "dev1", 24, 0).unwrap();
+        let raw_avg = raw.iter().map(|p| p.latency_ms).sum::<f64>() / raw.len() as f64;
+        let bucket_avg = buckets.iter().map(|p| p.latency_ms).sum::<f64>() / buckets.len() as f64;
+        assert_eq!(raw_avg, bucket_avg);
+
+        // A bucket larger than u32 seconds holds everything
+        let all = get_latency_history(&conn, "dev1", 24, u32::MAX).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].latency_ms, raw_avg);
+    }
+
+    #[test]
+    fn test_latency_history_skips_missing_latency() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        seed_latency(&conn);
+        // A sample with no latency three hours ago, alone in its bucket
+        conn.execute(
+            "INSERT INTO latency_history (device_id, latency_ms, packet_loss, measured_at)
+             VALUES ('dev1', NULL, 100.0, datetime('now', '-3 hours'))",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(get_latency_history(&conn, "dev1", 24, 0).unwrap().len(), 20);
+        assert_eq!(get_latency_history(&conn, "dev1", 24, 10).unwrap().len(), 2);
+    }
 }
@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, Utc};
+use rusqlite::{Connection, DatabaseName};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::db::{self, migrations, queries::settings as db_settings, RepairReport};
+use crate::state::AppState;
+use crate::util::time;
+
+/// Backups older than the newest this many are deleted after each backup.
+pub const BACKUP_RETENTION: usize = 7;
+
+/// Default hours between automatic backups.
+pub const DEFAULT_BACKUP_INTERVAL_HOURS: u64 = 24;
+
+const BACKUP_PREFIX: &str = "echolocate_backup_";
+const BACKUP_EXT: &str = ".db";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S_%3f";
+
+/// How often the scheduler checks whether a backup is due.
+const SCHEDULE_TICK: Duration = Duration::from_secs(15 * 60);
+
+/// Directory backups are written to, under the app data directory.
+pub fn backup_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("backups")
+}
+
+fn backup_file_name(at: NaiveDateTime) -> String {
+    format!("{}{}{}", BACKUP_PREFIX, at.format(TIMESTAMP_FORMAT), BACKUP_EXT)
+}
+
+/// When a backup was taken, from its file name. `None` for any other file.
+fn backup_timestamp(file_name: &str) -> Option<NaiveDateTime> {
+    let stamp = file_name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(BACKUP_EXT)?;
+    NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok()
+}
+
+/// A backup file as shown to the user.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub filename: String,
+    /// When the backup was taken, UTC, as `YYYY-MM-DD HH:MM:SS`.
+    pub timestamp: String,
+    pub size_bytes: u64,
+}
+
+/// Reject anything but a bare `echolocate_backup_<timestamp>.db` name, so a
+/// caller-supplied filename can never point outside the backup directory.
+pub fn validate_backup_name(filename: &str) -> Result<(), String> {
+    let bare = !filename.contains(['/', '\\']) && !filename.contains("..");
+    if bare && backup_timestamp(filename).is_some() {
+        Ok(())
+    } else {
+        Err(format!("Not a backup file name: {}", filename))
+    }
+}
+
+/// Backups in `dir`, newest first.
+pub fn list_backups(dir: &Path) -> std::io::Result<Vec<BackupInfo>> {
+    let mut backups = list_backup_files(dir)?
+        .into_iter()
+        .map(|(path, taken)| {
+            Ok(BackupInfo {
+                filename: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                timestamp: time::to_db_string(taken),
+                size_bytes: std::fs::metadata(&path)?.len(),
+            })
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Replace the live database's contents with backup `filename` from `dir`.
+///
+/// The restore goes through SQLite's backup API into the open database rather than
+/// swapping files under the pool, so every pooled connection sees the restored data on
+/// its next query. Migrations then bring an older backup's schema up to date.
+pub fn restore_from_backup(conn: &mut Connection, dir: &Path, filename: &str) -> Result<RepairReport, String> {
+    validate_backup_name(filename)?;
+    let path = dir.join(filename);
+    if !path.is_file() {
+        return Err(format!("Backup not found: {}", filename));
+    }
+
+    // Never overwrite a working database with a damaged copy
+    let source = Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())?;
+    if !db::check_integrity(&source).map_err(|e| e.to_string())? {
+        return Err(format!("Backup {} failed its integrity check", filename));
+    }
+    drop(source);
+
+    conn.restore(DatabaseName::Main, &path, None::<fn(rusqlite::backup::Progress)>)
+        .map_err(|e| e.to_string())?;
+    migrations::run(conn).map_err(|e| e.to_string())?;
+    db::diagnose_and_repair(conn).map_err(|e| e.to_string())
+}
+
+/// Backups in `dir`, oldest first. A missing directory has none.
+pub fn list_backup_files(dir: &Path) -> std::io::Result<Vec<(PathBuf, NaiveDateTime)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut backups: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let taken = backup_timestamp(path.file_name()?.to_str()?)?;
+            Some((path, taken))
+        })
+        .collect();
+    backups.sort_by_key(|(_, taken)| *taken);
+    Ok(backups)
+}
+
+/// Copy the live database into a new timestamped file in `dir` with SQLite's online
+/// backup API, which reads a consistent snapshot even while the WAL has unmerged writes.
+/// The copy is written under a temporary name so a crash never leaves a partial backup.
+pub fn create_backup(conn: &Connection, dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(backup_file_name(Utc::now().naive_utc()));
+    let partial = path.with_extension("db.partial");
+
+    conn.backup(DatabaseName::Main, &partial, None)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// Delete all but the newest `keep` backups in `dir`. Returns how many were removed.
+pub fn cleanup_old_backups(dir: &Path, keep: usize) -> std::io::Result<usize> {
+    let backups = list_backup_files(dir)?;
+    let excess = backups.len().saturating_sub(keep);
+    for (path, _) in &backups[..excess] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(excess)
+}
+
+/// Take a backup and prune old ones down to `keep`.
+pub fn run_backup(conn: &Connection, dir: &Path, keep: usize) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = create_backup(conn, dir)?;
+    cleanup_old_backups(dir, keep)?;
+    Ok(path)
+}
+
+/// Whether the newest backup in `dir` is at least `interval` old, or there is none.
+fn backup_due(dir: &Path, interval: Duration, now: NaiveDateTime) -> bool {
+    let newest = list_backup_files(dir).ok().and_then(|b| b.last().map(|(_, taken)| *taken));
+    match newest {
+        Some(taken) => (now - taken).to_std().is_ok_and(|age| age >= interval),
+        None => true,
+    }
+}
+
+/// Back up the database every `backup_interval_hours` until the app exits.
+/// Due-ness is judged from the newest backup on disk, so restarts don't reset the clock.
+pub async fn run_scheduled(app: AppHandle, dir: PathBuf) {
+    loop {
+        let pool = app.state::<Arc<AppState>>().db.clone();
+        let hours = pool
+            .get()
+            .ok()
+            .and_then(|c| db_settings::get_settings(&c).ok())
+            .map_or(DEFAULT_BACKUP_INTERVAL_HOURS, |s| s.backup_interval_hours);
+
+        if hours > 0 && backup_due(&dir, Duration::from_secs(hours * 3600), Utc::now().naive_utc()) {
+            let dir = dir.clone();
+            let outcome = tokio::task::spawn_blocking(move || {
+                let conn = pool.get().map_err(|e| e.to_string())?;
+                run_backup(&conn, &dir, BACKUP_RETENTION).map_err(|e| e.to_string())
+            })
+            .await;
+            match outcome {
+                Ok(Ok(path)) => log::info!("Database backed up to {}", path.display()),
+                Ok(Err(e)) => log::warn!("Scheduled backup failed: {}", e),
+                Err(e) => log::warn!("Scheduled backup task failed: {}", e),
+            }
+        }
+
+        tokio::time::sleep(SCHEDULE_TICK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("echolocate_{}_{}", name, uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_backup_count_stays_within_retention() {
+        let pool = db::init_test_db();
+        let conn = pool.get().unwrap();
+        conn.execute("INSERT INTO settings (key, value) VALUES ('backup_test', 'kept')", []).unwrap();
+        let dir = temp_dir("backups");
+
+        for _ in 0..(BACKUP_RETENTION + 3) {
+            run_backup(&conn, &dir, BACKUP_RETENTION).unwrap();
+            assert!(list_backup_files(&dir).unwrap().len() <= BACKUP_RETENTION);
+            // Distinct millisecond timestamps, so each run writes a new file
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        let backups = list_backup_files(&dir).unwrap();
+        assert_eq!(backups.len(), BACKUP_RETENTION);
+
+        // Backups are complete databases, and no partial files are left behind
+        let restored = Connection::open(&backups[0].0).unwrap();
+        let value: String = restored
+            .query_row("SELECT value FROM settings WHERE key = 'backup_test'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(value, "kept");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), BACKUP_RETENTION);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_backup_name_validation() {
+        assert!(validate_backup_name("echolocate_backup_20260101_120000_000.db").is_ok());
+
+        for bad in [
+            "../echolocate_backup_20260101_120000_000.db",
+            "backups/echolocate_backup_20260101_120000_000.db",
+            "..\\echolocate_backup_20260101_120000_000.db",
+            "echolocate_backup_../../etc/passwd.db",
+            "echolocate_backup_..db",
+            "echolocate.db",
+            "/tmp/echolocate_backup_20260101_120000_000.db",
+            "",
+        ] {
+            assert!(validate_backup_name(bad).is_err(), "accepted {:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_restore_from_backup() {
+        let pool = db::init_test_db();
+        let mut conn = pool.get().unwrap();
+        let dir = temp_dir("restore");
+
+        conn.execute("INSERT INTO settings (key, value) VALUES ('restore_test', 'before')", []).unwrap();
+        let backup = create_backup(&conn, &dir).unwrap();
+        conn.execute("UPDATE settings SET value = 'after' WHERE key = 'restore_test'", []).unwrap();
+
+        let listed = list_backups(&dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].size_bytes > 0);
+        let filename = backup.file_name().unwrap().to_str().unwrap();
+        assert_eq!(listed[0].filename, filename);
+
+        let report = restore_from_backup(&mut conn, &dir, filename).unwrap();
+        assert!(report.integrity_ok);
+        let value: String = conn
+            .query_row("SELECT value FROM settings WHERE key = 'restore_test'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(value, "before");
+
+        assert!(restore_from_backup(&mut conn, &dir, "echolocate_backup_20200101_000000_000.db")
+            .unwrap_err()
+            .contains("not found"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cleanup_keeps_newest_and_ignores_other_files() {
+        let dir = temp_dir("cleanup");
+        std::fs::create_dir_all(&dir).unwrap();
+        let names = [
+            "echolocate_backup_20260101_000000_000.db",
+            "echolocate_backup_20260301_000000_000.db",
+            "echolocate_backup_20260201_000000_000.db",
+            "echolocate.db",
+            "notes.txt",
+        ];
+        for name in names {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        assert_eq!(cleanup_old_backups(&dir, 2).unwrap(), 1);
+        assert!(!dir.join(names[0]).exists());
+        assert!(dir.join(names[1]).exists() && dir.join(names[2]).exists());
+        assert!(dir.join("echolocate.db").exists() && dir.join("notes.txt").exists());
+
+        let now = backup_timestamp(names[1]).unwrap();
+        assert!(!backup_due(&dir, Duration::from_secs(3600), now));
+        assert!(backup_due(&dir, Duration::from_secs(3600), now + chrono::Duration::hours(2)));
+        assert!(backup_due(&temp_dir("missing"), Duration::from_secs(3600), now));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
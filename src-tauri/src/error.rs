@@ -96,6 +96,11 @@ impl AppError {
         )
     }
 
+    /// Create not found error for a missing record
+    pub fn not_found(what: &str, id: &str) -> Self {
+        Self::new("NOT_FOUND", &format!("{} not found: {}", what, id))
+    }
+
     /// Create internal error (for unexpected conditions)
     pub fn internal(reason: &str) -> Self {
         Self::new("INTERNAL_ERROR", reason)
@@ -130,6 +135,24 @@ impl From<rusqlite::Error> for AppError {
     }
 }
 
+impl From<r2d2::Error> for AppError {
+    fn from(e: r2d2::Error) -> Self {
+        AppError::database(&e.to_string())
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for AppError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        AppError::internal(&e.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AppError::internal(&e.to_string())
+    }
+}
+
 impl From<std::io::Error> for AppError {
     fn from(e: std::io::Error) -> Self {
         match e.kind() {
@@ -213,6 +236,21 @@ mod tests {
         assert!(error.details.is_some());
     }
 
+    #[test]
+    fn test_app_error_not_found() {
+        let error = AppError::not_found("Device", "dev1");
+        assert_eq!(error.code, "NOT_FOUND");
+        assert_eq!(error.message, "Device not found: dev1");
+    }
+
+    #[test]
+    fn test_from_io_error_keeps_kind() {
+        let denied: AppError = std::io::Error::from(std::io::ErrorKind::PermissionDenied).into();
+        assert_eq!(denied.code, "PERMISSION_DENIED");
+        let missing: AppError = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        assert_eq!(missing.code, "FILE_NOT_FOUND");
+    }
+
     #[test]
     fn test_from_string() {
         let error: AppError = "test error".into();
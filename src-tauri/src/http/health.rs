@@ -0,0 +1,143 @@
+use std::net::SocketAddr;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::db;
+use crate::db::queries::{devices as db_devices, scans as db_scans};
+use crate::state::AppState;
+
+/// Payload served by `GET /healthz`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthStatus {
+    pub db_ok: bool,
+    pub monitor_running: bool,
+    pub last_scan_age_secs: Option<i64>,
+    pub device_count: u32,
+}
+
+/// Gather health information from the shared state.
+pub fn collect(state: &AppState) -> HealthStatus {
+    let conn = state.conn().ok();
+
+    let db_ok = conn
+        .as_ref()
+        .map(|c| db::check_integrity(c).unwrap_or(false))
+        .unwrap_or(false);
+
+    let last_scan_age_secs = conn
+        .as_ref()
+        .and_then(|c| db_scans::last_scan_age_secs(c).ok().flatten());
+
+    let device_count = conn
+        .as_ref()
+        .and_then(|c| db_devices::count_devices(c).ok())
+        .unwrap_or(0);
+
+    let monitor_running = state
+        .monitor_handle
+        .lock()
+        .map(|h| h.as_ref().is_some_and(|handle| !handle.is_finished()))
+        .unwrap_or(false);
+
+    HealthStatus {
+        db_ok,
+        monitor_running,
+        last_scan_age_secs,
+        device_count,
+    }
+}
+
+/// Map a request to a status code and JSON body.
+/// Reports 503 when the database is unhealthy so uptime checks fail loudly.
+pub fn respond(state: &AppState, request: &super::Request) -> (u16, serde_json::Value) {
+    if request.path != "/healthz" {
+        return (404, serde_json::json!({ "error": "not found" }));
+    }
+    if request.method != "GET" {
+        return (405, serde_json::json!({ "error": "method not allowed" }));
+    }
+
+    let status = collect(state);
+    let code = if status.db_ok { 200 } else { 503 };
+    (code, serde_json::to_value(&status).unwrap_or_default())
+}
+
+/// Serve the health endpoint until the listener fails.
+pub async fn serve(app: AppHandle, addr: SocketAddr) {
+    super::serve(app, addr, "Health", respond).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Request;
+    use crate::network::oui::OuiDatabase;
+
+    fn test_state() -> AppState {
+        AppState::new(db::init_test_db(), OuiDatabase::empty())
+    }
+
+    fn get(path: &str) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_collect_fresh_state() {
+        let state = test_state();
+        let status = collect(&state);
+        assert!(status.db_ok);
+        assert!(!status.monitor_running);
+        assert_eq!(status.last_scan_age_secs, None);
+        assert_eq!(status.device_count, 0);
+    }
+
+    #[test]
+    fn test_collect_counts_devices_and_scans() {
+        let state = test_state();
+        {
+            let conn = state.conn().unwrap();
+            db_devices::insert_device(
+                &conn, "dev1", Some("AA:BB:CC:DD:EE:FF"), None, None, "unknown", false, None,
+            ).unwrap();
+            db_scans::create_scan(&conn, "scan1", None, "quick").unwrap();
+            db_scans::complete_scan(&conn, "scan1", 1, 1, 100).unwrap();
+        }
+
+        let status = collect(&state);
+        assert_eq!(status.device_count, 1);
+        assert!(status.last_scan_age_secs.is_some());
+    }
+
+    #[test]
+    fn test_respond_healthz() {
+        let state = test_state();
+        let (code, body) = respond(&state, &get("/healthz"));
+        assert_eq!(code, 200);
+        assert_eq!(body["db_ok"], true);
+        assert_eq!(body["device_count"], 0);
+    }
+
+    #[test]
+    fn test_respond_unknown_path() {
+        let state = test_state();
+        let (code, _) = respond(&state, &get("/metrics"));
+        assert_eq!(code, 404);
+    }
+
+    #[test]
+    fn test_respond_wrong_method() {
+        let state = test_state();
+        let request = Request {
+            method: "POST".to_string(),
+            path: "/healthz".to_string(),
+            ..Default::default()
+        };
+        let (code, _) = respond(&state, &request);
+        assert_eq!(code, 405);
+    }
+}
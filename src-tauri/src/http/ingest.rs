@@ -0,0 +1,238 @@
+use std::net::SocketAddr;
+
+use tauri::AppHandle;
+
+use crate::commands::export::{self, ExportData};
+use crate::commands::validate::Validator;
+use crate::db::queries::settings as db_settings;
+use crate::state::AppState;
+
+/// Header naming the reporting agent; stored on each device it introduces.
+pub const AGENT_HEADER: &str = "x-echolocate-agent";
+
+/// Compare two tokens without short-circuiting on the first mismatched byte.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    let (a, b) = (given.as_bytes(), expected.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Whether the request carries `Authorization: Bearer <expected>`.
+fn authorized(request: &super::Request, expected: &str) -> bool {
+    request
+        .header("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| tokens_match(token.trim(), expected))
+}
+
+fn error(code: u16, message: &str) -> (u16, serde_json::Value) {
+    (code, serde_json::json!({ "error": message }))
+}
+
+/// Map a request to a status code and JSON body.
+/// Accepts `POST /ingest` with an `ExportData` body and merges it like a file import,
+/// so a repeat report fills in details the stored device is missing.
+pub fn respond(state: &AppState, request: &super::Request) -> (u16, serde_json::Value) {
+    if request.path != "/ingest" {
+        return error(404, "not found");
+    }
+    if request.method != "POST" {
+        return error(405, "method not allowed");
+    }
+
+    let Ok(conn) = state.conn() else {
+        return error(503, "database unavailable");
+    };
+
+    // Read the token per request so clearing it takes effect without a restart
+    let token = db_settings::get_settings(&conn).ok().and_then(|s| s.ingest_token);
+    let Some(token) = token.filter(|t| !t.is_empty()) else {
+        return error(503, "ingest disabled");
+    };
+    if !authorized(request, &token) {
+        return error(401, "unauthorized");
+    }
+
+    let agent = match request.header(AGENT_HEADER).map(Validator::validate_device_name) {
+        Some(Ok(agent)) => agent,
+        Some(Err(e)) => return error(400, &e),
+        None => return error(400, "missing X-Echolocate-Agent header"),
+    };
+
+    let data: ExportData = match serde_json::from_slice(&request.body) {
+        Ok(data) => data,
+        Err(e) => return error(400, &format!("invalid body: {}", e)),
+    };
+
+    match export::import_data(&conn, &data, Some(&agent), export::ImportStrategy::Merge) {
+        Ok(result) => {
+            log::info!(
+                "Agent {} reported {} new devices ({} updated, {} already known)",
+                agent,
+                result.imported,
+                result.updated,
+                result.skipped
+            );
+            (200, serde_json::to_value(&result).unwrap_or_default())
+        }
+        Err(e) => {
+            log::error!("Ingest from agent {} failed: {}", agent, e);
+            error(500, "import failed")
+        }
+    }
+}
+
+/// Serve the ingest endpoint until the listener fails.
+pub async fn serve(app: AppHandle, addr: SocketAddr) {
+    super::serve(app, addr, "Ingest", respond).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::queries::devices as db_devices;
+    use crate::http::Request;
+    use crate::network::oui::OuiDatabase;
+
+    fn test_state(token: Option<&str>) -> AppState {
+        let state = AppState::new(db::init_test_db(), OuiDatabase::empty());
+        {
+            let conn = state.conn().unwrap();
+            let mut settings = db_settings::get_settings(&conn).unwrap();
+            settings.ingest_token = token.map(|t| t.to_string());
+            db_settings::update_settings(&conn, &settings).unwrap();
+        }
+        state
+    }
+
+    fn report() -> serde_json::Value {
+        serde_json::json!({
+            "version": 1,
+            "exportedAt": "2026-01-01 00:00:00",
+            "devices": [{
+                "id": "remote-1",
+                "macAddress": "AA:BB:CC:00:11:22",
+                "vendor": null,
+                "hostname": "pi-neighbour",
+                "customName": null,
+                "deviceType": "iot",
+                "osGuess": null,
+                "osConfidence": 0.0,
+                "isTrusted": false,
+                "isGateway": false,
+                "notes": null,
+                "currentIp": "10.0.5.20",
+                "isOnline": true,
+                "latencyMs": null,
+                "openPorts": [],
+                "firstSeen": "2026-01-01 00:00:00",
+                "lastSeen": "2026-01-01 00:00:00"
+            }],
+            "alerts": []
+        })
+    }
+
+    fn body() -> Vec<u8> {
+        report().to_string().into_bytes()
+    }
+
+    fn post(headers: &[(&str, &str)], body: Vec<u8>) -> Request {
+        Request {
+            method: "POST".to_string(),
+            path: "/ingest".to_string(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), v.to_string()))
+                .collect(),
+            body,
+        }
+    }
+
+    #[test]
+    fn test_ingest_tags_source_agent() {
+        let state = test_state(Some("secret"));
+        let request = post(
+            &[("Authorization", "Bearer secret"), ("X-Echolocate-Agent", "basement-pi")],
+            body(),
+        );
+
+        let (code, result) = respond(&state, &request);
+        assert_eq!(code, 200);
+        assert_eq!(result["imported"], 1);
+
+        {
+            let conn = state.conn().unwrap();
+            let devices = db_devices::get_all_devices(&conn).unwrap();
+            assert_eq!(devices.len(), 1);
+            assert_eq!(devices[0].source_agent.as_deref(), Some("basement-pi"));
+        }
+
+        // Reporting the same device again is a no-op
+        let (_, result) = respond(&state, &request);
+        assert_eq!(result["skipped"], 1);
+    }
+
+    #[test]
+    fn test_ingest_fills_in_known_device() {
+        let state = test_state(Some("secret"));
+        let headers = [("Authorization", "Bearer secret"), ("X-Echolocate-Agent", "basement-pi")];
+        respond(&state, &post(&headers, body()));
+
+        let mut richer = report();
+        richer["devices"][0]["osGuess"] = "Raspbian".into();
+        richer["devices"][0]["osConfidence"] = 0.7.into();
+        richer["devices"][0]["notes"] = "Under the stairs".into();
+        richer["devices"][0]["hostname"] = "renamed-pi".into();
+        let (code, result) = respond(&state, &post(&headers, richer.to_string().into_bytes()));
+        assert_eq!(code, 200);
+        assert_eq!(result["updated"], 1);
+
+        let conn = state.conn().unwrap();
+        let devices = db_devices::get_all_devices(&conn).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].os_guess.as_deref(), Some("Raspbian"));
+        assert_eq!(devices[0].notes.as_deref(), Some("Under the stairs"));
+        // Details the device already had are kept
+        assert_eq!(devices[0].hostname.as_deref(), Some("pi-neighbour"));
+    }
+
+    #[test]
+    fn test_ingest_requires_token() {
+        let state = test_state(Some("secret"));
+        let agent = ("X-Echolocate-Agent", "pi");
+
+        let (code, _) = respond(&state, &post(&[agent], body()));
+        assert_eq!(code, 401);
+        let (code, _) = respond(&state, &post(&[("Authorization", "Bearer wrong"), agent], body()));
+        assert_eq!(code, 401);
+
+        let disabled = test_state(None);
+        let (code, _) = respond(&disabled, &post(&[("Authorization", "Bearer "), agent], body()));
+        assert_eq!(code, 503);
+    }
+
+    #[test]
+    fn test_ingest_rejects_bad_requests() {
+        let state = test_state(Some("secret"));
+        let auth = ("Authorization", "Bearer secret");
+
+        let (code, _) = respond(&state, &post(&[auth], body()));
+        assert_eq!(code, 400);
+        let (code, _) = respond(&state, &post(&[auth, ("X-Echolocate-Agent", "pi")], b"{".to_vec()));
+        assert_eq!(code, 400);
+
+        let mut get = post(&[auth], Vec::new());
+        get.method = "GET".to_string();
+        assert_eq!(respond(&state, &get).0, 405);
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("abc", "abc"));
+        assert!(!tokens_match("abd", "abc"));
+        assert!(!tokens_match("ab", "abc"));
+    }
+}
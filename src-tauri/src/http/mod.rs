@@ -0,0 +1,256 @@
+/// Minimal HTTP/1.1 plumbing for the optional localhost endpoints.
+/// Deliberately tiny: one request per connection, no keep-alive, no chunking.
+pub mod health;
+pub mod ingest;
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::state::AppState;
+
+/// Largest request head we are willing to buffer.
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+
+/// Largest request body we are willing to buffer.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// How long a client gets to send its request before we hang up.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The parts of an HTTP request the endpoints care about.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// First value of a header, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn content_length(&self) -> Option<usize> {
+        self.header("content-length")?.parse().ok()
+    }
+}
+
+/// Maps a request to a status code and JSON body.
+pub type Handler = fn(&AppState, &Request) -> (u16, serde_json::Value);
+
+/// Resolve a configured bind address. A bare port binds to loopback.
+pub fn resolve_bind_addr(value: &str) -> Result<SocketAddr, String> {
+    let value = value.trim();
+    if let Ok(port) = value.parse::<u16>() {
+        return Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+    }
+    value
+        .parse::<SocketAddr>()
+        .map_err(|_| format!("Invalid bind address: {} (expected host:port or port)", value))
+}
+
+/// Parse the request line and headers out of a raw request head.
+pub fn parse_request_head(head: &str) -> Option<Request> {
+    let mut lines = head.lines();
+    let line = lines.next()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    let version = parts.next()?;
+    if !version.starts_with("HTTP/") {
+        return None;
+    }
+
+    // Ignore any query string
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let headers = lines
+        .take_while(|l| !l.is_empty())
+        .filter_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            Some((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        })
+        .collect();
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    })
+}
+
+/// Read and parse a request from the stream, including a `Content-Length` body.
+pub async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+
+    let read = async {
+        let head_end = loop {
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if buf.len() > MAX_HEAD_BYTES {
+                return None;
+            }
+        };
+
+        let mut request = parse_request_head(&String::from_utf8_lossy(&buf[..head_end]))?;
+        let length = request.content_length().unwrap_or(0);
+        if length > MAX_BODY_BYTES {
+            return None;
+        }
+
+        let mut body = buf.split_off(head_end);
+        while body.len() < length {
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            body.extend_from_slice(&chunk[..n]);
+        }
+        body.truncate(length);
+        request.body = body;
+        Some(request)
+    };
+
+    tokio::time::timeout(READ_TIMEOUT, read).await.ok()?
+}
+
+/// Accept connections on `addr` and answer each request with `handler` until the listener fails.
+pub async fn serve(app: AppHandle, addr: SocketAddr, name: &'static str, handler: Handler) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            log::error!("Failed to bind {} endpoint on {}: {}", name, addr, e);
+            return;
+        }
+    };
+    log::info!("{} endpoint listening on http://{}", name, addr);
+
+    loop {
+        let (mut stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("{} endpoint accept failed: {}", name, e);
+                continue;
+            }
+        };
+
+        let app = app.clone();
+        tokio::spawn(async move {
+            let Some(request) = read_request(&mut stream).await else {
+                let _ = write_json(&mut stream, 400, &serde_json::json!({ "error": "bad request" })).await;
+                return;
+            };
+
+            // Handlers query SQLite, so keep them off the async workers
+            let state = app.state::<Arc<AppState>>().inner().clone();
+            let (code, body) = match tokio::task::spawn_blocking(move || handler(&state, &request)).await {
+                Ok(response) => response,
+                Err(e) => {
+                    log::error!("{} handler failed: {}", name, e);
+                    (500, serde_json::json!({ "error": "internal error" }))
+                }
+            };
+            if let Err(e) = write_json(&mut stream, code, &body).await {
+                log::debug!("Failed to write {} response: {}", name, e);
+            }
+        });
+    }
+}
+
+/// Serialize `body` as JSON and write a complete response.
+pub async fn write_json<T: Serialize>(stream: &mut TcpStream, status: u16, body: &T) -> std::io::Result<()> {
+    let json = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        json.len(),
+        json
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_bare_port_binds_loopback() {
+        let addr = resolve_bind_addr("9477").unwrap();
+        assert_eq!(addr.to_string(), "127.0.0.1:9477");
+    }
+
+    #[test]
+    fn test_resolve_explicit_addr() {
+        let addr = resolve_bind_addr("0.0.0.0:8080").unwrap();
+        assert_eq!(addr.to_string(), "0.0.0.0:8080");
+    }
+
+    #[test]
+    fn test_resolve_invalid_addr() {
+        assert!(resolve_bind_addr("localhost").is_err());
+        assert!(resolve_bind_addr("99999").is_err());
+        assert!(resolve_bind_addr("").is_err());
+    }
+
+    #[test]
+    fn test_parse_request_head() {
+        let req = parse_request_head("GET /healthz?verbose=1 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!(req.method, "GET");
+        assert_eq!(req.path, "/healthz");
+    }
+
+    #[test]
+    fn test_parse_request_headers() {
+        let req = parse_request_head(
+            "POST /ingest HTTP/1.1\r\nContent-Length: 42\r\nAuthorization: Bearer abc\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(req.header("content-length"), Some("42"));
+        assert_eq!(req.header("Authorization"), Some("Bearer abc"));
+        assert_eq!(req.content_length(), Some(42));
+        assert!(req.header("x-missing").is_none());
+    }
+
+    #[test]
+    fn test_parse_request_head_malformed() {
+        assert!(parse_request_head("").is_none());
+        assert!(parse_request_head("GET /healthz").is_none());
+        assert!(parse_request_head("GET /healthz SPDY/3\r\n\r\n").is_none());
+    }
+}
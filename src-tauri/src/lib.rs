@@ -2,12 +2,18 @@ pub mod alerts;
 pub mod commands;
 pub mod db;
 pub mod error;
+pub mod http;
 pub mod network;
 pub mod scanner;
+pub mod security;
+pub mod shutdown;
 pub mod state;
+pub mod util;
 
 pub use error::{AppError, TauriResult};
 
+use std::sync::Arc;
+
 use tauri::Manager;
 
 use state::AppState;
@@ -37,8 +43,56 @@ pub fn run() {
                     network::oui::OuiDatabase::empty()
                 });
 
+            let rules = scanner::fingerprint::Ruleset::load(app.handle()).unwrap_or_else(|e| {
+                log::warn!("Failed to load fingerprint rules: {}. Using the built-in rules.", e);
+                scanner::fingerprint::Ruleset::builtin()
+            });
+            rules.install();
+
+            let services = scanner::services::ServiceTable::load(app.handle()).unwrap_or_else(|e| {
+                log::warn!("Failed to load service table: {}. Only built-in port names will be shown.", e);
+                scanner::services::ServiceTable::empty()
+            });
+            services.install();
+
+            let startup_settings = db_pool
+                .get()
+                .ok()
+                .and_then(|conn| db::queries::settings::get_settings(&conn).ok());
+            let health_addr = startup_settings.as_ref().and_then(|s| s.health_bind_addr.clone());
+            let ingest_addr = startup_settings
+                .as_ref()
+                .filter(|s| s.ingest_token.as_deref().is_some_and(|t| !t.is_empty()))
+                .and_then(|s| s.ingest_bind_addr.clone());
+
             let app_state = AppState::new(db_pool, oui_db);
-            app.manage(app_state);
+            app.manage(Arc::new(app_state));
+
+            tauri::async_runtime::spawn(db::recovery::run_scheduled(
+                app.handle().clone(),
+                db::recovery::backup_dir(&app_data_dir),
+            ));
+
+            if let Some(addr) = health_addr {
+                match http::resolve_bind_addr(&addr) {
+                    Ok(addr) => {
+                        tauri::async_runtime::spawn(http::health::serve(app.handle().clone(), addr));
+                    }
+                    Err(e) => log::warn!("Health endpoint disabled: {}", e),
+                }
+            }
+
+            if let Some(addr) = ingest_addr {
+                match http::resolve_bind_addr(&addr) {
+                    Ok(addr) => {
+                        tauri::async_runtime::spawn(http::ingest::serve(app.handle().clone(), addr));
+                    }
+                    Err(e) => log::warn!("Ingest endpoint disabled: {}", e),
+                }
+            }
+
+            tauri::async_runtime::spawn(network::watcher::watch(app.handle().clone()));
+            tauri::async_runtime::spawn(network::gateway_monitor::run(app.handle().clone()));
 
             log::info!("Echolocate initialized successfully");
             Ok(())
@@ -47,11 +101,27 @@ pub fn run() {
             commands::scan::start_scan,
             commands::scan::stop_scan,
             commands::scan::get_scan_history,
+            commands::scan::get_scan_log,
+            commands::scan::get_scan_diff,
             commands::device::get_devices,
+            commands::device::get_devices_paged,
             commands::device::get_device,
+            commands::device::get_devices_needing_review,
+            commands::device::search_notes,
+            commands::device::get_ip_history,
+            commands::device::get_fingerprint_history,
+            commands::device::add_device_tag,
+            commands::device::remove_device_tag,
+            commands::device::get_tags,
+            commands::device::set_device_property,
+            commands::device::get_device_properties,
             commands::device::update_device,
+            commands::device::bulk_update_devices,
             commands::device::delete_device,
+            commands::device::check_device_now,
+            commands::device::wake_device,
             commands::alert::get_alerts,
+            commands::alert::get_alerts_by_rule,
             commands::alert::mark_alert_read,
             commands::alert::mark_all_alerts_read,
             commands::alert::get_alert_rules,
@@ -59,13 +129,28 @@ pub fn run() {
             commands::settings::get_interfaces,
             commands::settings::get_settings,
             commands::settings::update_settings,
+            commands::settings::diagnose_and_repair,
+            commands::settings::list_backups,
+            commands::settings::restore_backup,
             commands::settings::start_monitor,
             commands::settings::stop_monitor,
             commands::settings::get_latency_history,
             commands::settings::ping_device,
             commands::export::export_devices,
+            commands::export::export_devices_csv,
+            commands::export::export_scan_nmap_xml,
+            commands::export::export_latency_csv,
             commands::export::import_devices,
+            commands::export::preview_import,
+            commands::csv_import::import_devices_csv,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<Arc<AppState>>() {
+                    tauri::async_runtime::block_on(shutdown::graceful_shutdown(&state));
+                }
+            }
+        });
 }
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::alerts::{engine as alert_engine, notifier};
+use crate::db::queries::devices::{self as db_devices, LatencyCoalescing};
+use crate::db::queries::settings as db_settings;
+use crate::network::interface::NetworkInterface;
+use crate::scanner::ping;
+use crate::state::AppState;
+
+/// Default seconds between gateway pings. `0` turns the monitor off.
+pub const DEFAULT_GATEWAY_MONITOR_INTERVAL_SECS: u64 = 5;
+
+/// Consecutive missed pings before the gateway counts as down, so one lost packet isn't an outage.
+pub const DOWN_AFTER_FAILURES: u32 = 2;
+
+/// How often a disabled monitor re-reads its setting.
+const DISABLED_RECHECK: Duration = Duration::from_secs(30);
+
+/// Coalescing for gateway samples when the user hasn't configured any. Pinging every few
+/// seconds would otherwise store tens of thousands of near-identical rows a day.
+const MONITOR_COALESCING: LatencyCoalescing = LatencyCoalescing {
+    change_threshold_pct: 25.0,
+    min_interval_secs: 60,
+};
+
+/// How often old latency history is pruned.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Emitted as `gateway:status` when the gateway goes down or comes back.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayStatus {
+    pub gateway_ip: String,
+    pub reachable: bool,
+    pub latency_ms: Option<f64>,
+}
+
+/// Up/down state of the gateway across ping results.
+#[derive(Debug, Default)]
+pub struct GatewayTracker {
+    gateway_ip: Option<String>,
+    reachable: Option<bool>,
+    failures: u32,
+}
+
+impl GatewayTracker {
+    /// Record one ping result. Returns the new state when it flips; the first "up" only
+    /// sets the baseline, while a gateway unreachable from the start is reported as down.
+    pub fn observe(&mut self, gateway_ip: &str, reachable: bool) -> Option<bool> {
+        if self.gateway_ip.as_deref() != Some(gateway_ip) {
+            *self = Self {
+                gateway_ip: Some(gateway_ip.to_string()),
+                ..Self::default()
+            };
+        }
+
+        if reachable {
+            self.failures = 0;
+            let was = self.reachable.replace(true);
+            return (was == Some(false)).then_some(true);
+        }
+
+        self.failures += 1;
+        if self.failures >= DOWN_AFTER_FAILURES && self.reachable != Some(false) {
+            self.reachable = Some(false);
+            return Some(false);
+        }
+        None
+    }
+}
+
+/// Gateway of the first active physical interface, falling back to any active one (e.g. a VPN).
+pub fn select_gateway(interfaces: &[NetworkInterface]) -> Option<String> {
+    let with_gateway = |vpn_ok: bool| {
+        interfaces
+            .iter()
+            .filter(|i| i.is_active && (vpn_ok || !i.is_vpn))
+            .find_map(|i| i.gateway_ip.clone())
+    };
+    with_gateway(false).or_else(|| with_gateway(true))
+}
+
+/// The user's latency coalescing, or `MONITOR_COALESCING` when they haven't set an interval.
+pub fn monitor_coalescing(settings: &db_settings::AppSettings) -> LatencyCoalescing {
+    let policy = settings.latency_coalescing();
+    if policy.min_interval_secs == 0 {
+        MONITOR_COALESCING
+    } else {
+        policy
+    }
+}
+
+/// Ping the default gateway every `gateway_monitor_interval_secs` until the app exits.
+/// Uses the interface list kept by the interface watcher, and prunes latency history
+/// older than `LATENCY_RETENTION_DAYS` along the way.
+pub async fn run(app: AppHandle) {
+    let mut tracker = GatewayTracker::default();
+    let mut last_prune: Option<Instant> = None;
+
+    loop {
+        let state = app.state::<Arc<AppState>>();
+        if last_prune.is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL) {
+            last_prune = Some(Instant::now());
+            prune_latency_history(&state).await;
+        }
+        let Some(settings) = state.conn().ok().and_then(|c| db_settings::get_settings(&c).ok()) else {
+            tokio::time::sleep(DISABLED_RECHECK).await;
+            continue;
+        };
+        if settings.gateway_monitor_interval_secs == 0 {
+            tokio::time::sleep(DISABLED_RECHECK).await;
+            continue;
+        }
+
+        let gateway = state
+            .interfaces
+            .lock()
+            .ok()
+            .and_then(|list| list.as_deref().and_then(select_gateway));
+        if let Some(gateway_ip) = gateway {
+            let tools = settings.scan_tools();
+            let latency = ping::ping_with(&gateway_ip, settings.ping_timeout_ms, &tools).await;
+            if let Some(ms) = latency {
+                record_latency(&state, &gateway_ip, ms, &settings);
+            }
+            if let Some(reachable) = tracker.observe(&gateway_ip, latency.is_some()) {
+                on_transition(&app, &state, &gateway_ip, reachable, latency);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(settings.gateway_monitor_interval_secs)).await;
+    }
+}
+
+/// Store the gateway's latency against its device, if it has been discovered.
+fn record_latency(state: &AppState, gateway_ip: &str, ms: f64, settings: &db_settings::AppSettings) {
+    let Ok(conn) = state.conn() else { return };
+    if let Ok(Some(device_id)) = db_devices::get_device_by_ip(&conn, gateway_ip) {
+        if let Err(e) = db_devices::record_latency(&conn, &device_id, ms, &monitor_coalescing(settings)) {
+            log::warn!("Failed to record gateway latency: {}", e);
+        }
+    }
+}
+
+async fn prune_latency_history(state: &AppState) {
+    let pool = state.db.clone();
+    let outcome = tokio::task::spawn_blocking(move || {
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        db_devices::prune_latency_history(&conn, db_devices::LATENCY_RETENTION_DAYS).map_err(|e| e.to_string())
+    })
+    .await;
+    match outcome {
+        Ok(Ok(0)) => {}
+        Ok(Ok(removed)) => log::info!("Pruned {} old latency samples", removed),
+        Ok(Err(e)) => log::warn!("Failed to prune latency history: {}", e),
+        Err(e) => log::warn!("Latency pruning task failed: {}", e),
+    }
+}
+
+fn on_transition(app: &AppHandle, state: &AppState, gateway_ip: &str, reachable: bool, latency_ms: Option<f64>) {
+    log::info!("Gateway {} is {}", gateway_ip, if reachable { "up" } else { "down" });
+    let _ = app.emit(
+        "gateway:status",
+        &GatewayStatus {
+            gateway_ip: gateway_ip.to_string(),
+            reachable,
+            latency_ms,
+        },
+    );
+
+    if reachable {
+        return;
+    }
+    let Ok(conn) = state.conn() else { return };
+    let device_id = db_devices::get_device_by_ip(&conn, gateway_ip).ok().flatten();
+    match alert_engine::gateway_down_alert(&conn, gateway_ip, device_id.as_deref()) {
+        Ok(Some(alert)) => {
+            let _ = app.emit("alert:new", &alert_engine::AlertEvent::from(&alert));
+            notifier::notify(app, std::slice::from_ref(&alert));
+        }
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to record gateway alert: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iface(id: &str, gateway: Option<&str>, vpn: bool) -> NetworkInterface {
+        NetworkInterface {
+            id: id.to_string(),
+            name: id.to_string(),
+            ip_address: Some("192.168.1.10".to_string()),
+            subnet_mask: None,
+            mac_address: None,
+            gateway_ip: gateway.map(|g| g.to_string()),
+            ipv6_address: None,
+            gateway_ipv6: None,
+            is_active: true,
+            is_vpn: vpn,
+        }
+    }
+
+    #[test]
+    fn test_tracker_needs_consecutive_failures() {
+        let mut t = GatewayTracker::default();
+        assert_eq!(t.observe("192.168.1.1", true), None);
+        assert_eq!(t.observe("192.168.1.1", false), None);
+        assert_eq!(t.observe("192.168.1.1", true), None);
+
+        assert_eq!(t.observe("192.168.1.1", false), None);
+        assert_eq!(t.observe("192.168.1.1", false), Some(false));
+        assert_eq!(t.observe("192.168.1.1", false), None);
+        assert_eq!(t.observe("192.168.1.1", true), Some(true));
+    }
+
+    #[test]
+    fn test_tracker_resets_on_new_gateway() {
+        let mut t = GatewayTracker::default();
+        t.observe("192.168.1.1", false);
+        assert_eq!(t.observe("192.168.1.1", false), Some(false));
+
+        // A different network starts from scratch rather than reporting "back up"
+        assert_eq!(t.observe("10.0.0.1", true), None);
+        t.observe("10.0.0.1", false);
+        assert_eq!(t.observe("10.0.0.1", false), Some(false));
+    }
+
+    #[test]
+    fn test_monitor_coalesces_by_default() {
+        let pool = crate::db::init_test_db();
+        let conn = pool.get().unwrap();
+        let mut settings = db_settings::get_settings(&conn).unwrap();
+        assert_eq!(monitor_coalescing(&settings).min_interval_secs, MONITOR_COALESCING.min_interval_secs);
+
+        settings.latency_min_interval_secs = 600;
+        settings.latency_change_threshold_pct = 10.0;
+        let policy = monitor_coalescing(&settings);
+        assert_eq!((policy.min_interval_secs, policy.change_threshold_pct), (600, 10.0));
+    }
+
+    #[test]
+    fn test_select_gateway_prefers_physical() {
+        let list = vec![iface("utun3", Some("10.8.0.1"), true), iface("en0", Some("192.168.1.1"), false)];
+        assert_eq!(select_gateway(&list).as_deref(), Some("192.168.1.1"));
+
+        let vpn_only = vec![iface("utun3", Some("10.8.0.1"), true), iface("en0", None, false)];
+        assert_eq!(select_gateway(&vpn_only).as_deref(), Some("10.8.0.1"));
+        assert!(select_gateway(&[]).is_none());
+    }
+}
@@ -1,7 +1,8 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::collections::HashMap;
 use crate::commands::validate::Validator;
+use crate::util::command::{CommandRunner, SystemRunner};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,28 +13,48 @@ pub struct NetworkInterface {
     pub subnet_mask: Option<String>,
     pub mac_address: Option<String>,
     pub gateway_ip: Option<String>,
+    pub ipv6_address: Option<String>,
+    pub gateway_ipv6: Option<String>,
     pub is_active: bool,
+    /// Tunnel interface (utun, tun, wg, ppp...) typically created by a VPN client.
+    #[serde(default)]
+    pub is_vpn: bool,
+}
+
+/// Whether an interface name looks like a VPN tunnel.
+pub fn is_vpn_name(name: &str) -> bool {
+    const TUNNEL_PREFIXES: [&str; 7] = ["utun", "tun", "tap", "wg", "ppp", "ipsec", "gpd"];
+    let lower = name.to_ascii_lowercase();
+    TUNNEL_PREFIXES.iter().any(|p| lower.starts_with(p))
+        || lower.contains("vpn")
+        || lower.contains("wireguard")
 }
 
 /// Discover all network interfaces on this machine.
 pub fn get_interfaces() -> Vec<NetworkInterface> {
+    get_interfaces_with(&SystemRunner)
+}
+
+/// Discover interfaces, running the platform tools through `runner`.
+pub fn get_interfaces_with(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
     #[cfg(target_os = "macos")]
     {
-        discover_interfaces_macos()
+        discover_interfaces_macos(runner)
     }
 
     #[cfg(target_os = "linux")]
     {
-        discover_interfaces_linux()
+        discover_interfaces_linux(runner)
     }
 
     #[cfg(target_os = "windows")]
     {
-        discover_interfaces_windows()
+        discover_interfaces_windows(runner)
     }
 
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
     {
+        let _ = runner;
         log::warn!("Interface discovery not supported on this platform");
         Vec::new()
     }
@@ -41,73 +62,52 @@ pub fn get_interfaces() -> Vec<NetworkInterface> {
 
 /// macOS: Discover interfaces via ifconfig
 #[cfg(target_os = "macos")]
-fn discover_interfaces_macos() -> Vec<NetworkInterface> {
-    let mut interfaces = parse_ifconfig_macos();
-    let gateway = get_default_gateway_macos();
-
-    // Set gateway for the interface that has a route to it
-    if let Some(ref gw) = gateway {
-        for iface in &mut interfaces {
-            if let Some(ref ip) = iface.ip_address {
-                if same_subnet(ip, gw) {
-                    iface.gateway_ip = Some(gw.clone());
-                }
-            }
-        }
-    }
+fn discover_interfaces_macos(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let mut interfaces = parse_ifconfig_macos(runner);
+    let routes = runner
+        .stdout("netstat", &["-rn", "-f", "inet"])
+        .map(|text| parse_default_routes_macos(&text))
+        .unwrap_or_default();
+    apply_default_routes(&mut interfaces, &routes);
 
     interfaces
 }
 
 /// Linux: Discover interfaces via ip command
 #[cfg(target_os = "linux")]
-fn discover_interfaces_linux() -> Vec<NetworkInterface> {
-    let mut interfaces = parse_ip_addr_linux();
-    let gateway = get_default_gateway_linux();
-
-    // Set gateway for the interface that has a route to it
-    if let Some(ref gw) = gateway {
-        for iface in &mut interfaces {
-            if let Some(ref ip) = iface.ip_address {
-                if same_subnet(ip, gw) {
-                    iface.gateway_ip = Some(gw.clone());
-                }
-            }
-        }
-    }
+fn discover_interfaces_linux(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let mut interfaces = parse_ip_addr_linux(runner);
+    let routes = runner
+        .stdout("ip", &["route", "show"])
+        .map(|text| parse_default_routes_linux(&text))
+        .unwrap_or_default();
+    apply_default_routes(&mut interfaces, &routes);
 
     interfaces
 }
 
 /// Windows: Discover interfaces via PowerShell
+///
+/// Uses structured cmdlet output rather than `ipconfig`, whose labels are
+/// translated on non-English installs. Gateways come per-adapter from
+/// Get-NetIPConfiguration, so no subnet matching is needed.
 #[cfg(target_os = "windows")]
-fn discover_interfaces_windows() -> Vec<NetworkInterface> {
-    let mut interfaces = parse_ipconfig_windows();
-    let gateway = get_default_gateway_windows();
-
-    // Set gateway for the interface that has a route to it
-    if let Some(ref gw) = gateway {
-        for iface in &mut interfaces {
-            if let Some(ref ip) = iface.ip_address {
-                if same_subnet(ip, gw) {
-                    iface.gateway_ip = Some(gw.clone());
-                }
-            }
-        }
-    }
+fn discover_interfaces_windows(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let Some(output) = runner.stdout(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", WINDOWS_ADAPTER_SCRIPT],
+    ) else {
+        return Vec::new();
+    };
 
-    interfaces
+    parse_netadapter_csv(&output)
 }
 
 /// Parse ifconfig output to enumerate interfaces (macOS).
 #[cfg(target_os = "macos")]
-fn parse_ifconfig_macos() -> Vec<NetworkInterface> {
-    let output = match Command::new("ifconfig").output() {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
-        Err(e) => {
-            log::error!("Failed to run ifconfig: {}", e);
-            return Vec::new();
-        }
+fn parse_ifconfig_macos(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let Some(output) = runner.stdout("ifconfig", &[]) else {
+        return Vec::new();
     };
 
     let iface_re = Regex::new(r"^(\w+):").unwrap();
@@ -129,8 +129,9 @@ fn parse_ifconfig_macos() -> Vec<NetworkInterface> {
                  active: bool,
                  interfaces: &mut Vec<NetworkInterface>| {
         if let Some(ref n) = name {
-            // Skip loopback and virtual interfaces
-            if n == "lo0" || n.starts_with("utun") || n.starts_with("bridge")
+            // Skip loopback and virtual interfaces. VPN tunnels (utun) are kept
+            // so their subnets can be scanned when explicitly chosen.
+            if n == "lo0" || n.starts_with("bridge")
                 || n.starts_with("awdl") || n.starts_with("llw")
                 || n.starts_with("anpi") || n.starts_with("ap")
             {
@@ -145,7 +146,10 @@ fn parse_ifconfig_macos() -> Vec<NetworkInterface> {
                 subnet_mask: mask.clone(),
                 mac_address: mac.clone(),
                 gateway_ip: None,
+                ipv6_address: None,
+                gateway_ipv6: None,
                 is_active: active && has_ip,
+                is_vpn: is_vpn_name(n),
             });
         }
     };
@@ -193,87 +197,86 @@ fn parse_ifconfig_macos() -> Vec<NetworkInterface> {
     interfaces
 }
 
-/// Get the default gateway IP from the routing table (macOS).
-#[cfg(target_os = "macos")]
-fn get_default_gateway_macos() -> Option<String> {
-    let output = Command::new("netstat")
-        .args(["-rn"])
-        .output()
-        .ok()?;
-
-    let text = String::from_utf8_lossy(&output.stdout);
-    let gw_re = Regex::new(r"default\s+(\d+\.\d+\.\d+\.\d+)").unwrap();
-
-    gw_re.captures(&text).and_then(|caps| {
-        let ip = &caps[1];
-        if Validator::validate_ipv4(ip).is_ok() {
-            Some(ip.to_string())
-        } else {
-            None
-        }
-    })
+/// A `default` route and the interface it leaves through.
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+#[derive(Debug, Clone, PartialEq)]
+struct DefaultRoute {
+    gateway: String,
+    interface: Option<String>,
 }
 
-/// Get the default gateway IP from the routing table (Linux).
-#[cfg(target_os = "linux")]
-fn get_default_gateway_linux() -> Option<String> {
-    let output = Command::new("ip")
-        .args(&["route", "show"])
-        .output()
-        .ok()?;
-
-    let text = String::from_utf8_lossy(&output.stdout);
-
-    // Look for: default via 192.168.1.1 dev eth0
-    for line in text.lines() {
-        if line.starts_with("default via ") {
+/// Parse every IPv4 default route from `netstat -rn` (macOS).
+///
+/// With a VPN up there are several: the tunnel's plus a scoped one per physical
+/// interface, e.g. `default 192.168.1.1 UGScIg en0`. Routes via `link#N` are skipped.
+#[cfg(any(target_os = "macos", test))]
+fn parse_default_routes_macos(text: &str) -> Vec<DefaultRoute> {
+    text.lines()
+        .filter_map(|line| {
             let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() > 2 {
-                let ip = parts[2];
-                if Validator::validate_ipv4(ip).is_ok() {
-                    return Some(ip.to_string());
-                }
+            if parts.first() != Some(&"default") || parts.len() < 4 {
+                return None;
             }
-        }
-    }
+            let gateway = parts[1];
+            Validator::validate_ipv4(gateway).ok()?;
+            // Older netstat prints Refs/Use counters between Flags and Netif
+            let interface = parts[3..]
+                .iter()
+                .find(|p| p.parse::<u64>().is_err())
+                .map(|p| p.to_string());
+            Some(DefaultRoute {
+                gateway: gateway.to_string(),
+                interface,
+            })
+        })
+        .collect()
+}
 
-    None
+/// Parse every default route from `ip route show` (Linux),
+/// e.g. `default via 192.168.1.1 dev eth0 proto dhcp metric 600`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_default_routes_linux(text: &str) -> Vec<DefaultRoute> {
+    text.lines()
+        .filter(|line| line.starts_with("default via "))
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let gateway = *parts.get(2)?;
+            Validator::validate_ipv4(gateway).ok()?;
+            let interface = parts
+                .iter()
+                .position(|p| *p == "dev")
+                .and_then(|i| parts.get(i + 1))
+                .map(|d| d.to_string());
+            Some(DefaultRoute {
+                gateway: gateway.to_string(),
+                interface,
+            })
+        })
+        .collect()
 }
 
-/// Get the default gateway IP from the routing table (Windows).
-#[cfg(target_os = "windows")]
-fn get_default_gateway_windows() -> Option<String> {
-    let output = Command::new("powershell")
-        .args(&[
-            "-NoProfile",
-            "-Command",
-            "Get-NetRoute -DestinationPrefix '0.0.0.0/0' | Select-Object -ExpandProperty NextHop | Select-Object -First 1",
-        ])
-        .output()
-        .ok()?;
-
-    let text = String::from_utf8_lossy(&output.stdout);
-    let ip = text.trim();
-
-    if Validator::validate_ipv4(ip).is_ok() {
-        Some(ip.to_string())
-    } else {
-        None
+/// Give each interface its own gateway, independent of which one holds the default route.
+/// The primary route is matched by subnet as a fallback; a route naming an interface wins.
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+fn apply_default_routes(interfaces: &mut [NetworkInterface], routes: &[DefaultRoute]) {
+    if let Some(primary) = routes.first() {
+        assign_gateway(interfaces, &primary.gateway);
+    }
+    for route in routes {
+        let Some(ref dev) = route.interface else {
+            continue;
+        };
+        if let Some(iface) = interfaces.iter_mut().find(|i| i.name == *dev) {
+            iface.gateway_ip = Some(route.gateway.clone());
+        }
     }
 }
 
 /// Parse ip addr show output to enumerate interfaces (Linux).
 #[cfg(target_os = "linux")]
-fn parse_ip_addr_linux() -> Vec<NetworkInterface> {
-    let output = match Command::new("ip")
-        .args(&["addr", "show"])
-        .output()
-    {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
-        Err(e) => {
-            log::error!("Failed to run ip addr show: {}", e);
-            return Vec::new();
-        }
+fn parse_ip_addr_linux(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let Some(output) = runner.stdout("ip", &["addr", "show"]) else {
+        return Vec::new();
     };
 
     let mut interfaces = Vec::new();
@@ -302,11 +305,14 @@ fn parse_ip_addr_linux() -> Vec<NetworkInterface> {
 
                     current_interface = Some(NetworkInterface {
                         id: name.clone(),
+                        is_vpn: is_vpn_name(&name),
                         name,
                         ip_address: None,
                         subnet_mask: None,
                         mac_address: None,
                         gateway_ip: None,
+                        ipv6_address: None,
+                        gateway_ipv6: None,
                         is_active,
                     });
                 }
@@ -357,89 +363,199 @@ fn parse_ip_addr_linux() -> Vec<NetworkInterface> {
     interfaces
 }
 
-/// Parse ipconfig output to enumerate interfaces (Windows).
+/// One CSV row per adapter. Property names are locale-independent.
 #[cfg(target_os = "windows")]
-fn parse_ipconfig_windows() -> Vec<NetworkInterface> {
-    let output = match Command::new("ipconfig").output() {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
+const WINDOWS_ADAPTER_SCRIPT: &str = r#"Get-NetAdapter | ForEach-Object {
+    $idx = $_.ifIndex
+    $cfg = Get-NetIPConfiguration -InterfaceIndex $idx -ErrorAction SilentlyContinue
+    $v4 = Get-NetIPAddress -InterfaceIndex $idx -AddressFamily IPv4 -ErrorAction SilentlyContinue | Select-Object -First 1
+    $v6 = Get-NetIPAddress -InterfaceIndex $idx -AddressFamily IPv6 -ErrorAction SilentlyContinue | Sort-Object { $_.IPAddress -like 'fe80*' } | Select-Object -First 1
+    [PSCustomObject]@{
+        Name = $_.Name
+        Status = $_.Status
+        MacAddress = $_.MacAddress
+        IPv4Address = $v4.IPAddress
+        PrefixLength = $v4.PrefixLength
+        IPv6Address = $v6.IPAddress
+        IPv4DefaultGateway = ($cfg.IPv4DefaultGateway | Select-Object -First 1).NextHop
+        IPv6DefaultGateway = ($cfg.IPv6DefaultGateway | Select-Object -First 1).NextHop
+    }
+} | ConvertTo-Csv -NoTypeInformation"#;
+
+/// Parse the adapter CSV produced by `WINDOWS_ADAPTER_SCRIPT` (Windows).
+///
+/// Columns are looked up by header name, so column order doesn't matter.
+/// Adapters with neither an IPv4 nor an IPv6 address are skipped.
+#[cfg(any(target_os = "windows", test))]
+fn parse_netadapter_csv(output: &str) -> Vec<NetworkInterface> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(output.as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(h) => h.clone(),
         Err(e) => {
-            log::error!("Failed to run ipconfig: {}", e);
+            log::error!("Failed to read adapter CSV header: {}", e);
             return Vec::new();
         }
     };
+    let column = |name: &str| headers.iter().position(|h| h == name);
 
-    let mut interfaces = Vec::new();
-    let mut current_interface: Option<NetworkInterface> = None;
+    let (Some(name_col), Some(status_col)) = (column("Name"), column("Status")) else {
+        log::error!("Adapter CSV is missing Name/Status columns");
+        return Vec::new();
+    };
+    let mac_col = column("MacAddress");
+    let ipv4_col = column("IPv4Address");
+    let prefix_col = column("PrefixLength");
+    let ipv6_col = column("IPv6Address");
+    let gw4_col = column("IPv4DefaultGateway");
+    let gw6_col = column("IPv6DefaultGateway");
 
-    for line in output.lines() {
-        let trimmed = line.trim();
+    let mut interfaces = Vec::new();
 
-        // New interface section starts with letters (not whitespace)
-        if !line.starts_with(' ') && !line.starts_with('\t') && trimmed.contains(':') {
-            // Flush previous interface
-            if let Some(iface) = current_interface.take() {
-                if iface.ip_address.is_some() {
-                    interfaces.push(iface);
-                }
+    for record in reader.records() {
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                log::debug!("Skipping malformed adapter row: {}", e);
+                continue;
             }
+        };
+        let field = |col: Option<usize>| {
+            col.and_then(|c| record.get(c)).filter(|v| !v.is_empty())
+        };
+
+        let Some(name) = field(Some(name_col)) else {
+            continue;
+        };
+
+        let ip_address = field(ipv4_col)
+            .filter(|ip| Validator::validate_ipv4(ip).is_ok())
+            .map(str::to_string);
+
+        let subnet_mask = ip_address.as_ref().and_then(|_| {
+            field(prefix_col)
+                .and_then(|p| p.parse::<u32>().ok())
+                .filter(|p| *p <= 32)
+                .map(cidr_to_netmask)
+        });
+
+        let ipv6_address = field(ipv6_col).and_then(strip_ipv6_zone);
+
+        if ip_address.is_none() && ipv6_address.is_none() {
+            continue;
+        }
 
-            // Parse interface name (e.g., "Ethernet adapter Ethernet:")
-            if let Some(colon_idx) = trimmed.find(':') {
-                let name_part = trimmed[..colon_idx].to_string();
-                current_interface = Some(NetworkInterface {
-                    id: name_part.clone(),
-                    name: name_part,
-                    ip_address: None,
-                    subnet_mask: None,
-                    mac_address: None,
-                    gateway_ip: None,
-                    is_active: false,
-                });
-            }
-        } else if let Some(ref mut iface) = current_interface {
-            // Parse IPv4 Address
-            if trimmed.starts_with("IPv4 Address") {
-                if let Some(colon_idx) = trimmed.find(':') {
-                    let ip_part = trimmed[colon_idx + 1..].trim();
-                    if Validator::validate_ipv4(ip_part).is_ok() {
-                        iface.ip_address = Some(ip_part.to_string());
-                        iface.is_active = true;
-                    }
-                }
-            }
+        // Windows uses hyphens in MAC; convert to colons
+        let mac_address = field(mac_col)
+            .map(|mac| mac.replace('-', ":"))
+            .filter(|mac| Validator::validate_mac_address(mac).is_ok());
+
+        let gateway_ip = field(gw4_col)
+            .filter(|ip| Validator::validate_ipv4(ip).is_ok())
+            .map(str::to_string);
+
+        let gateway_ipv6 = field(gw6_col).and_then(strip_ipv6_zone);
+
+        interfaces.push(NetworkInterface {
+            id: name.to_string(),
+            name: name.to_string(),
+            ip_address,
+            subnet_mask,
+            mac_address,
+            gateway_ip,
+            ipv6_address,
+            gateway_ipv6,
+            is_active: field(Some(status_col)) == Some("Up"),
+            is_vpn: is_vpn_name(name),
+        });
+    }
 
-            // Parse Subnet Mask
-            if trimmed.starts_with("Subnet Mask") {
-                if let Some(colon_idx) = trimmed.find(':') {
-                    let mask_part = trimmed[colon_idx + 1..].trim();
-                    if Validator::validate_ipv4(mask_part).is_ok() {
-                        iface.subnet_mask = Some(mask_part.to_string());
-                    }
-                }
-            }
+    interfaces
+}
 
-            // Parse Physical Address (MAC)
-            if trimmed.starts_with("Physical Address") {
-                if let Some(colon_idx) = trimmed.find(':') {
-                    let mac_part = trimmed[colon_idx + 1..].trim();
-                    // Windows uses hyphens in MAC; convert to colons
-                    let mac = mac_part.replace("-", ":");
-                    if Validator::validate_mac_address(&mac).is_ok() {
-                        iface.mac_address = Some(mac);
-                    }
-                }
-            }
-        }
+/// Drop a `%zone` suffix (e.g. `fe80::1%12`) and validate what's left.
+#[cfg(any(target_os = "windows", test))]
+fn strip_ipv6_zone(ip: &str) -> Option<String> {
+    let addr = ip.split('%').next().unwrap_or(ip);
+    Validator::validate_ipv6(addr).ok().map(|_| addr.to_string())
+}
+
+/// Map local interface names to their medium: "wired", "wireless" or "virtual".
+/// Uses `networksetup` on macOS, where names like `en0` don't reveal the medium;
+/// other platforms fall back to `interface_medium`'s name heuristics.
+pub fn interface_media(runner: &dyn CommandRunner) -> HashMap<String, &'static str> {
+    #[cfg(target_os = "macos")]
+    {
+        runner
+            .stdout("networksetup", &["-listallhardwareports"])
+            .map(|text| parse_hardware_ports(&text))
+            .unwrap_or_default()
     }
 
-    // Flush last interface
-    if let Some(iface) = current_interface {
-        if iface.ip_address.is_some() {
-            interfaces.push(iface);
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = runner;
+        HashMap::new()
+    }
+}
+
+/// Medium of an interface, from the platform mapping or its name.
+pub fn interface_medium(media: &HashMap<String, &'static str>, name: &str) -> Option<&'static str> {
+    if let Some(medium) = media.get(name) {
+        return Some(medium);
+    }
+
+    let lower = name.to_lowercase();
+    const VIRTUAL: &[&str] = &["docker", "veth", "br-", "virbr", "vmnet", "vboxnet", "vethernet", "utun", "tap", "tun"];
+    const WIRELESS: &[&str] = &["wl", "wi-fi", "wifi", "wireless"];
+    const WIRED: &[&str] = &["eth", "en", "em"];
+
+    if VIRTUAL.iter().any(|p| lower.starts_with(p)) {
+        Some("virtual")
+    } else if WIRELESS.iter().any(|p| lower.starts_with(p)) {
+        Some("wireless")
+    } else if WIRED.iter().any(|p| lower.starts_with(p)) && !cfg!(target_os = "macos") {
+        // macOS uses en* for Wi-Fi too, so only trust the prefix elsewhere
+        Some("wired")
+    } else {
+        None
+    }
+}
+
+/// Parse `networksetup -listallhardwareports` into a device → medium map (macOS).
+///
+///   Hardware Port: Wi-Fi
+///   Device: en0
+///   Ethernet Address: aa:bb:cc:dd:ee:ff
+#[cfg(any(target_os = "macos", test))]
+fn parse_hardware_ports(output: &str) -> HashMap<String, &'static str> {
+    let mut media = HashMap::new();
+    let mut port: Option<String> = None;
+
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("Hardware Port:") {
+            port = Some(name.trim().to_lowercase());
+        } else if let Some(device) = line.strip_prefix("Device:") {
+            let Some(ref port_name) = port else {
+                continue;
+            };
+            let medium = if port_name.contains("wi-fi") || port_name.contains("airport") {
+                "wireless"
+            } else if port_name.contains("bridge") || port_name.contains("vlan") {
+                "virtual"
+            } else if port_name.contains("ethernet") || port_name.contains("lan") {
+                "wired"
+            } else {
+                continue;
+            };
+            media.insert(device.trim().to_string(), medium);
         }
     }
 
-    interfaces
+    media
 }
 
 /// Convert CIDR prefix length to dotted netmask notation.
@@ -476,7 +592,28 @@ fn convert_netmask(mask: &str) -> String {
     mask.to_string()
 }
 
+/// Attach the gateway to every interface whose network contains it.
+/// Uses the interface's netmask; falls back to a /24 comparison when the mask is unknown.
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+fn assign_gateway(interfaces: &mut [NetworkInterface], gateway: &str) {
+    for iface in interfaces {
+        let Some(ref ip) = iface.ip_address else {
+            continue;
+        };
+        let on_link = match iface.subnet_mask.as_deref() {
+            Some(mask) if crate::network::ipmath::parse_netmask(mask).is_some() => {
+                crate::network::ipmath::in_same_subnet(ip, gateway, mask)
+            }
+            _ => same_subnet(ip, gateway),
+        };
+        if on_link {
+            iface.gateway_ip = Some(gateway.to_string());
+        }
+    }
+}
+
 /// Check if two IPs are on the same /24 subnet (simple heuristic).
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
 fn same_subnet(ip1: &str, ip2: &str) -> bool {
     let parts1: Vec<&str> = ip1.split('.').collect();
     let parts2: Vec<&str> = ip2.split('.').collect();
@@ -489,6 +626,7 @@ fn same_subnet(ip1: &str, ip2: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::command::MockRunner;
 
     #[test]
     fn test_cidr_to_netmask() {
@@ -505,6 +643,65 @@ mod tests {
         assert!(!same_subnet("10.0.0.1", "192.168.1.1"));
     }
 
+    fn iface(name: &str, ip: &str, mask: Option<&str>) -> NetworkInterface {
+        NetworkInterface {
+            id: name.to_string(),
+            name: name.to_string(),
+            ip_address: Some(ip.to_string()),
+            subnet_mask: mask.map(str::to_string),
+            mac_address: None,
+            gateway_ip: None,
+            ipv6_address: None,
+            gateway_ipv6: None,
+            is_active: true,
+            is_vpn: false,
+        }
+    }
+
+    #[test]
+    fn test_assign_gateway_slash_16() {
+        let mut interfaces = vec![
+            iface("eth0", "172.16.40.7", Some("255.255.0.0")),
+            iface("eth1", "192.168.1.5", Some("255.255.255.0")),
+        ];
+        assign_gateway(&mut interfaces, "172.16.0.1");
+
+        assert_eq!(interfaces[0].gateway_ip.as_deref(), Some("172.16.0.1"));
+        assert!(interfaces[1].gateway_ip.is_none());
+    }
+
+    #[test]
+    fn test_assign_gateway_slash_23() {
+        let mut interfaces = vec![
+            iface("en0", "10.20.31.200", Some("255.255.254.0")),
+            iface("en1", "10.20.32.10", Some("255.255.254.0")),
+        ];
+        assign_gateway(&mut interfaces, "10.20.30.1");
+
+        assert_eq!(interfaces[0].gateway_ip.as_deref(), Some("10.20.30.1"));
+        assert!(interfaces[1].gateway_ip.is_none());
+    }
+
+    #[test]
+    fn test_assign_gateway_slash_25() {
+        // Both sit in 192.168.1.0/24, but only en0 shares the gateway's /25
+        let mut interfaces = vec![
+            iface("en0", "192.168.1.200", Some("255.255.255.128")),
+            iface("en1", "192.168.1.20", Some("255.255.255.128")),
+        ];
+        assign_gateway(&mut interfaces, "192.168.1.129");
+
+        assert_eq!(interfaces[0].gateway_ip.as_deref(), Some("192.168.1.129"));
+        assert!(interfaces[1].gateway_ip.is_none());
+    }
+
+    #[test]
+    fn test_assign_gateway_without_mask_uses_slash_24() {
+        let mut interfaces = vec![iface("wlan0", "192.168.1.42", None)];
+        assign_gateway(&mut interfaces, "192.168.1.1");
+        assert_eq!(interfaces[0].gateway_ip.as_deref(), Some("192.168.1.1"));
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_parse_ip_addr_linux() {
@@ -514,11 +711,93 @@ mod tests {
 2: eth0: <BROADCAST,MULTICAST,UP,LOWER_UP> mtu 1500 qdisc fq_codel state UP group default qlen 1000
     link/ether aa:bb:cc:dd:ee:ff brd ff:ff:ff:ff:ff:ff
     inet 192.168.1.100/24 brd 192.168.1.255 scope global eth0"#;
+        let runner = MockRunner::new()
+            .on("ip addr show", sample)
+            .on("ip route show", "default via 192.168.1.1 dev eth0 proto dhcp metric 100");
+
+        let interfaces = get_interfaces_with(&runner);
+        let eth0 = interfaces.iter().find(|i| i.name == "eth0").unwrap();
+        assert_eq!(eth0.ip_address.as_deref(), Some("192.168.1.100"));
+        assert_eq!(eth0.mac_address.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(eth0.gateway_ip.as_deref(), Some("192.168.1.1"));
+        assert!(eth0.is_active);
+
+        // No tools at all: nothing found, nothing panics
+        assert!(get_interfaces_with(&MockRunner::new()).is_empty());
+    }
+
+    const SAMPLE_NETADAPTER_CSV: &str = concat!(
+        "\"Name\",\"Status\",\"MacAddress\",\"IPv4Address\",\"PrefixLength\",\"IPv6Address\",\"IPv4DefaultGateway\",\"IPv6DefaultGateway\"\n",
+        "\"Ethernet\",\"Up\",\"AA-BB-CC-DD-EE-FF\",\"192.168.1.100\",\"24\",\"2001:db8::100\",\"192.168.1.1\",\"fe80::1%12\"\n",
+        "\"Wi-Fi\",\"Disconnected\",\"11-22-33-44-55-66\",\"169.254.10.20\",\"16\",,,\n",
+        "\"Bluetooth Network Connection\",\"Disconnected\",\"77-88-99-AA-BB-CC\",,,,,\n",
+    );
 
-        // Manual test since parse_ip_addr_linux is not public
-        // This would be called via get_interfaces() in real usage
-        assert!(sample.contains("192.168.1.100"));
-        assert!(sample.contains("aa:bb:cc:dd:ee:ff"));
+    #[test]
+    fn test_parse_netadapter_csv() {
+        let interfaces = parse_netadapter_csv(SAMPLE_NETADAPTER_CSV);
+
+        // Bluetooth adapter has no addresses and is skipped
+        assert_eq!(interfaces.len(), 2);
+
+        let eth = &interfaces[0];
+        assert_eq!(eth.name, "Ethernet");
+        assert_eq!(eth.ip_address.as_deref(), Some("192.168.1.100"));
+        assert_eq!(eth.subnet_mask.as_deref(), Some("255.255.255.0"));
+        assert_eq!(eth.mac_address.as_deref(), Some("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(eth.ipv6_address.as_deref(), Some("2001:db8::100"));
+        assert_eq!(eth.gateway_ip.as_deref(), Some("192.168.1.1"));
+        assert_eq!(eth.gateway_ipv6.as_deref(), Some("fe80::1"));
+        assert!(eth.is_active);
+
+        let wifi = &interfaces[1];
+        assert_eq!(wifi.subnet_mask.as_deref(), Some("255.255.0.0"));
+        assert!(wifi.gateway_ip.is_none());
+        assert!(wifi.ipv6_address.is_none());
+        assert!(!wifi.is_active);
+    }
+
+    #[test]
+    fn test_parse_netadapter_csv_reordered_columns() {
+        let output = "\"IPv4Address\",\"Name\",\"PrefixLength\",\"Status\"\n\"10.0.0.5\",\"Ethernet 2\",\"8\",\"Up\"\n";
+        let interfaces = parse_netadapter_csv(output);
+
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!(interfaces[0].name, "Ethernet 2");
+        assert_eq!(interfaces[0].subnet_mask.as_deref(), Some("255.0.0.0"));
+        assert!(interfaces[0].mac_address.is_none());
+    }
+
+    #[test]
+    fn test_parse_netadapter_csv_rejects_bad_input() {
+        assert!(parse_netadapter_csv("").is_empty());
+        // Required columns missing
+        assert!(parse_netadapter_csv("\"Foo\",\"Bar\"\n\"a\",\"b\"\n").is_empty());
+        // Invalid address leaves nothing to report
+        let output = "\"Name\",\"Status\",\"IPv4Address\"\n\"Eth\",\"Up\",\"999.1.1.1\"\n";
+        assert!(parse_netadapter_csv(output).is_empty());
+    }
+
+    #[test]
+    fn test_parse_hardware_ports() {
+        let output = "Hardware Port: Wi-Fi\nDevice: en0\nEthernet Address: aa:bb:cc:dd:ee:ff\n\n\
+Hardware Port: Thunderbolt Ethernet Slot 1\nDevice: en5\nEthernet Address: 11:22:33:44:55:66\n\n\
+Hardware Port: Thunderbolt Bridge\nDevice: bridge0\nEthernet Address: N/A\n";
+        let media = parse_hardware_ports(output);
+        assert_eq!(media.get("en0"), Some(&"wireless"));
+        assert_eq!(media.get("en5"), Some(&"wired"));
+        assert_eq!(media.get("bridge0"), Some(&"virtual"));
+    }
+
+    #[test]
+    fn test_interface_medium_prefers_platform_map() {
+        let mut media = HashMap::new();
+        media.insert("en0".to_string(), "wireless");
+        assert_eq!(interface_medium(&media, "en0"), Some("wireless"));
+        assert_eq!(interface_medium(&media, "wlan0"), Some("wireless"));
+        assert_eq!(interface_medium(&media, "Wi-Fi"), Some("wireless"));
+        assert_eq!(interface_medium(&media, "docker0"), Some("virtual"));
+        assert_eq!(interface_medium(&media, "lo"), None);
     }
 
     #[test]
@@ -527,4 +806,60 @@ mod tests {
         assert!(!same_subnet("10.0.0.1", "10.0.1.1"));
         assert!(same_subnet("172.16.5.1", "172.16.5.100"));
     }
+
+    #[test]
+    fn test_parse_default_routes_macos_with_vpn() {
+        let sample = "Routing tables\n\nInternet:\n\
+Destination        Gateway            Flags               Netif Expire\n\
+default            link#22            UCSg                utun4\n\
+default            192.168.1.1        UGScIg                en0\n\
+10.8/16            link#22            UCS                 utun4\n";
+        let routes = parse_default_routes_macos(sample);
+        assert_eq!(routes, vec![DefaultRoute {
+            gateway: "192.168.1.1".to_string(),
+            interface: Some("en0".to_string()),
+        }]);
+
+        // Older netstat with Refs/Use columns
+        let legacy = "default            192.168.1.1        UGSc           34        0     en0\n";
+        assert_eq!(parse_default_routes_macos(legacy)[0].interface.as_deref(), Some("en0"));
+    }
+
+    #[test]
+    fn test_parse_default_routes_linux() {
+        let sample = "default via 10.8.0.1 dev tun0 metric 50\n\
+default via 192.168.1.1 dev eth0 proto dhcp metric 600\n\
+192.168.1.0/24 dev eth0 proto kernel scope link src 192.168.1.100\n";
+        let routes = parse_default_routes_linux(sample);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[1].gateway, "192.168.1.1");
+        assert_eq!(routes[1].interface.as_deref(), Some("eth0"));
+    }
+
+    #[test]
+    fn test_apply_default_routes_per_interface() {
+        let mut vpn = iface("tun0", "10.8.0.6", Some("255.255.255.0"));
+        vpn.is_vpn = true;
+        let mut interfaces = vec![vpn, iface("eth0", "192.168.1.100", Some("255.255.255.0"))];
+        let routes = vec![
+            DefaultRoute { gateway: "10.8.0.1".to_string(), interface: Some("tun0".to_string()) },
+            DefaultRoute { gateway: "192.168.1.1".to_string(), interface: Some("eth0".to_string()) },
+        ];
+        apply_default_routes(&mut interfaces, &routes);
+
+        // The physical interface keeps its own gateway while the VPN holds the default route
+        assert_eq!(interfaces[0].gateway_ip.as_deref(), Some("10.8.0.1"));
+        assert_eq!(interfaces[1].gateway_ip.as_deref(), Some("192.168.1.1"));
+    }
+
+    #[test]
+    fn test_is_vpn_name() {
+        assert!(is_vpn_name("utun3"));
+        assert!(is_vpn_name("tun0"));
+        assert!(is_vpn_name("wg0"));
+        assert!(is_vpn_name("Corporate VPN"));
+        assert!(!is_vpn_name("en0"));
+        assert!(!is_vpn_name("eth0"));
+        assert!(!is_vpn_name("Wi-Fi"));
+    }
 }
@@ -0,0 +1,226 @@
+use std::net::Ipv4Addr;
+
+use crate::commands::validate::Validator;
+
+/// Parse a dotted netmask, rejecting non-contiguous masks like `255.0.255.0`.
+pub fn parse_netmask(mask: &str) -> Option<Ipv4Addr> {
+    let addr: Ipv4Addr = mask.trim().parse().ok()?;
+    let bits = u32::from(addr);
+    // A valid mask is a run of ones followed by a run of zeros
+    if bits.leading_ones() + bits.trailing_zeros() == 32 {
+        Some(addr)
+    } else {
+        None
+    }
+}
+
+/// Prefix length of a dotted netmask (`255.255.254.0` → 23).
+pub fn netmask_to_prefix(mask: &str) -> Option<u32> {
+    parse_netmask(mask).map(|m| u32::from(m).leading_ones())
+}
+
+/// Network address of `ip` under `mask`.
+pub fn network_address(ip: Ipv4Addr, mask: Ipv4Addr) -> Ipv4Addr {
+    Ipv4Addr::from(u32::from(ip) & u32::from(mask))
+}
+
+/// Whether `other` lies in the same network as `ip` under `mask`.
+/// Returns false if any argument fails to parse.
+pub fn in_same_subnet(ip: &str, other: &str, mask: &str) -> bool {
+    let (Ok(ip), Ok(other), Some(mask)) = (
+        ip.parse::<Ipv4Addr>(),
+        other.parse::<Ipv4Addr>(),
+        parse_netmask(mask),
+    ) else {
+        return false;
+    };
+    network_address(ip, mask) == network_address(other, mask)
+}
+
+/// Widest subnet `enumerate_subnet_hosts` sweeps; larger networks are narrowed to
+/// the /24 around the local address.
+pub const MAX_SWEEP_PREFIX: u32 = 24;
+
+/// Host addresses in `ip`'s subnet under `netmask`, excluding the network and
+/// broadcast addresses and `ip` itself. Subnets wider than /24 are capped to the
+/// /24 containing `ip`. Empty if either argument fails to parse.
+pub fn enumerate_subnet_hosts(ip: &str, netmask: &str) -> Vec<String> {
+    let (Ok(own), Some(mut mask)) = (ip.parse::<Ipv4Addr>(), parse_netmask(netmask)) else {
+        return Vec::new();
+    };
+    let prefix = u32::from(mask).leading_ones();
+    if prefix < MAX_SWEEP_PREFIX {
+        log::warn!(
+            "Subnet {}/{} is too large to sweep; limiting to the surrounding /{}",
+            network_address(own, mask),
+            prefix,
+            MAX_SWEEP_PREFIX
+        );
+        mask = Ipv4Addr::from(u32::MAX << (32 - MAX_SWEEP_PREFIX));
+    }
+
+    let network = u32::from(network_address(own, mask));
+    let broadcast = network | !u32::from(mask);
+    // /31 point-to-point links have no network or broadcast address
+    let (first, last) = if broadcast - network < 2 {
+        (network, broadcast)
+    } else {
+        (network + 1, broadcast - 1)
+    };
+
+    (first..=last)
+        .map(Ipv4Addr::from)
+        .filter(|addr| *addr != own)
+        .map(|addr| addr.to_string())
+        .collect()
+}
+
+/// Most hosts an explicit scan target may cover, a /22's worth.
+pub const MAX_TARGET_HOSTS: usize = 1022;
+
+/// Usable host addresses in a CIDR block such as `192.168.1.0/28`, excluding the
+/// network and broadcast addresses. Rejects blocks larger than `MAX_TARGET_HOSTS`.
+pub fn expand_cidr(cidr: &str) -> Result<Vec<String>, String> {
+    let (network, prefix) = Validator::validate_cidr(cidr)?;
+
+    let size = 1u64 << (32 - u32::from(prefix));
+    // /31 and /32 have no network or broadcast address to skip
+    let usable = if size <= 2 { size } else { size - 2 };
+    if usable > MAX_TARGET_HOSTS as u64 {
+        return Err(format!(
+            "{} covers {} hosts; at most {} can be scanned at once",
+            cidr, usable, MAX_TARGET_HOSTS
+        ));
+    }
+
+    let broadcast = network | (size - 1) as u32;
+    let (first, last) = if size <= 2 { (network, broadcast) } else { (network + 1, broadcast - 1) };
+    Ok((first..=last).map(|a| Ipv4Addr::from(a).to_string()).collect())
+}
+
+/// Parse an explicit list of IPv4 addresses, dropping duplicates and keeping order.
+pub fn parse_ip_list(ips: &[String]) -> Result<Vec<String>, String> {
+    let mut hosts: Vec<String> = Vec::with_capacity(ips.len());
+    for ip in ips {
+        let addr: Ipv4Addr = ip
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid IPv4 address: {}", ip))?;
+        let addr = addr.to_string();
+        if !hosts.contains(&addr) {
+            hosts.push(addr);
+        }
+    }
+    if hosts.len() > MAX_TARGET_HOSTS {
+        return Err(format!(
+            "{} addresses listed; at most {} can be scanned at once",
+            hosts.len(),
+            MAX_TARGET_HOSTS
+        ));
+    }
+    Ok(hosts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netmask() {
+        assert!(parse_netmask("255.255.255.0").is_some());
+        assert!(parse_netmask("0.0.0.0").is_some());
+        assert!(parse_netmask("255.255.255.255").is_some());
+        assert!(parse_netmask("255.0.255.0").is_none());
+        assert!(parse_netmask("not-a-mask").is_none());
+    }
+
+    #[test]
+    fn test_netmask_to_prefix() {
+        assert_eq!(netmask_to_prefix("255.255.255.0"), Some(24));
+        assert_eq!(netmask_to_prefix("255.255.254.0"), Some(23));
+        assert_eq!(netmask_to_prefix("255.255.0.0"), Some(16));
+        assert_eq!(netmask_to_prefix("0.0.0.0"), Some(0));
+    }
+
+    #[test]
+    fn test_in_same_subnet_slash_16() {
+        assert!(in_same_subnet("172.16.5.10", "172.16.200.1", "255.255.0.0"));
+        assert!(!in_same_subnet("172.16.5.10", "172.17.0.1", "255.255.0.0"));
+    }
+
+    #[test]
+    fn test_in_same_subnet_slash_23() {
+        // 10.1.2.0/23 spans 10.1.2.0 – 10.1.3.255
+        assert!(in_same_subnet("10.1.3.50", "10.1.2.1", "255.255.254.0"));
+        assert!(!in_same_subnet("10.1.3.50", "10.1.4.1", "255.255.254.0"));
+    }
+
+    #[test]
+    fn test_in_same_subnet_slash_25() {
+        // 192.168.1.0/25 ends at .127; .128 starts the next network
+        let mask = "255.255.255.128";
+        assert!(in_same_subnet("192.168.1.1", "192.168.1.127", mask));
+        assert!(!in_same_subnet("192.168.1.127", "192.168.1.128", mask));
+        assert!(in_same_subnet("192.168.1.129", "192.168.1.254", mask));
+    }
+
+    #[test]
+    fn test_enumerate_subnet_hosts() {
+        let hosts = enumerate_subnet_hosts("192.168.1.10", "255.255.255.0");
+        assert_eq!(hosts.len(), 253);
+        assert_eq!(hosts[0], "192.168.1.1");
+        assert_eq!(hosts.last().map(String::as_str), Some("192.168.1.254"));
+        assert!(!hosts.contains(&"192.168.1.10".to_string()));
+
+        let small = enumerate_subnet_hosts("10.0.0.5", "255.255.255.248");
+        assert_eq!(small, ["10.0.0.1", "10.0.0.2", "10.0.0.3", "10.0.0.4", "10.0.0.6"]);
+        assert_eq!(enumerate_subnet_hosts("10.0.0.0", "255.255.255.254"), ["10.0.0.1"]);
+        assert!(enumerate_subnet_hosts("10.0.0.1", "255.255.255.255").is_empty());
+        assert!(enumerate_subnet_hosts("bogus", "255.255.255.0").is_empty());
+    }
+
+    #[test]
+    fn test_enumerate_subnet_hosts_caps_large_subnets() {
+        let hosts = enumerate_subnet_hosts("172.16.5.10", "255.255.0.0");
+        assert_eq!(hosts.len(), 253);
+        assert!(hosts.iter().all(|h| h.starts_with("172.16.5.")));
+    }
+
+    #[test]
+    fn test_in_same_subnet_invalid_input() {
+        assert!(!in_same_subnet("10.0.0.1", "10.0.0.2", "255.0.255.0"));
+        assert!(!in_same_subnet("bogus", "10.0.0.2", "255.255.255.0"));
+    }
+
+    #[test]
+    fn test_expand_cidr() {
+        let hosts = expand_cidr("192.168.1.0/28").unwrap();
+        assert_eq!(hosts.len(), 14);
+        assert_eq!(hosts[0], "192.168.1.1");
+        assert_eq!(hosts[13], "192.168.1.14");
+
+        // Host bits in the address are ignored
+        assert_eq!(expand_cidr("10.0.0.77/30").unwrap(), ["10.0.0.77", "10.0.0.78"]);
+        assert_eq!(expand_cidr("10.0.0.9/32").unwrap(), ["10.0.0.9"]);
+        assert_eq!(expand_cidr("10.0.0.0/22").unwrap().len(), MAX_TARGET_HOSTS);
+    }
+
+    #[test]
+    fn test_expand_cidr_rejects_bad_or_oversized_ranges() {
+        assert!(expand_cidr("10.0.0.0/21").is_err());
+        assert!(expand_cidr("0.0.0.0/0").is_err());
+        assert!(expand_cidr("10.0.0.0").is_err());
+        assert!(expand_cidr("10.0.0.0/33").is_err());
+        assert!(expand_cidr("10.0.0/24").is_err());
+    }
+
+    #[test]
+    fn test_parse_ip_list() {
+        let ips = vec!["10.0.0.2".to_string(), " 10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        assert_eq!(parse_ip_list(&ips).unwrap(), ["10.0.0.2", "10.0.0.1"]);
+        assert!(parse_ip_list(&["10.0.0.256".to_string()]).is_err());
+
+        let many: Vec<String> = (0..=MAX_TARGET_HOSTS).map(|i| Ipv4Addr::from(0x0a00_0000 + i as u32).to_string()).collect();
+        assert!(parse_ip_list(&many).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Multicast DNS group and port (RFC 6762).
+pub(crate) const MDNS_ADDR: SocketAddr = SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+
+pub(crate) const TYPE_PTR: u16 = 12;
+const CLASS_IN: u16 = 1;
+/// Top bit of the question class asks responders to answer by unicast.
+const UNICAST_RESPONSE: u16 = 0x8000;
+/// Compression pointers followed before a name is treated as malformed.
+const MAX_POINTER_JUMPS: usize = 16;
+
+/// `in-addr.arpa` name for a reverse lookup of `ip`.
+fn reverse_name(ip: Ipv4Addr) -> String {
+    let [a, b, c, d] = ip.octets();
+    format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+}
+
+/// One-question PTR query for `ip`, requesting a unicast reply.
+pub fn build_ptr_query(ip: Ipv4Addr) -> Vec<u8> {
+    build_query(&reverse_name(ip))
+}
+
+/// One-question PTR query for `name`, requesting a unicast reply.
+pub(crate) fn build_query(name: &str) -> Vec<u8> {
+    // ID 0, standard query, one question
+    let mut packet = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in name.split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&(CLASS_IN | UNICAST_RESPONSE).to_be_bytes());
+    packet
+}
+
+pub(crate) fn read_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]))
+}
+
+/// Decode a possibly compressed name at `pos`. Returns the dotted name and the
+/// offset just past it in the original (uncompressed) position.
+pub(crate) fn read_name(packet: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *packet.get(pos)? as usize;
+        match len {
+            0 => break,
+            l if l & 0xc0 == 0xc0 => {
+                jumps += 1;
+                if jumps > MAX_POINTER_JUMPS {
+                    return None;
+                }
+                end.get_or_insert(pos + 2);
+                pos = (read_u16(packet, pos)? & 0x3fff) as usize;
+            }
+            l => {
+                let label = packet.get(pos + 1..pos + 1 + l)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + l;
+            }
+        }
+    }
+
+    Some((labels.join("."), end.unwrap_or(pos + 1)))
+}
+
+/// Hostname from a PTR answer for `ip` in an mDNS response, without the trailing dot.
+pub fn parse_ptr_response(packet: &[u8], ip: Ipv4Addr) -> Option<String> {
+    let flags = read_u16(packet, 2)?;
+    if flags & 0x8000 == 0 {
+        return None; // a query, not a response
+    }
+    let questions = read_u16(packet, 4)?;
+    let answers = read_u16(packet, 6)?;
+    let wanted = reverse_name(ip);
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        let (_, next) = read_name(packet, pos)?;
+        pos = next + 4;
+    }
+    for _ in 0..answers {
+        let (name, next) = read_name(packet, pos)?;
+        let rtype = read_u16(packet, next)?;
+        let rdlength = read_u16(packet, next + 8)? as usize;
+        let rdata = next + 10;
+        if rtype == TYPE_PTR && name.eq_ignore_ascii_case(&wanted) {
+            let (host, _) = read_name(packet, rdata)?;
+            let host = host.trim_end_matches('.');
+            if !host.is_empty() {
+                return Some(host.to_string());
+            }
+        }
+        pos = rdata + rdlength;
+    }
+    None
+}
+
+/// Ask the local link who owns `ip` over multicast DNS, waiting up to `timeout`.
+/// Only devices that publish themselves over mDNS (Apple, Avahi, most printers) answer.
+pub fn reverse_lookup(ip: &str, timeout: Duration) -> Option<String> {
+    let addr: Ipv4Addr = ip.parse().ok()?;
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.send_to(&build_ptr_query(addr), MDNS_ADDR).ok()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 1500];
+    loop {
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        socket.set_read_timeout(Some(remaining.max(Duration::from_millis(1)))).ok()?;
+        let (len, _) = socket.recv_from(&mut buf).ok()?;
+        // Other responders may share the socket's traffic; keep reading until ours arrives
+        if let Some(host) = parse_ptr_response(&buf[..len], addr) {
+            return Some(host);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 42);
+
+    /// Response echoing the question, answering with a pointer back to it.
+    fn response(host: &str) -> Vec<u8> {
+        let mut packet = build_ptr_query(IP);
+        packet[2] = 0x84; // response, authoritative
+        packet[7] = 1; // one answer
+        packet.extend_from_slice(&[0xc0, 12]); // name: pointer to the question
+        packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet.extend_from_slice(&120u32.to_be_bytes());
+        let mut rdata = Vec::new();
+        for label in host.split('.') {
+            rdata.push(label.len() as u8);
+            rdata.extend_from_slice(label.as_bytes());
+        }
+        rdata.push(0);
+        packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&rdata);
+        packet
+    }
+
+    #[test]
+    fn test_build_ptr_query() {
+        let packet = build_ptr_query(IP);
+        assert_eq!(&packet[4..6], &[0, 1]);
+        let (name, end) = read_name(&packet, 12).unwrap();
+        assert_eq!(name, "42.1.168.192.in-addr.arpa");
+        assert_eq!(read_u16(&packet, end), Some(TYPE_PTR));
+        assert_eq!(read_u16(&packet, end + 2), Some(CLASS_IN | UNICAST_RESPONSE));
+    }
+
+    #[test]
+    fn test_parse_ptr_response() {
+        assert_eq!(parse_ptr_response(&response("macbook.local"), IP).as_deref(), Some("macbook.local"));
+
+        // The query itself, or an answer for another address, is not a match
+        assert!(parse_ptr_response(&build_ptr_query(IP), IP).is_none());
+        assert!(parse_ptr_response(&response("macbook.local"), Ipv4Addr::new(192, 168, 1, 43)).is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_packets() {
+        let packet = response("printer.local");
+        assert!(parse_ptr_response(&packet[..packet.len() - 4], IP).is_none());
+        assert!(parse_ptr_response(&[0x00, 0x00, 0x84], IP).is_none());
+
+        // A pointer to itself must not loop forever
+        assert!(read_name(&[0xc0, 0x00], 0).is_none());
+    }
+}
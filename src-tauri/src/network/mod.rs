@@ -1,3 +1,8 @@
+pub mod gateway_monitor;
 pub mod interface;
+pub mod ipmath;
+pub mod mdns;
 pub mod oui;
 pub mod resolver;
+pub mod watcher;
+pub mod wol;
@@ -64,8 +64,12 @@ impl OuiDatabase {
 
     /// Look up the vendor for a MAC address.
     /// Accepts formats: "AA:BB:CC:DD:EE:FF", "AA-BB-CC-DD-EE-FF", "AABB.CCDD.EEFF"
+    /// Locally administered addresses have no registered vendor, so they never match.
     pub fn lookup(&self, mac: &str) -> Option<&str> {
         let prefix = parse_mac_prefix(mac)?;
+        if prefix[0] & LOCALLY_ADMINISTERED_BIT != 0 {
+            return None;
+        }
         self.entries.get(&prefix).map(|s| s.as_str())
     }
 
@@ -79,6 +83,15 @@ impl OuiDatabase {
     }
 }
 
+/// Second-lowest bit of the first octet; set on addresses not assigned by a vendor.
+const LOCALLY_ADMINISTERED_BIT: u8 = 0x02;
+
+/// Whether a MAC is locally administered rather than vendor-assigned, as with the
+/// randomized private addresses phones and laptops rotate through.
+pub fn is_locally_administered(mac: &str) -> bool {
+    parse_mac_prefix(mac).is_some_and(|prefix| prefix[0] & LOCALLY_ADMINISTERED_BIT != 0)
+}
+
 /// Parse a 6-char hex string (e.g., "AABBCC") into 3 bytes.
 fn parse_oui_hex(hex: &str) -> Option<[u8; 3]> {
     let hex = hex.trim();
@@ -177,4 +190,23 @@ mod tests {
         assert_eq!(db.lookup("3C:22:FB:00:11:22"), Some("Apple, Inc."));
         assert!(db.lookup("00:00:00:00:00:00").is_none());
     }
+
+    #[test]
+    fn test_is_locally_administered() {
+        assert!(is_locally_administered("02:00:5E:10:00:01"));
+        assert!(is_locally_administered("da:a1:19:2b:3c:4d"));
+        assert!(is_locally_administered("DE-AD-BE-EF-00-01"));
+        assert!(!is_locally_administered("00:1A:2B:3C:4D:5E"));
+        assert!(!is_locally_administered("AC:DE:48:00:11:22"));
+        assert!(!is_locally_administered("bogus"));
+    }
+
+    #[test]
+    fn test_lookup_skips_randomized_mac() {
+        let mut db = OuiDatabase::empty();
+        db.entries.insert([0x00, 0x1A, 0x2B], "Acme".to_string());
+        db.entries.insert([0xDA, 0xA1, 0x19], "Bogus".to_string());
+        assert_eq!(db.lookup("00:1A:2B:3C:4D:5E"), Some("Acme"));
+        assert_eq!(db.lookup("DA:A1:19:2B:3C:4D"), None);
+    }
 }
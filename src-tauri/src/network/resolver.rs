@@ -1,62 +1,105 @@
-use std::process::Command;
+use std::net::IpAddr;
 use std::time::Duration;
 
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+
+use crate::network::mdns;
+use crate::scanner::budget::ScanBudget;
+
+/// Per-host limit on the whole lookup, reverse DNS and mDNS together.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Per-query DNS timeout. Kept under `LOOKUP_TIMEOUT` so mDNS still gets a turn.
+const DNS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long to wait for an mDNS answer once reverse DNS has come up empty.
+pub const MDNS_TIMEOUT: Duration = Duration::from_millis(750);
+
+/// Resolver using the system's nameservers (resolv.conf, or the registry on Windows),
+/// falling back to public defaults if the system configuration can't be read.
+fn system_resolver() -> TokioAsyncResolver {
+    let (config, mut opts) = hickory_resolver::system_conf::read_system_conf().unwrap_or_else(|e| {
+        log::warn!("Could not read system DNS configuration, using defaults: {}", e);
+        (ResolverConfig::default(), ResolverOpts::default())
+    });
+    opts.timeout = DNS_TIMEOUT;
+    opts.attempts = 1;
+    TokioAsyncResolver::tokio(config, opts)
+}
+
 /// Attempt to resolve a hostname for an IP address.
-/// Tries reverse DNS first, then system hostname lookups.
+/// Tries reverse DNS first, then an mDNS reverse query on the local link.
 pub async fn resolve_hostname(ip: &str) -> Option<String> {
-    let ip = ip.to_string();
+    lookup(Some(&system_resolver()), ip, Some(MDNS_TIMEOUT), ()).await
+}
 
-    // Run in a blocking thread since it shells out
-    let result = tokio::task::spawn_blocking(move || {
-        resolve_hostname_sync(&ip)
-    })
-    .await
-    .ok()?;
+/// Reverse DNS alone, for addresses mDNS can't answer for.
+pub async fn reverse_dns_only(ip: &str) -> Option<String> {
+    lookup(Some(&system_resolver()), ip, None, ()).await
+}
 
-    result
+/// PTR lookup through `resolver`, without the trailing dot.
+async fn reverse_dns(resolver: &TokioAsyncResolver, ip: IpAddr) -> Option<String> {
+    let answer = resolver.reverse_lookup(ip).await.ok()?;
+    answer
+        .iter()
+        .map(|ptr| ptr.to_utf8().trim_end_matches('.').to_string())
+        .find(|host| !host.is_empty())
 }
 
-/// Synchronous hostname resolution using system tools.
-fn resolve_hostname_sync(ip: &str) -> Option<String> {
-    // Try `host` command for reverse DNS
-    let output = Command::new("host")
-        .arg(ip)
-        .output()
-        .ok()?;
-
-    if output.status.success() {
-        let text = String::from_utf8_lossy(&output.stdout);
-        // Output format: "1.168.192.in-addr.arpa domain name pointer hostname.local."
-        if let Some(ptr_line) = text.lines().find(|l| l.contains("domain name pointer")) {
-            let hostname = ptr_line
-                .split("domain name pointer")
-                .nth(1)?
-                .trim()
-                .trim_end_matches('.');
-            if !hostname.is_empty() {
-                return Some(hostname.to_string());
+/// A PTR query through `dns` when given, then mDNS when `mdns_timeout` is set, all within
+/// `LOOKUP_TIMEOUT`. `hold` is kept until the mDNS thread finishes, even if the lookup
+/// timed out.
+async fn lookup<H: Send + 'static>(
+    dns: Option<&TokioAsyncResolver>,
+    ip: &str,
+    mdns_timeout: Option<Duration>,
+    hold: H,
+) -> Option<String> {
+    let addr: IpAddr = ip.parse().ok()?;
+    let attempt = async {
+        if let Some(resolver) = dns {
+            if let Some(host) = reverse_dns(resolver, addr).await {
+                return Some(host);
             }
         }
-    }
+        let timeout = mdns_timeout?;
+        let ip = ip.to_string();
+        tokio::task::spawn_blocking(move || {
+            let _hold = hold;
+            mdns::reverse_lookup(&ip, timeout)
+        })
+        .await
+        .ok()
+        .flatten()
+    };
+    tokio::time::timeout(LOOKUP_TIMEOUT, attempt).await.ok().flatten()
+}
 
-    None
+/// Resolve hostnames for multiple IPs concurrently, one `budget` slot per lookup.
+/// The slot is held until the mDNS query ends, even if the lookup timed out.
+pub async fn resolve_hostnames(ips: &[String], budget: &ScanBudget) -> Vec<(String, Option<String>)> {
+    // One resolver for the batch so its connections and cache are shared
+    let resolver = system_resolver();
+    resolve_all(ips, budget, Some(&resolver), Some(MDNS_TIMEOUT)).await
 }
 
-/// Resolve hostnames for multiple IPs concurrently.
-pub async fn resolve_hostnames(ips: &[String]) -> Vec<(String, Option<String>)> {
+async fn resolve_all(
+    ips: &[String],
+    budget: &ScanBudget,
+    dns: Option<&TokioAsyncResolver>,
+    mdns_timeout: Option<Duration>,
+) -> Vec<(String, Option<String>)> {
     let mut handles = Vec::new();
 
     for ip in ips {
         let ip = ip.clone();
+        let budget = budget.clone();
+        let dns = dns.cloned();
         let handle = tokio::spawn(async move {
-            let hostname = tokio::time::timeout(
-                Duration::from_secs(2),
-                resolve_hostname(&ip),
-            )
-            .await
-            .ok()
-            .flatten();
-
+            let permit = budget.acquire().await;
+            let hostname = lookup(dns.as_ref(), &ip, mdns_timeout, permit).await;
             (ip, hostname)
         });
         handles.push(handle);
@@ -71,3 +114,24 @@ pub async fn resolve_hostnames(ips: &[String]) -> Vec<(String, Option<String>)>
 
     results
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_loopback() {
+        let resolver = system_resolver();
+        let ips = vec!["127.0.0.1".to_string(), "not-an-ip".to_string()];
+        let results = resolve_all(&ips, &ScanBudget::new(2), Some(&resolver), None).await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "127.0.0.1");
+        // Whether loopback has a PTR record depends on the host, but any answer is clean
+        if let Some(host) = &results[0].1 {
+            assert!(!host.is_empty());
+            assert!(!host.ends_with('.'));
+        }
+        assert_eq!(results[1], ("not-an-ip".to_string(), None));
+    }
+}
@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::network::interface::{self, NetworkInterface};
+use crate::state::AppState;
+
+/// How often the background watcher re-reads the interface list.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Emitted as `interfaces:changed` when active interfaces come or go.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfacesChanged {
+    pub added: Vec<NetworkInterface>,
+    pub removed: Vec<NetworkInterface>,
+    /// The full, current interface list.
+    pub interfaces: Vec<NetworkInterface>,
+}
+
+/// Compare active interfaces by id. Returns `None` when nothing was added or removed.
+pub fn diff(old: &[NetworkInterface], new: &[NetworkInterface]) -> Option<InterfacesChanged> {
+    let active = |list: &[NetworkInterface], id: &str| list.iter().any(|i| i.is_active && i.id == id);
+
+    let added: Vec<_> = new
+        .iter()
+        .filter(|i| i.is_active && !active(old, &i.id))
+        .cloned()
+        .collect();
+    let removed: Vec<_> = old
+        .iter()
+        .filter(|i| i.is_active && !active(new, &i.id))
+        .cloned()
+        .collect();
+
+    if added.is_empty() && removed.is_empty() {
+        return None;
+    }
+    Some(InterfacesChanged {
+        added,
+        removed,
+        interfaces: new.to_vec(),
+    })
+}
+
+/// Store `current` as the latest snapshot and report what changed since the previous one.
+/// The first snapshot only seeds the baseline.
+pub fn record_snapshot(state: &AppState, current: &[NetworkInterface]) -> Option<InterfacesChanged> {
+    let mut guard = state.interfaces.lock().ok()?;
+    let change = guard.as_deref().and_then(|previous| diff(previous, current));
+    *guard = Some(current.to_vec());
+    change
+}
+
+/// Record a fresh interface list and emit `interfaces:changed` if it differs.
+pub fn refresh(app: &AppHandle, state: &AppState, current: &[NetworkInterface]) {
+    if let Some(change) = record_snapshot(state, current) {
+        log::info!(
+            "Interfaces changed: +{:?} -{:?}",
+            change.added.iter().map(|i| &i.id).collect::<Vec<_>>(),
+            change.removed.iter().map(|i| &i.id).collect::<Vec<_>>()
+        );
+        let _ = app.emit("interfaces:changed", &change);
+    }
+}
+
+/// Check that a scan's interface still exists and is up. `"auto"` always passes.
+pub fn ensure_available(interfaces: &[NetworkInterface], interface_id: &str) -> Result<(), String> {
+    if interface_id == "auto" {
+        return Ok(());
+    }
+    match interfaces.iter().find(|i| i.id == interface_id) {
+        Some(i) if i.is_active => Ok(()),
+        Some(_) => Err(format!("Interface {} is down", interface_id)),
+        None => Err(format!("Interface {} is no longer available", interface_id)),
+    }
+}
+
+/// Poll the interface list until the app exits, emitting changes as they happen.
+pub async fn watch(app: AppHandle) {
+    loop {
+        // A failed poll says nothing about the interfaces, so keep the previous snapshot
+        // rather than reporting every interface as removed
+        match tokio::task::spawn_blocking(interface::get_interfaces).await {
+            Ok(current) => {
+                let state = app.state::<Arc<AppState>>();
+                refresh(&app, &state, &current);
+            }
+            Err(e) => log::warn!("Interface poll failed, skipping this tick: {}", e),
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::network::oui::OuiDatabase;
+
+    fn iface(id: &str, active: bool) -> NetworkInterface {
+        NetworkInterface {
+            id: id.to_string(),
+            name: id.to_string(),
+            ip_address: Some("192.168.1.10".to_string()),
+            subnet_mask: None,
+            mac_address: None,
+            gateway_ip: None,
+            ipv6_address: None,
+            gateway_ipv6: None,
+            is_active: active,
+            is_vpn: false,
+        }
+    }
+
+    #[test]
+    fn test_diff_added_and_removed() {
+        let old = vec![iface("en0", true), iface("en1", true)];
+        let new = vec![iface("en0", true), iface("utun3", true)];
+
+        let change = diff(&old, &new).unwrap();
+        assert_eq!(change.added.len(), 1);
+        assert_eq!(change.added[0].id, "utun3");
+        assert_eq!(change.removed.len(), 1);
+        assert_eq!(change.removed[0].id, "en1");
+    }
+
+    #[test]
+    fn test_diff_treats_down_as_removed() {
+        let old = vec![iface("en0", true)];
+        let new = vec![iface("en0", false)];
+        let change = diff(&old, &new).unwrap();
+        assert!(change.added.is_empty());
+        assert_eq!(change.removed[0].id, "en0");
+
+        assert!(diff(&new, &new).is_none());
+    }
+
+    #[test]
+    fn test_record_snapshot_seeds_then_diffs() {
+        let state = AppState::new(db::init_test_db(), OuiDatabase::empty());
+        assert!(record_snapshot(&state, &[iface("en0", true)]).is_none());
+        assert!(record_snapshot(&state, &[iface("en0", true)]).is_none());
+
+        let change = record_snapshot(&state, &[]).unwrap();
+        assert_eq!(change.removed[0].id, "en0");
+    }
+
+    #[test]
+    fn test_ensure_available() {
+        let list = vec![iface("en0", true), iface("en1", false)];
+        assert!(ensure_available(&list, "auto").is_ok());
+        assert!(ensure_available(&[], "auto").is_ok());
+        assert!(ensure_available(&list, "en0").is_ok());
+        assert!(ensure_available(&list, "en1").unwrap_err().contains("down"));
+        assert!(ensure_available(&list, "wlan0").unwrap_err().contains("no longer available"));
+    }
+}
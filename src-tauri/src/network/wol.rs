@@ -0,0 +1,56 @@
+use std::net::{Ipv4Addr, UdpSocket};
+
+/// Wake-on-LAN packets go to the discard port as a limited broadcast.
+const WOL_PORT: u16 = 9;
+
+/// Magic packet for `mac`: six 0xFF bytes, then the MAC sixteen times.
+pub fn magic_packet(mac: &[u8; 6]) -> [u8; 102] {
+    let mut packet = [0xFF; 102];
+    for chunk in packet[6..].chunks_exact_mut(6) {
+        chunk.copy_from_slice(mac);
+    }
+    packet
+}
+
+/// Parse a validated `AA:BB:CC:DD:EE:FF` or `AA-BB-CC-DD-EE-FF` address.
+pub fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let mut bytes = [0u8; 6];
+    let mut parts = mac.split([':', '-']);
+    for byte in &mut bytes {
+        *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+    }
+    parts.next().is_none().then_some(bytes)
+}
+
+/// Broadcast a magic packet for `mac` to 255.255.255.255:9.
+pub fn send_magic_packet(mac: &[u8; 6]) -> Result<(), String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|e| e.to_string())?;
+    socket.set_broadcast(true).map_err(|e| e.to_string())?;
+    socket
+        .send_to(&magic_packet(mac), (Ipv4Addr::BROADCAST, WOL_PORT))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_packet() {
+        let mac = parse_mac("3c:52:19:7A:4E:01").unwrap();
+        let packet = magic_packet(&mac);
+        assert_eq!(packet.len(), 102);
+        assert_eq!(&packet[..6], &[0xFF; 6]);
+        assert_eq!(&packet[6..12], &[0x3c, 0x52, 0x19, 0x7a, 0x4e, 0x01]);
+        assert_eq!(&packet[96..], &[0x3c, 0x52, 0x19, 0x7a, 0x4e, 0x01]);
+        assert!(packet[6..].chunks(6).all(|c| c == mac));
+    }
+
+    #[test]
+    fn test_parse_mac() {
+        assert_eq!(parse_mac("AA-BB-CC-DD-EE-FF"), Some([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]));
+        assert!(parse_mac("AA:BB:CC:DD:EE").is_none());
+        assert!(parse_mac("AA:BB:CC:DD:EE:FF:00").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default number of probes, lookups and connections a scan may have in flight.
+pub const DEFAULT_SCAN_CONCURRENCY: usize = 64;
+
+/// Concurrency limit shared by every phase of one scan, so sockets and child
+/// processes stay bounded however much work a phase queues up.
+#[derive(Debug, Clone)]
+pub struct ScanBudget {
+    permits: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+impl ScanBudget {
+    pub fn new(permits: usize) -> Self {
+        let permits = permits.max(1);
+        Self {
+            permits,
+            semaphore: Arc::new(Semaphore::new(permits)),
+        }
+    }
+
+    /// Total operations allowed in flight.
+    pub fn permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Wait for a free slot. The slot is returned when the permit is dropped.
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        self.semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("scan budget semaphore is never closed")
+    }
+}
+
+impl Default for ScanBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_SCAN_CONCURRENCY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_budget_bounds_in_flight_work() {
+        let budget = ScanBudget::new(2);
+        let first = budget.acquire().await;
+        let _second = budget.acquire().await;
+
+        let blocked = tokio::time::timeout(std::time::Duration::from_millis(20), budget.acquire()).await;
+        assert!(blocked.is_err());
+
+        drop(first);
+        let freed = tokio::time::timeout(std::time::Duration::from_millis(20), budget.acquire()).await;
+        assert!(freed.is_ok());
+    }
+
+    #[test]
+    fn test_budget_has_at_least_one_permit() {
+        assert_eq!(ScanBudget::new(0).permits(), 1);
+        assert_eq!(ScanBudget::default().permits(), DEFAULT_SCAN_CONCURRENCY);
+    }
+}
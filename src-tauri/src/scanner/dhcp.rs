@@ -0,0 +1,249 @@
+use std::net::Ipv4Addr;
+
+use tokio::net::UdpSocket;
+use tokio_util::sync::CancellationToken;
+
+/// DHCP server port; clients broadcast DISCOVER and REQUEST messages to it.
+const SERVER_PORT: u16 = 67;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+/// Fixed BOOTP header length before the magic cookie.
+const OPTIONS_OFFSET: usize = 240;
+
+const OPT_PAD: u8 = 0;
+const OPT_HOSTNAME: u8 = 12;
+const OPT_MESSAGE_TYPE: u8 = 53;
+const OPT_PARAMETER_LIST: u8 = 55;
+const OPT_VENDOR_CLASS: u8 = 60;
+const OPT_END: u8 = 255;
+
+const DHCPDISCOVER: u8 = 1;
+const DHCPREQUEST: u8 = 3;
+
+/// What a client revealed about itself in a DISCOVER or REQUEST.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DhcpFingerprint {
+    pub mac: String,
+    /// Option 12.
+    pub hostname: Option<String>,
+    /// Option 55: the options the client asks for, in its own order.
+    pub parameter_list: Vec<u8>,
+    /// Option 60, e.g. "MSFT 5.0" or "android-dhcp-14".
+    pub vendor_class: Option<String>,
+}
+
+/// OS and device type a fingerprint points to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DhcpMatch {
+    pub os: &'static str,
+    pub device_type: Option<&'static str>,
+    pub confidence: f64,
+}
+
+enum Pattern {
+    VendorPrefix(&'static str),
+    Parameters(&'static [u8]),
+}
+
+/// Known clients, most specific first. Vendor classes are checked as prefixes;
+/// parameter lists must match exactly, order included.
+const SIGNATURES: &[(Pattern, DhcpMatch)] = &[
+    (
+        Pattern::VendorPrefix("android-dhcp-"),
+        DhcpMatch { os: "Android", device_type: Some("phone"), confidence: 0.85 },
+    ),
+    (
+        Pattern::VendorPrefix("MSFT"),
+        DhcpMatch { os: "Windows", device_type: Some("computer"), confidence: 0.80 },
+    ),
+    (
+        Pattern::VendorPrefix("udhcp"),
+        DhcpMatch { os: "Embedded Linux", device_type: Some("iot"), confidence: 0.60 },
+    ),
+    (
+        Pattern::VendorPrefix("dhcpcd-"),
+        DhcpMatch { os: "Linux", device_type: None, confidence: 0.65 },
+    ),
+    (
+        Pattern::Parameters(&[1, 121, 3, 6, 15, 108, 114, 119, 252, 95, 44, 46]),
+        DhcpMatch { os: "macOS", device_type: Some("computer"), confidence: 0.80 },
+    ),
+    (
+        Pattern::Parameters(&[1, 121, 3, 6, 15, 119, 252, 95, 44, 46]),
+        DhcpMatch { os: "macOS", device_type: Some("computer"), confidence: 0.80 },
+    ),
+    (
+        Pattern::Parameters(&[1, 121, 3, 6, 15, 108, 114, 119, 252]),
+        DhcpMatch { os: "iOS", device_type: Some("phone"), confidence: 0.75 },
+    ),
+    (
+        Pattern::Parameters(&[1, 121, 3, 6, 15, 119, 252]),
+        DhcpMatch { os: "iOS", device_type: Some("phone"), confidence: 0.75 },
+    ),
+    (
+        Pattern::Parameters(&[1, 3, 6, 15, 31, 33, 43, 44, 46, 47, 119, 121, 249, 252]),
+        DhcpMatch { os: "Windows", device_type: Some("computer"), confidence: 0.75 },
+    ),
+];
+
+/// Extract the client fingerprint from a DHCP DISCOVER or REQUEST. Other messages,
+/// replies and malformed packets give `None`.
+pub fn parse_packet(packet: &[u8]) -> Option<DhcpFingerprint> {
+    // BOOTREQUEST over Ethernet: op 1, htype 1, hlen 6
+    if packet.get(..3)? != [1, 1, 6] || packet.get(236..OPTIONS_OFFSET)? != MAGIC_COOKIE {
+        return None;
+    }
+    let mac = packet
+        .get(28..34)?
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    let mut fingerprint = DhcpFingerprint {
+        mac,
+        hostname: None,
+        parameter_list: Vec::new(),
+        vendor_class: None,
+    };
+    let mut message_type = None;
+
+    let mut pos = OPTIONS_OFFSET;
+    loop {
+        let code = *packet.get(pos)?;
+        match code {
+            OPT_END => break,
+            OPT_PAD => {
+                pos += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let len = *packet.get(pos + 1)? as usize;
+        let value = packet.get(pos + 2..pos + 2 + len)?;
+        let text = || {
+            let s = String::from_utf8_lossy(value).trim_end_matches('\0').to_string();
+            Some(s).filter(|s| !s.is_empty())
+        };
+        match code {
+            OPT_MESSAGE_TYPE => message_type = value.first().copied(),
+            OPT_PARAMETER_LIST => fingerprint.parameter_list = value.to_vec(),
+            OPT_VENDOR_CLASS => fingerprint.vendor_class = text(),
+            OPT_HOSTNAME => fingerprint.hostname = text(),
+            _ => {}
+        }
+        pos += 2 + len;
+    }
+
+    matches!(message_type, Some(DHCPDISCOVER | DHCPREQUEST)).then_some(fingerprint)
+}
+
+/// Match a fingerprint against the signature table.
+pub fn identify(fingerprint: &DhcpFingerprint) -> Option<DhcpMatch> {
+    SIGNATURES.iter().find_map(|(pattern, matched)| {
+        let hit = match pattern {
+            Pattern::VendorPrefix(prefix) => {
+                fingerprint.vendor_class.as_deref().is_some_and(|v| v.starts_with(prefix))
+            }
+            Pattern::Parameters(list) => fingerprint.parameter_list == *list,
+        };
+        hit.then_some(*matched)
+    })
+}
+
+/// Capture client broadcasts on the DHCP server port until `stop` is cancelled,
+/// keeping the latest fingerprint per MAC. Binding port 67 needs privileges and
+/// fails beside a running DHCP server; either way this logs and returns nothing.
+pub async fn listen(stop: CancellationToken) -> Vec<DhcpFingerprint> {
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, SERVER_PORT)).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::warn!("DHCP fingerprinting unavailable (port {}): {}", SERVER_PORT, e);
+            return Vec::new();
+        }
+    };
+
+    let mut seen: Vec<DhcpFingerprint> = Vec::new();
+    let mut buf = [0u8; 1500];
+    loop {
+        tokio::select! {
+            _ = stop.cancelled() => break,
+            received = socket.recv_from(&mut buf) => {
+                let Ok((len, _)) = received else { break };
+                if let Some(fingerprint) = parse_packet(&buf[..len]) {
+                    seen.retain(|f| f.mac != fingerprint.mac);
+                    seen.push(fingerprint);
+                }
+            }
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Options of a DHCPDISCOVER from a Windows laptop: message type, client id,
+    /// hostname, vendor class and parameter request list.
+    const WINDOWS_DISCOVER_OPTIONS: &[u8] = b"\
+\x35\x01\x01\
+\x3d\x07\x01\x3c\x52\x19\x7a\x4e\x01\
+\x0c\x0aDESKTOP-7Q\
+\x3c\x08MSFT 5.0\
+\x37\x0e\x01\x03\x06\x0f\x1f\x21\x2b\x2c\x2e\x2f\x77\x79\xf9\xfc\
+\x00\x00\xff";
+
+    /// BOOTP header for a client request from 3C:52:19:7A:4E:01, followed by `options`.
+    fn packet(options: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0u8; OPTIONS_OFFSET];
+        packet[..4].copy_from_slice(&[1, 1, 6, 0]);
+        packet[4..8].copy_from_slice(&[0x3a, 0x1f, 0x6c, 0x02]); // transaction id
+        packet[28..34].copy_from_slice(&[0x3c, 0x52, 0x19, 0x7a, 0x4e, 0x01]);
+        packet[236..].copy_from_slice(&MAGIC_COOKIE);
+        packet.extend_from_slice(options);
+        packet
+    }
+
+    #[test]
+    fn test_parse_discover_options() {
+        let fingerprint = parse_packet(&packet(WINDOWS_DISCOVER_OPTIONS)).unwrap();
+        assert_eq!(fingerprint.mac, "3C:52:19:7A:4E:01");
+        assert_eq!(fingerprint.hostname.as_deref(), Some("DESKTOP-7Q"));
+        assert_eq!(fingerprint.vendor_class.as_deref(), Some("MSFT 5.0"));
+        assert_eq!(fingerprint.parameter_list, [1, 3, 6, 15, 31, 33, 43, 44, 46, 47, 119, 121, 249, 252]);
+
+        let matched = identify(&fingerprint).unwrap();
+        assert_eq!(matched.os, "Windows");
+        assert_eq!(matched.device_type, Some("computer"));
+    }
+
+    #[test]
+    fn test_parse_rejects_other_packets() {
+        // A server reply
+        let mut reply = packet(WINDOWS_DISCOVER_OPTIONS);
+        reply[0] = 2;
+        assert!(parse_packet(&reply).is_none());
+
+        // DHCPRELEASE carries no useful fingerprint
+        assert!(parse_packet(&packet(b"\x35\x01\x07\xff")).is_none());
+
+        // Truncated option or missing end marker
+        assert!(parse_packet(&packet(b"\x35\x01\x01\x37\x05\x01\x03")).is_none());
+        assert!(parse_packet(&packet(b"\x35\x01\x01")).is_none());
+        assert!(parse_packet(&[1, 1, 6]).is_none());
+    }
+
+    #[test]
+    fn test_identify_by_parameter_list() {
+        let iphone = DhcpFingerprint {
+            mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            hostname: None,
+            parameter_list: vec![1, 121, 3, 6, 15, 108, 114, 119, 252],
+            vendor_class: None,
+        };
+        assert_eq!(identify(&iphone).map(|m| m.os), Some("iOS"));
+
+        let unknown = DhcpFingerprint { parameter_list: vec![1, 3, 6], ..iphone };
+        assert!(identify(&unknown).is_none());
+    }
+}
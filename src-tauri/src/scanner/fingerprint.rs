@@ -1,3 +1,7 @@
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
 use crate::scanner::port::PortResult;
 
 /// OS fingerprinting result.
@@ -7,208 +11,314 @@ pub struct OsGuess {
     pub confidence: f64,
 }
 
-/// Guess the OS based on open port signatures.
-/// Uses well-known port patterns to infer the operating system.
-pub fn guess_os(ports: &[PortResult], vendor: Option<&str>) -> Option<OsGuess> {
-    let open_ports: Vec<u16> = ports.iter().map(|p| p.port).collect();
-
-    // iOS: port 62078 (iphone-sync / lockdownd)
-    if open_ports.contains(&62078) {
-        return Some(OsGuess {
-            os: "iOS".to_string(),
-            confidence: 0.85,
-        });
-    }
-
-    // macOS: AFP (548) or AirDrop-related (5353 + high port)
-    if open_ports.contains(&548) {
-        return Some(OsGuess {
-            os: "macOS".to_string(),
-            confidence: 0.80,
-        });
-    }
-
-    // Windows: SMB (445) + RPC (135)
-    if open_ports.contains(&445) && open_ports.contains(&135) {
-        return Some(OsGuess {
-            os: "Windows".to_string(),
-            confidence: 0.85,
-        });
-    }
-
-    // Windows: just SMB (445) without Linux indicators
-    if open_ports.contains(&445) && !open_ports.contains(&22) {
-        return Some(OsGuess {
-            os: "Windows".to_string(),
-            confidence: 0.60,
-        });
-    }
-
-    // Linux: SSH (22) without Windows indicators
-    if open_ports.contains(&22) && !open_ports.contains(&445) && !open_ports.contains(&135) {
-        return Some(OsGuess {
-            os: "Linux".to_string(),
-            confidence: 0.55,
-        });
-    }
-
-    // Printer: IPP (631) or JetDirect (9100)
-    if open_ports.contains(&631) || open_ports.contains(&9100) {
-        // Could be any OS, but printers often have their own firmware
-        return Some(OsGuess {
-            os: "Printer firmware".to_string(),
-            confidence: 0.70,
-        });
-    }
-
-    // Router/AP: HTTP (80) + HTTPS (443) with no SSH and few other ports
-    if open_ports.contains(&80) && open_ports.len() <= 3 {
-        if let Some(v) = vendor {
-            let v_lower = v.to_lowercase();
-            if v_lower.contains("ubiquiti") || v_lower.contains("mikrotik")
-                || v_lower.contains("cisco") || v_lower.contains("netgear")
-                || v_lower.contains("tp-link") || v_lower.contains("asus")
-                || v_lower.contains("linksys") || v_lower.contains("arris")
-            {
-                return Some(OsGuess {
-                    os: "Router firmware".to_string(),
-                    confidence: 0.75,
-                });
-            }
+/// Confidence added when the reply TTL matches a guess's OS family.
+const TTL_AGREES: f64 = 0.10;
+/// Confidence removed when the reply TTL contradicts it.
+const TTL_DISAGREES: f64 = 0.20;
+/// Confidence of a Windows guess made from the TTL alone.
+const TTL_ONLY_WINDOWS: f64 = 0.40;
+const MAX_CONFIDENCE: f64 = 0.95;
+
+/// OS families told apart by the initial TTL of their IP packets. Hosts on the LAN
+/// are at most a few hops away, so the observed TTL sits just below the initial one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TtlFamily {
+    /// Linux, macOS, iOS, Android and most embedded Unix systems start at 64.
+    Unix,
+    /// Windows starts at 128.
+    Windows,
+    /// Routers and switches commonly start at 255.
+    NetworkGear,
+}
+
+impl TtlFamily {
+    fn from_ttl(ttl: u8) -> Self {
+        match ttl {
+            0..=64 => TtlFamily::Unix,
+            65..=128 => TtlFamily::Windows,
+            _ => TtlFamily::NetworkGear,
         }
     }
 
-    // Vendor-based fallback guesses
-    if let Some(v) = vendor {
-        let v_lower = v.to_lowercase();
-        if v_lower.contains("apple") {
-            return Some(OsGuess {
-                os: "macOS/iOS".to_string(),
-                confidence: 0.40,
-            });
+    /// The family a guessed OS belongs to, if it has a typical TTL.
+    fn of_os(os: &str) -> Option<Self> {
+        match os {
+            "iOS" | "macOS" | "macOS/iOS" | "Linux" | "Android" => Some(TtlFamily::Unix),
+            "Windows" => Some(TtlFamily::Windows),
+            "Router firmware" => Some(TtlFamily::NetworkGear),
+            _ => None,
         }
-        if v_lower.contains("samsung") || v_lower.contains("oneplus")
-            || v_lower.contains("xiaomi") || v_lower.contains("huawei")
-        {
-            return Some(OsGuess {
-                os: "Android".to_string(),
-                confidence: 0.50,
-            });
-        }
-        if v_lower.contains("microsoft") {
-            return Some(OsGuess {
-                os: "Windows".to_string(),
-                confidence: 0.45,
-            });
+    }
+}
+
+/// Rules bundled with the app, used when `resources/fingerprints.json` is missing.
+const BUILTIN_RULES: &str = include_str!("../../resources/fingerprints.json");
+
+static ACTIVE_RULES: OnceLock<Ruleset> = OnceLock::new();
+
+/// Conditions a rule needs; every non-empty one must hold. Vendor and OS patterns
+/// are case-insensitive substrings, service types exact (e.g. `_ipp._tcp`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct Conditions {
+    /// At least one of these ports is open.
+    any_ports: Vec<u16>,
+    /// Every one of these ports is open.
+    all_ports: Vec<u16>,
+    /// None of these ports is open.
+    no_ports: Vec<u16>,
+    max_ports: Option<usize>,
+    min_ports: Option<usize>,
+    vendors: Vec<String>,
+    /// At least one of these mDNS service types was advertised.
+    services: Vec<String>,
+    /// The OS guess contains one of these.
+    os: Vec<String>,
+    /// The OS guess contains none of these.
+    not_os: Vec<String>,
+}
+
+/// Facts about one device that rules are matched against.
+struct Observed<'a> {
+    ports: Vec<u16>,
+    vendor: String,
+    os: Option<String>,
+    services: &'a [String],
+}
+
+impl<'a> Observed<'a> {
+    fn new(ports: &[PortResult], vendor: Option<&str>, os: Option<&str>, services: &'a [String]) -> Self {
+        Self {
+            ports: ports.iter().map(|p| p.port).collect(),
+            vendor: vendor.map(|v| v.to_lowercase()).unwrap_or_default(),
+            os: os.map(|o| o.to_lowercase()),
+            services,
         }
-        if v_lower.contains("raspberry") {
-            return Some(OsGuess {
-                os: "Linux".to_string(),
-                confidence: 0.70,
-            });
+    }
+}
+
+impl Conditions {
+    fn lowercase(&mut self) {
+        for pattern in self.vendors.iter_mut().chain(&mut self.os).chain(&mut self.not_os) {
+            *pattern = pattern.to_lowercase();
         }
     }
 
-    None
+    fn matches(&self, seen: &Observed) -> bool {
+        let os = seen.os.as_deref().unwrap_or_default();
+        (self.any_ports.is_empty() || self.any_ports.iter().any(|p| seen.ports.contains(p)))
+            && self.all_ports.iter().all(|p| seen.ports.contains(p))
+            && !self.no_ports.iter().any(|p| seen.ports.contains(p))
+            && self.max_ports.is_none_or(|max| seen.ports.len() <= max)
+            && self.min_ports.is_none_or(|min| seen.ports.len() >= min)
+            && (self.vendors.is_empty() || self.vendors.iter().any(|v| seen.vendor.contains(v.as_str())))
+            && (self.services.is_empty() || seen.services.iter().any(|s| self.services.contains(s)))
+            && (self.os.is_empty() || (seen.os.is_some() && self.os.iter().any(|o| os.contains(o.as_str()))))
+            && !self.not_os.iter().any(|o| os.contains(o.as_str()))
+    }
 }
 
-/// Classify device type based on vendor, ports, and OS guess.
-pub fn classify_device(
-    ports: &[PortResult],
-    vendor: Option<&str>,
-    os_guess: Option<&str>,
-    is_gateway: bool,
-) -> &'static str {
-    if is_gateway {
-        return "router";
-    }
+#[derive(Debug, Clone, Deserialize)]
+struct OsRule {
+    os: String,
+    confidence: f64,
+    #[serde(flatten)]
+    when: Conditions,
+}
 
-    let open_ports: Vec<u16> = ports.iter().map(|p| p.port).collect();
+#[derive(Debug, Clone, Deserialize)]
+struct TypeRule {
+    device_type: String,
+    #[serde(flatten)]
+    when: Conditions,
+}
 
-    // Printer detection
-    if open_ports.contains(&9100) || open_ports.contains(&631) {
-        if let Some(v) = vendor {
-            let v_lower = v.to_lowercase();
-            if v_lower.contains("hp") || v_lower.contains("epson")
-                || v_lower.contains("canon") || v_lower.contains("brother")
-                || v_lower.contains("xerox") || v_lower.contains("lexmark")
-            {
-                return "printer";
-            }
+/// Port, vendor and service signatures behind `guess_os` and `classify_device`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ruleset {
+    /// Every matching rule proposes its OS.
+    os_rules: Vec<OsRule>,
+    /// The first matching rule decides the device type.
+    type_rules: Vec<TypeRule>,
+}
+
+impl Ruleset {
+    /// Load rules from the bundled JSON resource, falling back to the built-in
+    /// copy when the file is missing.
+    pub fn load(app: &tauri::AppHandle) -> Result<Self, Box<dyn std::error::Error>> {
+        use tauri::Manager;
+        let resource_path = app
+            .path()
+            .resource_dir()
+            .map_err(|e| format!("Failed to get resource dir: {}", e))?
+            .join("resources")
+            .join("fingerprints.json");
+
+        if !resource_path.exists() {
+            log::warn!("Fingerprint rules not found at {}", resource_path.display());
+            return Ok(Self::builtin());
         }
-        return "printer";
+
+        let rules = Self::from_json(&std::fs::read_to_string(&resource_path)?)?;
+        log::info!(
+            "Loaded {} OS and {} device type fingerprint rules",
+            rules.os_rules.len(),
+            rules.type_rules.len()
+        );
+        Ok(rules)
     }
 
-    // Phone detection
-    if let Some(os) = os_guess {
-        let os_lower = os.to_lowercase();
-        if os_lower.contains("ios") || os_lower.contains("android") {
-            return "phone";
+    /// Parse a ruleset. Its rules replace the built-in ones entirely.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let mut rules: Self = serde_json::from_str(json)?;
+        for rule in &mut rules.os_rules {
+            rule.when.lowercase();
         }
+        for rule in &mut rules.type_rules {
+            rule.when.lowercase();
+        }
+        Ok(rules)
     }
-    if open_ports.contains(&62078) {
-        return "phone";
+
+    /// The rules compiled into the app.
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_RULES).expect("built-in fingerprint rules are valid")
     }
 
-    // Media device detection
-    if let Some(v) = vendor {
-        let v_lower = v.to_lowercase();
-        if v_lower.contains("sonos") || v_lower.contains("roku")
-            || v_lower.contains("amazon") || v_lower.contains("google")
-            || v_lower.contains("chromecast")
-        {
-            // Could be media device (smart speaker, streaming stick)
-            if open_ports.len() <= 5 {
-                return "media";
-            }
-        }
+    /// Make these the rules `guess_os` and `classify_device` use. Only the first
+    /// call takes effect; returns false if rules were already in place.
+    pub fn install(self) -> bool {
+        ACTIVE_RULES.set(self).is_ok()
+    }
 
-        // IoT detection
-        if v_lower.contains("espressif") || v_lower.contains("tuya")
-            || v_lower.contains("shenzhen") || v_lower.contains("wemo")
-            || v_lower.contains("nest") || v_lower.contains("ring")
-            || v_lower.contains("wyze") || v_lower.contains("lifx")
-        {
-            return "iot";
-        }
+    /// The installed rules, or the built-in ones if none were installed.
+    fn active() -> &'static Ruleset {
+        ACTIVE_RULES.get_or_init(Self::builtin)
+    }
 
-        // Router/AP detection by vendor
-        if v_lower.contains("ubiquiti") || v_lower.contains("mikrotik")
-            || v_lower.contains("cisco") || v_lower.contains("netgear")
-            || v_lower.contains("tp-link") || v_lower.contains("linksys")
-            || v_lower.contains("arris") || v_lower.contains("asus")
-        {
-            if open_ports.contains(&80) || open_ports.contains(&443) {
-                return "router";
+    /// Every OS suggested by open port signatures and the vendor, in rule order.
+    fn candidates(&self, ports: &[PortResult], vendor: Option<&str>) -> Vec<OsGuess> {
+        let seen = Observed::new(ports, vendor, None, &[]);
+        self.os_rules
+            .iter()
+            .filter(|rule| rule.when.matches(&seen))
+            .map(|rule| OsGuess {
+                os: rule.os.clone(),
+                confidence: rule.confidence,
+            })
+            .collect()
+    }
+
+    /// Guess the OS from open port signatures, the MAC vendor and, when the host
+    /// answered a ping, the reply TTL.
+    ///
+    /// Every matching rule proposes a candidate. The TTL then raises candidates of the
+    /// matching OS family and lowers the rest, which also breaks ties; a Windows TTL
+    /// proposes Windows by itself, since 64 and 255 are shared by too many systems to
+    /// name one. The most confident candidate wins, earlier rules on a tie.
+    pub fn guess_os(&self, ports: &[PortResult], vendor: Option<&str>, ttl: Option<u8>) -> Option<OsGuess> {
+        let mut found = self.candidates(ports, vendor);
+
+        if let Some(family) = ttl.map(TtlFamily::from_ttl) {
+            for guess in &mut found {
+                match TtlFamily::of_os(&guess.os) {
+                    Some(f) if f == family => guess.confidence = (guess.confidence + TTL_AGREES).min(MAX_CONFIDENCE),
+                    Some(_) => guess.confidence = (guess.confidence - TTL_DISAGREES).max(0.0),
+                    None => {}
+                }
+            }
+            if family == TtlFamily::Windows && !found.iter().any(|g| g.os == "Windows") {
+                found.push(OsGuess {
+                    os: "Windows".to_string(),
+                    confidence: TTL_ONLY_WINDOWS,
+                });
             }
         }
-    }
 
-    // Computer detection (SSH, RDP, or many open ports)
-    if open_ports.contains(&22) || open_ports.contains(&3389) || open_ports.contains(&548)
-        || open_ports.contains(&445) || open_ports.len() >= 5
-    {
-        return "computer";
+        found
+            .into_iter()
+            .reduce(|best, guess| if guess.confidence > best.confidence { guess } else { best })
     }
 
-    if let Some(os) = os_guess {
-        let os_lower = os.to_lowercase();
-        if os_lower.contains("windows") || os_lower.contains("macos") || os_lower.contains("linux") {
-            return "computer";
+    /// Device type from the first matching rule, or "unknown". A gateway is always a router.
+    pub fn classify_device(
+        &self,
+        ports: &[PortResult],
+        vendor: Option<&str>,
+        os_guess: Option<&str>,
+        services: &[String],
+        is_gateway: bool,
+    ) -> &str {
+        if is_gateway {
+            return "router";
         }
+
+        let seen = Observed::new(ports, vendor, os_guess, services);
+        self.type_rules
+            .iter()
+            .find(|rule| rule.when.matches(&seen))
+            .map_or("unknown", |rule| rule.device_type.as_str())
+    }
+}
+
+/// Guess the OS with the installed fingerprint rules; see `Ruleset::guess_os`.
+pub fn guess_os(ports: &[PortResult], vendor: Option<&str>, ttl: Option<u8>) -> Option<OsGuess> {
+    Ruleset::active().guess_os(ports, vendor, ttl)
+}
+
+/// Classify device type based on vendor, ports, OS guess, and the service types
+/// the device advertised over mDNS, with the installed fingerprint rules.
+pub fn classify_device(
+    ports: &[PortResult],
+    vendor: Option<&str>,
+    os_guess: Option<&str>,
+    services: &[String],
+    is_gateway: bool,
+) -> &'static str {
+    Ruleset::active().classify_device(ports, vendor, os_guess, services, is_gateway)
+}
+
+/// MAC prefixes assigned to hypervisors and container runtimes.
+const VIRTUAL_MAC_PREFIXES: &[&str] = &[
+    "00:05:69", "00:0C:29", "00:1C:14", "00:50:56", // VMware
+    "08:00:27", "0A:00:27",                         // VirtualBox
+    "00:15:5D",                                     // Hyper-V
+    "52:54:00",                                     // QEMU/KVM
+    "00:16:3E",                                     // Xen
+    "00:1C:42",                                     // Parallels
+    "02:42",                                        // Docker bridge
+];
+
+/// Whether a MAC address belongs to a known virtualization vendor.
+pub fn is_virtual_mac(mac: &str) -> bool {
+    let mac = mac.to_uppercase().replace('-', ":");
+    VIRTUAL_MAC_PREFIXES.iter().any(|prefix| mac.starts_with(prefix))
+}
+
+/// Classify how a device is attached: "virtual", "wireless", "wired" or "unknown".
+/// A virtualization MAC wins; otherwise the medium of the interface it was seen on.
+pub fn classify_connection(mac: Option<&str>, interface_medium: Option<&str>) -> &'static str {
+    if mac.is_some_and(is_virtual_mac) {
+        return "virtual";
     }
 
-    "unknown"
+    match interface_medium {
+        Some("virtual") => "virtual",
+        Some("wireless") => "wireless",
+        Some("wired") => "wired",
+        _ => "unknown",
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::scanner::port::{PortResult, PortState};
+    use crate::scanner::port::{PortResult, PortState, Protocol};
 
     fn port(p: u16) -> PortResult {
         PortResult {
             port: p,
+            protocol: Protocol::Tcp,
             state: PortState::Open,
             service_name: None,
             banner: None,
@@ -218,7 +328,7 @@ mod tests {
     #[test]
     fn test_ios_detection() {
         let ports = vec![port(62078)];
-        let guess = guess_os(&ports, None).unwrap();
+        let guess = guess_os(&ports, None, None).unwrap();
         assert_eq!(guess.os, "iOS");
         assert!(guess.confidence >= 0.8);
     }
@@ -226,50 +336,139 @@ mod tests {
     #[test]
     fn test_windows_detection() {
         let ports = vec![port(135), port(445), port(139)];
-        let guess = guess_os(&ports, None).unwrap();
+        let guess = guess_os(&ports, None, None).unwrap();
         assert_eq!(guess.os, "Windows");
     }
 
     #[test]
     fn test_macos_detection() {
         let ports = vec![port(548), port(22)];
-        let guess = guess_os(&ports, None).unwrap();
+        let guess = guess_os(&ports, None, None).unwrap();
         assert_eq!(guess.os, "macOS");
     }
 
     #[test]
     fn test_linux_detection() {
         let ports = vec![port(22), port(80)];
-        let guess = guess_os(&ports, None).unwrap();
+        let guess = guess_os(&ports, None, None).unwrap();
         assert_eq!(guess.os, "Linux");
     }
 
+    #[test]
+    fn test_ttl_adjusts_ssh_guess() {
+        let ports = vec![port(22)];
+
+        let unix = guess_os(&ports, None, Some(64)).unwrap();
+        assert_eq!(unix.os, "Linux");
+        assert!(unix.confidence > guess_os(&ports, None, None).unwrap().confidence);
+
+        // OpenSSH on Windows: the TTL outweighs the lone SSH port
+        let windows = guess_os(&ports, None, Some(128)).unwrap();
+        assert_eq!(windows.os, "Windows");
+        assert!(windows.confidence < unix.confidence);
+    }
+
+    #[test]
+    fn test_ttl_breaks_tie_between_port_guesses() {
+        let ports = vec![port(62078), port(135), port(445)];
+        assert_eq!(guess_os(&ports, None, None).unwrap().os, "iOS");
+        assert_eq!(guess_os(&ports, None, Some(127)).unwrap().os, "Windows");
+        assert_eq!(guess_os(&ports, None, Some(63)).unwrap().os, "iOS");
+    }
+
+    #[test]
+    fn test_ttl_alone() {
+        assert_eq!(guess_os(&[], None, Some(128)).unwrap().os, "Windows");
+        assert!(guess_os(&[], None, Some(64)).is_none());
+        assert!(guess_os(&[], None, Some(255)).is_none());
+
+        let printer = guess_os(&[port(9100)], None, Some(255)).unwrap();
+        assert_eq!(printer.os, "Printer firmware");
+        assert_eq!(printer.confidence, 0.70);
+    }
+
     #[test]
     fn test_classify_printer() {
         let ports = vec![port(9100), port(80)];
-        assert_eq!(classify_device(&ports, Some("HP Inc"), None, false), "printer");
+        assert_eq!(classify_device(&ports, Some("HP Inc"), None, &[], false), "printer");
     }
 
     #[test]
     fn test_classify_phone() {
         let ports = vec![port(62078)];
-        assert_eq!(classify_device(&ports, Some("Apple"), None, false), "phone");
+        assert_eq!(classify_device(&ports, Some("Apple"), None, &[], false), "phone");
     }
 
     #[test]
     fn test_classify_router() {
-        assert_eq!(classify_device(&[], None, None, true), "router");
+        assert_eq!(classify_device(&[], None, None, &[], true), "router");
     }
 
     #[test]
     fn test_classify_computer_by_os() {
         let ports = vec![];
-        assert_eq!(classify_device(&ports, None, Some("Windows"), false), "computer");
+        assert_eq!(classify_device(&ports, None, Some("Windows"), &[], false), "computer");
+    }
+
+    #[test]
+    fn test_classify_by_mdns_services() {
+        let services = |types: &[&str]| types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(classify_device(&[], None, None, &services(&["_ipp._tcp"]), false), "printer");
+        assert_eq!(classify_device(&[], None, None, &services(&["_airplay._tcp", "_raop._tcp"]), false), "media");
+        assert_eq!(classify_device(&[], Some("Espressif"), None, &services(&["_hap._tcp"]), false), "iot");
+
+        // A Mac receiving AirPlay is still a computer
+        let mac = classify_device(&[port(22)], Some("Apple"), Some("macOS"), &services(&["_airplay._tcp"]), false);
+        assert_eq!(mac, "computer");
+    }
+
+    #[test]
+    fn test_custom_ruleset_overrides_builtin() {
+        let rules = Ruleset::from_json(
+            r#"{
+                "os_rules": [{ "os": "FreeBSD", "confidence": 0.9, "all_ports": [22] }],
+                "type_rules": [{ "device_type": "nas", "vendors": ["Synology"] }]
+            }"#,
+        )
+        .unwrap();
+
+        let guess = rules.guess_os(&[port(22)], None, None).unwrap();
+        assert_eq!(guess.os, "FreeBSD");
+        assert_eq!(rules.classify_device(&[port(22)], Some("SYNOLOGY Inc."), None, &[], false), "nas");
+
+        // The file replaces the built-ins rather than adding to them
+        assert!(rules.guess_os(&[port(62078)], None, None).is_none());
+        assert_eq!(rules.classify_device(&[port(9100)], None, None, &[], false), "unknown");
+        assert_eq!(rules.classify_device(&[], None, None, &[], true), "router");
+    }
+
+    #[test]
+    fn test_ruleset_rejects_malformed_json() {
+        assert!(Ruleset::from_json(r#"{ "os_rules": [{ "os": "Linux" }], "type_rules": [] }"#).is_err());
+        assert!(Ruleset::from_json("not json").is_err());
     }
 
     #[test]
     fn test_vendor_android_guess() {
-        let guess = guess_os(&[], Some("Samsung Electronics")).unwrap();
+        let guess = guess_os(&[], Some("Samsung Electronics"), None).unwrap();
         assert_eq!(guess.os, "Android");
     }
+
+    #[test]
+    fn test_is_virtual_mac() {
+        assert!(is_virtual_mac("00:0c:29:12:34:56"));
+        assert!(is_virtual_mac("08-00-27-AA-BB-CC"));
+        assert!(is_virtual_mac("52:54:00:12:34:56"));
+        assert!(is_virtual_mac("02:42:ac:11:00:02"));
+        assert!(!is_virtual_mac("AA:BB:CC:DD:EE:FF"));
+    }
+
+    #[test]
+    fn test_classify_connection() {
+        assert_eq!(classify_connection(Some("00:50:56:01:02:03"), Some("wireless")), "virtual");
+        assert_eq!(classify_connection(Some("AA:BB:CC:DD:EE:FF"), Some("wireless")), "wireless");
+        assert_eq!(classify_connection(Some("AA:BB:CC:DD:EE:FF"), Some("wired")), "wired");
+        assert_eq!(classify_connection(None, Some("virtual")), "virtual");
+        assert_eq!(classify_connection(None, None), "unknown");
+    }
 }
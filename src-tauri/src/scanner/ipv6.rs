@@ -0,0 +1,98 @@
+use std::net::Ipv6Addr;
+
+use crate::network::resolver;
+
+/// Whether an address is reachable from outside the LAN (not link-local, ULA or loopback).
+pub fn is_global(ip: &str) -> bool {
+    let Ok(addr) = ip.parse::<Ipv6Addr>() else {
+        return false;
+    };
+    let first = addr.segments()[0];
+    let link_local = first & 0xffc0 == 0xfe80;
+    let unique_local = first & 0xfe00 == 0xfc00;
+    !(addr.is_loopback() || addr.is_unspecified() || addr.is_multicast() || link_local || unique_local)
+}
+
+/// `ip6.arpa` name for a reverse lookup of `addr`: its 32 nibbles, least significant first.
+pub fn reverse_name(addr: &Ipv6Addr) -> String {
+    let mut name = String::with_capacity(72);
+    for byte in addr.octets().iter().rev() {
+        name.push(char::from_digit((byte & 0x0f) as u32, 16).unwrap_or('0'));
+        name.push('.');
+        name.push(char::from_digit((byte >> 4) as u32, 16).unwrap_or('0'));
+        name.push('.');
+    }
+    name.push_str("ip6.arpa");
+    name
+}
+
+/// The MAC embedded in a SLAAC address's EUI-64 interface identifier, in the uppercase
+/// colon form devices are stored under. The identifier is the MAC with `ff:fe` inserted
+/// in the middle and the universal/local bit flipped. `None` when the marker is absent,
+/// as with privacy (temporary) and DHCPv6 addresses.
+pub fn mac_from_eui64(ipv6: &str) -> Option<String> {
+    let addr: Ipv6Addr = ipv6.parse().ok()?;
+    let iid = &addr.octets()[8..];
+    if iid[3] != 0xff || iid[4] != 0xfe {
+        return None;
+    }
+    let mac = [iid[0] ^ 0x02, iid[1], iid[2], iid[5], iid[6], iid[7]];
+    Some(
+        mac.iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// PTR lookup for a global IPv6 address through the same resolver as IPv4, i.e. a query
+/// for its `reverse_name`. Link-local and unique-local addresses have no public PTR
+/// records, so they are not looked up.
+pub async fn resolve_ipv6_hostname(ip: &str) -> Option<String> {
+    let addr: Ipv6Addr = ip.parse().ok()?;
+    if !is_global(ip) {
+        return None;
+    }
+    log::debug!("Querying PTR {}", reverse_name(&addr));
+    resolver::reverse_dns_only(&addr.to_string()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse_name() {
+        let addr: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        assert_eq!(
+            reverse_name(&addr),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa"
+        );
+    }
+
+    #[test]
+    fn test_mac_from_eui64() {
+        assert_eq!(mac_from_eui64("fe80::21a:2bff:fe3c:4d5e").as_deref(), Some("00:1A:2B:3C:4D:5E"));
+        // The U/L bit flips back: 0xba in the identifier is a universal 0xb8 OUI
+        assert_eq!(mac_from_eui64("2001:db8::ba27:ebff:fe12:3456").as_deref(), Some("B8:27:EB:12:34:56"));
+
+        // Privacy addresses, IPv4 and junk carry no MAC
+        assert!(mac_from_eui64("2001:db8::1c3e:9a41:77d2:5f10").is_none());
+        assert!(mac_from_eui64("192.168.1.10").is_none());
+        assert!(mac_from_eui64("not an address").is_none());
+    }
+
+    #[test]
+    fn test_is_global() {
+        assert!(is_global("2001:db8::20"));
+        for local in ["fe80::1", "fd12:3456::1", "::1", "::", "ff02::1", "192.168.1.1"] {
+            assert!(!is_global(local), "{}", local);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_addresses_not_looked_up() {
+        assert!(resolve_ipv6_hostname("fe80::1").await.is_none());
+        assert!(resolve_ipv6_hostname("192.168.1.1").await.is_none());
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::network::mdns::{build_query, read_name, read_u16, MDNS_ADDR, TYPE_PTR};
+
+/// DNS-SD meta-query: every responder lists the service types it offers (RFC 6763 §9).
+const SERVICES_QUERY: &str = "_services._dns-sd._udp.local";
+/// How long responses are collected after the query goes out.
+pub const DISCOVERY_WINDOW: Duration = Duration::from_millis(1500);
+
+const TYPE_A: u16 = 1;
+const TYPE_SRV: u16 = 33;
+
+/// What one device advertised over mDNS.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MdnsDevice {
+    pub ip: String,
+    /// The responder's `.local` name, from its address or SRV records.
+    pub hostname: Option<String>,
+    /// Service types without the domain, e.g. `_ipp._tcp`.
+    pub service_types: Vec<String>,
+}
+
+/// `_ipp._tcp.local` → `_ipp._tcp`; `None` for names that are not service types.
+fn service_type(name: &str) -> Option<String> {
+    let name = name.trim_end_matches('.');
+    let base = name.strip_suffix(".local").unwrap_or(name);
+    let mut labels = base.rsplit('.');
+    let proto = labels.next()?;
+    let service = labels.next()?;
+    if !(proto == "_tcp" || proto == "_udp") || !service.starts_with('_') {
+        return None;
+    }
+    Some(format!("{}.{}", service, proto))
+}
+
+/// Read the records of one mDNS response sent by `from`. `None` unless the packet
+/// is a well-formed response.
+pub fn parse_response(packet: &[u8], from: Ipv4Addr) -> Option<MdnsDevice> {
+    let flags = read_u16(packet, 2)?;
+    if flags & 0x8000 == 0 {
+        return None; // a query, not a response
+    }
+    let questions = read_u16(packet, 4)?;
+    let records: u16 = [6, 8, 10]
+        .iter()
+        .map(|&at| read_u16(packet, at))
+        .sum::<Option<u16>>()?;
+
+    let mut device = MdnsDevice {
+        ip: from.to_string(),
+        ..Default::default()
+    };
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        let (_, next) = read_name(packet, pos)?;
+        pos = next + 4;
+    }
+    for _ in 0..records {
+        let (name, next) = read_name(packet, pos)?;
+        let rtype = read_u16(packet, next)?;
+        let rdlength = read_u16(packet, next + 8)? as usize;
+        let rdata = next + 10;
+        let data = packet.get(rdata..rdata + rdlength)?;
+
+        match rtype {
+            // Meta-query answers point at a service type; instance answers are named by one
+            TYPE_PTR => {
+                let listed = if name.eq_ignore_ascii_case(SERVICES_QUERY) {
+                    service_type(&read_name(packet, rdata)?.0)
+                } else {
+                    service_type(&name)
+                };
+                if let Some(t) = listed {
+                    if !device.service_types.contains(&t) {
+                        device.service_types.push(t);
+                    }
+                }
+            }
+            TYPE_SRV if rdlength > 6 => {
+                let (target, _) = read_name(packet, rdata + 6)?;
+                device.hostname.get_or_insert(target);
+            }
+            TYPE_A if data == from.octets() => {
+                device.hostname = Some(name);
+            }
+            _ => {}
+        }
+        pos = rdata + rdlength;
+    }
+
+    if let Some(host) = &mut device.hostname {
+        *host = host.trim_end_matches('.').to_string();
+    }
+    device.hostname = device.hostname.filter(|h| !h.is_empty());
+    Some(device)
+}
+
+/// Fold responses into one entry per address, keeping the first hostname seen.
+fn merge(responses: impl IntoIterator<Item = MdnsDevice>) -> Vec<MdnsDevice> {
+    let mut by_ip: BTreeMap<String, MdnsDevice> = BTreeMap::new();
+    for response in responses {
+        let entry = by_ip.entry(response.ip.clone()).or_insert_with(|| MdnsDevice {
+            ip: response.ip.clone(),
+            ..Default::default()
+        });
+        if entry.hostname.is_none() {
+            entry.hostname = response.hostname;
+        }
+        for t in response.service_types {
+            if !entry.service_types.contains(&t) {
+                entry.service_types.push(t);
+            }
+        }
+    }
+    by_ip.into_values().collect()
+}
+
+/// Multicast the DNS-SD meta-query and collect answers for `window`.
+/// Devices that publish nothing over mDNS simply do not appear.
+pub fn discover(window: Duration) -> Vec<MdnsDevice> {
+    let Ok(socket) = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) else {
+        return Vec::new();
+    };
+    if let Err(e) = socket.send_to(&build_query(SERVICES_QUERY), MDNS_ADDR) {
+        log::debug!("mDNS service query failed: {}", e);
+        return Vec::new();
+    }
+
+    let deadline = Instant::now() + window;
+    let mut buf = [0u8; 9000];
+    let mut responses = Vec::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        if socket.set_read_timeout(Some(remaining.max(Duration::from_millis(1)))).is_err() {
+            break;
+        }
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                if let IpAddr::V4(from) = from.ip() {
+                    responses.extend(parse_response(&buf[..len], from));
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    merge(responses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRINTER: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 23);
+
+    /// A printer's reply to the meta-query: two service types, plus an SRV and an
+    /// address record in the additional section, with name compression throughout.
+    const PRINTER_RESPONSE: &[u8] = b"\
+\x00\x00\x84\x00\x00\x00\x00\x02\x00\x00\x00\x02\
+\t_services\x07_dns-sd\x04_udp\x05local\x00\x00\x0c\x00\x01\x00\x00\x11\x94\x00\x0c\x04_ipp\x04_tcp\xc0#\
+\xc0\x0c\x00\x0c\x00\x01\x00\x00\x11\x94\x00\x12\x0f_pdl-datastream\xc09\
+\tOfficeJet\xc04\x00!\x80\x01\x00\x00\x00x\x00\x14\x00\x00\x00\x00\x02w\x0bofficejet-1\xc0#\
+\xc0z\x00\x01\x80\x01\x00\x00\x00x\x00\x04\xc0\xa8\x01\x17";
+
+    #[test]
+    fn test_parse_captured_response() {
+        let device = parse_response(PRINTER_RESPONSE, PRINTER).unwrap();
+        assert_eq!(device.ip, "192.168.1.23");
+        assert_eq!(device.hostname.as_deref(), Some("officejet-1.local"));
+        assert_eq!(device.service_types, ["_ipp._tcp", "_pdl-datastream._tcp"]);
+
+        // Truncated packets and our own query are rejected
+        assert!(parse_response(&PRINTER_RESPONSE[..PRINTER_RESPONSE.len() - 3], PRINTER).is_none());
+        assert!(parse_response(&build_query(SERVICES_QUERY), PRINTER).is_none());
+    }
+
+    #[test]
+    fn test_address_record_for_another_host_is_not_its_name() {
+        let device = parse_response(PRINTER_RESPONSE, Ipv4Addr::new(192, 168, 1, 99)).unwrap();
+        // The SRV target still names the responder
+        assert_eq!(device.hostname.as_deref(), Some("officejet-1.local"));
+    }
+
+    #[test]
+    fn test_service_type() {
+        assert_eq!(service_type("_airplay._tcp.local").as_deref(), Some("_airplay._tcp"));
+        assert_eq!(service_type("Living Room._googlecast._tcp.local.").as_deref(), Some("_googlecast._tcp"));
+        assert!(service_type("macbook.local").is_none());
+        assert!(service_type("_tcp.local").is_none());
+    }
+
+    #[test]
+    fn test_merge_per_address() {
+        let a = |host: Option<&str>, types: &[&str]| MdnsDevice {
+            ip: "10.0.0.5".to_string(),
+            hostname: host.map(|h| h.to_string()),
+            service_types: types.iter().map(|t| t.to_string()).collect(),
+        };
+        let merged = merge([a(None, &["_airplay._tcp"]), a(Some("tv.local"), &["_airplay._tcp", "_raop._tcp"])]);
+        assert_eq!(merged, [a(Some("tv.local"), &["_airplay._tcp", "_raop._tcp"])]);
+    }
+}
@@ -1,11 +1,23 @@
+pub mod budget;
+pub mod dhcp;
 pub mod fingerprint;
+pub mod ipv6;
+pub mod mdns;
 pub mod orchestrator;
 pub mod passive;
 pub mod ping;
 pub mod port;
+pub mod probe;
+pub mod progress;
+pub mod queue;
+pub mod scan_log;
+pub mod services;
 
 use serde::{Deserialize, Serialize};
 
+use crate::network::ipmath;
+use crate::util::command::{self, CommandTemplate, SharedRunner};
+
 /// A device discovered during a scan (raw scan result before DB enrichment).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscoveredDevice {
@@ -13,6 +25,81 @@ pub struct DiscoveredDevice {
     pub mac: Option<String>,
     pub hostname: Option<String>,
     pub is_gateway: bool,
+    /// Local interface the neighbor entry was learned on (e.g. `en0`, `wlan0`).
+    #[serde(default)]
+    pub interface: Option<String>,
+}
+
+/// External programs used for pings and neighbor table reads; see the
+/// `ping_command` and `arp_command` settings.
+#[derive(Debug, Clone)]
+pub struct ScanTools {
+    pub ping: ping::PingTool,
+    /// Replacement for the platform neighbor table command. `None` uses the built-in one.
+    pub arp: Option<CommandTemplate>,
+    /// Runs every external program above; a mock in tests.
+    pub runner: SharedRunner,
+    /// Ports tried when a host ignores ICMP. Empty turns the TCP fallback off.
+    pub tcp_ping_ports: Vec<u16>,
+}
+
+impl Default for ScanTools {
+    fn default() -> Self {
+        Self {
+            ping: ping::PingTool::default(),
+            arp: None,
+            runner: command::system_runner(),
+            tcp_ping_ports: ping::TCP_PING_PORTS.to_vec(),
+        }
+    }
+}
+
+/// How a device made itself known during a scan, stored as tags on the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoverySource {
+    /// IPv4 entry in the ARP table.
+    Arp,
+    /// IPv6 entry in the neighbor table.
+    Ipv6Neighbor,
+    /// Answered the ICMP ping sweep.
+    Ping,
+    /// Answered a TCP connect probe while departure was being confirmed.
+    TcpProbe,
+    /// Answered the mDNS service discovery query.
+    Mdns,
+}
+
+impl DiscoverySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscoverySource::Arp => "arp",
+            DiscoverySource::Ipv6Neighbor => "ipv6_neighbor",
+            DiscoverySource::Ping => "ping",
+            DiscoverySource::TcpProbe => "tcp_probe",
+            DiscoverySource::Mdns => "mdns",
+        }
+    }
+
+    /// Neighbor table a discovered address came from.
+    pub fn neighbor_table(ip: &str) -> Self {
+        if ip.contains(':') {
+            DiscoverySource::Ipv6Neighbor
+        } else {
+            DiscoverySource::Arp
+        }
+    }
+
+    /// Tags for a device that answered a departure probe.
+    pub fn from_probe(ip: &str, methods: &[probe::ProbeMethod]) -> Vec<Self> {
+        methods
+            .iter()
+            .map(|m| match m {
+                probe::ProbeMethod::Icmp => DiscoverySource::Ping,
+                probe::ProbeMethod::Tcp => DiscoverySource::TcpProbe,
+                probe::ProbeMethod::Arp => Self::neighbor_table(ip),
+            })
+            .collect()
+    }
 }
 
 /// Scan configuration passed from the frontend.
@@ -22,6 +109,26 @@ pub struct ScanConfig {
     pub interface_id: String,
     pub scan_type: ScanType,
     pub port_range: PortRange,
+    /// Transports the port scan phase covers. TCP only unless UDP is asked for.
+    #[serde(default = "default_protocols")]
+    pub protocols: Vec<port::Protocol>,
+    /// Which addresses to look for devices on.
+    #[serde(default)]
+    pub target: ScanTarget,
+    /// Phases to run. `None` runs the scan type's usual phases.
+    #[serde(default)]
+    pub phases: Option<ScanPhases>,
+}
+
+impl ScanConfig {
+    /// The phases this scan runs.
+    pub fn phases(&self) -> ScanPhases {
+        self.phases.unwrap_or_else(|| ScanPhases::for_scan_type(&self.scan_type))
+    }
+}
+
+fn default_protocols() -> Vec<port::Protocol> {
+    vec![port::Protocol::Tcp]
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +148,69 @@ pub enum PortRange {
     Custom(Vec<u16>),
 }
 
+/// Addresses a scan covers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScanTarget {
+    /// Whatever the neighbor table holds, after sweeping the interface's subnet.
+    #[default]
+    AutoSubnet,
+    /// Every usable host in an IPv4 CIDR block, e.g. `10.0.8.0/24`.
+    Cidr(String),
+    /// Specific IPv4 addresses.
+    IpList(Vec<String>),
+}
+
+impl ScanTarget {
+    /// The hosts to sweep, or `None` for `AutoSubnet`. Errors on malformed input
+    /// or a range larger than `ipmath::MAX_TARGET_HOSTS`.
+    pub fn hosts(&self) -> Result<Option<Vec<String>>, String> {
+        match self {
+            ScanTarget::AutoSubnet => Ok(None),
+            ScanTarget::Cidr(cidr) => ipmath::expand_cidr(cidr).map(Some),
+            ScanTarget::IpList(ips) => ipmath::parse_ip_list(ips).map(Some),
+        }
+    }
+}
+
+/// Scan phases that can be switched off individually.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanPhases {
+    /// Address sweep and neighbor table read; every later phase works on what it finds.
+    pub discovery: bool,
+    /// Latency and TTL of each discovered host.
+    pub ping: bool,
+    /// mDNS and reverse DNS hostname lookups.
+    pub resolve: bool,
+    pub ports: bool,
+    /// OS and device type guesses, including DHCP fingerprinting.
+    pub fingerprint: bool,
+    pub alerts: bool,
+}
+
+impl ScanPhases {
+    pub fn for_scan_type(scan_type: &ScanType) -> Self {
+        let full = matches!(scan_type, ScanType::Full);
+        Self {
+            discovery: true,
+            ping: !matches!(scan_type, ScanType::Passive),
+            resolve: true,
+            ports: full,
+            fingerprint: full,
+            alerts: true,
+        }
+    }
+
+    /// Errors when a phase is enabled without one it depends on.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ports && !self.discovery {
+            return Err("the port scan needs discovery to find hosts".to_string());
+        }
+        Ok(())
+    }
+}
+
 /// Result of a completed scan.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -50,3 +220,25 @@ pub struct ScanResult {
     pub new_devices: u32,
     pub duration_ms: u64,
 }
+
+/// What `start_scan` returns: the finished scan, or its place in the queue when scans
+/// are queued. A queued scan's result arrives later as `scan:completed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum ScanStart {
+    Completed(ScanResult),
+    Queued(queue::ScanQueued),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_scan_requires_discovery() {
+        let phases = ScanPhases::for_scan_type(&ScanType::Full);
+        assert!(phases.validate().is_ok());
+        assert!(ScanPhases { discovery: false, ..phases }.validate().is_err());
+        assert!(ScanPhases { discovery: false, ports: false, ..phases }.validate().is_ok());
+    }
+}
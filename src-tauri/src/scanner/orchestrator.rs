@@ -1,25 +1,26 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::time::Instant;
 
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio_util::sync::CancellationToken;
 
 use crate::alerts::{engine as alert_engine, notifier};
-use crate::db::queries::{devices as db_devices, ports as db_ports, scans as db_scans};
-use crate::network::resolver;
+use crate::db::queries::{
+    devices as db_devices, ports as db_ports, scans as db_scans, settings as db_settings,
+};
+use crate::network::{interface, ipmath, resolver};
 use crate::scanner::{
-    fingerprint, passive, ping, port, PortRange, ScanConfig, ScanResult, ScanType,
+    dhcp, fingerprint, mdns, passive, ping, port, probe, scan_log, DiscoveredDevice,
+    DiscoverySource, PortRange, ScanConfig, ScanPhases, ScanResult, ScanType,
 };
+use crate::scanner::budget::ScanBudget;
+use crate::scanner::port::Protocol;
+use crate::scanner::progress::{Phase, PortProgress, ProgressPlan, ProgressSink, ScanProgress};
+use crate::scanner::scan_log::ScanLog;
+use crate::security::risk;
 use crate::state::AppState;
-
-/// Progress update sent to the frontend during a scan.
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ScanProgress {
-    scan_id: String,
-    phase: String,
-    devices_found: u32,
-    percent_complete: f64,
-}
+use crate::{AppError, TauriResult};
 
 /// Run a scan based on the provided configuration.
 /// Supports cancellation via the provided CancellationToken.
@@ -28,83 +29,302 @@ pub async fn run_scan(
     state: &AppState,
     config: ScanConfig,
     cancel: CancellationToken,
-) -> Result<ScanResult, String> {
+) -> TauriResult<ScanResult> {
     let scan_id = uuid::Uuid::new_v4().to_string();
     let start = Instant::now();
+    // One snapshot for the whole scan, so a change made mid-scan can't apply to some phases only
+    let settings = {
+        let conn = state.conn()?;
+        db_settings::get_settings(&conn)?
+    };
+    let mut scan_log = ScanLog::open(scan_log_dir(&app, &settings).as_deref(), &scan_id);
+    scan_log.line(format_args!(
+        "scan {} started: type={}, interface={}, ports={:?}",
+        scan_id,
+        scan_type_str(&config.scan_type),
+        config.interface_id,
+        config.port_range
+    ));
 
     // Snapshot previous device state for alert diffing
     let previous_devices = {
-        let conn = state.conn().map_err(|e| e.to_string())?;
-        db_devices::get_all_devices(&conn).map_err(|e| e.to_string())?
+        let conn = state.conn()?;
+        db_devices::get_all_devices(&conn)?
     };
 
     // Record scan start
     {
-        let conn = state.conn().map_err(|e| e.to_string())?;
+        let conn = state.conn()?;
         db_scans::create_scan(
             &conn,
             &scan_id,
             Some(&config.interface_id),
             &scan_type_str(&config.scan_type),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
 
     emit_progress(&app, &scan_id, "discovery", 0, 0.0);
 
     // Check cancellation between phases
     if cancel.is_cancelled() {
-        return fail_scan(state, &scan_id, "Scan cancelled");
+        return cancel_scan(state, &mut scan_log, &scan_id);
+    }
+
+    let tools = settings.scan_tools();
+    // Every phase draws from one budget so sockets and child processes stay bounded
+    let budget = ScanBudget::new(settings.scan_concurrency);
+    let (ping_timeout_ms, sampling) = (settings.ping_timeout_ms, settings.latency_sampling());
+
+    let interfaces = tokio::task::spawn_blocking(interface::get_interfaces)
+        .await
+        .unwrap_or_default();
+    let chosen = match config.interface_id.as_str() {
+        "auto" => interfaces
+            .iter()
+            .find(|i| i.is_active && !i.is_vpn && i.ip_address.is_some())
+            .or_else(|| interfaces.iter().find(|i| i.is_active && i.ip_address.is_some())),
+        id => interfaces.iter().find(|i| i.id == id),
+    };
+
+    let phases = config.phases();
+    if let Err(e) = phases.validate() {
+        return fail_scan(&app, state, &mut scan_log, &scan_id, AppError::validation("phases", &e));
     }
+    let targets = match config.target.hosts() {
+        Ok(targets) => targets,
+        Err(e) => return fail_scan(&app, state, &mut scan_log, &scan_id, AppError::validation("target", &e)),
+    };
 
-    // Phase 1: Device discovery (passive ARP table scan)
-    let discovered = passive::scan_arp_table();
+    // Phase 1a: Ping every address on the subnet, or in the requested range, so
+    // silent hosts land in the ARP cache
+    let mut responders = Vec::new();
+    let sweep = match targets {
+        _ if !phases.discovery => None,
+        Some(ref hosts) if !matches!(config.scan_type, ScanType::Passive) => Some(hosts.clone()),
+        Some(_) => None,
+        None if matches!(config.scan_type, ScanType::Quick | ScanType::Full) => Some(
+            chosen
+                .and_then(|i| {
+                    let (ip, mask) = (i.ip_address.as_deref()?, i.subnet_mask.as_deref()?);
+                    Some(ipmath::enumerate_subnet_hosts(ip, mask))
+                })
+                .unwrap_or_default(),
+        ),
+        None => None,
+    };
+    if let Some(hosts) = sweep {
+        if !hosts.is_empty() {
+            let single = ping::LatencySampling::SINGLE;
+            let results = match ping::ping_sweep(&hosts, &budget, ping_timeout_ms, single, &tools, &cancel).await {
+                Ok(results) => results,
+                Err(e) => return fail_scan(&app, state, &mut scan_log, &scan_id, e),
+            };
+            responders = results
+                .into_iter()
+                .filter(|(_, reply)| reply.is_some())
+                .map(|(ip, _)| ip)
+                .collect();
+            scan_log.line(format_args!("discovery: swept {} addresses, {} replied", hosts.len(), responders.len()));
+        }
+
+        if cancel.is_cancelled() {
+            return cancel_scan(state, &mut scan_log, &scan_id);
+        }
+    }
+
+    // Phase 1b: Device discovery (ARP table scan)
+    let mut discovered = if phases.discovery {
+        match passive::scan_arp_table_with(&tools) {
+            Ok(discovered) => discovered,
+            Err(e) => return fail_scan(&app, state, &mut scan_log, &scan_id, e),
+        }
+    } else {
+        scan_log.line("discovery: skipped");
+        Vec::new()
+    };
+
+    if let Some(ref hosts) = targets {
+        // An explicit range may be routed, so it is not scoped to an interface; hosts
+        // that answered without a neighbor entry are kept without a MAC
+        let before = discovered.len();
+        discovered = passive::scope_to_targets(discovered, hosts, &responders);
+        scan_log.line(format_args!(
+            "discovery: scoped to {} target addresses ({} hosts, {} neighbor entries)",
+            hosts.len(),
+            discovered.len(),
+            before
+        ));
+    } else if config.interface_id != "auto" {
+        // An explicitly chosen interface is scanned on its own subnet, whatever holds the default route
+        match chosen {
+            Some(iface) => {
+                let before = discovered.len();
+                discovered = passive::scope_to_interface(discovered, iface);
+                scan_log.line(format_args!(
+                    "discovery: scoped to {} ({} of {} entries)",
+                    iface.name,
+                    discovered.len(),
+                    before
+                ));
+            }
+            None => log::warn!("Interface {} not found; scanning all neighbors", config.interface_id),
+        }
+    }
+    // Departures are only judged among devices this scan looked for
+    let chosen_only = chosen.filter(|_| config.interface_id != "auto");
+    let scope = ScanScope::new(&phases, targets.as_deref(), chosen_only);
     let device_count = discovered.len() as u32;
 
-    emit_progress(&app, &scan_id, "discovery", device_count, 20.0);
+    scan_log.line(format_args!("discovery: {} hosts found", device_count));
+    for d in &discovered {
+        scan_log.line(format_args!(
+            "  host {} mac={} hostname={} gateway={}",
+            d.ip,
+            d.mac.as_deref().unwrap_or("-"),
+            d.hostname.as_deref().unwrap_or("-"),
+            d.is_gateway
+        ));
+    }
+
+    if phases.alerts {
+        let conn = state.conn()?;
+        match alert_engine::arp_spoof_alerts(&conn, &discovered) {
+            Ok(generated) if !generated.is_empty() => {
+                for alert in &generated {
+                    scan_log.line(format_args!("alert [{}] {}", alert.severity, alert.message));
+                    log::warn!("{}", alert.message);
+                    let _ = app.emit("alert:new", &alert_engine::AlertEvent::from(alert));
+                }
+                notifier::notify(&app, &generated);
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("ARP conflict check failed: {}", e),
+        }
+    }
+
+    let ports_to_scan = match config.port_range {
+        _ if !config.protocols.contains(&Protocol::Tcp) => Vec::new(),
+        PortRange::Top100 => port::top_100_ports(),
+        PortRange::Top1000 => port::top_1000_ports(),
+        PortRange::Custom(ref ports) => ports.clone(),
+    };
+    let udp_ports_to_scan = match config.port_range {
+        _ if !config.protocols.contains(&Protocol::Udp) => Vec::new(),
+        PortRange::Custom(ref ports) => ports.clone(),
+        _ => port::top_udp_ports(),
+    };
+    let plan = ProgressPlan::for_phases(
+        &phases,
+        discovered.len(),
+        ports_to_scan.len() + udp_ports_to_scan.len(),
+        budget.permits(),
+    );
+
+    emit_progress(&app, &scan_id, "discovery", device_count, plan.end(Phase::Discovery));
 
     if cancel.is_cancelled() {
-        return fail_scan(state, &scan_id, "Scan cancelled");
+        return cancel_scan(state, &mut scan_log, &scan_id);
     }
 
+    // Listen for DHCP client broadcasts while the full scan runs; the guard stops it on every exit
+    let dhcp_capture = phases.fingerprint.then(|| {
+        let stop = cancel.child_token();
+        (stop.clone().drop_guard(), tokio::spawn(dhcp::listen(stop)))
+    });
+
     // Phase 2: Ping sweep for latency (if not passive-only)
-    let ping_results = if !matches!(config.scan_type, ScanType::Passive) {
-        emit_progress(&app, &scan_id, "ping", device_count, 30.0);
+
+    let ping_results = if phases.ping {
+        emit_progress(&app, &scan_id, "ping", device_count, plan.start(Phase::Ping));
         let ips: Vec<String> = discovered.iter().map(|d| d.ip.clone()).collect();
-        ping::ping_sweep(&ips, 20).await
+        let results = match ping::ping_sweep(&ips, &budget, ping_timeout_ms, sampling, &tools, &cancel).await {
+            Ok(results) => results,
+            Err(e) => return fail_scan(&app, state, &mut scan_log, &scan_id, e),
+        };
+        for (ip, reply) in &results {
+            match reply {
+                Some(r) if r.method == probe::ProbeMethod::Tcp => {
+                    scan_log.line(format_args!("ping {} -> no ICMP reply, tcp connect {:.2}ms", ip, r.latency_ms))
+                }
+                Some(r) => match r.ttl {
+                    Some(ttl) => scan_log.line(format_args!("ping {} -> {:.2}ms ttl={}", ip, r.latency_ms, ttl)),
+                    None => scan_log.line(format_args!("ping {} -> {:.2}ms", ip, r.latency_ms)),
+                },
+                None => scan_log.line(format_args!("ping {} -> no reply", ip)),
+            }
+        }
+        results
     } else {
+        scan_log.line("ping: skipped");
         Vec::new()
     };
 
     if cancel.is_cancelled() {
-        return fail_scan(state, &scan_id, "Scan cancelled");
+        return cancel_scan(state, &mut scan_log, &scan_id);
     }
 
     // Phase 3: Hostname resolution (concurrent, 2s timeout per host)
-    emit_progress(&app, &scan_id, "resolving", device_count, 40.0);
-    let ips_for_resolve: Vec<String> = discovered
-        .iter()
-        .filter(|d| d.hostname.is_none())
-        .map(|d| d.ip.clone())
-        .collect();
+    if phases.resolve {
+        emit_progress(&app, &scan_id, "resolving", device_count, plan.start(Phase::Resolving));
+    }
+
+    // mDNS names and service types first; hosts it names skip reverse DNS
+    let mdns_devices = if phases.resolve && matches!(config.scan_type, ScanType::Quick | ScanType::Full) {
+        tokio::task::spawn_blocking(|| mdns::discover(mdns::DISCOVERY_WINDOW))
+            .await
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    for answer in &mdns_devices {
+        scan_log.line(format_args!(
+            "mdns {} -> {} [{}]",
+            answer.ip,
+            answer.hostname.as_deref().unwrap_or("(no name)"),
+            answer.service_types.join(", ")
+        ));
+        if let Some(device) = discovered.iter_mut().find(|d| d.ip == answer.ip) {
+            if device.hostname.is_none() {
+                device.hostname = answer.hostname.clone();
+            }
+        }
+    }
+    let ips_for_resolve = hosts_to_resolve(&phases, &discovered);
 
     let hostname_results = if !ips_for_resolve.is_empty() {
-        resolver::resolve_hostnames(&ips_for_resolve).await
+        resolver::resolve_hostnames(&ips_for_resolve, &budget).await
     } else {
         Vec::new()
     };
+    for (ip, hostname) in &hostname_results {
+        scan_log.line(format_args!(
+            "resolve {} -> {}",
+            ip,
+            hostname.as_deref().unwrap_or("(none)")
+        ));
+    }
 
     if cancel.is_cancelled() {
-        return fail_scan(state, &scan_id, "Scan cancelled");
+        return cancel_scan(state, &mut scan_log, &scan_id);
     }
 
-    emit_progress(&app, &scan_id, "enriching", device_count, 50.0);
+    emit_progress(&app, &scan_id, "enriching", device_count, plan.start(Phase::Enriching));
 
     // Phase 4: Enrich with OUI data and persist to database
     let mut new_device_count = 0u32;
+    let mut departure_candidates = Vec::new();
+    // Devices this scan saw, or confirmed still present
+    let mut present: HashSet<String> = HashSet::new();
 
     {
-        let conn = state.conn().map_err(|e| e.to_string())?;
+        let mut conn = state.conn()?;
+        let latency_policy = settings.latency_coalescing();
+        let media = interface::interface_media(tools.runner.as_ref());
+
+        // One transaction for the whole phase: per-statement commits dominate on
+        // large networks. Returning early drops it and rolls everything back.
+        let tx = conn.transaction()?;
+        let mut discovered_ids = Vec::with_capacity(discovered.len());
 
         for device in &discovered {
             let vendor = device
@@ -113,10 +333,10 @@ pub async fn run_scan(
                 .and_then(|mac| state.oui_db.lookup(mac))
                 .map(|s| s.to_string());
 
-            let latency = ping_results
+            let reply = ping_results
                 .iter()
                 .find(|(ip, _)| ip == &device.ip)
-                .and_then(|(_, lat)| *lat);
+                .and_then(|(_, reply)| *reply);
 
             // Merge resolved hostname (prefer ARP-discovered hostname)
             let hostname = device.hostname.clone().or_else(|| {
@@ -126,20 +346,17 @@ pub async fn run_scan(
                     .and_then(|(_, h)| h.clone())
             });
 
-            // Check if device already exists (by MAC)
-            let existing_id = device
-                .mac
-                .as_deref()
-                .and_then(|mac| db_devices::get_device_by_mac(&conn, mac).ok().flatten());
+            // Check if device already exists (by MAC, or by IP when it has none)
+            let existing_id = find_device(&tx, device);
 
             let device_id = if let Some(id) = existing_id {
                 // Update existing device
-                db_devices::touch_device(&conn, &id).map_err(|e| e.to_string())?;
-                db_devices::upsert_device_ip(&conn, &id, &device.ip).map_err(|e| e.to_string())?;
+                db_devices::touch_device(&tx, &id)?;
+                db_devices::upsert_device_ip(&tx, &id, &device.ip)?;
 
-                // Update hostname if we resolved one and device doesn't have one yet
+                // Keep the latest resolved hostname so renames surface as hostname_changed alerts
                 if let Some(ref hn) = hostname {
-                    db_devices::update_hostname(&conn, &id, hn).map_err(|e| e.to_string())?;
+                    db_devices::update_hostname(&tx, &id, hn)?;
                 }
 
                 id
@@ -152,7 +369,7 @@ pub async fn run_scan(
                     "unknown"
                 };
                 db_devices::insert_device(
-                    &conn,
+                    &tx,
                     &id,
                     device.mac.as_deref(),
                     vendor.as_deref(),
@@ -160,108 +377,231 @@ pub async fn run_scan(
                     device_type,
                     device.is_gateway,
                     Some(&device.ip),
-                )
-                .map_err(|e| e.to_string())?;
+                )?;
                 new_device_count += 1;
+                scan_log.line(format_args!("new device {} at {}", id, device.ip));
                 id
             };
 
-            // Record latency
-            if let Some(lat) = latency {
-                db_devices::record_latency(&conn, &device_id, lat).map_err(|e| e.to_string())?;
+            // Keep the last known connection type when this scan can't tell
+            let medium = device
+                .interface
+                .as_deref()
+                .and_then(|name| interface::interface_medium(&media, name));
+            let connection_type = fingerprint::classify_connection(device.mac.as_deref(), medium);
+            if connection_type != "unknown" {
+                db_devices::update_connection_type(&tx, &device_id, connection_type)?;
             }
 
-            // Emit device discovered event
-            if let Ok(Some(full_device)) = db_devices::get_device_by_id(&conn, &device_id) {
+            let mut sources = vec![DiscoverySource::neighbor_table(&device.ip)];
+            if let Some(reply) = reply {
+                sources.extend(DiscoverySource::from_probe(&device.ip, &[reply.method]));
+            }
+            if mdns_devices.iter().any(|m| m.ip == device.ip) {
+                sources.push(DiscoverySource::Mdns);
+            }
+            let tags: Vec<&str> = sources.iter().map(|s| s.as_str()).collect();
+            db_devices::set_discovery_sources(&tx, &device_id, &tags)?;
+
+            // Record latency; a ping reply also confirms presence. A TCP connect says
+            // nothing about ICMP loss, so its loss is left unmeasured.
+            match reply {
+                Some(reply) if reply.method == probe::ProbeMethod::Icmp => {
+                    let quality = db_devices::PingQuality {
+                        packet_loss: Some(reply.stats.loss_percent),
+                        jitter_ms: reply.stats.jitter_ms,
+                    };
+                    db_devices::mark_ping_confirmed(&tx, &device_id)?;
+                    db_devices::record_latency_with_quality(&tx, &device_id, reply.latency_ms, quality, &latency_policy)?;
+                }
+                Some(reply) => {
+                    db_devices::record_latency(&tx, &device_id, reply.latency_ms, &latency_policy)?;
+                }
+                None => {}
+            }
+
+            discovered_ids.push(device_id);
+        }
+
+        tx.commit()?;
+
+        // Emit only once committed, so listeners that query back see the rows
+        for device_id in &discovered_ids {
+            if let Ok(Some(full_device)) = db_devices::get_device_by_id(&conn, device_id) {
                 let _ = app.emit("scan:device-discovered", &full_device);
             }
         }
 
-        // Mark devices as departed (previously online, not seen this scan)
-        let current_macs: Vec<&str> = discovered.iter().filter_map(|d| d.mac.as_deref()).collect();
+        present.extend(discovered_ids);
 
+        // Mark devices as departed (previously online, looked for but not seen this scan)
         for prev in &previous_devices {
-            if prev.is_online {
-                let still_here = prev
-                    .mac_address
-                    .as_deref()
-                    .map(|mac| current_macs.contains(&mac))
-                    .unwrap_or(false);
-
-                if !still_here {
-                    let _ = app.emit(
-                        "device:departed",
-                        &DepartedEvent {
-                            device_id: prev.id.clone(),
-                        },
-                    );
-                }
+            if prev.is_online && scope.covers(prev) && !present.contains(&prev.id) {
+                departure_candidates.push(prev);
             }
         }
     }
 
-    if cancel.is_cancelled() {
-        return fail_scan(state, &scan_id, "Scan cancelled");
+    // Confirm departures with a multi-protocol probe; ARP entries age out even
+    // when a device is still up, and some devices ignore ICMP.
+    let mut probes = tokio::task::JoinSet::new();
+    for prev in departure_candidates {
+        let device_id = prev.id.clone();
+        let ip = prev.current_ip.clone();
+        let probe_ip = ip.clone().unwrap_or_default();
+        let mac = prev.mac_address.clone();
+        let budget = budget.clone();
+        let tools = tools.clone();
+        probes.spawn(async move {
+            let _permit = budget.acquire().await;
+            let reach = match ip {
+                Some(ref ip) => Some(probe::probe_device(ip, mac.as_deref(), ping_timeout_ms, &tools).await),
+                None => None,
+            };
+            (device_id, probe_ip, reach)
+        });
     }
 
-    // Phase 5: Port scan (full scan only)
-    if matches!(config.scan_type, ScanType::Full) {
-        emit_progress(&app, &scan_id, "port_scan", device_count, 60.0);
-
-        let ports_to_scan = match config.port_range {
-            PortRange::Top100 => port::top_100_ports(),
-            PortRange::Top1000 => port::top_100_ports(), // TODO: add top 1000
-            PortRange::Custom(ref ports) => ports.clone(),
+    while let Some(joined) = probes.join_next().await {
+        let Ok((device_id, ip, reach)) = joined else {
+            continue;
         };
 
-        for (i, device) in discovered.iter().enumerate() {
-            if cancel.is_cancelled() {
-                return fail_scan(state, &scan_id, "Scan cancelled");
-            }
+        if let Some(reach) = reach.filter(|r| r.reachable) {
+            scan_log.line(format_args!(
+                "departure of {} not confirmed: reachable via {:?}",
+                device_id, reach.methods
+            ));
+            let conn = state.conn()?;
+            db_devices::touch_device(&conn, &device_id)?;
+            let tags: Vec<&str> = DiscoverySource::from_probe(&ip, &reach.methods)
+                .iter()
+                .map(|s| s.as_str())
+                .collect();
+            db_devices::set_discovery_sources(&conn, &device_id, &tags)?;
+            present.insert(device_id);
+            continue;
+        }
+
+        scan_log.line(format_args!("departed: device {}", device_id));
+        let _ = app.emit("device:departed", &DepartedEvent { device_id });
+    }
+
+    if cancel.is_cancelled() {
+        return cancel_scan(state, &mut scan_log, &scan_id);
+    }
 
-            let progress = 60.0 + (30.0 * (i as f64 / discovered.len().max(1) as f64));
-            emit_progress(&app, &scan_id, "port_scan", device_count, progress);
+    // Phase 5: Port scan (full scan only, unless phases say otherwise)
+    if phases.ports {
+        emit_progress(&app, &scan_id, "port_scan", device_count, plan.start(Phase::PortScan));
+
+        let ips: Vec<String> = discovered.iter().map(|d| d.ip.clone()).collect();
+        let (tick_tx, mut ticks) = tokio::sync::mpsc::unbounded_channel();
+        let mut scans = port::scan_hosts(
+            &ips,
+            &ports_to_scan,
+            &udp_ports_to_scan,
+            &budget,
+            2000,
+            port::PARALLEL_HOSTS,
+            Some(tick_tx),
+        );
+        let ports_per_host = ports_to_scan.len() + udp_ports_to_scan.len();
+        let mut port_progress = PortProgress::new(&plan, &scan_id, &ips, ports_per_host, device_count);
+
+        loop {
+            let joined = tokio::select! {
+                _ = cancel.cancelled() => {
+                    scans.abort_all();
+                    return cancel_scan(state, &mut scan_log, &scan_id);
+                }
+                Some(tick) = ticks.recv() => {
+                    port_progress.tick(&app, tick);
+                    continue;
+                }
+                joined = scans.join_next() => joined,
+            };
+            let Some(joined) = joined else { break };
+            let host = match joined {
+                Ok(host) => host,
+                Err(e) => {
+                    log::error!("Port scan task failed: {}", e);
+                    continue;
+                }
+            };
 
-            let results = port::scan_ports(&device.ip, &ports_to_scan, 100, 2000).await;
+            let results = host.results;
+            scan_log.line(format_args!(
+                "ports {}: tried {}, {} responded in {}ms",
+                host.ip,
+                ports_to_scan.len() + udp_ports_to_scan.len(),
+                results.len(),
+                host.elapsed.as_millis()
+            ));
+            for pr in &results {
+                scan_log.line(format_args!(
+                    "  {}/{} {} {}",
+                    pr.port,
+                    pr.protocol.as_str(),
+                    pr.state,
+                    pr.service_name.as_deref().unwrap_or("")
+                ));
+            }
 
             if !results.is_empty() {
-                let conn = state.conn().map_err(|e| e.to_string())?;
+                let conn = state.conn()?;
 
-                let device_id = device
-                    .mac
-                    .as_deref()
-                    .and_then(|mac| db_devices::get_device_by_mac(&conn, mac).ok().flatten());
+                let device_id = find_device(&conn, &discovered[host.index]);
 
+                // A host's ports are stored together, so a cancelled scan keeps whole hosts only
                 if let Some(ref dev_id) = device_id {
+                    let tx = conn.unchecked_transaction()?;
                     for pr in &results {
                         db_ports::insert_port(
-                            &conn,
+                            &tx,
                             dev_id,
                             &scan_id,
                             pr.port,
-                            "tcp",
+                            pr.protocol.as_str(),
                             &pr.state.to_string(),
                             pr.service_name.as_deref(),
                             pr.banner.as_deref(),
-                        )
-                        .map_err(|e| e.to_string())?;
+                        )?;
                     }
+                    tx.commit()?;
                 }
             }
         }
+        // The last host can finish before its final ticks are read
+        while let Ok(tick) = ticks.try_recv() {
+            port_progress.tick(&app, tick);
+        }
     }
 
-    // Phase 6: OS fingerprinting & device classification (full scan only)
-    if matches!(config.scan_type, ScanType::Full) {
-        emit_progress(&app, &scan_id, "fingerprinting", device_count, 92.0);
+    let dhcp_fingerprints = match dhcp_capture {
+        Some((stop, capture)) => {
+            drop(stop);
+            capture.await.unwrap_or_default()
+        }
+        None => Vec::new(),
+    };
+    for fp in &dhcp_fingerprints {
+        scan_log.line(format_args!(
+            "dhcp {}: vendor_class={} params={:?}",
+            fp.mac,
+            fp.vendor_class.as_deref().unwrap_or("-"),
+            fp.parameter_list
+        ));
+    }
 
-        let conn = state.conn().map_err(|e| e.to_string())?;
+    // Phase 6: OS fingerprinting & device classification (full scan only, unless phases say otherwise)
+    if phases.fingerprint {
+        emit_progress(&app, &scan_id, "fingerprinting", device_count, plan.start(Phase::Fingerprinting));
+
+        let conn = state.conn()?;
 
         for device in &discovered {
-            let device_id = device
-                .mac
-                .as_deref()
-                .and_then(|mac| db_devices::get_device_by_mac(&conn, mac).ok().flatten());
+            let device_id = find_device(&conn, device);
 
             if let Some(ref dev_id) = device_id {
                 // Get the ports we just scanned for this device
@@ -270,6 +610,7 @@ pub async fn run_scan(
                     .iter()
                     .map(|p| port::PortResult {
                         port: p.port,
+                        protocol: if p.protocol == "udp" { Protocol::Udp } else { Protocol::Tcp },
                         state: port::PortState::Open,
                         service_name: p.service_name.clone(),
                         banner: None,
@@ -282,10 +623,34 @@ pub async fn run_scan(
                     .and_then(|mac| state.oui_db.lookup(mac))
                     .map(|s| s.to_string());
 
-                // OS fingerprinting
-                if let Some(os_guess) = fingerprint::guess_os(&port_results, vendor.as_deref()) {
-                    db_devices::update_os_guess(&conn, dev_id, &os_guess.os, os_guess.confidence)
-                        .map_err(|e| e.to_string())?;
+                let ttl = ping_results
+                    .iter()
+                    .find(|(ip, _)| ip == &device.ip)
+                    .and_then(|(_, reply)| reply.and_then(|r| r.ttl));
+
+                let dhcp_match = device
+                    .mac
+                    .as_deref()
+                    .and_then(|mac| dhcp_fingerprints.iter().find(|f| f.mac.eq_ignore_ascii_case(mac)))
+                    .and_then(dhcp::identify);
+
+                // OS fingerprinting; a DHCP signature wins when it is the more confident
+                let os_guess = match (fingerprint::guess_os(&port_results, vendor.as_deref(), ttl), dhcp_match) {
+                    (Some(guess), Some(m)) if guess.confidence >= m.confidence => Some(guess),
+                    (_, Some(m)) => Some(fingerprint::OsGuess {
+                        os: m.os.to_string(),
+                        confidence: m.confidence,
+                    }),
+                    (guess, None) => guess,
+                };
+                if let Some(ref os_guess) = os_guess {
+                    scan_log.line(format_args!(
+                        "os {}: {} ({:.0}%)",
+                        device.ip,
+                        os_guess.os,
+                        os_guess.confidence * 100.0
+                    ));
+                    db_devices::update_os_guess(&conn, dev_id, &os_guess.os, os_guess.confidence)?;
                 }
 
                 // Device classification
@@ -294,51 +659,95 @@ pub async fn run_scan(
                     .flatten()
                     .and_then(|d| d.os_guess);
 
+                let services = mdns_devices
+                    .iter()
+                    .find(|m| m.ip == device.ip)
+                    .map(|m| m.service_types.as_slice())
+                    .unwrap_or_default();
+
                 let device_type = fingerprint::classify_device(
                     &port_results,
                     vendor.as_deref(),
                     current_os.as_deref(),
+                    services,
                     device.is_gateway,
                 );
+                // A type is as trustworthy as the evidence behind it: the OS guess it was
+                // drawn from, or the DHCP signature that supplied it
+                let dhcp_type = dhcp_match.and_then(|m| Some((m.device_type?, m.confidence)));
+                let (device_type, type_confidence) = match dhcp_type {
+                    Some(dhcp) if device_type == "unknown" => dhcp,
+                    _ if device.is_gateway => (device_type, 1.0),
+                    _ => (device_type, os_guess.as_ref().map_or(0.0, |g| g.confidence)),
+                };
 
                 if device_type != "unknown" {
-                    db_devices::update_device_type(&conn, dev_id, device_type)
-                        .map_err(|e| e.to_string())?;
+                    db_devices::update_device_type(&conn, dev_id, device_type, type_confidence)?;
                 }
+                db_devices::record_fingerprint(
+                    &conn,
+                    dev_id,
+                    &scan_id,
+                    os_guess.as_ref().map(|g| (g.os.as_str(), g.confidence)),
+                    device_type,
+                )?;
             }
         }
     }
 
-    // Phase 7: Alert evaluation
-    emit_progress(&app, &scan_id, "alerts", device_count, 95.0);
+    // Rescore every device; trust, ports and OS guesses may all have changed
+    {
+        let conn = state.conn()?;
+        let devices = db_devices::get_all_devices(&conn)?;
+        for device in &devices {
+            let score = risk::refresh_risk_score(&conn, device)?;
+            if score != device.risk_score {
+                scan_log.line(format_args!("risk {}: {} -> {}", device.id, device.risk_score, score));
+            }
+        }
+    }
 
+    // Snapshot what this scan saw, so it can be diffed against other scans later
     {
-        let conn = state.conn().map_err(|e| e.to_string())?;
-        let current_devices = db_devices::get_all_devices(&conn).map_err(|e| e.to_string())?;
+        let conn = state.conn()?;
+        for dev in &discovered {
+            let Some(device_id) = find_device(&conn, dev) else {
+                continue;
+            };
+            if let Some(device) = db_devices::get_device_by_id(&conn, &device_id)? {
+                let ports = db_ports::get_latest_ports(&conn, &device_id)?;
+                db_scans::record_scan_device(&conn, &scan_id, &device, &ports)?;
+            }
+        }
+    }
+
+    // Phase 7: Alert evaluation
+    if phases.alerts {
+        emit_progress(&app, &scan_id, "alerts", device_count, plan.start(Phase::Alerts));
+
+        let conn = state.conn()?;
+        let current_devices = devices_after_scan(db_devices::get_all_devices(&conn)?, &present, |d| scope.covers(d));
 
         match alert_engine::evaluate_alerts(&conn, &previous_devices, &current_devices) {
             Ok(generated) => {
                 // Emit each alert to frontend
                 for alert in &generated {
-                    let _ = app.emit(
-                        "alert:new",
-                        &AlertEvent {
-                            alert_type: alert.alert_type.clone(),
-                            device_id: alert.device_id.clone(),
-                            message: alert.message.clone(),
-                            severity: alert.severity.clone(),
-                        },
-                    );
+                    let _ = app.emit("alert:new", &alert_engine::AlertEvent::from(alert));
                 }
 
                 // Send desktop notifications
                 notifier::notify(&app, &generated);
 
+                for alert in &generated {
+                    scan_log.line(format_args!("alert [{}] {}", alert.severity, alert.message));
+                }
+
                 if !generated.is_empty() {
                     log::info!("Generated {} alerts from scan", generated.len());
                 }
             }
             Err(e) => {
+                scan_log.line(format_args!("alert evaluation failed: {}", e));
                 log::error!("Alert evaluation failed: {}", e);
             }
         }
@@ -348,9 +757,8 @@ pub async fn run_scan(
     let duration_ms = start.elapsed().as_millis() as u64;
 
     {
-        let conn = state.conn().map_err(|e| e.to_string())?;
-        db_scans::complete_scan(&conn, &scan_id, device_count, new_device_count, duration_ms)
-            .map_err(|e| e.to_string())?;
+        let conn = state.conn()?;
+        db_scans::complete_scan(&conn, &scan_id, device_count, new_device_count, duration_ms)?;
     }
 
     let result = ScanResult {
@@ -363,6 +771,11 @@ pub async fn run_scan(
     emit_progress(&app, &scan_id, "completed", device_count, 100.0);
     let _ = app.emit("scan:completed", &result);
 
+    scan_log.line(format_args!(
+        "scan completed: {} devices, {} new, {}ms",
+        device_count, new_device_count, duration_ms
+    ));
+
     log::info!(
         "Scan {} completed: {} devices found, {} new, {}ms",
         scan_id,
@@ -374,16 +787,6 @@ pub async fn run_scan(
     Ok(result)
 }
 
-/// Alert event emitted to the frontend.
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct AlertEvent {
-    alert_type: String,
-    device_id: Option<String>,
-    message: String,
-    severity: String,
-}
-
 /// Departed device payload emitted to the frontend.
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -391,16 +794,31 @@ struct DepartedEvent {
     device_id: String,
 }
 
+/// Directory for this scan's log file, or `None` when verbose scan logs are off.
+fn scan_log_dir(app: &AppHandle, settings: &db_settings::AppSettings) -> Option<PathBuf> {
+    if !settings.verbose_scan_logs {
+        return None;
+    }
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| scan_log::log_dir(&dir))
+}
+
+/// Hosts that still need a reverse DNS lookup: those without a name, when resolution is enabled.
+fn hosts_to_resolve(phases: &ScanPhases, discovered: &[DiscoveredDevice]) -> Vec<String> {
+    if !phases.resolve {
+        return Vec::new();
+    }
+    discovered
+        .iter()
+        .filter(|d| d.hostname.is_none())
+        .map(|d| d.ip.clone())
+        .collect()
+}
+
 fn emit_progress(app: &AppHandle, scan_id: &str, phase: &str, devices_found: u32, percent: f64) {
-    let _ = app.emit(
-        "scan:progress",
-        ScanProgress {
-            scan_id: scan_id.to_string(),
-            phase: phase.to_string(),
-            devices_found,
-            percent_complete: percent,
-        },
-    );
+    app.progress(ScanProgress::new(scan_id, phase, devices_found, percent));
 }
 
 fn scan_type_str(scan_type: &ScanType) -> &'static str {
@@ -412,10 +830,211 @@ fn scan_type_str(scan_type: &ScanType) -> &'static str {
     }
 }
 
-/// Mark a scan as failed in the DB and return an error.
-fn fail_scan(state: &AppState, scan_id: &str, reason: &str) -> Result<ScanResult, String> {
+/// Which devices a scan looked for, so that not finding one means it left.
+struct ScanScope<'a> {
+    /// Without discovery nothing was looked for, so nothing can be missing.
+    discovery: bool,
+    /// The requested target addresses; devices elsewhere weren't looked for.
+    targets: Option<HashSet<&'a str>>,
+    /// Address and mask of an explicitly chosen interface; devices last seen on
+    /// other subnets weren't looked for.
+    subnet: Option<(&'a str, &'a str)>,
+}
+
+impl<'a> ScanScope<'a> {
+    /// `interface` is the explicitly chosen one, if any; it is ignored when there are targets.
+    fn new(phases: &ScanPhases, targets: Option<&'a [String]>, interface: Option<&'a interface::NetworkInterface>) -> Self {
+        let subnet = interface
+            .filter(|_| targets.is_none())
+            .and_then(|i| Some((i.ip_address.as_deref()?, i.subnet_mask.as_deref()?)));
+        Self {
+            discovery: phases.discovery,
+            targets: targets.map(|hosts| hosts.iter().map(String::as_str).collect()),
+            subnet,
+        }
+    }
+
+    fn covers(&self, device: &db_devices::Device) -> bool {
+        if !self.discovery {
+            return false;
+        }
+        let ip = device.current_ip.as_deref();
+        if let Some(targets) = &self.targets {
+            return ip.is_some_and(|ip| targets.contains(ip));
+        }
+        match self.subnet {
+            Some((own, mask)) => ip.is_some_and(|ip| ipmath::in_same_subnet(own, ip, mask)),
+            None => true,
+        }
+    }
+}
+
+/// The devices to compare against the pre-scan snapshot: every device in `all` that
+/// is `present`, plus any the scan did not look for, which are assumed unchanged.
+/// Devices it looked for and missed are left out, so they count as departed.
+fn devices_after_scan(
+    all: Vec<db_devices::Device>,
+    present: &HashSet<String>,
+    looked_for: impl Fn(&db_devices::Device) -> bool,
+) -> Vec<db_devices::Device> {
+    all.into_iter()
+        .filter(|d| present.contains(&d.id) || !looked_for(d))
+        .collect()
+}
+
+/// Saved device for a discovery: by MAC, or by current IP when the host was
+/// reached without a neighbor entry.
+fn find_device(conn: &rusqlite::Connection, device: &DiscoveredDevice) -> Option<String> {
+    match device.mac.as_deref() {
+        Some(mac) => db_devices::get_device_by_mac(conn, mac).ok().flatten(),
+        None => db_devices::get_device_by_ip(conn, &device.ip).ok().flatten(),
+    }
+}
+
+/// Mark a scan as failed in the DB and return an error, telling the frontend why,
+/// e.g. so it can ask for elevated privileges.
+fn fail_scan(
+    app: &AppHandle,
+    state: &AppState,
+    scan_log: &mut ScanLog,
+    scan_id: &str,
+    error: AppError,
+) -> TauriResult<ScanResult> {
+    scan_log.line(format_args!("scan failed: [{}] {}", error.code, error.message));
     if let Ok(conn) = state.conn() {
         let _ = db_scans::fail_scan(&conn, scan_id);
     }
-    Err(reason.to_string())
+    let _ = app.emit("scan:error", &error);
+    Err(error)
+}
+
+/// Mark the scan failed after a stop request. The caller asked for this, so no error event is sent.
+fn cancel_scan(state: &AppState, scan_log: &mut ScanLog, scan_id: &str) -> TauriResult<ScanResult> {
+    scan_log.line("scan failed: Scan cancelled");
+    if let Ok(conn) = state.conn() {
+        let _ = db_scans::fail_scan(&conn, scan_id);
+    }
+    Err(AppError::scan("Scan cancelled"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(ip: &str, hostname: Option<&str>) -> DiscoveredDevice {
+        DiscoveredDevice {
+            ip: ip.to_string(),
+            mac: None,
+            hostname: hostname.map(|h| h.to_string()),
+            is_gateway: false,
+            interface: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_disabled_skips_lookups() {
+        let discovered = [found("192.168.1.10", None), found("192.168.1.11", Some("nas.local"))];
+
+        let phases = ScanPhases::for_scan_type(&ScanType::Full);
+        assert_eq!(hosts_to_resolve(&phases, &discovered), ["192.168.1.10"]);
+
+        let phases = ScanPhases { resolve: false, ..phases };
+        assert!(hosts_to_resolve(&phases, &discovered).is_empty());
+    }
+
+    #[test]
+    fn test_device_missing_from_scan_departs() {
+        let pool = crate::db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "stays", Some("AA:BB:CC:00:00:01"), None, None, "unknown", false, Some("192.168.1.10")).unwrap();
+        db_devices::insert_device(&conn, "leaves", Some("AA:BB:CC:00:00:02"), None, None, "unknown", false, Some("192.168.1.11")).unwrap();
+        let previous = db_devices::get_all_devices(&conn).unwrap();
+        assert!(previous.iter().all(|d| d.is_online));
+
+        let phases = ScanPhases::for_scan_type(&ScanType::Quick);
+        let present: HashSet<String> = ["stays".to_string()].into();
+        let scope = ScanScope::new(&phases, None, None);
+        let current = devices_after_scan(db_devices::get_all_devices(&conn).unwrap(), &present, |d| scope.covers(d));
+        assert_eq!(current.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["stays"]);
+
+        let alerts = alert_engine::evaluate_alerts(&conn, &previous, &current).unwrap();
+        let departed: Vec<_> = alerts.iter().filter(|a| a.alert_type == "device_departed").collect();
+        assert_eq!(departed.len(), 1);
+        assert_eq!(departed[0].device_id.as_deref(), Some("leaves"));
+
+        // A scan that looked for nothing reports nobody missing
+        let phases = ScanPhases { discovery: false, ..phases };
+        let scope = ScanScope::new(&phases, None, None);
+        let current = devices_after_scan(db_devices::get_all_devices(&conn).unwrap(), &HashSet::new(), |d| scope.covers(d));
+        assert_eq!(current.len(), 2);
+    }
+
+    #[test]
+    fn test_targeted_scan_only_misses_devices_in_range() {
+        let pool = crate::db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "inside", None, None, None, "unknown", false, Some("192.168.1.10")).unwrap();
+        db_devices::insert_device(&conn, "outside", None, None, None, "unknown", false, Some("10.0.0.5")).unwrap();
+        db_devices::insert_device(&conn, "no_ip", None, None, None, "unknown", false, None).unwrap();
+
+        let phases = ScanPhases::for_scan_type(&ScanType::Quick);
+        let targets: Vec<String> = (1..=20).map(|n| format!("192.168.1.{}", n)).collect();
+        let scope = ScanScope::new(&phases, Some(&targets), None);
+        let devices = db_devices::get_all_devices(&conn).unwrap();
+        let covered: Vec<&str> = devices.iter().filter(|d| scope.covers(d)).map(|d| d.id.as_str()).collect();
+        assert_eq!(covered, ["inside"]);
+
+        // Nothing answered in the range: only the device inside it departs
+        let previous = db_devices::get_all_devices(&conn).unwrap();
+        let current = devices_after_scan(devices, &HashSet::new(), |d| scope.covers(d));
+        let alerts = alert_engine::evaluate_alerts(&conn, &previous, &current).unwrap();
+        let departed: Vec<_> = alerts
+            .iter()
+            .filter(|a| a.alert_type == "device_departed")
+            .filter_map(|a| a.device_id.as_deref())
+            .collect();
+        assert_eq!(departed, ["inside"]);
+    }
+
+    #[test]
+    fn test_chosen_interface_only_misses_devices_on_its_subnet() {
+        let pool = crate::db::init_test_db();
+        let conn = pool.get().unwrap();
+        db_devices::insert_device(&conn, "lan", None, None, None, "unknown", false, Some("192.168.1.10")).unwrap();
+        db_devices::insert_device(&conn, "vpn", None, None, None, "unknown", false, Some("10.8.0.5")).unwrap();
+
+        let iface = interface::NetworkInterface {
+            id: "en0".to_string(),
+            name: "en0".to_string(),
+            ip_address: Some("192.168.1.2".to_string()),
+            subnet_mask: Some("255.255.255.0".to_string()),
+            mac_address: None,
+            gateway_ip: Some("192.168.1.1".to_string()),
+            ipv6_address: None,
+            gateway_ipv6: None,
+            is_active: true,
+            is_vpn: false,
+        };
+        let phases = ScanPhases::for_scan_type(&ScanType::Quick);
+        let devices = db_devices::get_all_devices(&conn).unwrap();
+
+        let scope = ScanScope::new(&phases, None, Some(&iface));
+        let covered: Vec<&str> = devices.iter().filter(|d| scope.covers(d)).map(|d| d.id.as_str()).collect();
+        assert_eq!(covered, ["lan"]);
+
+        let previous = devices.clone();
+        let current = devices_after_scan(devices, &HashSet::new(), |d| scope.covers(d));
+        let alerts = alert_engine::evaluate_alerts(&conn, &previous, &current).unwrap();
+        let departed: Vec<_> = alerts
+            .iter()
+            .filter(|a| a.alert_type == "device_departed")
+            .filter_map(|a| a.device_id.as_deref())
+            .collect();
+        assert_eq!(departed, ["lan"]);
+
+        // Explicit targets take over from the interface
+        let targets = vec!["10.8.0.5".to_string()];
+        let scope = ScanScope::new(&phases, Some(&targets), Some(&iface));
+        assert_eq!(previous.iter().filter(|d| scope.covers(d)).map(|d| d.id.as_str()).collect::<Vec<_>>(), ["vpn"]);
+    }
 }
@@ -1,84 +1,194 @@
 use regex::Regex;
-use std::process::Command;
 
-use super::DiscoveredDevice;
+use super::{DiscoveredDevice, ScanTools};
 use crate::commands::validate::Validator;
+use crate::network::interface::NetworkInterface;
+use crate::network::ipmath;
+use crate::util::command::{permission_error, CommandRunner};
+use crate::AppError;
 
 /// Parse the system ARP table to discover devices on the local network.
 /// Works without elevated privileges.
 pub fn scan_arp_table() -> Vec<DiscoveredDevice> {
+    scan_arp_table_with(&ScanTools::default()).unwrap_or_else(|e| {
+        log::warn!("{}", e.message);
+        Vec::new()
+    })
+}
+
+/// Read the neighbor table through `tools`, using the custom command when one is set.
+/// Fails only when the command was refused for lack of privileges, so an empty
+/// result means the table really was empty (or the command could not run at all).
+pub fn scan_arp_table_with(tools: &ScanTools) -> Result<Vec<DiscoveredDevice>, AppError> {
+    let runner = tools.runner.as_ref();
+    let Some(template) = tools.arp.as_ref() else {
+        return scan_platform_table(runner);
+    };
+
+    let result = template.run(runner, &template.render(&[]));
+    if let Some(denied) = permission_error(template.program_name(), &result) {
+        return Err(denied);
+    }
+    let output = match result {
+        Ok(o) => o.stdout,
+        Err(e) => {
+            log::error!("Failed to run custom ARP command {}: {}", template.program_name(), e);
+            return Ok(Vec::new());
+        }
+    };
+
+    Ok(parse_neighbor_output(&output, default_gateway_ip(runner).as_deref()))
+}
+
+/// Read the neighbor table with the platform's built-in command.
+fn scan_platform_table(runner: &dyn CommandRunner) -> Result<Vec<DiscoveredDevice>, AppError> {
     #[cfg(target_os = "macos")]
     {
-        scan_arp_macos()
+        scan_arp_macos(runner)
     }
 
     #[cfg(target_os = "linux")]
     {
-        scan_arp_linux()
+        scan_arp_linux(runner)
     }
 
     #[cfg(target_os = "windows")]
     {
-        scan_arp_windows()
+        scan_arp_windows(runner)
     }
 
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
     {
         log::warn!("ARP scanning not supported on this platform");
-        Vec::new()
+        Ok(Vec::new())
+    }
+}
+
+/// Best-effort parser for custom neighbor table commands: takes the first IPv4 and MAC on
+/// each line. Understands `ip neigh`, BSD/busybox `arp -a` and `/proc/net/arp` layouts.
+fn parse_neighbor_output(output: &str, gateway_ip: Option<&str>) -> Vec<DiscoveredDevice> {
+    let mut devices = Vec::new();
+
+    for line in output.lines() {
+        if line.contains("(incomplete)") || line.contains("FAILED") {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let Some(ip_index) = tokens
+            .iter()
+            .position(|t| Validator::validate_ipv4(t.trim_matches(|c| c == '(' || c == ')')).is_ok())
+        else {
+            continue;
+        };
+        let ip = tokens[ip_index].trim_matches(|c| c == '(' || c == ')').to_string();
+
+        let Some(mac) = tokens
+            .iter()
+            .map(|t| t.replace('-', ":"))
+            .find(|t| Validator::validate_mac_address(t).is_ok())
+            .filter(|m| m != "00:00:00:00:00:00" && !m.eq_ignore_ascii_case("ff:ff:ff:ff:ff:ff"))
+        else {
+            continue;
+        };
+
+        let interface = tokens
+            .iter()
+            .position(|t| *t == "dev" || *t == "on")
+            .and_then(|i| tokens.get(i + 1))
+            .map(|s| s.to_string());
+
+        // `arp -a` puts the hostname before the parenthesised IP
+        let hostname = (ip_index == 1 && tokens[1].starts_with('('))
+            .then(|| tokens[0])
+            .filter(|h| *h != "?")
+            .map(|h| h.to_string());
+
+        devices.push(DiscoveredDevice {
+            is_gateway: gateway_ip == Some(ip.as_str()),
+            ip,
+            mac: Some(mac),
+            hostname,
+            interface,
+        });
+    }
+
+    devices
+}
+
+/// Default gateway IP for the current platform.
+fn default_gateway_ip(runner: &dyn CommandRunner) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    {
+        get_gateway_ip_macos(runner)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        get_gateway_ip_linux(runner)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        get_gateway_ip_windows(runner)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+    {
+        let _ = runner;
+        None
     }
 }
 
 /// macOS: Parse `arp -a` output
 #[cfg(target_os = "macos")]
-fn scan_arp_macos() -> Vec<DiscoveredDevice> {
-    let output = match Command::new("arp").arg("-a").output() {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
-        Err(e) => {
-            log::error!("Failed to run arp -a: {}", e);
-            return Vec::new();
-        }
+fn scan_arp_macos(runner: &dyn CommandRunner) -> Result<Vec<DiscoveredDevice>, AppError> {
+    let Some(output) = runner.checked_stdout("arp", &["-a"])? else {
+        return Ok(Vec::new());
     };
 
-    parse_arp_macos(&output)
+    Ok(parse_arp_macos(&output, get_gateway_ip_macos(runner).as_deref()))
 }
 
-/// Linux: Parse `ip neigh show` output
+/// The kernel's neighbor table, readable without any userspace tools.
 #[cfg(target_os = "linux")]
-fn scan_arp_linux() -> Vec<DiscoveredDevice> {
-    let output = match Command::new("ip")
-        .args(&["neigh", "show"])
-        .output()
-    {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
+const PROC_NET_ARP: &str = "/proc/net/arp";
+
+/// Linux: Parse `ip neigh show` output, falling back to `/proc/net/arp` when `ip`
+/// can't be run (minimal containers, older systems).
+#[cfg(target_os = "linux")]
+fn scan_arp_linux(runner: &dyn CommandRunner) -> Result<Vec<DiscoveredDevice>, AppError> {
+    if let Some(output) = runner.checked_stdout("ip", &["neigh", "show"])? {
+        return Ok(parse_arp_linux(&output, get_gateway_ip_linux(runner).as_deref()));
+    }
+
+    match std::fs::read_to_string(PROC_NET_ARP) {
+        Ok(table) => {
+            log::info!("`ip neigh` unavailable; reading {} instead", PROC_NET_ARP);
+            Ok(parse_proc_net_arp(&table, get_gateway_ip_linux(runner).as_deref()))
+        }
         Err(e) => {
-            log::error!("Failed to run ip neigh show: {}", e);
-            return Vec::new();
+            log::warn!("Failed to read {}: {}", PROC_NET_ARP, e);
+            Ok(Vec::new())
         }
-    };
-
-    parse_arp_linux(&output)
+    }
 }
 
 /// Windows: Parse `Get-NetNeighbor` PowerShell output
 #[cfg(target_os = "windows")]
-fn scan_arp_windows() -> Vec<DiscoveredDevice> {
-    let output = match Command::new("powershell")
-        .args(&[
+fn scan_arp_windows(runner: &dyn CommandRunner) -> Result<Vec<DiscoveredDevice>, AppError> {
+    let Some(output) = runner.checked_stdout(
+        "powershell",
+        &[
             "-NoProfile",
             "-Command",
-            "Get-NetNeighbor -AddressFamily IPv4 | Where-Object {$_.State -ne 'Unreachable'} | Select-Object -Property IPAddress,LinkLayerAddress | ConvertTo-Csv -NoTypeInformation",
-        ])
-        .output()
-    {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
-        Err(e) => {
-            log::error!("Failed to run Get-NetNeighbor: {}", e);
-            return Vec::new();
-        }
+            "Get-NetNeighbor -AddressFamily IPv4 | Where-Object {$_.State -ne 'Unreachable'} | Select-Object -Property IPAddress,LinkLayerAddress,InterfaceAlias | ConvertTo-Csv -NoTypeInformation",
+        ],
+    )? else {
+        return Ok(Vec::new());
     };
 
-    parse_arp_windows(&output)
+    Ok(parse_arp_windows(&output, get_gateway_ip_windows(runner).as_deref()))
 }
 
 /// Parse arp -a output (macOS format).
@@ -89,13 +199,12 @@ fn scan_arp_windows() -> Vec<DiscoveredDevice> {
 ///
 /// Entries with "(incomplete)" MAC are skipped.
 #[cfg(target_os = "macos")]
-fn parse_arp_macos(output: &str) -> Vec<DiscoveredDevice> {
+fn parse_arp_macos(output: &str, gateway_ip: Option<&str>) -> Vec<DiscoveredDevice> {
     let re = Regex::new(
-        r"(?:(\S+)\s+)?\((\d+\.\d+\.\d+\.\d+)\)\s+at\s+([0-9a-f:]+)"
+        r"(?:(\S+)\s+)?\((\d+\.\d+\.\d+\.\d+)\)\s+at\s+([0-9a-f:]+)(?:\s+on\s+(\S+))?"
     ).unwrap();
 
     let mut devices = Vec::new();
-    let gateway_ip = get_gateway_ip_macos();
 
     for line in output.lines() {
         // Skip incomplete entries
@@ -107,6 +216,7 @@ fn parse_arp_macos(output: &str) -> Vec<DiscoveredDevice> {
             let hostname_raw = caps.get(1).map(|m| m.as_str()).unwrap_or("?");
             let ip = caps[2].to_string();
             let mac = caps[3].to_string();
+            let interface = caps.get(4).map(|m| m.as_str().to_string());
 
             // Validate IP before adding
             if Validator::validate_ipv4(&ip).is_err() {
@@ -120,13 +230,14 @@ fn parse_arp_macos(output: &str) -> Vec<DiscoveredDevice> {
                 Some(hostname_raw.to_string())
             };
 
-            let is_gateway = gateway_ip.as_deref() == Some(ip.as_str());
+            let is_gateway = gateway_ip == Some(ip.as_str());
 
             devices.push(DiscoveredDevice {
                 ip,
                 mac: Some(mac),
                 hostname,
                 is_gateway,
+                interface,
             });
         }
     }
@@ -141,9 +252,8 @@ fn parse_arp_macos(output: &str) -> Vec<DiscoveredDevice> {
 ///   192.168.1.42 dev eth0 lladdr dd:ee:ff:00:11:22 STALE
 ///   192.168.1.99 dev eth0  FAILED
 #[cfg(target_os = "linux")]
-fn parse_arp_linux(output: &str) -> Vec<DiscoveredDevice> {
+fn parse_arp_linux(output: &str, gateway_ip: Option<&str>) -> Vec<DiscoveredDevice> {
     let mut devices = Vec::new();
-    let gateway_ip = get_gateway_ip_linux();
 
     for line in output.lines() {
         let parts: Vec<&str> = line.split_whitespace().collect();
@@ -173,15 +283,22 @@ fn parse_arp_linux(output: &str) -> Vec<DiscoveredDevice> {
             }
         }
 
+        let interface = parts
+            .iter()
+            .position(|p| *p == "dev")
+            .and_then(|i| parts.get(i + 1))
+            .map(|s| s.to_string());
+
         // Only add if we have a MAC (device is reachable)
         if let Some(mac_addr) = mac {
-            let is_gateway = gateway_ip.as_deref() == Some(ip);
+            let is_gateway = gateway_ip == Some(ip);
 
             devices.push(DiscoveredDevice {
                 ip: ip.to_string(),
                 mac: Some(mac_addr),
                 hostname: None, // Hostname not in ip neigh output
                 is_gateway,
+                interface,
             });
         }
     }
@@ -189,32 +306,86 @@ fn parse_arp_linux(output: &str) -> Vec<DiscoveredDevice> {
     devices
 }
 
-/// Parse Get-NetNeighbor PowerShell output (Windows format).
+/// Parse the kernel's `/proc/net/arp` table (Linux).
 ///
-/// CSV format (after ConvertTo-Csv):
-///   "IPAddress","LinkLayerAddress"
-///   "192.168.1.1","aa-bb-cc-dd-ee-ff"
-///   "192.168.1.42","dd-ee-ff-00-11-22"
-#[cfg(target_os = "windows")]
-fn parse_arp_windows(output: &str) -> Vec<DiscoveredDevice> {
-    use std::io::Read;
-
+/// Format (header, then one row per entry):
+///   IP address       HW type     Flags       HW address            Mask     Device
+///   192.168.1.1      0x1         0x2         aa:bb:cc:dd:ee:ff     *        eth0
+///   192.168.1.99     0x1         0x0         00:00:00:00:00:00     *        eth0
+///
+/// Incomplete entries (flags `0x0`, all-zero MAC) are skipped.
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_net_arp(table: &str, gateway_ip: Option<&str>) -> Vec<DiscoveredDevice> {
     let mut devices = Vec::new();
-    let gateway_ip = get_gateway_ip_windows();
 
-    // Parse CSV manually (simple case)
-    let lines: Vec<&str> = output.lines().collect();
-
-    for line in &lines[1..] { // Skip header
-        // Simple CSV parsing (doesn't handle quoted commas)
-        let fields: Vec<&str> = line.split(',').map(|f| f.trim_matches('"')).collect();
+    for line in table.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let [ip, _hw_type, flags, mac, _mask, device, ..] = parts[..] else {
+            continue;
+        };
 
-        if fields.len() < 2 {
+        if Validator::validate_ipv4(ip).is_err() || flags == "0x0" || mac == "00:00:00:00:00:00" {
             continue;
         }
+        if Validator::validate_mac_address(mac).is_err() {
+            continue;
+        }
+
+        devices.push(DiscoveredDevice {
+            ip: ip.to_string(),
+            mac: Some(mac.to_string()),
+            hostname: None,
+            is_gateway: gateway_ip == Some(ip),
+            interface: Some(device.to_string()),
+        });
+    }
+
+    devices
+}
+
+/// Parse Get-NetNeighbor PowerShell output (Windows format).
+///
+/// CSV format (after ConvertTo-Csv):
+///   "IPAddress","LinkLayerAddress","InterfaceAlias"
+///   "192.168.1.1","aa-bb-cc-dd-ee-ff","Ethernet"
+///   "192.168.1.42","dd-ee-ff-00-11-22","Wi-Fi"
+///
+/// Columns are looked up by header name, and quoted fields may contain commas.
+#[cfg(any(target_os = "windows", test))]
+fn parse_arp_windows(output: &str, gateway_ip: Option<&str>) -> Vec<DiscoveredDevice> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(output.as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(h) => h.clone(),
+        Err(e) => {
+            log::error!("Failed to read neighbor CSV header: {}", e);
+            return Vec::new();
+        }
+    };
+    let column = |name: &str| headers.iter().position(|h| h == name);
+
+    let (Some(ip_col), Some(mac_col)) = (column("IPAddress"), column("LinkLayerAddress")) else {
+        log::error!("Neighbor CSV is missing IPAddress/LinkLayerAddress columns");
+        return Vec::new();
+    };
+    let interface_col = column("InterfaceAlias");
+
+    let mut devices = Vec::new();
 
-        let ip = fields[0];
-        let mac_raw = fields[1];
+    for record in reader.records() {
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                log::debug!("Skipping malformed neighbor row: {}", e);
+                continue;
+            }
+        };
+        let (Some(ip), Some(mac_raw)) = (record.get(ip_col), record.get(mac_col)) else {
+            continue;
+        };
 
         // Validate IP
         if Validator::validate_ipv4(ip).is_err() {
@@ -229,13 +400,17 @@ fn parse_arp_windows(output: &str) -> Vec<DiscoveredDevice> {
             continue;
         }
 
-        let is_gateway = gateway_ip.as_deref() == Some(ip);
+        let is_gateway = gateway_ip == Some(ip);
 
         devices.push(DiscoveredDevice {
             ip: ip.to_string(),
             mac: Some(mac),
             hostname: None, // Hostname not in Get-NetNeighbor output
             is_gateway,
+            interface: interface_col
+                .and_then(|c| record.get(c))
+                .filter(|f| !f.is_empty())
+                .map(|f| f.to_string()),
         });
     }
 
@@ -244,13 +419,8 @@ fn parse_arp_windows(output: &str) -> Vec<DiscoveredDevice> {
 
 /// Get the default gateway IP from the routing table (macOS).
 #[cfg(target_os = "macos")]
-fn get_gateway_ip_macos() -> Option<String> {
-    let output = Command::new("netstat")
-        .args(["-rn"])
-        .output()
-        .ok()?;
-
-    let text = String::from_utf8_lossy(&output.stdout);
+fn get_gateway_ip_macos(runner: &dyn CommandRunner) -> Option<String> {
+    let text = runner.stdout("netstat", &["-rn"])?;
     let gw_re = Regex::new(r"default\s+(\d+\.\d+\.\d+\.\d+)").unwrap();
 
     gw_re.captures(&text).map(|caps| caps[1].to_string())
@@ -258,13 +428,8 @@ fn get_gateway_ip_macos() -> Option<String> {
 
 /// Get the default gateway IP from the routing table (Linux).
 #[cfg(target_os = "linux")]
-fn get_gateway_ip_linux() -> Option<String> {
-    let output = Command::new("ip")
-        .args(&["route", "show"])
-        .output()
-        .ok()?;
-
-    let text = String::from_utf8_lossy(&output.stdout);
+fn get_gateway_ip_linux(runner: &dyn CommandRunner) -> Option<String> {
+    let text = runner.stdout("ip", &["route", "show"])?;
 
     // Look for: default via 192.168.1.1 dev eth0
     for line in text.lines() {
@@ -284,17 +449,15 @@ fn get_gateway_ip_linux() -> Option<String> {
 
 /// Get the default gateway IP from the routing table (Windows).
 #[cfg(target_os = "windows")]
-fn get_gateway_ip_windows() -> Option<String> {
-    let output = Command::new("powershell")
-        .args(&[
+fn get_gateway_ip_windows(runner: &dyn CommandRunner) -> Option<String> {
+    let text = runner.stdout(
+        "powershell",
+        &[
             "-NoProfile",
             "-Command",
             "Get-NetRoute -DestinationPrefix '0.0.0.0/0' | Select-Object -Property NextHop | ConvertTo-Csv -NoTypeInformation",
-        ])
-        .output()
-        .ok()?;
-
-    let text = String::from_utf8_lossy(&output.stdout);
+        ],
+    )?;
 
     // Parse CSV: header, then IP addresses
     for line in text.lines().skip(1) {
@@ -307,9 +470,56 @@ fn get_gateway_ip_windows() -> Option<String> {
     None
 }
 
+/// Keep only neighbors reachable through `iface`, so a chosen interface is scanned even
+/// when another one (typically a VPN) holds the default route. Entries without an
+/// interface name are matched by subnet. Gateway flags follow the interface's own gateway.
+pub fn scope_to_interface(devices: Vec<DiscoveredDevice>, iface: &NetworkInterface) -> Vec<DiscoveredDevice> {
+    let on_subnet = |ip: &str| match (iface.ip_address.as_deref(), iface.subnet_mask.as_deref()) {
+        (Some(own), Some(mask)) => ipmath::in_same_subnet(own, ip, mask),
+        _ => false,
+    };
+
+    devices
+        .into_iter()
+        .filter(|d| match d.interface.as_deref() {
+            Some(name) => name == iface.name,
+            None => on_subnet(&d.ip),
+        })
+        .map(|mut d| {
+            if let Some(ref gw) = iface.gateway_ip {
+                d.is_gateway = d.ip == *gw;
+            }
+            d
+        })
+        .collect()
+}
+
+/// Keep neighbor entries inside `targets`, then add each of `responders` that has
+/// no entry (a routed host, or one whose entry has not appeared yet) without a MAC.
+pub fn scope_to_targets(
+    devices: Vec<DiscoveredDevice>,
+    targets: &[String],
+    responders: &[String],
+) -> Vec<DiscoveredDevice> {
+    let mut scoped: Vec<DiscoveredDevice> = devices.into_iter().filter(|d| targets.contains(&d.ip)).collect();
+    for ip in responders {
+        if !scoped.iter().any(|d| &d.ip == ip) {
+            scoped.push(DiscoveredDevice {
+                ip: ip.clone(),
+                mac: None,
+                hostname: None,
+                is_gateway: false,
+                interface: None,
+            });
+        }
+    }
+    scoped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::command::MockRunner;
 
     const SAMPLE_ARP_MACOS: &str = r#"? (192.168.1.1) at aa:bb:cc:dd:ee:ff on en0 ifscope [ethernet]
 macbook.local (192.168.1.42) at 11:22:33:44:55:66 on en0 ifscope [ethernet]
@@ -328,10 +538,52 @@ printer.local (192.168.1.50) at ab:cd:ef:12:34:56 on en0 ifscope [ethernet]"#;
 10.0.0.0/8 via 192.168.1.2 dev eth0 proto static
 192.168.1.0/24 dev eth0 proto kernel scope link src 192.168.1.100"#;
 
+    #[test]
+    fn test_parse_proc_net_arp() {
+        let table = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         aa:bb:cc:dd:ee:ff     *        eth0
+192.168.1.42     0x1         0x2         11:22:33:44:55:66     *        eth0
+192.168.1.99     0x1         0x0         00:00:00:00:00:00     *        eth0
+10.8.0.5         0x1         0x6         de:ad:be:ef:ca:fe     *        tun0
+";
+        let devices = parse_proc_net_arp(table, Some("192.168.1.1"));
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[0].ip, "192.168.1.1");
+        assert_eq!(devices[0].mac.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert!(devices[0].is_gateway);
+        assert!(!devices[1].is_gateway);
+        assert_eq!(devices[2].interface.as_deref(), Some("tun0"));
+
+        // Header only, or truncated rows
+        assert!(parse_proc_net_arp("IP address HW type Flags HW address Mask Device\n", None).is_empty());
+        assert!(parse_proc_net_arp("header\n192.168.1.1 0x1 0x2\n", None).is_empty());
+    }
+
+    #[test]
+    fn test_parse_arp_windows_quoted_fields() {
+        // Columns reordered, and an interface alias containing a comma
+        let output = "\"InterfaceAlias\",\"LinkLayerAddress\",\"IPAddress\"\r\n\
+\"vEthernet (WSL, Hyper-V)\",\"aa-bb-cc-dd-ee-ff\",\"192.168.1.1\"\r\n\
+\"Wi-Fi\",\"dd-ee-ff-00-11-22\",\"192.168.1.42\"\r\n\
+\"Wi-Fi\",\"00-00-00-00-00-00\",\"not an ip\"\r\n";
+
+        let devices = parse_arp_windows(output, Some("192.168.1.1"));
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].ip, "192.168.1.1");
+        assert_eq!(devices[0].mac.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(devices[0].interface.as_deref(), Some("vEthernet (WSL, Hyper-V)"));
+        assert!(devices[0].is_gateway);
+        assert_eq!(devices[1].ip, "192.168.1.42");
+        assert_eq!(devices[1].interface.as_deref(), Some("Wi-Fi"));
+
+        assert!(parse_arp_windows("\"Name\",\"State\"\r\n\"x\",\"y\"\r\n", None).is_empty());
+    }
+
     #[test]
     #[cfg(target_os = "macos")]
     fn test_parse_arp_macos() {
-        let devices = parse_arp_macos(SAMPLE_ARP_MACOS);
+        let devices = parse_arp_macos(SAMPLE_ARP_MACOS, None);
 
         // Should skip broadcast (ff:ff:ff:ff:ff:ff) and incomplete entries
         assert_eq!(devices.len(), 4);
@@ -351,12 +603,13 @@ printer.local (192.168.1.50) at ab:cd:ef:12:34:56 on en0 ifscope [ethernet]"#;
         // Fourth device (printer)
         assert_eq!(devices[3].ip, "192.168.1.50");
         assert_eq!(devices[3].hostname.as_deref(), Some("printer.local"));
+        assert_eq!(devices[3].interface.as_deref(), Some("en0"));
     }
 
     #[test]
     #[cfg(target_os = "macos")]
     fn test_empty_arp_table() {
-        let devices = parse_arp_macos("");
+        let devices = parse_arp_macos("", None);
         assert!(devices.is_empty());
     }
 
@@ -364,14 +617,14 @@ printer.local (192.168.1.50) at ab:cd:ef:12:34:56 on en0 ifscope [ethernet]"#;
     #[cfg(target_os = "macos")]
     fn test_all_incomplete() {
         let output = "? (192.168.1.1) at (incomplete) on en0\n? (192.168.1.2) at (incomplete) on en0";
-        let devices = parse_arp_macos(output);
+        let devices = parse_arp_macos(output, None);
         assert!(devices.is_empty());
     }
 
     #[test]
     #[cfg(target_os = "linux")]
     fn test_parse_arp_linux() {
-        let devices = parse_arp_linux(SAMPLE_ARP_LINUX);
+        let devices = parse_arp_linux(SAMPLE_ARP_LINUX, None);
 
         // Should skip FAILED entries (no MAC)
         assert_eq!(devices.len(), 4);
@@ -386,23 +639,157 @@ printer.local (192.168.1.50) at ab:cd:ef:12:34:56 on en0 ifscope [ethernet]"#;
 
         // Last device
         assert_eq!(devices[3].ip, "192.168.1.50");
+        assert_eq!(devices[3].interface.as_deref(), Some("eth0"));
     }
 
     #[test]
     #[cfg(target_os = "linux")]
     fn test_parse_arp_linux_invalid_ip() {
         let output = "999.999.999.999 dev eth0 lladdr aa:bb:cc:dd:ee:ff REACHABLE";
-        let devices = parse_arp_linux(output);
+        let devices = parse_arp_linux(output, None);
         assert!(devices.is_empty(), "Should reject invalid IP");
     }
 
     #[test]
     #[cfg(target_os = "linux")]
     fn test_parse_gateway_linux() {
-        // This tests the gateway detection logic
-        // The actual gateway IP extraction is tested implicitly in parse_arp_linux
-        let test_route = "default via 192.168.1.1 dev eth0 proto dhcp metric 100";
-        assert!(test_route.contains("default via"));
-        assert!(test_route.contains("192.168.1.1"));
+        let runner = MockRunner::new().on("ip route show", SAMPLE_ROUTE_LINUX);
+        assert_eq!(get_gateway_ip_linux(&runner).as_deref(), Some("192.168.1.1"));
+        assert!(get_gateway_ip_linux(&MockRunner::new()).is_none());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_scan_arp_table_end_to_end() {
+        let runner = MockRunner::new()
+            .on("ip neigh show", SAMPLE_ARP_LINUX)
+            .on("ip route show", SAMPLE_ROUTE_LINUX);
+        let tools = ScanTools {
+            runner: std::sync::Arc::new(runner),
+            ..ScanTools::default()
+        };
+
+        let devices = scan_arp_table_with(&tools).unwrap();
+        assert_eq!(devices.len(), 4);
+        assert!(devices[0].is_gateway);
+        assert!(devices[1..].iter().all(|d| !d.is_gateway));
+    }
+
+    #[test]
+    fn test_custom_arp_command_end_to_end() {
+        use crate::util::command::CommandTemplate;
+
+        let runner = MockRunner::new().on("busybox arp -n", SAMPLE_ARP_MACOS);
+        let tools = ScanTools {
+            arp: Some(CommandTemplate::parse("busybox arp -n", &[]).unwrap()),
+            runner: std::sync::Arc::new(runner),
+            ..ScanTools::default()
+        };
+
+        // The gateway lookup fails under the mock, so no entry is flagged
+        let devices = scan_arp_table_with(&tools).unwrap();
+        assert_eq!(devices.len(), 4);
+        assert!(devices.iter().all(|d| !d.is_gateway));
+    }
+
+    #[test]
+    fn test_denied_neighbor_command_is_an_error() {
+        use crate::util::command::{CommandOutput, CommandTemplate};
+
+        let denied = CommandOutput {
+            success: false,
+            stdout: String::new(),
+            stderr: "arp: bpf: Operation not permitted\n".to_string(),
+        };
+        let tools = |runner: MockRunner| ScanTools {
+            arp: Some(CommandTemplate::parse("busybox arp -n", &[]).unwrap()),
+            runner: std::sync::Arc::new(runner),
+            ..ScanTools::default()
+        };
+
+        let err = scan_arp_table_with(&tools(MockRunner::new().reply("busybox arp", denied))).unwrap_err();
+        assert_eq!(err.code, "PERMISSION_DENIED");
+
+        // An empty table is not an error
+        let empty = scan_arp_table_with(&tools(MockRunner::new().on("busybox arp", ""))).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_parse_neighbor_output_common_layouts() {
+        let bsd = parse_neighbor_output(SAMPLE_ARP_MACOS, Some("192.168.1.1"));
+        assert_eq!(bsd.len(), 4);
+        assert!(bsd[0].is_gateway);
+        assert_eq!(bsd[1].hostname.as_deref(), Some("macbook.local"));
+        assert_eq!(bsd[1].interface.as_deref(), Some("en0"));
+
+        let neigh = parse_neighbor_output(SAMPLE_ARP_LINUX, None);
+        assert!(neigh.iter().all(|d| d.interface.as_deref() == Some("eth0") && d.hostname.is_none()));
+        assert!(neigh.iter().all(|d| d.ip != "192.168.1.99"));
+
+        let proc_arp = "IP address       HW type     Flags       HW address            Mask     Device\n\
+                        192.168.1.1      0x1         0x2         aa:bb:cc:dd:ee:ff     *        eth0\n\
+                        192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        eth0\n";
+        let entries = parse_neighbor_output(proc_arp, None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].mac.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+    }
+
+    #[test]
+    fn test_scope_to_interface() {
+        let device = |ip: &str, interface: Option<&str>| DiscoveredDevice {
+            ip: ip.to_string(),
+            mac: None,
+            hostname: None,
+            is_gateway: false,
+            interface: interface.map(str::to_string),
+        };
+        let en0 = NetworkInterface {
+            id: "en0".to_string(),
+            name: "en0".to_string(),
+            ip_address: Some("192.168.1.50".to_string()),
+            subnet_mask: Some("255.255.255.0".to_string()),
+            mac_address: None,
+            gateway_ip: Some("192.168.1.1".to_string()),
+            ipv6_address: None,
+            gateway_ipv6: None,
+            is_active: true,
+            is_vpn: false,
+        };
+
+        let scoped = scope_to_interface(
+            vec![
+                device("192.168.1.1", Some("en0")),
+                device("10.8.0.1", Some("utun4")),
+                device("192.168.1.20", None),
+                device("172.16.0.5", None),
+            ],
+            &en0,
+        );
+
+        let ips: Vec<_> = scoped.iter().map(|d| d.ip.as_str()).collect();
+        assert_eq!(ips, vec!["192.168.1.1", "192.168.1.20"]);
+        assert!(scoped[0].is_gateway);
+        assert!(!scoped[1].is_gateway);
+    }
+
+    #[test]
+    fn test_scope_to_targets() {
+        let device = |ip: &str, mac: &str| DiscoveredDevice {
+            ip: ip.to_string(),
+            mac: Some(mac.to_string()),
+            hostname: None,
+            is_gateway: false,
+            interface: Some("en0".to_string()),
+        };
+        let neighbors = vec![device("192.168.1.5", "AA:00:00:00:00:05"), device("192.168.1.40", "AA:00:00:00:00:40")];
+        let targets: Vec<String> = (1..=14).map(|i| format!("192.168.1.{}", i)).collect();
+        let responders = vec!["192.168.1.5".to_string(), "192.168.1.9".to_string()];
+
+        let scoped = scope_to_targets(neighbors, &targets, &responders);
+        let ips: Vec<&str> = scoped.iter().map(|d| d.ip.as_str()).collect();
+        assert_eq!(ips, ["192.168.1.5", "192.168.1.9"]);
+        assert_eq!(scoped[0].mac.as_deref(), Some("AA:00:00:00:00:05"));
+        assert!(scoped[1].mac.is_none());
     }
 }
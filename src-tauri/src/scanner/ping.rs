@@ -1,36 +1,300 @@
 use regex::Regex;
-use std::process::Command;
 use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::scanner::budget::ScanBudget;
+use crate::scanner::probe::ProbeMethod;
+use crate::scanner::ScanTools;
+use crate::util::command::{permission_error, CommandRunner, CommandTemplate};
+use crate::AppError;
+
+/// Default per-host ping timeout.
+pub const DEFAULT_PING_TIMEOUT_MS: u64 = 2000;
+
+/// How many echo requests make up one latency measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencySampling {
+    /// Echo requests sent per host.
+    pub samples: u32,
+    /// Replies required before a latency is reported.
+    pub min_successful: u32,
+    /// Drop the slowest reply before averaging (when at least three arrived).
+    pub trim_outliers: bool,
+}
+
+impl Default for LatencySampling {
+    fn default() -> Self {
+        Self {
+            samples: 3,
+            min_successful: 2,
+            trim_outliers: true,
+        }
+    }
+}
+
+impl LatencySampling {
+    /// One echo request, reported as-is.
+    pub const SINGLE: Self = Self {
+        samples: 1,
+        min_successful: 1,
+        trim_outliers: false,
+    };
+}
+
+/// Placeholder a custom ping command must contain.
+pub const PING_PLACEHOLDER: &str = "{ip}";
+
+/// The program used for pings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum PingTool {
+    /// The platform `ping` with built-in flags.
+    #[default]
+    System,
+    /// A user template with `{ip}`, `{timeout_ms}` and `{timeout_s}` placeholders.
+    Custom(CommandTemplate),
+}
+
+impl PingTool {
+    /// `fping` takes every target in one invocation, so sweeps batch all hosts together.
+    fn is_fping(&self) -> bool {
+        matches!(self, PingTool::Custom(t) if t.program_name() == "fping")
+    }
+}
+
+/// Template variables for a ping reply timeout.
+fn timeout_vars(timeout_ms: u64) -> [(&'static str, String); 2] {
+    [
+        ("timeout_ms", timeout_ms.max(1).to_string()),
+        ("timeout_s", timeout_secs(timeout_ms).to_string()),
+    ]
+}
+
+/// A reply timeout rounded up to the whole seconds tools like iputils `ping -W` take.
+fn timeout_secs(timeout_ms: u64) -> u64 {
+    timeout_ms.div_ceil(1000).max(1)
+}
+
+/// Extra time allowed on top of the ping timeout for spawning and reaping the process.
+const PROCESS_GRACE_MS: u64 = 500;
+
+/// Platform whose `ping` flag conventions to use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PingPlatform {
+    Linux,
+    MacOs,
+    Windows,
+}
+
+impl PingPlatform {
+    fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            PingPlatform::Windows
+        } else if cfg!(target_os = "macos") {
+            PingPlatform::MacOs
+        } else {
+            PingPlatform::Linux
+        }
+    }
+}
+
+/// How long `tool` may wait for a reply given `timeout_ms`. Tools taking whole seconds wait
+/// for the rounded-up value, and a template may use `{timeout_s}`, so both count that.
+fn reply_wait_ms(platform: PingPlatform, tool: &PingTool, timeout_ms: u64) -> u64 {
+    match (tool, platform) {
+        (PingTool::System, PingPlatform::MacOs | PingPlatform::Windows) => timeout_ms.max(1),
+        _ => timeout_secs(timeout_ms) * 1000,
+    }
+}
+
+/// Build `ping` arguments for a single echo request with the given reply timeout.
+fn ping_args(platform: PingPlatform, ip: &str, timeout_ms: u64) -> Vec<String> {
+    match platform {
+        // iputils: -W is seconds (whole seconds on older versions), so round up
+        PingPlatform::Linux => {
+            vec!["-c".into(), "1".into(), "-W".into(), timeout_secs(timeout_ms).to_string(), "-n".into(), ip.into()]
+        }
+        // BSD ping: -W is milliseconds
+        PingPlatform::MacOs => {
+            vec!["-c".into(), "1".into(), "-W".into(), timeout_ms.max(1).to_string(), "-n".into(), ip.into()]
+        }
+        // Windows: -n count, -w milliseconds
+        PingPlatform::Windows => {
+            vec!["-n".into(), "1".into(), "-w".into(), timeout_ms.max(1).to_string(), ip.into()]
+        }
+    }
+}
 
 /// Ping a single IP address and return the latency in milliseconds.
 /// Uses the system `ping` command (no raw sockets needed).
-pub async fn ping(ip: &str) -> Option<f64> {
+/// `timeout_ms` drives both the command's reply timeout and the async deadline.
+pub async fn ping(ip: &str, timeout_ms: u64) -> Option<f64> {
+    ping_with(ip, timeout_ms, &ScanTools::default()).await
+}
+
+/// Ping a single IP address with the configured tool.
+pub async fn ping_with(ip: &str, timeout_ms: u64, tools: &ScanTools) -> Option<f64> {
+    ping_reply(ip, timeout_ms, tools).await.map(|r| r.latency_ms)
+}
+
+/// An answered echo request, or several combined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PingReply {
+    pub latency_ms: f64,
+    /// IP TTL of the reply, when the tool prints it. Hints at the sender's OS.
+    pub ttl: Option<u8>,
+    pub stats: PingStats,
+    /// `Tcp` when ICMP went unanswered and a TCP connect got through instead.
+    pub method: ProbeMethod,
+}
+
+/// Round trips and loss across the echo requests behind one reply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PingStats {
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    /// Variation between round trips, when more than one came back.
+    pub jitter_ms: Option<f64>,
+    /// Share of echo requests that went unanswered, 0 to 100.
+    pub loss_percent: f64,
+}
+
+impl PingReply {
+    /// A reply measured by one round trip.
+    fn single(latency_ms: f64, ttl: Option<u8>, method: ProbeMethod) -> Self {
+        Self {
+            latency_ms,
+            ttl,
+            stats: PingStats::from_samples(&[latency_ms], 1).expect("one sample"),
+            method,
+        }
+    }
+}
+
+impl PingStats {
+    /// Stats for `rtts` received out of `sent` requests. Jitter is the mean difference
+    /// between consecutive round trips. `None` when nothing came back.
+    fn from_samples(rtts: &[f64], sent: u32) -> Option<Self> {
+        let first = *rtts.first()?;
+        let sent = sent.max(rtts.len() as u32);
+        let jitter_ms = (rtts.len() > 1).then(|| {
+            rtts.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / (rtts.len() - 1) as f64
+        });
+        Some(Self {
+            min_ms: rtts.iter().copied().fold(first, f64::min),
+            avg_ms: rtts.iter().sum::<f64>() / rtts.len() as f64,
+            max_ms: rtts.iter().copied().fold(first, f64::max),
+            jitter_ms,
+            loss_percent: 100.0 * f64::from(sent - rtts.len() as u32) / f64::from(sent),
+        })
+    }
+}
+
+/// Ping a single IP address with the configured tool, keeping the reply's TTL.
+pub async fn ping_reply(ip: &str, timeout_ms: u64, tools: &ScanTools) -> Option<PingReply> {
+    ping_attempt(ip, timeout_ms, tools).await.unwrap_or_else(|e| {
+        log::debug!("{}", e.message);
+        None
+    })
+}
+
+/// One echo request. Errors only when the tool was refused for lack of privileges;
+/// an unanswered request is `Ok(None)`.
+async fn ping_attempt(ip: &str, timeout_ms: u64, tools: &ScanTools) -> Result<Option<PingReply>, AppError> {
     let ip = ip.to_string();
+    let tools = tools.clone();
+    let wait_ms = reply_wait_ms(PingPlatform::current(), &tools.ping, timeout_ms);
+    let deadline = Duration::from_millis(wait_ms + PROCESS_GRACE_MS);
 
-    tokio::task::spawn_blocking(move || ping_sync(&ip))
-        .await
-        .ok()?
+    let run = tokio::task::spawn_blocking(move || ping_sync(&ip, timeout_ms, &tools.ping, tools.runner.as_ref()));
+    match tokio::time::timeout(deadline, run).await {
+        Ok(Ok(result)) => result,
+        _ => Ok(None),
+    }
 }
 
 /// Synchronous ping using system command.
-fn ping_sync(ip: &str) -> Option<f64> {
-    let output = Command::new("ping")
-        .args(["-c", "1", "-W", "2", "-n", ip])
-        .output()
-        .ok()?;
+fn ping_sync(
+    ip: &str,
+    timeout_ms: u64,
+    tool: &PingTool,
+    runner: &dyn CommandRunner,
+) -> Result<Option<PingReply>, AppError> {
+    let (program, result) = match tool {
+        PingTool::System => ("ping", runner.run("ping", &ping_args(PingPlatform::current(), ip, timeout_ms))),
+        PingTool::Custom(template) => {
+            let [ms, secs] = timeout_vars(timeout_ms);
+            let args = template.render(&[("ip", ip), (ms.0, &ms.1), (secs.0, &secs.1)]);
+            (template.program_name(), template.run(runner, &args))
+        }
+    };
+    if let Some(denied) = permission_error(program, &result) {
+        return Err(denied);
+    }
+
+    let Ok(output) = result else {
+        return Ok(None);
+    };
+    if !output.success {
+        return Ok(None);
+    }
 
-    if !output.status.success() {
+    // A custom command may send several requests itself; its summary then speaks for all of them
+    let ttl = parse_ttl(&output.stdout);
+    if let Some(stats) = parse_ping_stats(&output.stdout) {
+        return Ok(Some(PingReply { latency_ms: stats.avg_ms, ttl, stats, method: ProbeMethod::Icmp }));
+    }
+    Ok(parse_ping_output(&output.stdout).map(|latency_ms| PingReply::single(latency_ms, ttl, ProbeMethod::Icmp)))
+}
+
+/// Read the statistics block `ping` prints after several requests:
+/// Linux and macOS "3 packets transmitted, 2 received, 33.3% packet loss" with
+/// "rtt min/avg/max/mdev = ..." (macOS: "round-trip min/avg/max/stddev"), or Windows
+/// "Sent = 3, Received = 2" with "Minimum = 1ms, Maximum = 3ms, Average = 2ms".
+/// `None` for a single request, which the reply line already covers, or when none came back.
+fn parse_ping_stats(output: &str) -> Option<PingStats> {
+    let unix_counts = Regex::new(r"(\d+) packets transmitted, (\d+) (?:packets )?received").unwrap();
+    let windows_counts = Regex::new(r"Sent = (\d+), Received = (\d+)").unwrap();
+    let counts = unix_counts.captures(output).or_else(|| windows_counts.captures(output))?;
+    let sent: u32 = counts[1].parse().ok()?;
+    let received: u32 = counts[2].parse().ok()?;
+    if sent < 2 || received == 0 || received > sent {
         return None;
     }
+    let loss_percent = 100.0 * f64::from(sent - received) / f64::from(sent);
 
-    let text = String::from_utf8_lossy(&output.stdout);
-    parse_ping_output(&text)
+    let unix_rtt = Regex::new(r"min/avg/max/\w+ = ([\d.]+)/([\d.]+)/([\d.]+)/([\d.]+)").unwrap();
+    if let Some(rtt) = unix_rtt.captures(output) {
+        return Some(PingStats {
+            min_ms: rtt[1].parse().ok()?,
+            avg_ms: rtt[2].parse().ok()?,
+            max_ms: rtt[3].parse().ok()?,
+            jitter_ms: (received > 1).then(|| rtt[4].parse().ok()).flatten(),
+            loss_percent,
+        });
+    }
+    let windows_rtt = Regex::new(r"Minimum = (\d+)ms, Maximum = (\d+)ms, Average = (\d+)ms").unwrap();
+    let rtt = windows_rtt.captures(output)?;
+    Some(PingStats {
+        min_ms: rtt[1].parse().ok()?,
+        avg_ms: rtt[3].parse().ok()?,
+        max_ms: rtt[2].parse().ok()?,
+        jitter_ms: None,
+        loss_percent,
+    })
 }
 
 /// Extract RTT from ping output.
 /// macOS format: "round-trip min/avg/max/stddev = 1.234/1.456/1.789/0.123 ms"
-/// Also matches: "time=1.234 ms" in individual ping lines
+/// Also matches: "time=1.234 ms" in individual ping lines, Windows' "time<1ms" and
+/// "Average = 1ms" summary, and fping's "is alive (1.234 ms)"
 fn parse_ping_output(output: &str) -> Option<f64> {
+    // Windows exits 0 when the local router answers "Destination host unreachable"
+    if output.contains("Destination host unreachable") {
+        return None;
+    }
+
     // Try the per-line format first
     let time_re = Regex::new(r"time[=<](\d+\.?\d*)\s*ms").unwrap();
     if let Some(caps) = time_re.captures(output) {
@@ -43,49 +307,282 @@ fn parse_ping_output(output: &str) -> Option<f64> {
         return caps[1].parse().ok();
     }
 
+    // Windows summary
+    let average_re = Regex::new(r"Average = (\d+)ms").unwrap();
+    if let Some(caps) = average_re.captures(output) {
+        return caps[1].parse().ok();
+    }
+
+    // Custom tools such as fping
+    let alive_re = Regex::new(r"(\d+\.?\d*) ms").unwrap();
+    if let Some(caps) = alive_re.captures(output) {
+        return caps[1].parse().ok();
+    }
+
     None
 }
 
-/// Ping multiple IPs concurrently, returning (ip, latency_ms) pairs.
-pub async fn ping_sweep(ips: &[String], max_concurrent: usize) -> Vec<(String, Option<f64>)> {
-    use tokio::sync::Semaphore;
-    use std::sync::Arc;
+/// Extract the reply TTL: "ttl=64" on Unix, "TTL=128" on Windows.
+fn parse_ttl(output: &str) -> Option<u8> {
+    let ttl_re = Regex::new(r"(?i)\bttl=(\d+)").unwrap();
+    ttl_re.captures(output)?[1].parse().ok()
+}
+
+/// Collect per-host RTT samples from fping output, in either `-e` form
+/// ("10.0.0.1 is alive (0.52 ms)") or `-c` form ("10.0.0.1 : [0], 64 bytes, 0.52 ms (0.52 avg, 0% loss)").
+fn parse_fping_output(output: &str) -> std::collections::HashMap<String, Vec<f64>> {
+    let re = Regex::new(r"^(\S+)\s+(?:is alive \(|:\s+\[\d+\],\s+\d+ bytes,\s+)(\d+\.?\d*) ms").unwrap();
+    let mut samples: std::collections::HashMap<String, Vec<f64>> = std::collections::HashMap::new();
+    for line in output.lines() {
+        if let Some(caps) = re.captures(line.trim()) {
+            if let Ok(ms) = caps[2].parse() {
+                samples.entry(caps[1].to_string()).or_default().push(ms);
+            }
+        }
+    }
+    samples
+}
 
-    let semaphore = Arc::new(Semaphore::new(max_concurrent));
-    let mut handles = Vec::new();
+/// Reduce RTT samples to one latency: `None` with too few replies, otherwise the mean
+/// with the slowest reply dropped when trimming.
+fn trimmed_mean(samples: &[f64], sampling: &LatencySampling) -> Option<f64> {
+    if samples.is_empty() || samples.len() < sampling.min_successful as usize {
+        return None;
+    }
+    let mut kept = samples.to_vec();
+    if sampling.trim_outliers && kept.len() >= 3 {
+        let slowest = kept
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)?;
+        kept.swap_remove(slowest);
+    }
+    Some(kept.iter().sum::<f64>() / kept.len() as f64)
+}
 
-    for ip in ips {
+/// Ping a host `sampling.samples` times in sequence and combine the replies.
+/// A single lost request does not make the host silent; it gives up once enough
+/// have been lost that `min_successful` can no longer be reached.
+/// The TTL is taken from the first reply that carried one.
+pub async fn ping_sampled(
+    ip: &str,
+    timeout_ms: u64,
+    sampling: &LatencySampling,
+    tools: &ScanTools,
+) -> Result<Option<PingReply>, AppError> {
+    let attempts = sampling.samples.max(1);
+    if attempts == 1 {
+        return ping_attempt(ip, timeout_ms, tools).await;
+    }
+    let mut replies = Vec::with_capacity(attempts as usize);
+    let mut ttl = None;
+
+    for attempt in 0..attempts {
+        match ping_attempt(ip, timeout_ms, tools).await? {
+            Some(reply) => {
+                replies.push(reply.latency_ms);
+                ttl = ttl.or(reply.ttl);
+            }
+            None => {
+                let remaining = attempts - attempt - 1;
+                if (replies.len() as u32 + remaining) < sampling.min_successful {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    let Some(stats) = PingStats::from_samples(&replies, attempts) else {
+        return Ok(None);
+    };
+    Ok(trimmed_mean(&replies, sampling).map(|latency_ms| PingReply {
+        latency_ms,
+        ttl,
+        stats,
+        method: ProbeMethod::Icmp,
+    }))
+}
+
+/// Ports tried by `tcp_ping`: web, SSH, SMB and the iOS lockdown service.
+pub const TCP_PING_PORTS: &[u16] = &[80, 443, 22, 445, 62078];
+
+/// TCP "ping": attempt connections to several ports at once and return the time
+/// to the first answer. A refused connection counts, since the host had to send the RST.
+pub async fn tcp_ping(ip: &str, ports: &[u16], timeout_ms: u64) -> Option<f64> {
+    use tokio::net::TcpStream;
+
+    let ip: std::net::IpAddr = ip.parse().ok()?;
+    let start = std::time::Instant::now();
+    let mut set = JoinSet::new();
+
+    for &port in ports {
+        set.spawn(async move {
+            let addr = std::net::SocketAddr::new(ip, port);
+            match tokio::time::timeout(Duration::from_millis(timeout_ms), TcpStream::connect(addr)).await {
+                Ok(Ok(_)) => true,
+                Ok(Err(e)) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+                Err(_) => false,
+            }
+        });
+    }
+
+    while let Some(result) = set.join_next().await {
+        if let Ok(true) = result {
+            set.abort_all();
+            return Some(start.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+
+    None
+}
+
+/// Ping multiple IPs concurrently, one `budget` slot per host, returning (ip, reply) pairs.
+/// A custom `fping` tool pings every host in a single invocation instead; fping
+/// does not print TTLs, so its replies carry none.
+/// Hosts that ignore ICMP get a TCP connect attempt on `tools.tcp_ping_ports`, so
+/// firewalled machines still count as up.
+/// Fails if the tool was refused for lack of privileges, rather than reporting every host silent.
+/// Cancelling `cancel` aborts the pings still in flight and returns only the hosts
+/// already answered, so the caller should check the token afterwards.
+pub async fn ping_sweep(
+    ips: &[String],
+    budget: &ScanBudget,
+    timeout_ms: u64,
+    sampling: LatencySampling,
+    tools: &ScanTools,
+    cancel: &CancellationToken,
+) -> Result<Vec<(String, Option<PingReply>)>, AppError> {
+    if let PingTool::Custom(template) = &tools.ping {
+        if tools.ping.is_fping() {
+            let mut results = {
+                let _permit = budget.acquire().await;
+                tokio::select! {
+                    _ = cancel.cancelled() => return Ok(Vec::new()),
+                    results = fping_sweep(template, tools, ips, timeout_ms, &sampling) => results?,
+                }
+            };
+            let mut fallbacks = JoinSet::new();
+            for (i, (ip, _)) in results.iter().enumerate().filter(|(_, (_, reply))| reply.is_none()) {
+                let (ip, budget, tools) = (ip.clone(), budget.clone(), tools.clone());
+                fallbacks.spawn(async move {
+                    let _permit = budget.acquire().await;
+                    (i, tcp_fallback(&ip, timeout_ms, &tools).await)
+                });
+            }
+            loop {
+                let joined = tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    joined = fallbacks.join_next() => joined,
+                };
+                let Some(joined) = joined else { break };
+                if let Ok((i, reply)) = joined {
+                    results[i].1 = reply;
+                }
+            }
+            return Ok(results);
+        }
+    }
+
+    // Dropping the set, on cancellation or a failed ping, aborts the pings still running
+    let mut sweep = JoinSet::new();
+    for (i, ip) in ips.iter().enumerate() {
         let ip = ip.clone();
-        let sem = semaphore.clone();
-
-        let handle = tokio::spawn(async move {
-            let _permit = sem.acquire().await.unwrap();
-            let latency = tokio::time::timeout(
-                Duration::from_secs(3),
-                ping(&ip),
-            )
-            .await
-            .ok()
-            .flatten();
-
-            (ip, latency)
+        let budget = budget.clone();
+        let tools = tools.clone();
+
+        sweep.spawn(async move {
+            let _permit = budget.acquire().await;
+            let reply = match ping_sampled(&ip, timeout_ms, &sampling, &tools).await {
+                Ok(None) => Ok(tcp_fallback(&ip, timeout_ms, &tools).await),
+                reply => reply,
+            };
+
+            (i, reply)
         });
-        handles.push(handle);
     }
 
-    let mut results = Vec::new();
-    for handle in handles {
-        if let Ok(result) = handle.await {
-            results.push(result);
+    let mut replies = vec![None; ips.len()];
+    loop {
+        let joined = tokio::select! {
+            _ = cancel.cancelled() => break,
+            joined = sweep.join_next() => joined,
+        };
+        let Some(joined) = joined else { break };
+        if let Ok((i, reply)) = joined {
+            replies[i] = Some(reply?);
         }
     }
 
-    results
+    Ok(ips
+        .iter()
+        .zip(replies)
+        .filter_map(|(ip, reply)| Some((ip.clone(), reply?)))
+        .collect())
+}
+
+/// Run one `fping` over every host. The template's own flags decide how many echo
+/// requests go out, so `min_successful` is capped at the most replies any host returned,
+/// and loss is counted against that same number.
+async fn fping_sweep(
+    template: &CommandTemplate,
+    tools: &ScanTools,
+    ips: &[String],
+    timeout_ms: u64,
+    sampling: &LatencySampling,
+) -> Result<Vec<(String, Option<PingReply>)>, AppError> {
+    let [ms, secs] = timeout_vars(timeout_ms);
+    let args = template.render_list("ip", ips, &[(ms.0, &ms.1), (secs.0, &secs.1)]);
+    let template = template.clone();
+    let runner = tools.runner.clone();
+
+    // fping paces its probes (~10ms apart by default), so allow for every host and sample
+    let wait_ms = reply_wait_ms(PingPlatform::current(), &tools.ping, timeout_ms);
+    let budget_ms = wait_ms * u64::from(sampling.samples.max(1)) + 25 * ips.len() as u64;
+    let deadline = Duration::from_millis(budget_ms + PROCESS_GRACE_MS);
+    let program = template.program_name().to_string();
+    let run = tokio::task::spawn_blocking(move || template.run(runner.as_ref(), &args));
+
+    // fping exits non-zero when any host is unreachable, so the status is ignored
+    let samples = match tokio::time::timeout(deadline, run).await {
+        Ok(Ok(result)) if permission_error(&program, &result).is_some() => {
+            return Err(AppError::permission_denied(&program));
+        }
+        Ok(Ok(Ok(output))) => parse_fping_output(&(output.stdout + &output.stderr)),
+        _ => {
+            log::warn!("fping sweep of {} hosts failed or timed out", ips.len());
+            Default::default()
+        }
+    };
+
+    let most = samples.values().map(|s| s.len()).max().unwrap_or(0) as u32;
+    let effective = LatencySampling {
+        min_successful: sampling.min_successful.min(most.max(1)),
+        ..*sampling
+    };
+    Ok(ips
+        .iter()
+        .map(|ip| {
+            let reply = samples.get(ip).and_then(|s| {
+                let latency_ms = trimmed_mean(s, &effective)?;
+                let stats = PingStats::from_samples(s, most)?;
+                Some(PingReply { latency_ms, ttl: None, stats, method: ProbeMethod::Icmp })
+            });
+            (ip.clone(), reply)
+        })
+        .collect())
+}
+
+/// A TCP connect to one of `tools.tcp_ping_ports`, for a host that ignored ICMP.
+async fn tcp_fallback(ip: &str, timeout_ms: u64, tools: &ScanTools) -> Option<PingReply> {
+    let latency_ms = tcp_ping(ip, &tools.tcp_ping_ports, timeout_ms).await?;
+    Some(PingReply::single(latency_ms, None, ProbeMethod::Tcp))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::command::{CommandOutput, MockRunner};
 
     #[test]
     fn test_parse_ping_time_equals() {
@@ -107,9 +604,271 @@ mod tests {
         assert_eq!(parse_ping_output(output), Some(2.567));
     }
 
+    const SAMPLE_PING_WINDOWS: &str = "Pinging 192.168.1.1 with 32 bytes of data:
+Reply from 192.168.1.1: bytes=32 time=4ms TTL=64
+
+Ping statistics for 192.168.1.1:
+    Packets: Sent = 1, Received = 1, Lost = 0 (0% loss),
+Approximate round trip times in milli-seconds:
+    Minimum = 4ms, Maximum = 4ms, Average = 4ms";
+
+    #[test]
+    fn test_parse_ping_windows() {
+        assert_eq!(parse_ping_output(SAMPLE_PING_WINDOWS), Some(4.0));
+        assert_eq!(
+            parse_ping_output("Reply from 10.0.0.1: bytes=32 time<1ms TTL=128"),
+            Some(1.0)
+        );
+
+        // Summary only, e.g. when reply lines were filtered out
+        let summary = "    Minimum = 2ms, Maximum = 9ms, Average = 5ms";
+        assert_eq!(parse_ping_output(summary), Some(5.0));
+
+        let unreachable = "Pinging 10.0.0.9 with 32 bytes of data:
+Reply from 10.0.0.2: Destination host unreachable.
+
+Ping statistics for 10.0.0.9:
+    Packets: Sent = 1, Received = 1, Lost = 0 (0% loss),";
+        assert_eq!(parse_ping_output(unreachable), None);
+    }
+
+    #[test]
+    fn test_parse_multi_sample_summaries() {
+        let linux = "PING 10.0.0.1 (10.0.0.1) 56(84) bytes of data.
+64 bytes from 10.0.0.1: icmp_seq=1 ttl=64 time=0.512 ms
+64 bytes from 10.0.0.1: icmp_seq=3 ttl=64 time=0.768 ms
+
+--- 10.0.0.1 ping statistics ---
+3 packets transmitted, 2 received, 33.3333% packet loss, time 2003ms
+rtt min/avg/max/mdev = 0.512/0.640/0.768/0.128 ms";
+        let stats = parse_ping_stats(linux).unwrap();
+        assert_eq!((stats.min_ms, stats.avg_ms, stats.max_ms), (0.512, 0.640, 0.768));
+        assert_eq!(stats.jitter_ms, Some(0.128));
+        assert!((stats.loss_percent - 100.0 / 3.0).abs() < 1e-9);
+
+        let macos = "--- 10.0.0.1 ping statistics ---
+4 packets transmitted, 4 packets received, 0.0% packet loss
+round-trip min/avg/max/stddev = 1.100/1.400/2.000/0.350 ms";
+        let stats = parse_ping_stats(macos).unwrap();
+        assert_eq!((stats.avg_ms, stats.loss_percent), (1.4, 0.0));
+
+        let windows = "Ping statistics for 10.0.0.1:
+    Packets: Sent = 4, Received = 3, Lost = 1 (25% loss),
+Approximate round trip times in milli-seconds:
+    Minimum = 1ms, Maximum = 9ms, Average = 4ms";
+        let stats = parse_ping_stats(windows).unwrap();
+        assert_eq!((stats.min_ms, stats.avg_ms, stats.max_ms), (1.0, 4.0, 9.0));
+        assert_eq!((stats.jitter_ms, stats.loss_percent), (None, 25.0));
+
+        // One request is left to the reply line; no replies means no stats
+        assert!(parse_ping_stats(SAMPLE_PING_WINDOWS).is_none());
+        assert!(parse_ping_stats("3 packets transmitted, 0 received, 100% packet loss").is_none());
+    }
+
+    #[test]
+    fn test_stats_from_samples() {
+        let stats = PingStats::from_samples(&[2.0, 4.0, 3.0], 4).unwrap();
+        assert_eq!((stats.min_ms, stats.avg_ms, stats.max_ms), (2.0, 3.0, 4.0));
+        assert_eq!(stats.jitter_ms, Some(1.5));
+        assert_eq!(stats.loss_percent, 25.0);
+
+        assert_eq!(PingStats::from_samples(&[5.0], 1).unwrap().jitter_ms, None);
+        assert!(PingStats::from_samples(&[], 3).is_none());
+    }
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("64 bytes from 192.168.1.1: icmp_seq=0 ttl=64 time=1.234 ms"), Some(64));
+        assert_eq!(parse_ttl(SAMPLE_PING_WINDOWS), Some(64));
+        assert_eq!(parse_ttl("Reply from 10.0.0.1: bytes=32 time<1ms TTL=128"), Some(128));
+        assert_eq!(parse_ttl("round-trip min/avg/max/stddev = 1.234/2.567/3.890/0.456 ms"), None);
+        assert_eq!(parse_ttl("ttl=300"), None);
+    }
+
     #[test]
     fn test_parse_ping_no_response() {
         let output = "Request timeout for icmp_seq 0";
         assert_eq!(parse_ping_output(output), None);
     }
+
+    #[test]
+    fn test_parse_fping_alive_and_count_forms() {
+        let output = "10.0.0.1 is alive (0.52 ms)\n10.0.0.9 is unreachable\n";
+        let samples = parse_fping_output(output);
+        assert_eq!(samples["10.0.0.1"], vec![0.52]);
+        assert!(!samples.contains_key("10.0.0.9"));
+
+        let output = "10.0.0.2 : [0], 64 bytes, 1.10 ms (1.10 avg, 0% loss)\n\
+                      10.0.0.2 : [1], 64 bytes, 1.30 ms (1.20 avg, 0% loss)\n\
+                      10.0.0.2 : xmt/rcv/%loss = 2/2/0%, min/avg/max = 1.10/1.20/1.30\n";
+        assert_eq!(parse_fping_output(output)["10.0.0.2"], vec![1.10, 1.30]);
+
+        assert_eq!(parse_ping_output("10.0.0.1 is alive (0.52 ms)"), Some(0.52));
+    }
+
+    #[test]
+    fn test_timeout_vars() {
+        let [ms, secs] = timeout_vars(1500);
+        assert_eq!(ms, ("timeout_ms", "1500".to_string()));
+        assert_eq!(secs, ("timeout_s", "2".to_string()));
+    }
+
+    #[test]
+    fn test_ping_args_linux_rounds_up_to_seconds() {
+        assert_eq!(
+            ping_args(PingPlatform::Linux, "10.0.0.1", 1500),
+            ["-c", "1", "-W", "2", "-n", "10.0.0.1"]
+        );
+        // Sub-second timeouts still wait at least one second
+        assert_eq!(ping_args(PingPlatform::Linux, "10.0.0.1", 200)[3], "1");
+    }
+
+    #[test]
+    fn test_deadline_covers_rounded_timeout() {
+        // A 200ms timeout makes iputils wait a full second, so the deadline must too
+        assert_eq!(reply_wait_ms(PingPlatform::Linux, &PingTool::System, 200), 1000);
+        assert_eq!(reply_wait_ms(PingPlatform::Linux, &PingTool::System, 1500), 2000);
+        assert_eq!(reply_wait_ms(PingPlatform::MacOs, &PingTool::System, 200), 200);
+        assert_eq!(reply_wait_ms(PingPlatform::Windows, &PingTool::System, 750), 750);
+
+        let template = CommandTemplate::parse("busybox ping -c 1 -W {timeout_s} {ip}", &[PING_PLACEHOLDER]).unwrap();
+        assert_eq!(reply_wait_ms(PingPlatform::MacOs, &PingTool::Custom(template), 200), 1000);
+    }
+
+    #[test]
+    fn test_ping_args_macos_uses_milliseconds() {
+        assert_eq!(
+            ping_args(PingPlatform::MacOs, "10.0.0.1", 1500),
+            ["-c", "1", "-W", "1500", "-n", "10.0.0.1"]
+        );
+    }
+
+    #[test]
+    fn test_ping_args_windows() {
+        assert_eq!(
+            ping_args(PingPlatform::Windows, "10.0.0.1", 750),
+            ["-n", "1", "-w", "750", "10.0.0.1"]
+        );
+    }
+
+    #[test]
+    fn test_trimmed_mean_drops_spike() {
+        let sampling = LatencySampling::default();
+        assert_eq!(trimmed_mean(&[2.0, 4.0, 250.0], &sampling), Some(3.0));
+
+        // Too few replies to trust
+        assert_eq!(trimmed_mean(&[2.0], &sampling), None);
+        assert_eq!(trimmed_mean(&[], &LatencySampling::SINGLE), None);
+
+        // Two replies are averaged without trimming
+        assert_eq!(trimmed_mean(&[2.0, 4.0], &sampling), Some(3.0));
+
+        let untrimmed = LatencySampling { trim_outliers: false, ..sampling };
+        assert_eq!(trimmed_mean(&[3.0, 3.0, 9.0], &untrimmed), Some(5.0));
+        assert_eq!(trimmed_mean(&[1.5], &LatencySampling::SINGLE), Some(1.5));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_ping_open_and_refused_ports() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap().port();
+        assert!(tcp_ping("127.0.0.1", &[open], 500).await.is_some());
+
+        // Nothing listening: the RST still proves the host is up
+        drop(listener);
+        assert!(tcp_ping("127.0.0.1", &[open], 500).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_ping_no_ports_or_bad_ip() {
+        assert!(tcp_ping("127.0.0.1", &[], 500).await.is_none());
+        assert!(tcp_ping("not-an-ip", &[80], 500).await.is_none());
+    }
+
+    fn mock_tools(ping: PingTool, runner: MockRunner) -> ScanTools {
+        ScanTools {
+            ping,
+            runner: std::sync::Arc::new(runner),
+            // Keep sweeps off the real network
+            tcp_ping_ports: Vec::new(),
+            ..ScanTools::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ping_with_system_tool_parses_reply() {
+        let runner = MockRunner::new()
+            .on("ping -c 1 -W 1 -n 10.0.0.1", "64 bytes from 10.0.0.1: icmp_seq=1 ttl=64 time=3.25 ms")
+            .reply("ping", CommandOutput { success: false, ..CommandOutput::default() });
+        let tools = mock_tools(PingTool::System, runner);
+
+        if PingPlatform::current() == PingPlatform::Linux {
+            assert_eq!(ping_with("10.0.0.1", 1000, &tools).await, Some(3.25));
+            assert_eq!(ping_reply("10.0.0.1", 1000, &tools).await.and_then(|r| r.ttl), Some(64));
+        }
+        // A non-zero exit means no reply, whatever was printed
+        assert!(ping_with("10.0.0.2", 1000, &tools).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fping_sweep_runs_once() {
+        let template = CommandTemplate::parse("fping -e -t{timeout_ms} {ip}", &[PING_PLACEHOLDER]).unwrap();
+        let runner = std::sync::Arc::new(MockRunner::new().on(
+            "fping -e -t500 10.0.0.1 10.0.0.2",
+            "10.0.0.1 is alive (0.52 ms)\n10.0.0.2 is unreachable\n",
+        ));
+        let tools = ScanTools {
+            ping: PingTool::Custom(template),
+            runner: runner.clone(),
+            tcp_ping_ports: Vec::new(),
+            ..ScanTools::default()
+        };
+
+        let ips = vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        let results = ping_sweep(&ips, &ScanBudget::new(4), 500, LatencySampling::default(), &tools, &CancellationToken::new()).await.unwrap();
+        let reply = PingReply::single(0.52, None, ProbeMethod::Icmp);
+        assert_eq!(results, [("10.0.0.1".to_string(), Some(reply)), ("10.0.0.2".to_string(), None)]);
+        assert_eq!(runner.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_falls_back_to_tcp_connect() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let silent = CommandOutput { success: false, ..CommandOutput::default() };
+        let tools = ScanTools {
+            tcp_ping_ports: vec![listener.local_addr().unwrap().port()],
+            ..mock_tools(PingTool::System, MockRunner::new().reply("ping", silent))
+        };
+
+        let ips = vec!["127.0.0.1".to_string()];
+        let results = ping_sweep(&ips, &ScanBudget::new(4), 500, LatencySampling::SINGLE, &tools, &CancellationToken::new()).await.unwrap();
+        let reply = results[0].1.expect("connect succeeded");
+        assert_eq!(reply.method, ProbeMethod::Tcp);
+        assert!(reply.latency_ms >= 0.0);
+        assert_eq!(reply.ttl, None);
+    }
+
+    #[tokio::test]
+    async fn test_denied_ping_fails_the_sweep() {
+        let denied = |stderr: &str| CommandOutput { success: false, stdout: String::new(), stderr: stderr.to_string() };
+        let ips = vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        let sweep = |runner: MockRunner| {
+            let ips = ips.clone();
+            async move {
+                let tools = mock_tools(PingTool::System, runner);
+                ping_sweep(&ips, &ScanBudget::new(4), 500, LatencySampling::SINGLE, &tools, &CancellationToken::new()).await
+            }
+        };
+
+        // Captured from an unprivileged Linux ping and from macOS
+        for stderr in ["ping: socket: Operation not permitted\n", "ping: sendto: Permission denied\n"] {
+            let err = sweep(MockRunner::new().reply("ping", denied(stderr))).await.unwrap_err();
+            assert_eq!(err.code, "PERMISSION_DENIED");
+        }
+
+        // Hosts that simply don't answer are not an error
+        let silent = denied("1 packets transmitted, 0 received, 100% packet loss\n");
+        let results = sweep(MockRunner::new().reply("ping", silent)).await.unwrap();
+        assert!(results.iter().all(|(_, reply)| reply.is_none()));
+    }
 }
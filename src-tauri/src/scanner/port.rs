@@ -1,15 +1,40 @@
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 use std::sync::Arc;
 use std::time::Duration;
+use serde::{Deserialize, Serialize};
 use tokio::net::TcpStream;
-use tokio::sync::Semaphore;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+
+use crate::scanner::budget::ScanBudget;
+use crate::scanner::services;
+
+/// Transport a port was scanned over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl Protocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        }
+    }
+}
 
 /// Result of scanning a single port.
 #[derive(Debug, Clone)]
 pub struct PortResult {
     pub port: u16,
+    pub protocol: Protocol,
     pub state: PortState,
     pub service_name: Option<String>,
     pub banner: Option<String>,
@@ -20,6 +45,10 @@ pub enum PortState {
     Open,
     Closed,
     Filtered,
+    /// UDP only: no reply and no ICMP port-unreachable. UDP services need not answer
+    /// a probe they don't understand, and firewalls drop datagrams silently, so an
+    /// open port and a filtered one look the same from here.
+    OpenFiltered,
 }
 
 impl std::fmt::Display for PortState {
@@ -28,42 +57,141 @@ impl std::fmt::Display for PortState {
             PortState::Open => write!(f, "open"),
             PortState::Closed => write!(f, "closed"),
             PortState::Filtered => write!(f, "filtered"),
+            PortState::OpenFiltered => write!(f, "open|filtered"),
         }
     }
 }
 
-/// Scan multiple ports on a target IP.
+/// Hosts whose ports are scanned at once. Every probe still draws from the shared
+/// budget, so this bounds how many hosts are in progress, not how many sockets.
+pub const PARALLEL_HOSTS: usize = 4;
+
+/// Open ports found on one host by `scan_hosts`.
+#[derive(Debug)]
+pub struct HostScan {
+    /// Position of the host in the list passed to `scan_hosts`.
+    pub index: usize,
+    pub ip: String,
+    pub results: Vec<PortResult>,
+    pub elapsed: Duration,
+}
+
+/// One port probe finished on the host at `index` in the list passed to `scan_hosts`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PortTick {
+    pub index: usize,
+    pub port: u16,
+}
+
+/// Scan TCP and UDP ports on each of `hosts`, up to `parallel` hosts at a time.
+/// Hosts finish in any order; each result arrives on the set as it completes.
+/// When `ticks` is given, every finished probe is reported on it as it completes.
+/// Aborting the set also aborts the probes of the hosts in progress.
+pub fn scan_hosts(
+    hosts: &[String],
+    tcp_ports: &[u16],
+    udp_ports: &[u16],
+    budget: &ScanBudget,
+    timeout_ms: u64,
+    parallel: usize,
+    ticks: Option<mpsc::UnboundedSender<PortTick>>,
+) -> JoinSet<HostScan> {
+    let slots = Arc::new(Semaphore::new(parallel.max(1)));
+    let tcp_ports: Arc<[u16]> = tcp_ports.into();
+    let udp_ports: Arc<[u16]> = udp_ports.into();
+    let mut set = JoinSet::new();
+
+    for (index, ip) in hosts.iter().enumerate() {
+        let (ip, budget, slots) = (ip.clone(), budget.clone(), slots.clone());
+        let (tcp_ports, udp_ports) = (tcp_ports.clone(), udp_ports.clone());
+        let ticks = ticks.clone();
+        set.spawn(async move {
+            let _slot = slots.acquire_owned().await.expect("host slots are never closed");
+            let start = std::time::Instant::now();
+            let on_done = |port: u16| {
+                if let Some(ref ticks) = ticks {
+                    let _ = ticks.send(PortTick { index, port });
+                }
+            };
+            let running = CancellationToken::new();
+            let mut results = scan_tcp_reporting(&ip, &tcp_ports, &budget, timeout_ms, &running, &on_done).await;
+            if !udp_ports.is_empty() {
+                results.extend(scan_udp_reporting(&ip, &udp_ports, &budget, timeout_ms, &running, &on_done).await);
+            }
+            HostScan {
+                index,
+                ip,
+                results,
+                elapsed: start.elapsed(),
+            }
+        });
+    }
+    set
+}
+
+/// Scan multiple ports on a target IP, one `budget` slot per connection attempt.
+/// Cancelling `cancel` aborts the attempts still running and returns the open ports
+/// found so far.
 pub async fn scan_ports(
     ip: &str,
     ports: &[u16],
-    max_concurrent: usize,
+    budget: &ScanBudget,
     timeout_ms: u64,
+    cancel: &CancellationToken,
+) -> Vec<PortResult> {
+    scan_tcp_reporting(ip, ports, budget, timeout_ms, cancel, &|_| {}).await
+}
+
+/// `scan_ports`, calling `on_done` with each port once its attempt has finished.
+async fn scan_tcp_reporting(
+    ip: &str,
+    ports: &[u16],
+    budget: &ScanBudget,
+    timeout_ms: u64,
+    cancel: &CancellationToken,
+    on_done: &(dyn Fn(u16) + Sync),
 ) -> Vec<PortResult> {
-    let semaphore = Arc::new(Semaphore::new(max_concurrent));
     let services = service_map();
-    let mut handles = Vec::new();
+    let mut probes = JoinSet::new();
 
     for &port in ports {
         let ip = ip.to_string();
-        let sem = semaphore.clone();
+        let budget = budget.clone();
         let services = services.clone();
 
-        let handle = tokio::spawn(async move {
-            let _permit = sem.acquire().await.unwrap();
-            scan_single_port(&ip, port, timeout_ms, &services).await
+        probes.spawn(async move {
+            let _permit = budget.acquire().await;
+            (port, scan_single_port(&ip, port, timeout_ms, &services).await)
         });
-        handles.push(handle);
     }
 
+    collect_open(probes, cancel, on_done).await
+}
+
+/// Gather the open ports from finished probes, in port order, calling `on_done` for
+/// each finished probe. On cancellation the probes still running are aborted.
+async fn collect_open(
+    mut probes: JoinSet<(u16, Result<PortResult, ()>)>,
+    cancel: &CancellationToken,
+    on_done: &(dyn Fn(u16) + Sync),
+) -> Vec<PortResult> {
     let mut results = Vec::new();
-    for handle in handles {
-        if let Ok(Ok(result)) = handle.await {
+    loop {
+        let joined = tokio::select! {
+            _ = cancel.cancelled() => break,
+            joined = probes.join_next() => joined,
+        };
+        let Some(joined) = joined else { break };
+        let Ok((port, outcome)) = joined else { continue };
+        on_done(port);
+        if let Ok(result) = outcome {
             // Only include open ports to reduce noise
             if result.state == PortState::Open {
                 results.push(result);
             }
         }
     }
+    probes.abort_all();
 
     results.sort_by_key(|r| r.port);
     results
@@ -82,11 +210,16 @@ async fn scan_single_port(
     match timeout(Duration::from_millis(timeout_ms), TcpStream::connect(addr)).await {
         Ok(Ok(stream)) => {
             // Port is open — try banner grab
-            let banner = grab_banner(stream, port).await;
+            let (banner, detected) = grab_banner(stream, port).await;
             Ok(PortResult {
                 port,
+                protocol: Protocol::Tcp,
                 state: PortState::Open,
-                service_name,
+                // A service recognised from the banner beats the registered name
+                service_name: detected
+                    .map(|s| s.to_string())
+                    .or(service_name)
+                    .or_else(|| services::lookup(port, Protocol::Tcp).map(|s| s.to_string())),
                 banner,
             })
         }
@@ -94,6 +227,7 @@ async fn scan_single_port(
             // Connection refused — port is closed
             Ok(PortResult {
                 port,
+                protocol: Protocol::Tcp,
                 state: PortState::Closed,
                 service_name: None,
                 banner: None,
@@ -103,6 +237,7 @@ async fn scan_single_port(
             // Timeout — port is filtered
             Ok(PortResult {
                 port,
+                protocol: Protocol::Tcp,
                 state: PortState::Filtered,
                 service_name: None,
                 banner: None,
@@ -111,26 +246,276 @@ async fn scan_single_port(
     }
 }
 
-/// Attempt to read a service banner from an open port.
-async fn grab_banner(stream: TcpStream, _port: u16) -> Option<String> {
+/// How to coax a banner out of a TCP service.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BannerProbe {
+    /// Send a bare HTTP request; web servers say nothing until asked.
+    Http,
+    /// Send a TLS ClientHello; any handshake or alert record back confirms TLS.
+    Tls,
+    /// Only listen: SSH, SMTP, FTP and friends greet the client first.
+    Listen,
+}
+
+fn banner_probe(port: u16) -> BannerProbe {
+    match port {
+        80 | 8000 | 8008 | 8080 | 8888 => BannerProbe::Http,
+        443 | 8443 => BannerProbe::Tls,
+        _ => BannerProbe::Listen,
+    }
+}
+
+const HTTP_REQUEST: &[u8] = b"GET / HTTP/1.0\r\n\r\n";
+
+/// TLS 1.2 ClientHello offering common AES-GCM and AES-CBC suites, with no extensions.
+const TLS_CLIENT_HELLO: &[u8] = &[
+    0x16, 0x03, 0x01, 0x00, 0x33, // record: handshake, TLS 1.0 framing, 51 bytes
+    0x01, 0x00, 0x00, 0x2f, // ClientHello, 47 bytes
+    0x03, 0x03, // client version TLS 1.2
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+    0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+    0x1e, 0x1f, // random
+    0x00, // no session id
+    0x00, 0x08, 0xc0, 0x2f, 0xc0, 0x30, 0x00, 0x9c, 0x00, 0x2f, // cipher suites
+    0x01, 0x00, // null compression
+];
+
+/// Attempt to read a service banner from an open port. Returns the banner and,
+/// when the reply identifies the protocol, the service name to record.
+async fn grab_banner(stream: TcpStream, port: u16) -> (Option<String>, Option<&'static str>) {
+    probe_banner(stream, banner_probe(port)).await
+}
+
+async fn probe_banner(mut stream: TcpStream, probe: BannerProbe) -> (Option<String>, Option<&'static str>) {
+    use tokio::io::AsyncWriteExt;
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(1);
+    let request = match probe {
+        BannerProbe::Http => Some(HTTP_REQUEST),
+        BannerProbe::Tls => Some(TLS_CLIENT_HELLO),
+        BannerProbe::Listen => None,
+    };
+    if let Some(request) = request {
+        // Best effort: a server that hangs up on the probe just yields no banner
+        if !matches!(tokio::time::timeout_at(deadline, stream.write_all(request)).await, Ok(Ok(()))) {
+            return (None, None);
+        }
+    }
+
+    let reply = read_reply(&mut stream, probe, deadline).await;
+    parse_banner(probe, &reply)
+}
+
+/// Read until the deadline, the peer closes, or enough has arrived to parse.
+async fn read_reply(stream: &mut TcpStream, probe: BannerProbe, deadline: tokio::time::Instant) -> Vec<u8> {
     use tokio::io::AsyncReadExt;
 
-    let mut stream = stream;
-    let mut buf = vec![0u8; 256];
-
-    match timeout(Duration::from_secs(1), stream.read(&mut buf)).await {
-        Ok(Ok(n)) if n > 0 => {
-            let banner = String::from_utf8_lossy(&buf[..n])
-                .trim()
-                .to_string();
-            if !banner.is_empty() {
-                Some(banner)
-            } else {
-                None
-            }
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 1024];
+    while reply.len() < 4096 {
+        match tokio::time::timeout_at(deadline, stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => reply.extend_from_slice(&buf[..n]),
+            _ => break,
+        }
+        let complete = match probe {
+            BannerProbe::Http => reply.windows(4).any(|w| w == b"\r\n\r\n"),
+            // A record header is all that is needed to recognise TLS
+            BannerProbe::Tls => reply.len() >= 5,
+            BannerProbe::Listen => true,
+        };
+        if complete {
+            break;
         }
-        _ => None,
     }
+    reply
+}
+
+/// Turn a probe reply into a banner and, when recognised, a service name.
+fn parse_banner(probe: BannerProbe, reply: &[u8]) -> (Option<String>, Option<&'static str>) {
+    if probe == BannerProbe::Tls {
+        // 0x16 handshake (ServerHello) or 0x15 alert, with a 3.x record version
+        let is_tls = reply.len() >= 5 && matches!(reply[0], 0x15 | 0x16) && reply[1] == 0x03;
+        return (None, is_tls.then_some("https"));
+    }
+
+    let text = String::from_utf8_lossy(reply);
+    let first_line = text.lines().next().unwrap_or("").trim();
+
+    if first_line.starts_with("HTTP/") {
+        let server = text
+            .lines()
+            .take_while(|l| !l.trim().is_empty())
+            .find_map(|l| {
+                let (name, value) = l.split_once(':')?;
+                name.trim().eq_ignore_ascii_case("server").then(|| value.trim().to_string())
+            })
+            .filter(|v| !v.is_empty());
+        return (server.or_else(|| Some(first_line.to_string())), Some("http"));
+    }
+
+    if first_line.starts_with("SSH-") {
+        return (Some(first_line.to_string()), Some("ssh"));
+    }
+
+    let banner = text.trim();
+    ((!banner.is_empty()).then(|| banner.to_string()), None)
+}
+
+/// Scan UDP ports on a target IP, one `budget` slot per probe. Only ports that
+/// answered are returned: silence is `OpenFiltered` and too ambiguous to record.
+/// Cancelling `cancel` stops waiting on the probes still running.
+pub async fn scan_udp_ports(
+    ip: &str,
+    ports: &[u16],
+    budget: &ScanBudget,
+    timeout_ms: u64,
+    cancel: &CancellationToken,
+) -> Vec<PortResult> {
+    scan_udp_reporting(ip, ports, budget, timeout_ms, cancel, &|_| {}).await
+}
+
+/// `scan_udp_ports`, calling `on_done` with each port once its probe has finished.
+async fn scan_udp_reporting(
+    ip: &str,
+    ports: &[u16],
+    budget: &ScanBudget,
+    timeout_ms: u64,
+    cancel: &CancellationToken,
+    on_done: &(dyn Fn(u16) + Sync),
+) -> Vec<PortResult> {
+    let services = udp_service_map();
+    let mut probes = JoinSet::new();
+
+    for &port in ports {
+        let ip = ip.to_string();
+        let budget = budget.clone();
+        let services = services.clone();
+
+        probes.spawn(async move {
+            let _permit = budget.acquire().await;
+            (port, scan_single_udp_port(&ip, port, timeout_ms, &services).await)
+        });
+    }
+
+    collect_open(probes, cancel, on_done).await
+}
+
+/// Probe a single UDP port.
+async fn scan_single_udp_port(
+    ip: &str,
+    port: u16,
+    timeout_ms: u64,
+    services: &HashMap<u16, &'static str>,
+) -> Result<PortResult, ()> {
+    let addr: SocketAddr = format!("{}:{}", ip, port).parse().map_err(|_| ())?;
+    let (state, banner) =
+        tokio::task::spawn_blocking(move || probe_udp(addr, Duration::from_millis(timeout_ms)))
+            .await
+            .map_err(|_| ())?;
+
+    Ok(PortResult {
+        port,
+        protocol: Protocol::Udp,
+        service_name: (state == PortState::Open)
+            .then(|| service_name(services, port, Protocol::Udp))
+            .flatten(),
+        state,
+        banner,
+    })
+}
+
+/// Send the port's probe and wait for a reply. The socket is connected so the
+/// kernel reports an ICMP port-unreachable back to us as a refused receive; this
+/// uses a blocking socket because tokio's readiness never wakes on that error alone.
+fn probe_udp(addr: SocketAddr, wait: Duration) -> (PortState, Option<String>) {
+    let local: SocketAddr = if addr.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let Ok(socket) = UdpSocket::bind(local) else {
+        return (PortState::Filtered, None);
+    };
+    let wait = wait.max(Duration::from_millis(1));
+    if socket.connect(addr).is_err() || socket.set_read_timeout(Some(wait)).is_err() {
+        return (PortState::Filtered, None);
+    }
+
+    let mut buf = [0u8; 512];
+    match socket.send(udp_probe(addr.port())).and_then(|_| socket.recv(&mut buf)) {
+        Ok(n) => (PortState::Open, udp_banner(&buf[..n])),
+        // Windows reports the ICMP error as a reset rather than a refusal
+        Err(e) if matches!(e.kind(), ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset) => {
+            (PortState::Closed, None)
+        }
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            (PortState::OpenFiltered, None)
+        }
+        Err(_) => (PortState::Filtered, None),
+    }
+}
+
+/// Payload most likely to draw a reply from the service usually on `port`.
+/// Unknown ports get an empty datagram, which only some services answer.
+fn udp_probe(port: u16) -> &'static [u8] {
+    match port {
+        // DNS: standard query for the root NS records
+        53 => &[0x13, 0x37, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1],
+        // NTP: version 3 client request
+        123 => &NTP_REQUEST,
+        // NetBIOS name service: node status request for "*"
+        137 => &[
+            0x13, 0x37, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0x20, b'C', b'K', b'A', b'A', b'A', b'A',
+            b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A',
+            b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', 0, 0, 0x21,
+            0, 1,
+        ],
+        // SNMPv1 get-request for sysDescr.0 with community "public"
+        161 => &[
+            0x30, 0x26, 0x02, 0x01, 0x00, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xa0,
+            0x19, 0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30, 0x0c,
+            0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x05, 0x00,
+        ],
+        1900 => b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 1\r\nST: ssdp:all\r\n\r\n",
+        // mDNS: unicast query for the DNS-SD service list
+        5353 => &[
+            0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 9, b'_', b's', b'e', b'r', b'v', b'i', b'c', b'e',
+            b's', 7, b'_', b'd', b'n', b's', b'-', b's', b'd', 4, b'_', b'u', b'd', b'p', 5, b'l',
+            b'o', b'c', b'a', b'l', 0, 0, 12, 0x80, 1,
+        ],
+        _ => &[],
+    }
+}
+
+const NTP_REQUEST: [u8; 48] = {
+    let mut packet = [0u8; 48];
+    packet[0] = 0x1b; // LI 0, version 3, mode 3 (client)
+    packet
+};
+
+/// Printable text from a UDP reply, for services like SSDP that answer in ASCII.
+fn udp_banner(reply: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(reply).ok()?.trim();
+    (!text.is_empty() && text.chars().all(|c| !c.is_control() || c.is_whitespace()))
+        .then(|| text.lines().next().unwrap_or(text).to_string())
+}
+
+/// Common UDP services, probed when a scan includes UDP and no custom range is set.
+pub fn top_udp_ports() -> Vec<u16> {
+    vec![
+        53, 67, 68, 69, 123, 137, 138, 161, 162, 500, 514, 520, 631, 1434, 1900, 4500, 5060,
+        5353, 5683, 11211,
+    ]
+}
+
+fn udp_service_map() -> Arc<HashMap<u16, &'static str>> {
+    Arc::new(HashMap::from([
+        (53, "dns"), (67, "dhcp"), (68, "dhcp-client"), (69, "tftp"),
+        (123, "ntp"), (137, "netbios-ns"), (138, "netbios-dgm"), (161, "snmp"),
+        (162, "snmp-trap"), (500, "isakmp"), (514, "syslog"), (520, "rip"),
+        (631, "ipp"), (1434, "mssql-browser"), (1900, "ssdp"), (4500, "ipsec-nat-t"),
+        (5060, "sip"), (5353, "mdns"), (5683, "coap"), (11211, "memcached"),
+    ]))
 }
 
 /// Top 100 most common ports (nmap default).
@@ -147,6 +532,94 @@ pub fn top_100_ports() -> Vec<u16> {
     ]
 }
 
+/// nmap's 1000 most frequently open TCP ports, plus WinRM (5985), which the nmap
+/// list leaves out but is common on Windows hosts. Sorted ascending.
+pub fn top_1000_ports() -> Vec<u16> {
+    vec![
+        1, 3, 4, 6, 7, 9, 13, 17, 19, 20, 21, 22, 23, 24, 25, 26, 30, 32, 33, 37, 42, 43, 49,
+        53, 70, 79, 80, 81, 82, 83, 84, 85, 88, 89, 90, 99, 100, 106, 109, 110, 111, 113, 119,
+        125, 135, 139, 143, 144, 146, 161, 163, 179, 199, 211, 212, 222, 254, 255, 256, 259,
+        264, 280, 301, 306, 311, 340, 366, 389, 406, 407, 416, 417, 425, 427, 443, 444, 445,
+        458, 464, 465, 481, 497, 500, 512, 513, 514, 515, 524, 541, 543, 544, 545, 548, 554,
+        555, 563, 587, 593, 616, 617, 625, 631, 636, 646, 648, 666, 667, 668, 683, 687, 691,
+        700, 705, 711, 714, 720, 722, 726, 749, 765, 777, 783, 787, 800, 801, 808, 843, 873,
+        880, 888, 898, 900, 901, 902, 903, 911, 912, 981, 987, 990, 992, 993, 995, 999, 1000,
+        1001, 1002, 1007, 1009, 1010, 1011, 1021, 1022, 1023, 1024, 1025, 1026, 1027, 1028,
+        1029, 1030, 1031, 1032, 1033, 1034, 1035, 1036, 1037, 1038, 1039, 1040, 1041, 1042,
+        1043, 1044, 1045, 1046, 1047, 1048, 1049, 1050, 1051, 1052, 1053, 1054, 1055, 1056,
+        1057, 1058, 1059, 1060, 1061, 1062, 1063, 1064, 1065, 1066, 1067, 1068, 1069, 1070,
+        1071, 1072, 1073, 1074, 1075, 1076, 1077, 1078, 1079, 1080, 1081, 1082, 1083, 1084,
+        1085, 1086, 1087, 1088, 1089, 1090, 1091, 1092, 1093, 1094, 1095, 1096, 1097, 1098,
+        1099, 1100, 1102, 1104, 1105, 1106, 1107, 1108, 1110, 1111, 1112, 1113, 1114, 1117,
+        1119, 1121, 1122, 1123, 1124, 1126, 1130, 1131, 1132, 1137, 1138, 1141, 1145, 1147,
+        1148, 1149, 1151, 1152, 1154, 1163, 1164, 1165, 1166, 1169, 1174, 1175, 1183, 1185,
+        1186, 1187, 1192, 1198, 1199, 1201, 1213, 1216, 1217, 1218, 1233, 1234, 1236, 1244,
+        1247, 1248, 1259, 1271, 1272, 1277, 1287, 1296, 1300, 1301, 1309, 1310, 1311, 1322,
+        1328, 1334, 1352, 1417, 1433, 1434, 1443, 1455, 1461, 1494, 1500, 1501, 1503, 1521,
+        1524, 1533, 1556, 1580, 1583, 1594, 1600, 1641, 1658, 1666, 1687, 1688, 1700, 1717,
+        1718, 1719, 1720, 1721, 1723, 1755, 1761, 1782, 1783, 1801, 1805, 1812, 1839, 1840,
+        1862, 1863, 1864, 1875, 1900, 1914, 1935, 1947, 1971, 1972, 1974, 1984, 1998, 1999,
+        2000, 2001, 2002, 2003, 2004, 2005, 2006, 2007, 2008, 2009, 2010, 2013, 2020, 2021,
+        2022, 2030, 2033, 2034, 2035, 2038, 2040, 2041, 2042, 2043, 2045, 2046, 2047, 2048,
+        2049, 2065, 2068, 2099, 2100, 2103, 2105, 2106, 2107, 2111, 2119, 2121, 2126, 2135,
+        2144, 2160, 2161, 2170, 2179, 2190, 2191, 2196, 2200, 2222, 2251, 2260, 2288, 2301,
+        2323, 2366, 2381, 2382, 2383, 2393, 2394, 2399, 2401, 2492, 2500, 2522, 2525, 2557,
+        2601, 2602, 2604, 2605, 2607, 2608, 2638, 2701, 2702, 2710, 2717, 2718, 2725, 2800,
+        2809, 2811, 2869, 2875, 2909, 2910, 2920, 2967, 2968, 2998, 3000, 3001, 3003, 3005,
+        3006, 3007, 3011, 3013, 3017, 3030, 3031, 3052, 3071, 3077, 3128, 3168, 3211, 3221,
+        3260, 3261, 3268, 3269, 3283, 3300, 3301, 3306, 3322, 3323, 3324, 3325, 3333, 3351,
+        3367, 3369, 3370, 3371, 3372, 3389, 3390, 3404, 3476, 3493, 3517, 3527, 3546, 3551,
+        3580, 3659, 3689, 3690, 3703, 3737, 3766, 3784, 3800, 3801, 3809, 3814, 3826, 3827,
+        3828, 3851, 3869, 3871, 3878, 3880, 3889, 3905, 3914, 3918, 3920, 3945, 3971, 3986,
+        3995, 3998, 4000, 4001, 4002, 4003, 4004, 4005, 4006, 4045, 4111, 4125, 4126, 4129,
+        4224, 4242, 4279, 4321, 4343, 4443, 4444, 4445, 4446, 4449, 4550, 4567, 4662, 4848,
+        4899, 4900, 4998, 5000, 5001, 5002, 5003, 5004, 5009, 5030, 5033, 5050, 5051, 5054,
+        5060, 5061, 5080, 5087, 5100, 5101, 5102, 5120, 5190, 5200, 5214, 5221, 5222, 5225,
+        5226, 5269, 5280, 5298, 5357, 5405, 5414, 5431, 5432, 5440, 5500, 5510, 5544, 5550,
+        5555, 5560, 5566, 5631, 5633, 5666, 5678, 5679, 5718, 5730, 5800, 5801, 5802, 5810,
+        5811, 5815, 5822, 5825, 5850, 5859, 5862, 5877, 5900, 5901, 5902, 5903, 5904, 5906,
+        5907, 5910, 5911, 5915, 5922, 5925, 5950, 5952, 5959, 5960, 5961, 5962, 5963, 5985,
+        5987, 5988, 5989, 5998, 5999, 6000, 6001, 6002, 6003, 6004, 6005, 6006, 6007, 6009,
+        6025, 6059, 6100, 6101, 6106, 6112, 6123, 6129, 6156, 6346, 6389, 6502, 6510, 6543,
+        6547, 6565, 6566, 6567, 6580, 6646, 6666, 6667, 6668, 6669, 6689, 6692, 6699, 6779,
+        6788, 6789, 6792, 6839, 6881, 6901, 6969, 7000, 7001, 7002, 7004, 7007, 7019, 7025,
+        7070, 7100, 7103, 7106, 7200, 7201, 7402, 7435, 7443, 7496, 7512, 7625, 7627, 7676,
+        7741, 7777, 7778, 7800, 7911, 7920, 7921, 7937, 7938, 7999, 8000, 8001, 8002, 8007,
+        8008, 8009, 8010, 8011, 8021, 8022, 8031, 8042, 8045, 8080, 8081, 8082, 8083, 8084,
+        8085, 8086, 8087, 8088, 8089, 8090, 8093, 8099, 8100, 8180, 8181, 8192, 8193, 8194,
+        8200, 8222, 8254, 8290, 8291, 8292, 8300, 8333, 8383, 8400, 8402, 8443, 8500, 8600,
+        8649, 8651, 8652, 8654, 8701, 8800, 8873, 8888, 8899, 8994, 9000, 9001, 9002, 9003,
+        9009, 9010, 9011, 9040, 9050, 9071, 9080, 9081, 9090, 9091, 9099, 9100, 9101, 9102,
+        9103, 9110, 9111, 9200, 9207, 9220, 9290, 9415, 9418, 9485, 9500, 9502, 9503, 9535,
+        9575, 9593, 9594, 9595, 9618, 9666, 9876, 9877, 9878, 9898, 9900, 9917, 9929, 9943,
+        9944, 9968, 9998, 9999, 10000, 10001, 10002, 10003, 10004, 10009, 10010, 10012, 10024,
+        10025, 10082, 10180, 10215, 10243, 10566, 10616, 10617, 10621, 10626, 10628, 10629,
+        10778, 11110, 11111, 11967, 12000, 12174, 12265, 12345, 13456, 13722, 13782, 13783,
+        14000, 14238, 14441, 14442, 15000, 15002, 15003, 15004, 15660, 15742, 16000, 16001,
+        16012, 16016, 16018, 16080, 16113, 16992, 16993, 17877, 17988, 18040, 18101, 18988,
+        19101, 19283, 19315, 19350, 19780, 19801, 19842, 20000, 20005, 20031, 20221, 20222,
+        20828, 21571, 22939, 23502, 24444, 24800, 25734, 25735, 26214, 27000, 27352, 27353,
+        27355, 27356, 27715, 28201, 30000, 30718, 30951, 31038, 31337, 32768, 32769, 32770,
+        32771, 32772, 32773, 32774, 32775, 32776, 32777, 32778, 32779, 32780, 32781, 32782,
+        32783, 32784, 32785, 33354, 33899, 34571, 34572, 34573, 35500, 38292, 40193, 40911,
+        41511, 42510, 44176, 44442, 44443, 44501, 45100, 48080, 49152, 49153, 49154, 49155,
+        49156, 49157, 49158, 49159, 49160, 49161, 49163, 49165, 49167, 49175, 49176, 49400,
+        49999, 50000, 50001, 50002, 50003, 50006, 50300, 50389, 50500, 50636, 50800, 51103,
+        51493, 52673, 52822, 52848, 52869, 54045, 54328, 55055, 55056, 55555, 55600, 56737,
+        56738, 57294, 57797, 58080, 60020, 60443, 61532, 61900, 62078, 63331, 64623, 64680,
+        65000, 65129, 65389,
+    ]
+}
+
+/// The built-in name for a port, or else the one from the installed service table.
+fn service_name(builtin: &HashMap<u16, &'static str>, port: u16, protocol: Protocol) -> Option<String> {
+    builtin
+        .get(&port)
+        .copied()
+        .or_else(|| services::lookup(port, protocol))
+        .map(|s| s.to_string())
+}
+
 /// Well-known port to service name mapping.
 fn service_map() -> Arc<HashMap<u16, &'static str>> {
     Arc::new(HashMap::from([
@@ -179,6 +652,17 @@ mod tests {
         assert!(ports.contains(&443));
     }
 
+    #[test]
+    fn test_top_1000_ports() {
+        let ports = top_1000_ports();
+        assert_eq!(ports.len(), 1001);
+        for port in [22, 443, 1521, 5985, 62078] {
+            assert!(ports.contains(&port), "missing {}", port);
+        }
+        assert!(ports.windows(2).all(|w| w[0] < w[1]), "sorted without duplicates");
+        assert!(top_100_ports().iter().all(|p| ports.contains(p)));
+    }
+
     #[test]
     fn test_service_map_known_ports() {
         let services = service_map();
@@ -187,4 +671,242 @@ mod tests {
         assert_eq!(services.get(&443), Some(&"https"));
         assert_eq!(services.get(&3306), Some(&"mysql"));
     }
+
+    #[test]
+    fn test_service_names_fall_back_to_table() {
+        let table = services::ServiceTable::from_reader(include_str!("../../resources/services.csv").as_bytes());
+        table.install();
+
+        let builtin = service_map();
+        assert!(!builtin.contains_key(&5672));
+        assert_eq!(service_name(&builtin, 5672, Protocol::Tcp).as_deref(), Some("amqp"));
+        assert_eq!(service_name(&builtin, 27017, Protocol::Tcp).as_deref(), Some("mongodb"));
+        // Built-in names still win, and unknown ports stay bare
+        assert_eq!(service_name(&builtin, 445, Protocol::Tcp).as_deref(), Some("smb"));
+        assert_eq!(service_name(&builtin, 40123, Protocol::Tcp), None);
+    }
+
+    #[test]
+    fn test_udp_probes() {
+        // DNS and mDNS probes carry exactly one question
+        assert_eq!(&udp_probe(53)[4..6], &[0, 1]);
+        assert_eq!(&udp_probe(5353)[4..6], &[0, 1]);
+        assert_eq!(udp_probe(123)[0], 0x1b);
+        assert_eq!(udp_probe(161)[1] as usize, udp_probe(161).len() - 2);
+        assert!(udp_probe(9).is_empty());
+    }
+
+    #[test]
+    fn test_udp_banner() {
+        assert_eq!(udp_banner(b"HTTP/1.1 200 OK\r\nST: upnp:rootdevice\r\n").as_deref(), Some("HTTP/1.1 200 OK"));
+        assert!(udp_banner(&[0x13, 0x37, 0x81, 0x80]).is_none());
+        assert!(udp_banner(b"  ").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_udp_scan_against_loopback() {
+        use crate::scanner::budget::ScanBudget;
+
+        use tokio::net::UdpSocket;
+
+        // Responder: answers every datagram
+        let responder = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let open = responder.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((_, from)) = responder.recv_from(&mut buf).await {
+                let _ = responder.send_to(b"pong", from).await;
+            }
+        });
+
+        // Bound but never reads: no reply and no ICMP error
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let silent_port = silent.local_addr().unwrap().port();
+
+        // Nothing bound: the kernel answers with port-unreachable
+        let closed = {
+            let s = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            s.local_addr().unwrap().port()
+        };
+
+        let services = udp_service_map();
+        let r = scan_single_udp_port("127.0.0.1", open, 1000, &services).await.unwrap();
+        assert_eq!(r.state, PortState::Open);
+        assert_eq!(r.protocol, Protocol::Udp);
+        assert_eq!(r.banner.as_deref(), Some("pong"));
+
+        let r = scan_single_udp_port("127.0.0.1", closed, 1000, &services).await.unwrap();
+        assert_eq!(r.state, PortState::Closed);
+
+        let r = scan_single_udp_port("127.0.0.1", silent_port, 200, &services).await.unwrap();
+        assert_eq!(r.state, PortState::OpenFiltered);
+        assert_eq!(r.state.to_string(), "open|filtered");
+
+        // Only the answering port is reported
+        let results = scan_udp_ports(
+            "127.0.0.1",
+            &[open, closed, silent_port],
+            &ScanBudget::new(4),
+            200,
+            &CancellationToken::new(),
+        )
+        .await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].port, open);
+        drop(silent);
+    }
+
+    /// Listener that records the first request (if any) and answers with `reply`.
+    async fn canned_server(reply: &'static [u8], expect_request: bool) -> (SocketAddr, tokio::task::JoinHandle<Vec<u8>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 512];
+            let n = if expect_request { sock.read(&mut request).await.unwrap() } else { 0 };
+            sock.write_all(reply).await.unwrap();
+            request.truncate(n);
+            request
+        });
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn test_http_probe_reads_server_header() {
+        let (addr, server) =
+            canned_server(b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nServer: nginx/1.25.3\r\n\r\n<html>", true).await;
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (banner, service) = probe_banner(stream, BannerProbe::Http).await;
+        assert_eq!(banner.as_deref(), Some("nginx/1.25.3"));
+        assert_eq!(service, Some("http"));
+        assert_eq!(server.await.unwrap(), HTTP_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_ssh_banner_without_probe() {
+        let (addr, server) = canned_server(b"SSH-2.0-OpenSSH_9.6\r\n", false).await;
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (banner, service) = probe_banner(stream, BannerProbe::Listen).await;
+        assert_eq!(banner.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+        assert_eq!(service, Some("ssh"));
+        assert!(server.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_detected_service_beats_registered_name() {
+        let (addr, _server) = canned_server(b"SSH-2.0-OpenSSH_9.6\r\n", false).await;
+        // Registered as a web port, but an SSH daemon answers
+        let services = HashMap::from([(addr.port(), "http-alt")]);
+
+        let result = scan_single_port("127.0.0.1", addr.port(), 1000, &services).await.unwrap();
+        assert_eq!(result.state, PortState::Open);
+        assert_eq!(result.service_name.as_deref(), Some("ssh"));
+        assert_eq!(result.banner.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+    }
+
+    #[tokio::test]
+    async fn test_tls_probe_recognises_server_hello() {
+        let (addr, server) = canned_server(&[0x16, 0x03, 0x03, 0x00, 0x4a, 0x02], true).await;
+        let stream = TcpStream::connect(addr).await.unwrap();
+        assert_eq!(probe_banner(stream, BannerProbe::Tls).await, (None, Some("https")));
+        assert_eq!(server.await.unwrap(), TLS_CLIENT_HELLO);
+    }
+
+    #[tokio::test]
+    async fn test_silent_server_yields_no_banner() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _server = tokio::spawn(async move {
+            let _held = listener.accept().await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        assert_eq!(probe_banner(stream, BannerProbe::Listen).await, (None, None));
+    }
+
+    #[test]
+    fn test_parse_banner() {
+        // No Server header: fall back to the status line
+        assert_eq!(
+            parse_banner(BannerProbe::Http, b"HTTP/1.0 404 Not Found\r\n\r\n"),
+            (Some("HTTP/1.0 404 Not Found".to_string()), Some("http"))
+        );
+        assert_eq!(
+            parse_banner(BannerProbe::Listen, b"220 mail.example.com ESMTP Postfix\r\n"),
+            (Some("220 mail.example.com ESMTP Postfix".to_string()), None)
+        );
+        // Plain HTTP on a TLS port is not TLS
+        assert_eq!(parse_banner(BannerProbe::Tls, b"HTTP/1.1 400 Bad Request"), (None, None));
+        assert_eq!(parse_banner(BannerProbe::Tls, &[0x15, 0x03, 0x01, 0x00, 0x02]), (None, Some("https")));
+        assert_eq!(banner_probe(8080), BannerProbe::Http);
+        assert_eq!(banner_probe(22), BannerProbe::Listen);
+    }
+
+    #[tokio::test]
+    async fn test_hosts_scanned_in_parallel() {
+        use crate::scanner::budget::ScanBudget;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::AsyncWriteExt;
+
+        // Each host probes one port on this server, which counts the probes it is
+        // holding open and greets each one after a pause
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (counter, high) = (in_flight.clone(), peak.clone());
+        let server = tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let (counter, high) = (counter.clone(), high.clone());
+                tokio::spawn(async move {
+                    high.fetch_max(counter.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    counter.fetch_sub(1, Ordering::SeqCst);
+                    let _ = sock.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await;
+                });
+            }
+        });
+
+        let parallel = 3;
+        let hosts = vec!["127.0.0.1".to_string(); 6];
+        let mut set = scan_hosts(&hosts, &[port], &[], &ScanBudget::new(16), 200, parallel, None);
+        let mut seen = Vec::new();
+        while let Some(Ok(host)) = set.join_next().await {
+            assert_eq!(host.ip, "127.0.0.1");
+            seen.push(host.index);
+        }
+        server.abort();
+        seen.sort();
+        assert_eq!(seen, [0, 1, 2, 3, 4, 5]);
+
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(peak > 1 && peak <= parallel, "peak of {} hosts in flight", peak);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_interrupts_port_scan() {
+        use crate::scanner::budget::ScanBudget;
+
+        // Silent UDP ports: without cancellation each probe waits out its 2s timeout
+        let silent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = silent.local_addr().unwrap().port();
+        let budget = ScanBudget::new(2);
+        let cancel = CancellationToken::new();
+
+        let stop = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            stop.cancel();
+        });
+        let start = std::time::Instant::now();
+        let results = scan_udp_ports("127.0.0.1", &[port; 4], &budget, 2000, &cancel).await;
+        assert!(results.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(1), "took {:?}", start.elapsed());
+
+        // Probes aborted by the cancel hand their budget slots back
+        assert!(tokio::time::timeout(Duration::from_secs(1), budget.acquire()).await.is_ok());
+    }
 }
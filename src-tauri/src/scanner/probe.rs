@@ -0,0 +1,102 @@
+use serde::Serialize;
+
+use crate::scanner::{passive, ping, ScanTools};
+
+/// How a device answered a reachability probe.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeMethod {
+    Icmp,
+    Tcp,
+    Arp,
+}
+
+/// Combined result of probing a device over several protocols.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reachability {
+    pub ip: String,
+    pub reachable: bool,
+    /// Every method that got an answer, strongest first.
+    pub methods: Vec<ProbeMethod>,
+    /// Round-trip time from ICMP, or TCP connect time when ICMP is blocked.
+    pub latency_ms: Option<f64>,
+}
+
+impl Reachability {
+    fn from_results(ip: &str, icmp: Option<f64>, tcp: Option<f64>, arp: bool) -> Self {
+        let mut methods = Vec::new();
+        if icmp.is_some() {
+            methods.push(ProbeMethod::Icmp);
+        }
+        if tcp.is_some() {
+            methods.push(ProbeMethod::Tcp);
+        }
+        if arp {
+            methods.push(ProbeMethod::Arp);
+        }
+
+        Self {
+            ip: ip.to_string(),
+            reachable: !methods.is_empty(),
+            methods,
+            latency_ms: icmp.or(tcp),
+        }
+    }
+}
+
+/// Probe a device with ICMP ping, TCP ping and an ARP table lookup concurrently.
+/// The device counts as reachable if any method succeeds, so hosts that drop
+/// ICMP or have no open ports still register as online.
+pub async fn probe_device(ip: &str, mac: Option<&str>, timeout_ms: u64, tools: &ScanTools) -> Reachability {
+    let arp_ip = ip.to_string();
+    let arp_mac = mac.map(|m| m.to_string());
+    let arp_tools = tools.clone();
+
+    let (icmp, tcp, arp) = tokio::join!(
+        ping::ping_with(ip, timeout_ms, tools),
+        ping::tcp_ping(ip, &tools.tcp_ping_ports, timeout_ms),
+        tokio::task::spawn_blocking(move || in_arp_table(&arp_ip, arp_mac.as_deref(), &arp_tools)),
+    );
+
+    Reachability::from_results(ip, icmp, tcp, arp.unwrap_or(false))
+}
+
+/// Whether the ARP table currently maps `ip` (and `mac`, when known) to a live entry.
+fn in_arp_table(ip: &str, mac: Option<&str>, tools: &ScanTools) -> bool {
+    passive::scan_arp_table_with(tools).unwrap_or_default().iter().any(|entry| {
+        entry.ip == ip
+            && match (mac, entry.mac.as_deref()) {
+                (Some(want), Some(got)) => want.eq_ignore_ascii_case(got),
+                (Some(_), None) => false,
+                (None, _) => true,
+            }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reachability_verdict() {
+        let r = Reachability::from_results("10.0.0.2", Some(1.5), Some(3.0), true);
+        assert!(r.reachable);
+        assert_eq!(r.methods, vec![ProbeMethod::Icmp, ProbeMethod::Tcp, ProbeMethod::Arp]);
+        assert_eq!(r.latency_ms, Some(1.5));
+
+        // ICMP blocked: TCP connect time stands in for latency
+        let r = Reachability::from_results("10.0.0.2", None, Some(3.0), false);
+        assert!(r.reachable);
+        assert_eq!(r.methods, vec![ProbeMethod::Tcp]);
+        assert_eq!(r.latency_ms, Some(3.0));
+
+        let r = Reachability::from_results("10.0.0.2", None, None, true);
+        assert!(r.reachable);
+        assert!(r.latency_ms.is_none());
+
+        let r = Reachability::from_results("10.0.0.2", None, None, false);
+        assert!(!r.reachable);
+        assert!(r.methods.is_empty());
+    }
+}
@@ -0,0 +1,297 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::scanner::port::PortTick;
+use crate::scanner::{ScanPhases, ScanType};
+
+/// Scan phases in the order the orchestrator runs them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Discovery,
+    Ping,
+    Resolving,
+    Enriching,
+    PortScan,
+    Fingerprinting,
+    Alerts,
+}
+
+const PHASES: [Phase; 7] = [
+    Phase::Discovery,
+    Phase::Ping,
+    Phase::Resolving,
+    Phase::Enriching,
+    Phase::PortScan,
+    Phase::Fingerprinting,
+    Phase::Alerts,
+];
+
+/// Share of the progress bar each phase occupies for one scan.
+///
+/// Weights are rough cost estimates in units of "one network round of timeouts",
+/// so the bar moves close to linearly with wall-clock time whatever the scan type.
+#[derive(Debug, Clone)]
+pub struct ProgressPlan {
+    /// Cumulative percent at the start of each phase, indexed like `PHASES`.
+    starts: [f64; 7],
+    /// Percent width of each phase.
+    widths: [f64; 7],
+}
+
+impl ProgressPlan {
+    /// `concurrency` is the scan's shared budget, which bounds both the ping sweep and
+    /// the connection attempts per host.
+    pub fn new(scan_type: &ScanType, host_count: usize, port_count: usize, concurrency: usize) -> Self {
+        Self::for_phases(&ScanPhases::for_scan_type(scan_type), host_count, port_count, concurrency)
+    }
+
+    /// Like `new`, for a scan running only the enabled `phases`.
+    pub fn for_phases(phases: &ScanPhases, host_count: usize, port_count: usize, concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        let hosts = host_count as f64;
+
+        let cost = |phase: Phase| -> f64 {
+            match phase {
+                Phase::Discovery => 1.0,
+                Phase::Ping if phases.ping => host_count.div_ceil(concurrency) as f64,
+                // Lookups run concurrently under a single timeout
+                Phase::Resolving if phases.resolve => 1.0,
+                Phase::Enriching => 0.02 * hosts,
+                Phase::PortScan if phases.ports => hosts * port_count.div_ceil(concurrency) as f64,
+                Phase::Fingerprinting if phases.fingerprint => 0.02 * hosts,
+                Phase::Alerts if phases.alerts => 0.5,
+                _ => 0.0,
+            }
+        };
+
+        let costs = PHASES.map(cost);
+        let total: f64 = costs.iter().sum();
+
+        let mut starts = [0.0; 7];
+        let mut widths = [0.0; 7];
+        let mut acc = 0.0;
+        for (i, c) in costs.iter().enumerate() {
+            starts[i] = acc;
+            widths[i] = 100.0 * c / total;
+            acc += widths[i];
+        }
+
+        Self { starts, widths }
+    }
+
+    fn index(phase: Phase) -> usize {
+        PHASES.iter().position(|p| *p == phase).unwrap_or(0)
+    }
+
+    /// Percent complete when `phase` begins.
+    pub fn start(&self, phase: Phase) -> f64 {
+        self.starts[Self::index(phase)]
+    }
+
+    /// Percent complete when `phase` finishes.
+    pub fn end(&self, phase: Phase) -> f64 {
+        self.at(phase, 1.0)
+    }
+
+    /// Percent complete `fraction` of the way through `phase`.
+    pub fn at(&self, phase: Phase, fraction: f64) -> f64 {
+        let i = Self::index(phase);
+        (self.starts[i] + self.widths[i] * fraction.clamp(0.0, 1.0)).min(100.0)
+    }
+}
+
+/// Progress update sent to the frontend during a scan.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanProgress {
+    pub scan_id: String,
+    pub phase: String,
+    pub devices_found: u32,
+    pub percent_complete: f64,
+    /// Host whose probe just finished, during the port scan.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_port: Option<u16>,
+    /// Probes finished so far across all hosts, out of `ports_total`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports_done: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports_total: Option<u32>,
+}
+
+impl ScanProgress {
+    pub fn new(scan_id: &str, phase: &str, devices_found: u32, percent_complete: f64) -> Self {
+        Self {
+            scan_id: scan_id.to_string(),
+            phase: phase.to_string(),
+            devices_found,
+            percent_complete,
+            current_target: None,
+            current_port: None,
+            ports_done: None,
+            ports_total: None,
+        }
+    }
+}
+
+/// Where progress updates go: the frontend in the app, a recorder in tests.
+pub trait ProgressSink {
+    fn progress(&self, update: ScanProgress);
+}
+
+impl ProgressSink for AppHandle {
+    fn progress(&self, update: ScanProgress) {
+        let _ = self.emit("scan:progress", update);
+    }
+}
+
+/// Updates per port scan; finer ticks would flood the event channel on large scans.
+const PORT_UPDATES: u32 = 100;
+
+/// Turns finished port probes into port-scan progress updates.
+pub struct PortProgress<'a> {
+    plan: &'a ProgressPlan,
+    scan_id: &'a str,
+    hosts: &'a [String],
+    devices_found: u32,
+    done: u32,
+    total: u32,
+    step: u32,
+}
+
+impl<'a> PortProgress<'a> {
+    /// `ports_per_host` counts TCP and UDP probes together.
+    pub fn new(
+        plan: &'a ProgressPlan,
+        scan_id: &'a str,
+        hosts: &'a [String],
+        ports_per_host: usize,
+        devices_found: u32,
+    ) -> Self {
+        let total = (hosts.len() * ports_per_host) as u32;
+        Self {
+            plan,
+            scan_id,
+            hosts,
+            devices_found,
+            done: 0,
+            total,
+            step: (total / PORT_UPDATES).max(1),
+        }
+    }
+
+    /// Count one finished probe, reporting it every `step` probes and on the last one.
+    pub fn tick(&mut self, sink: &impl ProgressSink, tick: PortTick) {
+        self.done += 1;
+        if !self.done.is_multiple_of(self.step) && self.done < self.total {
+            return;
+        }
+        let fraction = self.done as f64 / self.total.max(1) as f64;
+        sink.progress(ScanProgress {
+            current_target: self.hosts.get(tick.index).cloned(),
+            current_port: Some(tick.port),
+            ports_done: Some(self.done),
+            ports_total: Some(self.total),
+            ..ScanProgress::new(
+                self.scan_id,
+                "port_scan",
+                self.devices_found,
+                self.plan.at(Phase::PortScan, fraction),
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::budget::DEFAULT_SCAN_CONCURRENCY;
+
+    #[test]
+    fn test_full_scan_dominated_by_port_scan() {
+        let plan = ProgressPlan::new(&ScanType::Full, 20, 100, DEFAULT_SCAN_CONCURRENCY);
+        let port_share = plan.end(Phase::PortScan) - plan.start(Phase::PortScan);
+        assert!(port_share > 70.0, "port scan share was {}", port_share);
+        assert!((plan.end(Phase::Alerts) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_passive_scan_skips_ping_and_ports() {
+        let plan = ProgressPlan::new(&ScanType::Passive, 20, 100, DEFAULT_SCAN_CONCURRENCY);
+        assert_eq!(plan.start(Phase::Ping), plan.end(Phase::Ping));
+        assert_eq!(plan.start(Phase::PortScan), plan.end(Phase::PortScan));
+        // Remaining phases still spread across the bar rather than jumping to 100
+        assert!(plan.end(Phase::Discovery) > 10.0);
+        assert!(plan.start(Phase::Alerts) < 100.0);
+    }
+
+    #[test]
+    fn test_phases_are_monotonic() {
+        for scan_type in [ScanType::Quick, ScanType::Full, ScanType::PortOnly, ScanType::Passive] {
+            let plan = ProgressPlan::new(&scan_type, 50, 1000, DEFAULT_SCAN_CONCURRENCY);
+            let mut last = 0.0;
+            for phase in PHASES {
+                assert!(plan.start(phase) >= last);
+                assert!(plan.at(phase, 0.5) >= plan.start(phase));
+                last = plan.end(phase);
+            }
+            assert!((last - 100.0).abs() < 1e-9);
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<ScanProgress>>);
+
+    impl ProgressSink for Recorder {
+        fn progress(&self, update: ScanProgress) {
+            self.0.lock().unwrap().push(update);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_port_scan_reports_ports_done() {
+        use crate::scanner::{budget::ScanBudget, port};
+
+        // Nothing listens on these, so every attempt ends quickly
+        let hosts = vec!["127.0.0.1".to_string(), "127.0.0.2".to_string()];
+        let tcp_ports = [1, 2, 3];
+        let plan = ProgressPlan::new(&ScanType::Full, hosts.len(), tcp_ports.len(), 4);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut scans = port::scan_hosts(&hosts, &tcp_ports, &[], &ScanBudget::new(4), 200, 2, Some(tx));
+
+        let recorder = Recorder::default();
+        let mut progress = PortProgress::new(&plan, "scan1", &hosts, tcp_ports.len(), 2);
+        while let Some(tick) = rx.recv().await {
+            progress.tick(&recorder, tick);
+        }
+        while scans.join_next().await.is_some() {}
+
+        let updates = recorder.0.into_inner().unwrap();
+        let done: Vec<u32> = updates.iter().map(|u| u.ports_done.unwrap()).collect();
+        assert_eq!(done, [1, 2, 3, 4, 5, 6]);
+        assert!(updates.iter().all(|u| u.ports_total == Some(6) && u.phase == "port_scan"));
+        assert!(updates.iter().all(|u| hosts.contains(u.current_target.as_ref().unwrap())));
+        assert!(updates.windows(2).all(|w| w[0].percent_complete <= w[1].percent_complete));
+        assert!((updates[5].percent_complete - plan.end(Phase::PortScan)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_disabled_phases_take_no_share() {
+        let phases = ScanPhases {
+            resolve: false,
+            alerts: false,
+            ..ScanPhases::for_scan_type(&ScanType::Full)
+        };
+        let plan = ProgressPlan::for_phases(&phases, 20, 100, DEFAULT_SCAN_CONCURRENCY);
+        assert_eq!(plan.start(Phase::Resolving), plan.end(Phase::Resolving));
+        assert_eq!(plan.start(Phase::Alerts), plan.end(Phase::Alerts));
+        assert!(plan.end(Phase::PortScan) - plan.start(Phase::PortScan) > 70.0);
+    }
+
+    #[test]
+    fn test_empty_network() {
+        let plan = ProgressPlan::new(&ScanType::Full, 0, 100, DEFAULT_SCAN_CONCURRENCY);
+        assert!(plan.end(Phase::Alerts).is_finite());
+        assert!((plan.end(Phase::Alerts) - 100.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::scanner::{orchestrator, ScanConfig};
+use crate::state::AppState;
+
+/// A scan request waiting for its turn.
+struct QueuedScan {
+    job_id: String,
+    config: ScanConfig,
+}
+
+#[derive(Default)]
+struct QueueInner {
+    jobs: VecDeque<QueuedScan>,
+    worker_running: bool,
+}
+
+/// FIFO queue of pending scans, drained one at a time by a single worker task.
+#[derive(Default)]
+pub struct ScanQueue {
+    inner: Mutex<QueueInner>,
+}
+
+/// Emitted as `scan:queued` when a request is added to the queue, and returned to its caller.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanQueued {
+    pub job_id: String,
+    /// 1-based position among pending (not yet started) scans.
+    pub position: usize,
+}
+
+/// Emitted as `scan:started` when a queued request begins running.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStarted {
+    pub job_id: String,
+}
+
+impl ScanQueue {
+    /// Number of scans waiting to start.
+    pub async fn pending(&self) -> usize {
+        self.inner.lock().await.jobs.len()
+    }
+
+    /// Drop all pending jobs. Returns how many were dropped.
+    /// Non-blocking so it can run from synchronous shutdown code.
+    pub fn cancel_pending(&self) -> usize {
+        match self.inner.try_lock() {
+            Ok(mut inner) => {
+                let dropped = inner.jobs.len();
+                inner.jobs.clear();
+                dropped
+            }
+            Err(_) => 0,
+        }
+    }
+
+    /// Append a job and report its position. Returns whether the caller must start a worker.
+    async fn push(&self, job: QueuedScan) -> (usize, bool) {
+        let mut inner = self.inner.lock().await;
+        inner.jobs.push_back(job);
+        let spawn_worker = !inner.worker_running;
+        inner.worker_running = true;
+        (inner.jobs.len(), spawn_worker)
+    }
+
+    /// Take the next job, or mark the worker stopped when the queue is empty.
+    /// Both happen under the same lock so a concurrent `push` never strands a job.
+    async fn pop(&self) -> Option<QueuedScan> {
+        let mut inner = self.inner.lock().await;
+        let job = inner.jobs.pop_front();
+        if job.is_none() {
+            inner.worker_running = false;
+        }
+        job
+    }
+}
+
+/// Queue a scan and return its place in line right away. Scans execute in FIFO order,
+/// one at a time; each result arrives as `scan:completed` (or `scan:error`).
+pub async fn enqueue(app: AppHandle, config: ScanConfig) -> ScanQueued {
+    let job_id = uuid::Uuid::new_v4().to_string();
+
+    let state = app.state::<Arc<AppState>>();
+    let (position, spawn_worker) = state
+        .scan_queue
+        .push(QueuedScan {
+            job_id: job_id.clone(),
+            config,
+        })
+        .await;
+
+    log::info!("Scan {} queued at position {}", job_id, position);
+    let queued = ScanQueued { job_id, position };
+    let _ = app.emit("scan:queued", &queued);
+
+    if spawn_worker {
+        tauri::async_runtime::spawn(run_worker(app.clone()));
+    }
+    queued
+}
+
+/// Drain the queue, running each scan to completion before starting the next. A job
+/// waits for any scan already holding the slot, including a monitor scan.
+async fn run_worker(app: AppHandle) {
+    let state = app.state::<Arc<AppState>>();
+
+    while let Some(job) = state.scan_queue.pop().await {
+        let cancel = CancellationToken::new();
+        let _slot = match state.claim_scan_when_free(cancel.clone()).await {
+            Ok(slot) => slot,
+            Err(e) => {
+                log::error!("Queued scan {} could not start: {}", job.job_id, e.message);
+                continue;
+            }
+        };
+
+        let _ = app.emit(
+            "scan:started",
+            ScanStarted {
+                job_id: job.job_id.clone(),
+            },
+        );
+        // The orchestrator reports the outcome as `scan:completed` or `scan:error`
+        if let Err(e) = orchestrator::run_scan(app.clone(), &state, job.config, cancel).await {
+            log::error!("Queued scan {} failed: {}", job.job_id, e.message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::network::oui::OuiDatabase;
+    use crate::scanner::port::Protocol;
+    use crate::scanner::{PortRange, ScanTarget, ScanType};
+
+    fn job(id: &str) -> QueuedScan {
+        QueuedScan {
+            job_id: id.to_string(),
+            config: ScanConfig {
+                interface_id: "auto".to_string(),
+                scan_type: ScanType::Quick,
+                port_range: PortRange::Top100,
+                protocols: vec![Protocol::Tcp],
+                target: ScanTarget::AutoSubnet,
+                phases: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_is_fifo_with_single_worker() {
+        let queue = ScanQueue::default();
+
+        let a = job("a");
+        let b = job("b");
+        assert_eq!(queue.push(a).await, (1, true));
+        // Worker already claimed; second push must not spawn another
+        assert_eq!(queue.push(b).await, (2, false));
+        assert_eq!(queue.pending().await, 2);
+
+        assert_eq!(queue.pop().await.unwrap().job_id, "a");
+        assert_eq!(queue.pop().await.unwrap().job_id, "b");
+        assert!(queue.pop().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_worker_released_when_queue_drains() {
+        let queue = ScanQueue::default();
+
+        let a = job("a");
+        queue.push(a).await;
+        queue.pop().await;
+        assert!(queue.pop().await.is_none());
+
+        // Worker exited, so the next push must start a new one
+        let b = job("b");
+        assert_eq!(queue.push(b).await, (1, true));
+    }
+
+    #[tokio::test]
+    async fn test_queued_job_waits_for_held_slot() {
+        let state = Arc::new(AppState::new(db::init_test_db(), OuiDatabase::empty()));
+        // A monitor scan is running
+        let monitor = state.claim_scan(CancellationToken::new()).unwrap();
+
+        let (started_tx, mut started) = tokio::sync::oneshot::channel();
+        let worker_state = state.clone();
+        let worker = tokio::spawn(async move {
+            let cancel = CancellationToken::new();
+            let _slot = worker_state.claim_scan_when_free(cancel.clone()).await.unwrap();
+            started_tx.send(()).unwrap();
+            cancel.cancelled().await;
+        });
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(started.try_recv().is_err(), "job must not start while the slot is held");
+
+        drop(monitor);
+        started.await.unwrap();
+        assert!(state.claim_scan(CancellationToken::new()).is_err());
+
+        state.scan_cancel.lock().unwrap().as_ref().unwrap().cancel();
+        worker.await.unwrap();
+    }
+}
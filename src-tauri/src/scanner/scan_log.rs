@@ -0,0 +1,221 @@
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Maximum number of scan log files kept on disk; oldest are pruned first.
+pub const MAX_SCAN_LOGS: usize = 50;
+
+/// Directory holding per-scan log files.
+pub fn log_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("scan_logs")
+}
+
+fn log_path(dir: &Path, scan_id: &str) -> PathBuf {
+    dir.join(format!("scan_{}.log", scan_id))
+}
+
+/// Human-readable, timestamped trace of a single scan.
+/// A disabled log accepts writes and discards them, so callers never branch on it.
+pub struct ScanLog {
+    writer: Option<BufWriter<File>>,
+    start: Instant,
+}
+
+impl ScanLog {
+    pub fn disabled() -> Self {
+        Self {
+            writer: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// Create `scan_<id>.log` in `dir`, pruning old logs to stay under `MAX_SCAN_LOGS`.
+    pub fn create(dir: &Path, scan_id: &str) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        prune(dir, MAX_SCAN_LOGS.saturating_sub(1));
+        let file = File::create(log_path(dir, scan_id))?;
+        Ok(Self {
+            writer: Some(BufWriter::new(file)),
+            start: Instant::now(),
+        })
+    }
+
+    /// Open a log if enabled, falling back to a disabled log on I/O failure.
+    pub fn open(dir: Option<&Path>, scan_id: &str) -> Self {
+        match dir {
+            Some(dir) => Self::create(dir, scan_id).unwrap_or_else(|e| {
+                log::warn!("Could not create scan log in {}: {}", dir.display(), e);
+                Self::disabled()
+            }),
+            None => Self::disabled(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Append one line prefixed with the elapsed time since the scan started.
+    pub fn line(&mut self, message: impl Display) {
+        if let Some(ref mut w) = self.writer {
+            let elapsed = self.start.elapsed().as_secs_f64();
+            if writeln!(w, "[{:>8.3}s] {}", elapsed, message).is_err() {
+                // Stop writing after the first failure rather than spamming errors
+                self.writer = None;
+            }
+        }
+    }
+}
+
+impl Drop for ScanLog {
+    fn drop(&mut self) {
+        if let Some(ref mut w) = self.writer {
+            let _ = w.flush();
+        }
+    }
+}
+
+/// Read a scan's log. The id must be a UUID so it can't escape the log directory.
+pub fn read_log(dir: &Path, scan_id: &str) -> Result<String, String> {
+    uuid::Uuid::parse_str(scan_id).map_err(|_| format!("Invalid scan id: {}", scan_id))?;
+
+    let path = log_path(dir, scan_id);
+    fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("No log recorded for scan {}", scan_id),
+        _ => format!("Failed to read scan log: {}", e),
+    })
+}
+
+/// Delete the oldest scan logs so at most `keep` remain.
+pub fn prune(dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut logs: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("scan_") && name.ends_with(".log")
+        })
+        .filter_map(|e| {
+            let modified = e.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, e.path()))
+        })
+        .collect();
+
+    if logs.len() <= keep {
+        return;
+    }
+
+    // Oldest first; name breaks ties for files written within the same mtime tick
+    logs.sort();
+    let excess = logs.len() - keep;
+    for (_, path) in logs.into_iter().take(excess) {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("Failed to remove old scan log {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("echolocate_{}_{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_scan_log_write_and_read() {
+        let dir = temp_dir("scan_log");
+        let scan_id = uuid::Uuid::new_v4().to_string();
+
+        {
+            let mut log = ScanLog::create(&dir, &scan_id).unwrap();
+            assert!(log.is_enabled());
+            log.line("discovery: 3 hosts");
+            log.line(format_args!("ping {} -> {:?}", "192.168.1.1", Some(1.5)));
+        }
+
+        let text = read_log(&dir, &scan_id).unwrap();
+        assert!(text.contains("discovery: 3 hosts"));
+        assert!(text.contains("ping 192.168.1.1 -> Some(1.5)"));
+        assert_eq!(text.lines().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_log_rejects_bad_ids() {
+        let dir = temp_dir("scan_log_ids");
+        assert!(read_log(&dir, "../../etc/passwd").is_err());
+        let missing = read_log(&dir, &uuid::Uuid::new_v4().to_string()).unwrap_err();
+        assert!(missing.contains("No log recorded"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Write `scan_0.log`..`scan_{count-1}.log`, each a minute newer than the last.
+    fn write_logs(dir: &Path, count: u64) {
+        let start = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for i in 0..count {
+            let path = dir.join(format!("scan_{}.log", i));
+            fs::write(&path, "x").unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(start + std::time::Duration::from_secs(60 * i)).unwrap();
+        }
+    }
+
+    fn remaining_logs(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.ends_with(".log"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = temp_dir("scan_log_prune");
+        write_logs(&dir, 5);
+        fs::write(dir.join("notes.txt"), "keep me").unwrap();
+
+        prune(&dir, 2);
+
+        assert_eq!(remaining_logs(&dir), ["scan_3.log", "scan_4.log"]);
+        assert!(dir.join("notes.txt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_boundary() {
+        let dir = temp_dir("scan_log_prune_boundary");
+        write_logs(&dir, 3);
+
+        // Exactly at the limit: nothing goes
+        prune(&dir, 3);
+        assert_eq!(remaining_logs(&dir), ["scan_0.log", "scan_1.log", "scan_2.log"]);
+
+        // One over: only the oldest goes
+        prune(&dir, 2);
+        assert_eq!(remaining_logs(&dir), ["scan_1.log", "scan_2.log"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disabled_log_is_noop() {
+        let mut log = ScanLog::disabled();
+        log.line("ignored");
+        assert!(!log.is_enabled());
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::scanner::port::Protocol;
+
+static ACTIVE_TABLE: OnceLock<ServiceTable> = OnceLock::new();
+
+/// Port to service name lookup from the bundled IANA-derived table. Covers ports the
+/// port scanner's built-in names do not; those names still win where both have one.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceTable {
+    entries: HashMap<(u16, Protocol), String>,
+}
+
+impl ServiceTable {
+    /// Load the service table from the bundled CSV resource.
+    pub fn load(app: &tauri::AppHandle) -> Result<Self, Box<dyn std::error::Error>> {
+        use tauri::Manager;
+        let resource_path = app
+            .path()
+            .resource_dir()
+            .map_err(|e| format!("Failed to get resource dir: {}", e))?
+            .join("resources")
+            .join("services.csv");
+
+        if !resource_path.exists() {
+            log::warn!("Service table not found at {}", resource_path.display());
+            return Ok(Self::empty());
+        }
+
+        let table = Self::from_reader(std::fs::File::open(&resource_path)?);
+        log::info!("Loaded {} service names", table.len());
+        Ok(table)
+    }
+
+    /// Parse `port,protocol,service` records with a header row, skipping malformed ones.
+    pub fn from_reader(reader: impl std::io::Read) -> Self {
+        let mut entries = HashMap::new();
+        let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(reader);
+
+        for result in reader.records() {
+            let record = match result {
+                Ok(r) => r,
+                Err(e) => {
+                    log::debug!("Skipping malformed service record: {}", e);
+                    continue;
+                }
+            };
+            if record.len() < 3 {
+                continue;
+            }
+
+            let Ok(port) = record[0].trim().parse::<u16>() else { continue };
+            let protocol = match record[1].trim() {
+                "tcp" => Protocol::Tcp,
+                "udp" => Protocol::Udp,
+                _ => continue,
+            };
+            let service = record[2].trim();
+            if !service.is_empty() {
+                entries.insert((port, protocol), service.to_string());
+            }
+        }
+
+        Self { entries }
+    }
+
+    /// Create an empty table (fallback when the file is missing).
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// The service name registered for a port.
+    pub fn lookup(&self, port: u16, protocol: Protocol) -> Option<&str> {
+        self.entries.get(&(port, protocol)).map(|s| s.as_str())
+    }
+
+    /// Number of entries loaded.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Make this the table port scans label ports from. Only the first call takes
+    /// effect; returns false if a table was already in place.
+    pub fn install(self) -> bool {
+        ACTIVE_TABLE.set(self).is_ok()
+    }
+}
+
+/// The service name for a port from the installed table. No names are known before
+/// a table is installed.
+pub fn lookup(port: u16, protocol: Protocol) -> Option<&'static str> {
+    ACTIVE_TABLE.get()?.lookup(port, protocol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLED: &str = include_str!("../../resources/services.csv");
+
+    #[test]
+    fn test_bundled_table_labels_extra_ports() {
+        let table = ServiceTable::from_reader(BUNDLED.as_bytes());
+        assert_eq!(table.lookup(5672, Protocol::Tcp), Some("amqp"));
+        assert_eq!(table.lookup(27017, Protocol::Tcp), Some("mongodb"));
+        assert_eq!(table.lookup(6379, Protocol::Tcp), Some("redis"));
+        assert_eq!(table.lookup(51820, Protocol::Udp), Some("wireguard"));
+        // Entries are per protocol
+        assert_eq!(table.lookup(51820, Protocol::Tcp), None);
+    }
+
+    #[test]
+    fn test_malformed_records_skipped() {
+        let csv = "port,protocol,service\n8086,tcp,influxdb\nhttp,tcp,web\n70000,tcp,big\n9,sctp,discard\n10,tcp,\n2181,tcp\n";
+        let table = ServiceTable::from_reader(csv.as_bytes());
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.lookup(8086, Protocol::Tcp), Some("influxdb"));
+        assert!(ServiceTable::empty().is_empty());
+    }
+}
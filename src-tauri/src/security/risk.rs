@@ -0,0 +1,170 @@
+use rusqlite::Connection;
+
+use crate::db::queries::devices::{self as db_devices, Device};
+use crate::db::queries::ports::{self as db_ports, PortInfo};
+use crate::scanner::ipv6;
+
+/// TLS certificate health for a device's HTTPS service, when known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertStatus {
+    Valid,
+    /// Past its expiry date.
+    Expired,
+    /// Still valid but weak (old signature algorithm, short key) or expiring soon.
+    Outdated,
+}
+
+/// Services that are commonly abused when exposed on a LAN.
+const RISKY_PORTS: &[u16] = &[
+    21,    // ftp
+    23,    // telnet
+    135,   // msrpc
+    139,   // netbios-ssn
+    445,   // smb
+    1433,  // mssql
+    3306,  // mysql
+    3389,  // rdp
+    5432,  // postgresql
+    5900,  // vnc
+    6379,  // redis
+    9200,  // elasticsearch
+    27017, // mongodb
+];
+
+const UNTRUSTED: u32 = 20;
+const PER_RISKY_PORT: u32 = 15;
+const RISKY_PORTS_CAP: u32 = 45;
+const CERT_EXPIRED: u32 = 15;
+const CERT_OUTDATED: u32 = 10;
+const UNKNOWN_OS: u32 = 10;
+const GLOBAL_IPV6: u32 = 10;
+/// Extra weight when the gateway itself exposes risky services.
+const GATEWAY_EXPOSED: u32 = 10;
+
+/// Triage score from 0 (nothing notable) to 100 (look at this first).
+pub fn risk_score(device: &Device, ports: &[PortInfo], cert: Option<CertStatus>) -> u32 {
+    let mut score = 0;
+
+    if !device.is_trusted {
+        score += UNTRUSTED;
+    }
+
+    let risky = ports
+        .iter()
+        .filter(|p| p.state == "open" && RISKY_PORTS.contains(&p.port))
+        .count() as u32;
+    score += (risky * PER_RISKY_PORT).min(RISKY_PORTS_CAP);
+
+    score += match cert {
+        Some(CertStatus::Expired) => CERT_EXPIRED,
+        Some(CertStatus::Outdated) => CERT_OUTDATED,
+        Some(CertStatus::Valid) | None => 0,
+    };
+
+    if device.os_guess.is_none() {
+        score += UNKNOWN_OS;
+    }
+
+    if device.current_ip.iter().chain(&device.ipv6_addresses).any(|ip| ipv6::is_global(ip)) {
+        score += GLOBAL_IPV6;
+    }
+
+    if device.is_gateway && risky > 0 {
+        score += GATEWAY_EXPOSED;
+    }
+
+    score.min(100)
+}
+
+/// Recompute and store the score for a device from its latest port scan.
+/// No certificate inspection runs yet, so certificate status is not considered.
+pub fn refresh_risk_score(conn: &Connection, device: &Device) -> Result<u32, rusqlite::Error> {
+    let ports = db_ports::get_latest_ports(conn, &device.id)?;
+    let score = risk_score(device, &ports, None);
+    if score != device.risk_score {
+        db_devices::update_risk_score(conn, &device.id, score)?;
+    }
+    Ok(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device() -> Device {
+        Device {
+            os_guess: Some("Linux".to_string()),
+            os_confidence: 0.8,
+            is_trusted: true,
+            current_ip: Some("192.168.1.20".to_string()),
+            ..db_devices::test_device("dev1")
+        }
+    }
+
+    fn open(port: u16) -> PortInfo {
+        PortInfo {
+            port,
+            protocol: "tcp".to_string(),
+            state: "open".to_string(),
+            service_name: None,
+            banner: None,
+        }
+    }
+
+    #[test]
+    fn test_trusted_known_device_scores_zero() {
+        assert_eq!(risk_score(&device(), &[open(80), open(443)], None), 0);
+        assert_eq!(risk_score(&device(), &[], Some(CertStatus::Valid)), 0);
+    }
+
+    #[test]
+    fn test_each_signal_adds_weight() {
+        let mut d = device();
+        d.is_trusted = false;
+        assert_eq!(risk_score(&d, &[], None), UNTRUSTED);
+
+        let d = device();
+        assert_eq!(risk_score(&d, &[open(23)], None), PER_RISKY_PORT);
+        assert_eq!(risk_score(&d, &[], Some(CertStatus::Expired)), CERT_EXPIRED);
+        assert_eq!(risk_score(&d, &[], Some(CertStatus::Outdated)), CERT_OUTDATED);
+
+        let mut d = device();
+        d.os_guess = None;
+        assert_eq!(risk_score(&d, &[], None), UNKNOWN_OS);
+
+        let mut d = device();
+        d.current_ip = Some("2001:db8::20".to_string());
+        assert_eq!(risk_score(&d, &[], None), GLOBAL_IPV6);
+        d.current_ip = Some("fe80::20".to_string());
+        assert_eq!(risk_score(&d, &[], None), 0);
+
+        let mut d = device();
+        d.ipv6_addresses = vec!["fe80::20".to_string(), "2001:db8::20".to_string()];
+        assert_eq!(risk_score(&d, &[], None), GLOBAL_IPV6);
+    }
+
+    #[test]
+    fn test_gateway_exposure_and_caps() {
+        let mut d = device();
+        d.is_gateway = true;
+        assert_eq!(risk_score(&d, &[], None), 0);
+        assert_eq!(risk_score(&d, &[open(3389)], None), PER_RISKY_PORT + GATEWAY_EXPOSED);
+
+        let many: Vec<_> = RISKY_PORTS.iter().map(|p| open(*p)).collect();
+        assert_eq!(risk_score(&device(), &many, None), RISKY_PORTS_CAP);
+
+        let mut worst = device();
+        worst.is_trusted = false;
+        worst.is_gateway = true;
+        worst.os_guess = None;
+        worst.current_ip = Some("2001:db8::1".to_string());
+        assert_eq!(risk_score(&worst, &many, Some(CertStatus::Expired)), 100);
+    }
+
+    #[test]
+    fn test_closed_ports_ignored() {
+        let mut closed = open(23);
+        closed.state = "closed".to_string();
+        assert_eq!(risk_score(&device(), &[closed], None), 0);
+    }
+}
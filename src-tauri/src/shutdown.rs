@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use crate::db;
+use crate::db::queries::scans as db_scans;
+use crate::state::AppState;
+
+/// How long to wait for in-flight work to observe cancellation before forcing it.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Stop background work and leave the database in a clean state.
+///
+/// Cancels the monitor loop, any running or queued scan, waits up to `SHUTDOWN_GRACE`
+/// for them to wind down, marks scans still in `running` as interrupted and checkpoints
+/// the WAL. The exit hook blocks on it.
+pub async fn graceful_shutdown(state: &AppState) {
+    log::info!("Shutting down");
+
+    let dropped = state.scan_queue.cancel_pending();
+    if dropped > 0 {
+        log::info!("Dropped {} queued scans", dropped);
+    }
+
+    if let Ok(guard) = state.monitor_cancel.lock() {
+        if let Some(ref token) = *guard {
+            token.cancel();
+        }
+    }
+    if let Ok(guard) = state.scan_cancel.lock() {
+        if let Some(ref token) = *guard {
+            token.cancel();
+        }
+    }
+
+    let monitor = state.monitor_handle.lock().ok().and_then(|mut guard| guard.take());
+    let monitor_abort = monitor.as_ref().map(|handle| handle.abort_handle());
+    let wind_down = async {
+        if let Some(handle) = monitor {
+            let _ = handle.await;
+        }
+        scan_finished(state).await;
+    };
+    if tokio::time::timeout(SHUTDOWN_GRACE, wind_down).await.is_err() {
+        log::warn!("Background work did not stop within {:?}; aborting", SHUTDOWN_GRACE);
+        if let Some(abort) = monitor_abort {
+            abort.abort();
+        }
+    }
+
+    match state.conn() {
+        Ok(conn) => {
+            match db_scans::interrupt_running_scans(&conn) {
+                Ok(0) => {}
+                Ok(n) => log::info!("Marked {} running scans as interrupted", n),
+                Err(e) => log::error!("Failed to mark running scans interrupted: {}", e),
+            }
+            if let Err(e) = db::checkpoint(&conn) {
+                log::error!("WAL checkpoint failed: {}", e);
+            }
+        }
+        Err(e) => log::error!("No database connection during shutdown: {}", e),
+    }
+}
+
+/// Resolve once no user scan holds the scan slot.
+async fn scan_finished(state: &AppState) {
+    loop {
+        // Register for the wakeup before checking, so a release in between isn't missed
+        let notified = state.scan_finished.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        let running = state.scan_cancel.lock().map(|t| t.is_some()).unwrap_or(false);
+        if !running {
+            return;
+        }
+        notified.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use crate::network::oui::OuiDatabase;
+    use tokio_util::sync::CancellationToken;
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_cancels_and_interrupts() {
+        let state = AppState::new(db::init_test_db(), OuiDatabase::empty());
+        {
+            let conn = state.conn().unwrap();
+            db_scans::create_scan(&conn, "scan1", None, "quick").unwrap();
+        }
+
+        let monitor = CancellationToken::new();
+        *state.monitor_cancel.lock().unwrap() = Some(monitor.clone());
+
+        graceful_shutdown(&state).await;
+
+        assert!(monitor.is_cancelled());
+        let conn = state.conn().unwrap();
+        let history = db_scans::get_scan_history(&conn, 10).unwrap();
+        assert_eq!(history[0].status, "interrupted");
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_waits_for_scan_to_stop() {
+        let state = Arc::new(AppState::new(db::init_test_db(), OuiDatabase::empty()));
+        let (claimed_tx, claimed) = tokio::sync::oneshot::channel();
+        let scan_state = state.clone();
+        let scan = tokio::spawn(async move {
+            let cancel = CancellationToken::new();
+            let _slot = scan_state.claim_scan(cancel.clone()).unwrap();
+            claimed_tx.send(()).unwrap();
+            cancel.cancelled().await;
+        });
+        claimed.await.unwrap();
+
+        let started = Instant::now();
+        graceful_shutdown(&state).await;
+
+        // Returns as soon as the scan lets go of its slot, well inside the grace period
+        assert!(started.elapsed() < SHUTDOWN_GRACE);
+        assert!(state.scan_cancel.lock().unwrap().is_none());
+        scan.await.unwrap();
+    }
+}
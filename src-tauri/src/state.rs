@@ -2,13 +2,18 @@ use std::sync::Mutex;
 
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
+use tokio::sync::Notify;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
+use crate::error::AppError;
+use crate::network::interface::NetworkInterface;
 use crate::network::oui::OuiDatabase;
+use crate::scanner::queue::ScanQueue;
 
 /// Shared application state managed by Tauri.
-/// Accessed via `tauri::State<AppState>` in commands.
+/// Managed behind an `Arc` so background tasks can hold it; accessed via
+/// `tauri::State<Arc<AppState>>` in commands.
 pub struct AppState {
     pub db: Pool<SqliteConnectionManager>,
     pub oui_db: OuiDatabase,
@@ -18,6 +23,12 @@ pub struct AppState {
     pub monitor_cancel: Mutex<Option<CancellationToken>>,
     /// Token to cancel the current in-progress scan.
     pub scan_cancel: Mutex<Option<CancellationToken>>,
+    /// Woken whenever the scan slot is released.
+    pub scan_finished: Notify,
+    /// Pending scans when `queue_scans` is enabled.
+    pub scan_queue: ScanQueue,
+    /// Last interface list seen by the watcher; `None` until the first poll.
+    pub interfaces: Mutex<Option<Vec<NetworkInterface>>>,
 }
 
 impl AppState {
@@ -28,6 +39,9 @@ impl AppState {
             monitor_handle: Mutex::new(None),
             monitor_cancel: Mutex::new(None),
             scan_cancel: Mutex::new(None),
+            scan_finished: Notify::new(),
+            scan_queue: ScanQueue::default(),
+            interfaces: Mutex::new(None),
         }
     }
 
@@ -35,4 +49,66 @@ impl AppState {
     pub fn conn(&self) -> Result<r2d2::PooledConnection<SqliteConnectionManager>, r2d2::Error> {
         self.db.get()
     }
+
+    /// Claim the single scan slot, storing `cancel` so `stop_scan` can reach the scan.
+    /// Fails with `SCAN_IN_PROGRESS` while another scan holds it.
+    pub fn claim_scan(&self, cancel: CancellationToken) -> Result<ScanSlot<'_>, AppError> {
+        let mut guard = self.scan_cancel.lock()?;
+        if guard.is_some() {
+            return Err(AppError::new("SCAN_IN_PROGRESS", "A scan is already in progress"));
+        }
+        *guard = Some(cancel);
+        Ok(ScanSlot { state: self })
+    }
+
+    /// Like `claim_scan`, but waits for whichever scan holds the slot (a user, queued or
+    /// monitor scan) to release it instead of failing.
+    pub async fn claim_scan_when_free(&self, cancel: CancellationToken) -> Result<ScanSlot<'_>, AppError> {
+        loop {
+            // Register for the wakeup before trying, so a release in between isn't missed
+            let notified = self.scan_finished.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            match self.claim_scan(cancel.clone()) {
+                Err(e) if e.code == "SCAN_IN_PROGRESS" => notified.await,
+                claimed => return claimed,
+            }
+        }
+    }
+}
+
+/// Held for the length of a scan; dropping it frees the slot for the next one.
+pub struct ScanSlot<'a> {
+    state: &'a AppState,
+}
+
+impl Drop for ScanSlot<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut guard) = self.state.scan_cancel.lock() {
+            *guard = None;
+        }
+        self.state.scan_finished.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_second_scan_is_rejected_until_the_first_ends() {
+        let state = AppState::new(db::init_test_db(), OuiDatabase::empty());
+
+        let first = state.claim_scan(CancellationToken::new()).unwrap();
+        let err = state.claim_scan(CancellationToken::new()).err().unwrap();
+        assert_eq!(err.code, "SCAN_IN_PROGRESS");
+        // The running scan keeps its token
+        assert!(state.scan_cancel.lock().unwrap().is_some());
+
+        drop(first);
+        assert!(state.scan_cancel.lock().unwrap().is_none());
+        assert!(state.claim_scan(CancellationToken::new()).is_ok());
+    }
 }
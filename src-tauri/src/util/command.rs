@@ -0,0 +1,307 @@
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use crate::AppError;
+
+/// What a finished command printed, with output decoded lossily as UTF-8.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl From<std::process::Output> for CommandOutput {
+    fn from(output: std::process::Output) -> Self {
+        Self {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        }
+    }
+}
+
+/// What tools print to stderr when the OS refuses them for lack of privileges:
+/// the `EPERM`/`EACCES` messages on Linux and macOS, and Windows' "Access is denied".
+const PERMISSION_DENIED_MARKERS: &[&str] = &[
+    "operation not permitted",
+    "permission denied",
+    "access is denied",
+    "access denied",
+];
+
+impl CommandOutput {
+    /// Whether the command ran but failed for lack of privileges, as opposed to
+    /// succeeding with nothing to report.
+    pub fn permission_denied(&self) -> bool {
+        let stderr = self.stderr.to_lowercase();
+        !self.success && PERMISSION_DENIED_MARKERS.iter().any(|m| stderr.contains(m))
+    }
+}
+
+/// A `PERMISSION_DENIED` error for `command` when `result` is a run the OS refused,
+/// either at launch or with a privilege error on stderr.
+pub fn permission_error(command: &str, result: &std::io::Result<CommandOutput>) -> Option<AppError> {
+    let denied = match result {
+        Ok(output) => output.permission_denied(),
+        Err(e) => e.kind() == std::io::ErrorKind::PermissionDenied,
+    };
+    denied.then(|| AppError::permission_denied(command))
+}
+
+/// Runs external programs. Scanners take one of these instead of calling `Command`
+/// directly, so tests can substitute canned output.
+pub trait CommandRunner: std::fmt::Debug + Send + Sync {
+    /// Run `program` to completion. Fails only if it could not be started.
+    fn run(&self, program: &str, args: &[String]) -> std::io::Result<CommandOutput>;
+
+    /// Stdout of a run, logging (at `warn`) and returning `None` if it could not be started.
+    fn stdout(&self, program: &str, args: &[&str]) -> Option<String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        match self.run(program, &args) {
+            Ok(output) => Some(output.stdout),
+            Err(e) => {
+                log::warn!("Failed to run {} {}: {}", program, args.join(" "), e);
+                None
+            }
+        }
+    }
+
+    /// Like `stdout`, but a run refused for lack of privileges is an error instead of `None`.
+    fn checked_stdout(&self, program: &str, args: &[&str]) -> Result<Option<String>, AppError> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let result = self.run(program, &args);
+        let command = std::iter::once(program).chain(args.iter().map(|a| a.as_str())).collect::<Vec<_>>().join(" ");
+        if let Some(denied) = permission_error(&command, &result) {
+            return Err(denied);
+        }
+        match result {
+            Ok(output) => Ok(Some(output.stdout)),
+            Err(e) => {
+                log::warn!("Failed to run {}: {}", command, e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Shared handle to a runner, cheap to clone into spawned tasks.
+pub type SharedRunner = Arc<dyn CommandRunner>;
+
+/// Runs commands on this machine.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[String]) -> std::io::Result<CommandOutput> {
+        Command::new(program).args(args).output().map(CommandOutput::from)
+    }
+}
+
+/// The runner for real commands.
+pub fn system_runner() -> SharedRunner {
+    Arc::new(SystemRunner)
+}
+
+/// Test runner replying with canned output to command lines that start with a given prefix.
+/// Anything unmatched fails as if the program were not installed.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MockRunner {
+    replies: Vec<(String, CommandOutput)>,
+    calls: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl MockRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Succeed with `stdout` for command lines (program and arguments joined by spaces)
+    /// starting with `prefix`. Earlier replies win.
+    pub fn on(self, prefix: &str, stdout: &str) -> Self {
+        self.reply(prefix, CommandOutput { success: true, stdout: stdout.to_string(), stderr: String::new() })
+    }
+
+    /// Like `on`, with a full output including the exit status.
+    pub fn reply(mut self, prefix: &str, output: CommandOutput) -> Self {
+        self.replies.push((prefix.to_string(), output));
+        self
+    }
+
+    /// Every command line run so far, in order.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl CommandRunner for MockRunner {
+    fn run(&self, program: &str, args: &[String]) -> std::io::Result<CommandOutput> {
+        let line = std::iter::once(program.to_string())
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.calls.lock().unwrap().push(line.clone());
+        self.replies
+            .iter()
+            .find(|(prefix, _)| line.starts_with(prefix.as_str()))
+            .map(|(_, output)| output.clone())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("no mock for {}", line)))
+    }
+}
+
+/// A user-supplied command line with `{name}` placeholders, e.g. `fping -e -t {timeout_ms} {ip}`.
+/// Arguments are split on whitespace; quoting is not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandTemplate {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandTemplate {
+    /// Parse a template, requiring each of `required` (e.g. `"{ip}"`) to appear in an argument.
+    pub fn parse(template: &str, required: &[&str]) -> Result<Self, String> {
+        let mut parts = template.split_whitespace().map(|s| s.to_string());
+        let program = parts
+            .next()
+            .ok_or_else(|| "Command template cannot be empty".to_string())?;
+        let args: Vec<String> = parts.collect();
+
+        for placeholder in required {
+            if !args.iter().any(|a| a.contains(placeholder)) {
+                return Err(format!(
+                    "Command template must contain {} (got: {})",
+                    placeholder, template
+                ));
+            }
+        }
+        Ok(Self { program, args })
+    }
+
+    /// The executable's file name, e.g. `fping` for `/usr/sbin/fping`.
+    pub fn program_name(&self) -> &str {
+        Path::new(&self.program)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&self.program)
+    }
+
+    /// Substitute `vars` (`("ip", "10.0.0.1")` replaces `{ip}`) into every argument.
+    pub fn render(&self, vars: &[(&str, &str)]) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|arg| {
+                vars.iter()
+                    .fold(arg.clone(), |acc, (name, value)| acc.replace(&format!("{{{}}}", name), value))
+            })
+            .collect()
+    }
+
+    /// Like `render`, but an argument that is exactly `{list}` expands to one argument per value.
+    pub fn render_list(&self, list: &str, values: &[String], vars: &[(&str, &str)]) -> Vec<String> {
+        let whole = format!("{{{}}}", list);
+        let mut out = Vec::new();
+        for (arg, rendered) in self.args.iter().zip(self.render(vars)) {
+            if *arg == whole {
+                out.extend(values.iter().cloned());
+            } else {
+                out.push(rendered);
+            }
+        }
+        out
+    }
+
+    /// Run this program with the given (rendered) arguments.
+    pub fn run(&self, runner: &dyn CommandRunner, args: &[String]) -> std::io::Result<CommandOutput> {
+        runner.run(&self.program, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed(stderr: &str) -> CommandOutput {
+        CommandOutput { success: false, stdout: String::new(), stderr: stderr.to_string() }
+    }
+
+    #[test]
+    fn test_permission_denied_stderr() {
+        // Linux: unprivileged ping without the raw socket capability, and iproute2
+        assert!(failed("ping: socket: Operation not permitted\n").permission_denied());
+        assert!(failed("RTNETLINK answers: Operation not permitted\n").permission_denied());
+        // macOS
+        assert!(failed("ping: sendto: Permission denied\n").permission_denied());
+        assert!(failed("arp: bpf: Operation not permitted\n").permission_denied());
+        // Windows: ping, and PowerShell cmdlets
+        assert!(failed("Access is denied.\r\n").permission_denied());
+        assert!(failed("Get-NetNeighbor : Access denied\r\n").permission_denied());
+
+        // Other failures, and warnings from commands that still succeeded
+        assert!(!failed("ping: unknown host nosuchhost\n").permission_denied());
+        assert!(!failed("connect: Network is unreachable\n").permission_denied());
+        let warned = CommandOutput { success: true, ..failed("arp: Permission denied reading /etc/ethers") };
+        assert!(!warned.permission_denied());
+    }
+
+    #[test]
+    fn test_checked_stdout() {
+        let runner = MockRunner::new()
+            .on("ip neigh show", "10.0.0.1 dev eth0 lladdr aa:bb:cc:dd:ee:ff REACHABLE\n")
+            .reply("ping -c 1", failed("ping: socket: Operation not permitted\n"));
+
+        assert!(runner.checked_stdout("ip", &["neigh", "show"]).unwrap().is_some());
+        let err = runner.checked_stdout("ping", &["-c", "1", "10.0.0.1"]).unwrap_err();
+        assert_eq!(err.code, "PERMISSION_DENIED");
+        assert!(err.message.contains("ping -c 1 10.0.0.1"));
+        // A missing program is not a privilege problem
+        assert_eq!(runner.checked_stdout("arp", &["-a"]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_requires_placeholders() {
+        assert!(CommandTemplate::parse("busybox ping -c 1 {ip}", &["{ip}"]).is_ok());
+        assert!(CommandTemplate::parse("ping -c 1", &["{ip}"]).unwrap_err().contains("{ip}"));
+        assert!(CommandTemplate::parse("   ", &[]).is_err());
+        // The program name itself doesn't count as an argument
+        assert!(CommandTemplate::parse("{ip}", &["{ip}"]).is_err());
+    }
+
+    #[test]
+    fn test_render_substitutes_vars() {
+        let t = CommandTemplate::parse("busybox ping -c 1 -W {timeout_s} {ip}", &["{ip}"]).unwrap();
+        assert_eq!(t.program_name(), "busybox");
+        assert_eq!(
+            t.render(&[("ip", "10.0.0.1"), ("timeout_s", "2")]),
+            ["ping", "-c", "1", "-W", "2", "10.0.0.1"]
+        );
+    }
+
+    #[test]
+    fn test_template_runs_through_runner() {
+        let runner = MockRunner::new().on("busybox arp", "? (10.0.0.1) at aa:bb:cc:dd:ee:ff");
+        let t = CommandTemplate::parse("busybox arp -n", &[]).unwrap();
+
+        let output = t.run(&runner, &t.render(&[])).unwrap();
+        assert!(output.success);
+        assert!(output.stdout.contains("10.0.0.1"));
+        assert_eq!(runner.calls(), ["busybox arp -n"]);
+
+        assert_eq!(runner.run("ping", &[]).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+        assert!(runner.stdout("ping", &["-c", "1"]).is_none());
+    }
+
+    #[test]
+    fn test_render_list_expands_whole_argument() {
+        let t = CommandTemplate::parse("/usr/sbin/fping -e -t{timeout_ms} {ip}", &["{ip}"]).unwrap();
+        assert_eq!(t.program_name(), "fping");
+        let ips = vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        assert_eq!(
+            t.render_list("ip", &ips, &[("timeout_ms", "500")]),
+            ["-e", "-t500", "10.0.0.1", "10.0.0.2"]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// Timestamp format used for every date stored in the database.
+/// Matches SQLite's `datetime('now')`, which is always UTC.
+pub const DB_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Current UTC time in the database format.
+pub fn now_db_string() -> String {
+    to_db_string(Utc::now().naive_utc())
+}
+
+/// Format a UTC time in the database format.
+pub fn to_db_string(time: NaiveDateTime) -> String {
+    time.format(DB_TIME_FORMAT).to_string()
+}
+
+/// Parse a stored timestamp as UTC.
+/// Also accepts RFC 3339 (e.g. from imported files), converting any offset to UTC.
+pub fn parse_db_time(timestamp: &str) -> Option<NaiveDateTime> {
+    let timestamp = timestamp.trim();
+    NaiveDateTime::parse_from_str(timestamp, DB_TIME_FORMAT)
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc).naive_utc())
+        })
+}
+
+/// Seconds elapsed since a stored timestamp, or `None` if it can't be parsed.
+pub fn seconds_since(timestamp: &str) -> Option<i64> {
+    parse_db_time(timestamp).map(|dt| Utc::now().naive_utc().signed_duration_since(dt).num_seconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_format_matches_sqlite() {
+        let now = now_db_string();
+        assert_eq!(now.len(), 19);
+        assert_eq!(&now[10..11], " ");
+
+        let conn = db::init_test_db().get().unwrap();
+        let sqlite_now: String = conn
+            .query_row("SELECT datetime('now')", [], |row| row.get(0))
+            .unwrap();
+        let drift = parse_db_time(&now).unwrap() - parse_db_time(&sqlite_now).unwrap();
+        assert!(drift.num_seconds().abs() <= 2, "drift was {}", drift);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let time = parse_db_time("2026-03-04 05:06:07").unwrap();
+        assert_eq!(to_db_string(time), "2026-03-04 05:06:07");
+        assert_eq!(to_db_string(parse_db_time(&now_db_string()).unwrap()).len(), 19);
+    }
+
+    #[test]
+    fn test_rfc3339_normalized_to_utc() {
+        let time = parse_db_time("2026-03-04T07:06:07+02:00").unwrap();
+        assert_eq!(to_db_string(time), "2026-03-04 05:06:07");
+        assert!(parse_db_time("yesterday").is_none());
+        assert!(seconds_since("2000-01-01 00:00:00").unwrap() > 0);
+    }
+}
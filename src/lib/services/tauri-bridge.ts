@@ -3,14 +3,15 @@
  * This is the ONLY file that calls invoke().
  */
 import { invoke } from '@tauri-apps/api/core';
-import type { Device, DeviceUpdate } from '$lib/types/device';
-import type { ScanConfig, ScanResult, ScanSummary } from '$lib/types/scan';
+import type { CsvImportResult, Device, DevicePage, DeviceQuery, DeviceUpdate, FingerprintEntry, ImportPreview, ImportResult, ImportStrategy, IpHistoryEntry, NoteMatch } from '$lib/types/device';
+import type { MonitorStarted, ScanConfig, ScanDiff, ScanStart, ScanSummary } from '$lib/types/scan';
 import type { Alert, AlertRule, AlertRuleUpdate } from '$lib/types/alert';
-import type { NetworkInterface, AppSettings, LatencyPoint, PingResult } from '$lib/types/network';
+import type { NetworkInterface, AppSettings, BackupInfo, LatencyPoint, PingResult, Reachability, RepairReport } from '$lib/types/network';
 
 // ── Scanning ──
 
-export async function startScan(config: ScanConfig): Promise<ScanResult> {
+/** A queued scan resolves as soon as it is queued; its result arrives as scan:completed */
+export async function startScan(config: ScanConfig): Promise<ScanStart> {
 	return invoke('start_scan', { config });
 }
 
@@ -22,12 +23,28 @@ export async function getScanHistory(limit: number = 50): Promise<ScanSummary[]>
 	return invoke('get_scan_history', { limit });
 }
 
+export async function getScanLog(scanId: string): Promise<string> {
+	return invoke('get_scan_log', { scanId });
+}
+
+export async function getScanDiff(scanIdA: string, scanIdB: string): Promise<ScanDiff> {
+	return invoke('get_scan_diff', { scanIdA, scanIdB });
+}
+
 // ── Devices ──
 
 export async function getDevices(): Promise<Device[]> {
 	return invoke('get_devices');
 }
 
+export async function getDevicesPaged(query: DeviceQuery): Promise<DevicePage> {
+	return invoke('get_devices_paged', { query });
+}
+
+export async function getDevicesNeedingReview(): Promise<Device[]> {
+	return invoke('get_devices_needing_review');
+}
+
 export async function getDevice(deviceId: string): Promise<Device> {
 	return invoke('get_device', { deviceId });
 }
@@ -36,10 +53,56 @@ export async function updateDevice(deviceId: string, updates: DeviceUpdate): Pro
 	return invoke('update_device', { deviceId, updates });
 }
 
+/** Applies `updates` to every device in one transaction; resolves to how many were updated */
+export async function bulkUpdateDevices(deviceIds: string[], updates: DeviceUpdate): Promise<number> {
+	return invoke('bulk_update_devices', { deviceIds, updates });
+}
+
+export async function searchNotes(query: string): Promise<NoteMatch[]> {
+	return invoke('search_notes', { query });
+}
+
+export async function getIpHistory(deviceId: string): Promise<IpHistoryEntry[]> {
+	return invoke('get_ip_history', { deviceId });
+}
+
+export async function getFingerprintHistory(deviceId: string): Promise<FingerprintEntry[]> {
+	return invoke('get_fingerprint_history', { deviceId });
+}
+
+export async function addDeviceTag(deviceId: string, tag: string): Promise<Device> {
+	return invoke('add_device_tag', { deviceId, tag });
+}
+
+export async function removeDeviceTag(deviceId: string, tag: string): Promise<Device> {
+	return invoke('remove_device_tag', { deviceId, tag });
+}
+
+export async function getTags(): Promise<string[]> {
+	return invoke('get_tags');
+}
+
+/** Pass `null` to remove the property */
+export async function setDeviceProperty(deviceId: string, key: string, value: string | null): Promise<Device> {
+	return invoke('set_device_property', { deviceId, key, value });
+}
+
+export async function getDeviceProperties(deviceId: string): Promise<Record<string, string>> {
+	return invoke('get_device_properties', { deviceId });
+}
+
 export async function deleteDevice(deviceId: string): Promise<void> {
 	return invoke('delete_device', { deviceId });
 }
 
+export async function checkDeviceNow(deviceId: string): Promise<Reachability> {
+	return invoke('check_device_now', { deviceId });
+}
+
+export async function wakeDevice(deviceId: string): Promise<void> {
+	return invoke('wake_device', { deviceId });
+}
+
 // ── Alerts ──
 
 export async function getAlerts(unreadOnly: boolean = false): Promise<Alert[]> {
@@ -54,6 +117,10 @@ export async function markAllAlertsRead(): Promise<void> {
 	return invoke('mark_all_alerts_read');
 }
 
+export async function getAlertsByRule(ruleId: string): Promise<Alert[]> {
+	return invoke('get_alerts_by_rule', { ruleId });
+}
+
 export async function getAlertRules(): Promise<AlertRule[]> {
 	return invoke('get_alert_rules');
 }
@@ -70,7 +137,7 @@ export async function getInterfaces(): Promise<NetworkInterface[]> {
 
 // ── Monitoring ──
 
-export async function startMonitor(intervalSecs: number): Promise<void> {
+export async function startMonitor(intervalSecs: number): Promise<MonitorStarted> {
 	return invoke('start_monitor', { intervalSecs });
 }
 
@@ -88,10 +155,23 @@ export async function updateSettings(settings: AppSettings): Promise<void> {
 	return invoke('update_settings', { settings });
 }
 
+export async function diagnoseAndRepair(): Promise<RepairReport> {
+	return invoke('diagnose_and_repair');
+}
+
+export async function listBackups(): Promise<BackupInfo[]> {
+	return invoke('list_backups');
+}
+
+export async function restoreBackup(filename: string): Promise<RepairReport> {
+	return invoke('restore_backup', { filename });
+}
+
 // ── Latency ──
 
-export async function getLatencyHistory(deviceId: string, hours: number = 24): Promise<LatencyPoint[]> {
-	return invoke('get_latency_history', { deviceId, hours });
+/** `bucketMinutes` above zero averages points per bucket; zero returns every sample */
+export async function getLatencyHistory(deviceId: string, hours: number = 24, bucketMinutes: number = 0): Promise<LatencyPoint[]> {
+	return invoke('get_latency_history', { deviceId, hours, bucketMinutes });
 }
 
 export async function pingDevice(ip: string): Promise<PingResult> {
@@ -104,6 +184,26 @@ export async function exportDevices(): Promise<string> {
 	return invoke('export_devices');
 }
 
-export async function importDevices(jsonData: string): Promise<{ imported: number; skipped: number }> {
-	return invoke('import_devices', { jsonData });
+export async function exportDevicesCsv(): Promise<string> {
+	return invoke('export_devices_csv');
+}
+
+export async function exportLatencyCsv(deviceId: string, hours: number = 24): Promise<string> {
+	return invoke('export_latency_csv', { deviceId, hours });
+}
+
+export async function exportScanNmapXml(scanId: string): Promise<string> {
+	return invoke('export_scan_nmap_xml', { scanId });
+}
+
+export async function importDevices(jsonData: string, strategy: ImportStrategy = 'skip'): Promise<ImportResult> {
+	return invoke('import_devices', { jsonData, strategy });
+}
+
+export async function previewImport(jsonData: string): Promise<ImportPreview> {
+	return invoke('preview_import', { jsonData });
+}
+
+export async function importDevicesCsv(csv: string, strategy: ImportStrategy = 'skip'): Promise<CsvImportResult> {
+	return invoke('import_devices_csv', { csv, strategy });
 }
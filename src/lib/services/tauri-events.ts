@@ -4,18 +4,24 @@
  */
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type { Device } from '$lib/types/device';
-import type { ScanProgress, ScanResult } from '$lib/types/scan';
+import type { ScanProgress, ScanQueued, ScanResult, ScanStarted } from '$lib/types/scan';
 import type { Alert } from '$lib/types/alert';
+import type { GatewayStatus, InterfacesChanged } from '$lib/types/network';
+import type { AppError } from '$lib/stores/error.svelte';
 
 export interface EventHandlers {
 	onScanProgress: (progress: ScanProgress) => void;
 	onDeviceDiscovered: (device: Device) => void;
 	onScanCompleted: (result: ScanResult) => void;
-	onScanError: (error: { message: string }) => void;
+	onScanError: (error: AppError) => void;
+	onScanQueued: (job: ScanQueued) => void;
+	onScanStarted: (job: ScanStarted) => void;
 	onDeviceUpdated: (device: Device) => void;
 	onDeviceDeparted: (data: { deviceId: string }) => void;
 	onAlertNew: (alert: Alert) => void;
 	onMonitorStatus: (status: { isRunning: boolean; nextScanIn: number | null }) => void;
+	onInterfacesChanged: (change: InterfacesChanged) => void;
+	onGatewayStatus: (status: GatewayStatus) => void;
 }
 
 const EVENT_NAMES = {
@@ -23,10 +29,14 @@ const EVENT_NAMES = {
 	DEVICE_DISCOVERED: 'scan:device-discovered',
 	SCAN_COMPLETED: 'scan:completed',
 	SCAN_ERROR: 'scan:error',
+	SCAN_QUEUED: 'scan:queued',
+	SCAN_STARTED: 'scan:started',
 	DEVICE_UPDATED: 'device:updated',
 	DEVICE_DEPARTED: 'device:departed',
 	ALERT_NEW: 'alert:new',
-	MONITOR_STATUS: 'monitor:status'
+	MONITOR_STATUS: 'monitor:status',
+	INTERFACES_CHANGED: 'interfaces:changed',
+	GATEWAY_STATUS: 'gateway:status'
 } as const;
 
 /**
@@ -44,9 +54,15 @@ export async function subscribeAll(handlers: EventHandlers): Promise<UnlistenFn[
 		listen<ScanResult>(EVENT_NAMES.SCAN_COMPLETED, (event) => {
 			handlers.onScanCompleted(event.payload);
 		}),
-		listen<{ message: string }>(EVENT_NAMES.SCAN_ERROR, (event) => {
+		listen<AppError>(EVENT_NAMES.SCAN_ERROR, (event) => {
 			handlers.onScanError(event.payload);
 		}),
+		listen<ScanQueued>(EVENT_NAMES.SCAN_QUEUED, (event) => {
+			handlers.onScanQueued(event.payload);
+		}),
+		listen<ScanStarted>(EVENT_NAMES.SCAN_STARTED, (event) => {
+			handlers.onScanStarted(event.payload);
+		}),
 		listen<Device>(EVENT_NAMES.DEVICE_UPDATED, (event) => {
 			handlers.onDeviceUpdated(event.payload);
 		}),
@@ -58,6 +74,12 @@ export async function subscribeAll(handlers: EventHandlers): Promise<UnlistenFn[
 		}),
 		listen<{ isRunning: boolean; nextScanIn: number | null }>(EVENT_NAMES.MONITOR_STATUS, (event) => {
 			handlers.onMonitorStatus(event.payload);
+		}),
+		listen<InterfacesChanged>(EVENT_NAMES.INTERFACES_CHANGED, (event) => {
+			handlers.onInterfacesChanged(event.payload);
+		}),
+		listen<GatewayStatus>(EVENT_NAMES.GATEWAY_STATUS, (event) => {
+			handlers.onGatewayStatus(event.payload);
 		})
 	]);
 
@@ -13,6 +13,9 @@ export const monitoringActive = writable<boolean>(false);
 /** Next monitor scan countdown in seconds */
 export const nextScanIn = writable<number>(0);
 
+/** Number of queued scans that have not started yet */
+export const queuedScans = writable<number>(0);
+
 /** Scan history */
 export const scanHistory = writable<ScanSummary[]>([]);
 
@@ -43,3 +46,12 @@ export function updateMonitorStatus(running: boolean, countdown: number | null):
 	monitoringActive.set(running);
 	nextScanIn.set(countdown ?? 0);
 }
+
+export function scanQueued(position: number): void {
+	queuedScans.set(position);
+}
+
+export function scanStarted(): void {
+	queuedScans.update((n) => Math.max(0, n - 1));
+	isScanning.set(true);
+}
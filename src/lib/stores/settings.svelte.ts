@@ -1,9 +1,12 @@
 import { writable, derived } from 'svelte/store';
-import type { NetworkInterface, AppSettings } from '$lib/types/network';
+import type { NetworkInterface, AppSettings, GatewayStatus } from '$lib/types/network';
 
 /** Available network interfaces */
 export const interfaces = writable<NetworkInterface[]>([]);
 
+/** Last reachability change reported by the gateway monitor */
+export const gatewayStatus = writable<GatewayStatus | null>(null);
+
 /** App settings */
 export const settings = writable<AppSettings>({
 	defaultInterfaceId: null,
@@ -12,17 +15,41 @@ export const settings = writable<AppSettings>({
 	theme: 'dark',
 	graphRepulsion: 300,
 	graphLinkDistance: 100,
-	graphGravity: 0.1
+	graphGravity: 0.1,
+	healthBindAddr: null,
+	ingestBindAddr: null,
+	ingestToken: null,
+	latencyChangeThresholdPct: 0,
+	latencyMinIntervalSecs: 0,
+	queueScans: false,
+	verboseScanLogs: false,
+	pingTimeoutMs: 2000,
+	pingSamples: 3,
+	pingMinSamples: 2,
+	pingTrimOutliers: true,
+	scanConcurrency: 64,
+	pingCommand: null,
+	arpCommand: null,
+	gatewayMonitorIntervalSecs: 5,
+	backupIntervalHours: 24,
+	alertCooldownSecs: 3600,
+	quietHoursStart: null,
+	quietHoursEnd: null
 });
 
+/** First active interface, preferring physical ones over VPN tunnels */
+function firstActive(list: NetworkInterface[]): NetworkInterface | null {
+	return list.find((i) => i.isActive && !i.isVpn) ?? list.find((i) => i.isActive) ?? null;
+}
+
 /** The currently active interface (derived from settings + interfaces) */
 export const activeInterface = derived(
 	[interfaces, settings],
 	([$interfaces, $settings]) => {
 		if ($settings.defaultInterfaceId) {
-			return $interfaces.find((i) => i.id === $settings.defaultInterfaceId) ?? $interfaces.find((i) => i.isActive) ?? null;
+			return $interfaces.find((i) => i.id === $settings.defaultInterfaceId) ?? firstActive($interfaces);
 		}
-		return $interfaces.find((i) => i.isActive) ?? null;
+		return firstActive($interfaces);
 	}
 );
 
@@ -30,6 +57,10 @@ export function setInterfaces(list: NetworkInterface[]): void {
 	interfaces.set(list);
 }
 
+export function setGatewayStatus(status: GatewayStatus): void {
+	gatewayStatus.set(status);
+}
+
 export function setSettings(s: AppSettings): void {
 	settings.set(s);
 }
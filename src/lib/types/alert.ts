@@ -1,6 +1,6 @@
-export type AlertEventType = 'new_device' | 'device_departed' | 'port_changed' | 'unknown_device';
+export type AlertEventType = 'new_device' | 'device_departed' | 'port_changed' | 'unknown_device' | 'network_event' | 'gateway_down' | 'hostname_changed' | 'arp_spoof' | 'ip_changed' | 'packet_loss' | 'identity_changed';
 
-export type AlertRuleType = 'new_device' | 'device_departed' | 'port_changed' | 'untrusted_device';
+export type AlertRuleType = 'new_device' | 'device_departed' | 'port_changed' | 'untrusted_device' | 'gateway_down' | 'hostname_changed' | 'arp_spoof' | 'ip_changed' | 'packet_loss' | 'identity_changed';
 
 export type Severity = 'info' | 'warning' | 'critical';
 
@@ -12,6 +12,7 @@ export interface Alert {
 	severity: Severity;
 	isRead: boolean;
 	createdAt: string;
+	ruleId: string | null;
 }
 
 export interface AlertRule {
@@ -2,6 +2,8 @@ import type { SimulationNodeDatum, SimulationLinkDatum } from 'd3-force';
 
 export type DeviceType = 'router' | 'computer' | 'phone' | 'tablet' | 'iot' | 'printer' | 'unknown';
 
+export type ConnectionType = 'wired' | 'wireless' | 'virtual' | 'unknown';
+
 export interface Device {
 	id: string;
 	macAddress: string | null;
@@ -14,9 +16,32 @@ export interface Device {
 	isTrusted: boolean;
 	isGateway: boolean;
 	notes: string | null;
+	/** Notes flagged as important */
+	notesPinned: boolean;
+	/** Locally administered MAC, usually a randomized private address */
+	isRandomizedMac: boolean;
+	icon: string | null;
+	connectionType: ConnectionType;
+	sourceAgent: string | null;
+	/** The current IPv4 address, or the current IPv6 one for a device without IPv4 */
 	currentIp: string | null;
+	/** Every IPv6 address the device has held, current first */
+	ipv6Addresses: string[];
 	isOnline: boolean;
+	/** Confidence in [0, 1] that the device is present, decaying since last contact */
+	presenceScore: number;
+	lastConfirmedPing: string | null;
+	/** Triage score from 0 to 100 */
+	riskScore: number;
+	/** How the latest scan found the device, e.g. ['arp', 'ping'] */
+	discoverySources: string[];
+	/** User labels such as 'work' or 'kids-devices', alphabetical */
+	tags: string[];
+	/** Key/values attached by the user or fingerprinting, e.g. { room: 'office' } */
+	properties: Record<string, string>;
 	latencyMs: number | null;
+	/** Percent of echo requests lost in the latest latency sample, when measured */
+	packetLoss: number | null;
 	openPorts: PortInfo[];
 	firstSeen: string;
 	lastSeen: string;
@@ -35,6 +60,90 @@ export interface DeviceUpdate {
 	deviceType?: DeviceType;
 	isTrusted?: boolean;
 	notes?: string | null;
+	notesPinned?: boolean;
+	icon?: string | null;
+}
+
+/** Filters and paging for getDevicesPaged; omitted filters match everything */
+export interface DeviceQuery {
+	limit?: number;
+	offset?: number;
+	/** Matches hostname, custom name, current IP or MAC */
+	search?: string;
+	deviceType?: DeviceType;
+	/** Only devices carrying this tag */
+	tag?: string;
+	trustedOnly?: boolean;
+	onlineOnly?: boolean;
+}
+
+export interface DevicePage {
+	items: Device[];
+	/** Devices matching the filters across all pages */
+	total: number;
+}
+
+/** An address a device has held */
+export interface IpHistoryEntry {
+	ipAddress: string;
+	isCurrent: boolean;
+	firstSeen: string;
+	lastSeen: string;
+}
+
+/** What one fingerprint pass concluded about a device */
+export interface FingerprintEntry {
+	osGuess: string | null;
+	osConfidence: number | null;
+	deviceType: string;
+	scanId: string | null;
+	createdAt: string;
+}
+
+/** A device whose notes matched a search */
+export interface NoteMatch {
+	deviceId: string;
+	/** The matching part of the notes, with … where text was cut */
+	snippet: string;
+	notesPinned: boolean;
+}
+
+/** How a JSON import treats devices whose MAC is already known */
+export type ImportStrategy = 'skip' | 'overwrite' | 'merge';
+
+export interface ImportResult {
+	imported: number;
+	/** Known devices changed by an overwrite or merge */
+	updated: number;
+	skipped: number;
+	invalid: number;
+}
+
+export interface CsvRowError {
+	line: number;
+	reason: string;
+}
+
+export interface CsvImportResult {
+	imported: number;
+	updated: number;
+	skipped: number;
+	errors: CsvRowError[];
+}
+
+export interface InvalidRecord {
+	index: number;
+	deviceId: string;
+	reason: string;
+}
+
+export interface ImportPreview {
+	version: number;
+	exportedAt: string;
+	deviceCount: number;
+	alertCount: number;
+	invalidRecords: InvalidRecord[];
+	newVsExisting: { new: number; existing: number };
 }
 
 /** Node representation for d3-force graph */
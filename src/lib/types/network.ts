@@ -5,7 +5,23 @@ export interface NetworkInterface {
 	subnetMask: string | null;
 	macAddress: string | null;
 	gatewayIp: string | null;
+	ipv6Address: string | null;
+	gatewayIpv6: string | null;
 	isActive: boolean;
+	/** Tunnel interface created by a VPN client */
+	isVpn: boolean;
+}
+
+export interface InterfacesChanged {
+	added: NetworkInterface[];
+	removed: NetworkInterface[];
+	interfaces: NetworkInterface[];
+}
+
+export interface GatewayStatus {
+	gatewayIp: string;
+	reachable: boolean;
+	latencyMs: number | null;
 }
 
 export interface AppSettings {
@@ -16,11 +32,54 @@ export interface AppSettings {
 	graphRepulsion: number;
 	graphLinkDistance: number;
 	graphGravity: number;
+	healthBindAddr: string | null;
+	ingestBindAddr: string | null;
+	ingestToken: string | null;
+	latencyChangeThresholdPct: number;
+	latencyMinIntervalSecs: number;
+	queueScans: boolean;
+	verboseScanLogs: boolean;
+	pingTimeoutMs: number;
+	pingSamples: number;
+	pingMinSamples: number;
+	pingTrimOutliers: boolean;
+	/** Pings, lookups and port connections one scan may have in flight */
+	scanConcurrency: number;
+	/** Custom ping command line containing {ip}; null uses the platform ping */
+	pingCommand: string | null;
+	/** Custom neighbor table command; null uses the platform default */
+	arpCommand: string | null;
+	/** Seconds between gateway pings; 0 disables the gateway monitor */
+	gatewayMonitorIntervalSecs: number;
+	/** Hours between automatic database backups; 0 disables them */
+	backupIntervalHours: number;
+	/** Seconds an identical alert is suppressed after firing; 0 keeps every occurrence */
+	alertCooldownSecs: number;
+	/** Local 'HH:MM' from which desktop notifications are held back; needs quietHoursEnd too */
+	quietHoursStart: string | null;
+	/** Local 'HH:MM' at which notifications resume; may wrap past midnight */
+	quietHoursEnd: string | null;
+}
+
+export interface RepairReport {
+	integrityOk: boolean;
+	currentIpsRepaired: number;
+}
+
+/** An automatic database backup */
+export interface BackupInfo {
+	filename: string;
+	/** UTC, YYYY-MM-DD HH:MM:SS */
+	timestamp: string;
+	sizeBytes: number;
 }
 
 export interface LatencyPoint {
 	latencyMs: number;
 	measuredAt: string;
+	/** Set for samples taken from several echo requests */
+	packetLoss: number | null;
+	jitterMs: number | null;
 }
 
 export interface PingResult {
@@ -28,3 +87,12 @@ export interface PingResult {
 	latencyMs: number | null;
 	success: boolean;
 }
+
+export type ProbeMethod = 'icmp' | 'tcp' | 'arp';
+
+export interface Reachability {
+	ip: string;
+	reachable: boolean;
+	methods: ProbeMethod[];
+	latencyMs: number | null;
+}
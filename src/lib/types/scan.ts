@@ -2,10 +2,30 @@ export type ScanType = 'quick' | 'full' | 'portOnly' | 'passive';
 
 export type PortRange = 'top100' | 'top1000' | { custom: number[] };
 
+export type Protocol = 'tcp' | 'udp';
+
+/** Which addresses a scan covers. Explicit ranges are capped at 1022 hosts. */
+export type ScanTarget = 'autoSubnet' | { cidr: string } | { ipList: string[] };
+
 export interface ScanConfig {
 	interfaceId: string;
 	scanType: ScanType;
 	portRange: PortRange;
+	/** Defaults to TCP only. UDP ports are reported only when they answer a probe. */
+	protocols?: Protocol[];
+	/** Defaults to the interface's subnet and neighbor table. */
+	target?: ScanTarget;
+	/** Defaults to the scan type's usual phases. The port scan needs discovery. */
+	phases?: ScanPhases;
+}
+
+export interface ScanPhases {
+	discovery: boolean;
+	ping: boolean;
+	resolve: boolean;
+	ports: boolean;
+	fingerprint: boolean;
+	alerts: boolean;
 }
 
 export interface ScanProgress {
@@ -13,6 +33,11 @@ export interface ScanProgress {
 	phase: string;
 	devicesFound: number;
 	percentComplete: number;
+	/** Set during the port scan. */
+	currentTarget?: string;
+	currentPort?: number;
+	portsDone?: number;
+	portsTotal?: number;
 }
 
 export interface ScanResult {
@@ -22,6 +47,18 @@ export interface ScanResult {
 	durationMs: number;
 }
 
+export interface ScanQueued {
+	jobId: string;
+	position: number;
+}
+
+export interface ScanStarted {
+	jobId: string;
+}
+
+/** What starting a scan returns: the finished scan, or its queue position when scans are queued */
+export type ScanStart = ({ status: 'completed' } & ScanResult) | ({ status: 'queued' } & ScanQueued);
+
 export interface ScanSummary {
 	id: string;
 	scanType: ScanType;
@@ -32,3 +69,35 @@ export interface ScanSummary {
 	startedAt: string;
 	completedAt: string | null;
 }
+
+/** A device as one scan saw it */
+export interface ScanDevice {
+	deviceId: string;
+	ipAddress: string | null;
+	osGuess: string | null;
+	/** e.g. "22/tcp" */
+	openPorts: string[];
+}
+
+export interface DeviceChange {
+	deviceId: string;
+	before: ScanDevice;
+	after: ScanDevice;
+	openedPorts: string[];
+	closedPorts: string[];
+}
+
+/** What changed from scanA to the later scanB */
+export interface ScanDiff {
+	scanA: string;
+	scanB: string;
+	added: ScanDevice[];
+	removed: ScanDevice[];
+	changed: DeviceChange[];
+}
+
+/** Confirmation from start_monitor with the interval it accepted */
+export interface MonitorStarted {
+	intervalSecs: number;
+	startedAt: string;
+}